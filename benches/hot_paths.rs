@@ -0,0 +1,120 @@
+//! Timings of the sampling and bucketing hot paths at a few tick sizes,
+//! without Criterion: each is run for a fixed time from the same seed, and
+//! the median time per call is printed.
+//!
+//! ```text
+//! cargo bench
+//! cargo bench -- counting
+//! ```
+//!
+//! A name after `--` runs only the benchmarks whose names contain it. The
+//! `central-limit bench` subcommand measures the sampler alone, for longer.
+
+use central_limit::{sampling, simulation::Simulation};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const SEED: u64 = 42;
+
+/// `(b_count, r_max)`: samples per tick and steps per sample.
+const SIZES: [(usize, u32); 4] = [(1_000, 19), (10_000, 19), (10_000, 100), (1_000, 1_000)];
+
+/// How long each benchmark is run for, after a tenth as long to warm up.
+const RUN: Duration = Duration::from_millis(500);
+
+fn main() {
+    // `cargo bench` passes `--bench`; anything else is a filter.
+    let filter = std::env::args()
+        .skip(1)
+        .find(|arg| !arg.starts_with('-'))
+        .unwrap_or_default();
+    println!(
+        "{:<36} {:>8} {:>12} {:>14}",
+        "benchmark", "calls", "median", "samples/s"
+    );
+    for (samples, steps) in SIZES {
+        let size = format!("{samples}x{steps}");
+        let run = |name: &str, f: &mut dyn FnMut()| {
+            let name = format!("{name}/{size}");
+            if name.contains(&filter) {
+                report(&name, samples, measure(f));
+            }
+        };
+
+        let mut rng = StdRng::seed_from_u64(SEED);
+        run("generate_sums", &mut || {
+            black_box(sampling::generate_sums(&mut rng, samples, steps));
+        });
+
+        let sums = sampling::generate_sums(&mut StdRng::seed_from_u64(SEED), samples, steps);
+        run("counting/single_pass", &mut || {
+            black_box(count_single_pass(black_box(&sums), steps));
+        });
+        run("counting/per_bucket_filter", &mut || {
+            black_box(count_per_bucket(black_box(&sums), steps));
+        });
+
+        let mut sim = Simulation::new(steps as i32, SEED).unwrap();
+        sim.advance(samples);
+        run("stats", &mut || {
+            black_box(sim.stats());
+        });
+
+        let mut sim = Simulation::new(steps as i32, SEED).unwrap();
+        run("tick", &mut || {
+            sim.advance(samples);
+            black_box(sim.stats());
+        });
+    }
+}
+
+/// Each sum counted into its bucket as it's read, as `Simulation` does.
+fn count_single_pass(sums: &[i32], steps: u32) -> Vec<u64> {
+    let mut counts = vec![0; steps as usize + 1];
+    for &sum in sums {
+        counts[((sum + steps as i32) / 2) as usize] += 1;
+    }
+    counts
+}
+
+/// Every bucket counted by filtering all the sums for it, as ticks were
+/// once counted: a pass over the sums per bucket.
+fn count_per_bucket(sums: &[i32], steps: u32) -> Vec<u64> {
+    (0..=steps as i32)
+        .map(|i| {
+            let x = 2 * i - steps as i32;
+            sums.iter().filter(|&&sum| sum == x).count() as u64
+        })
+        .collect()
+}
+
+/// Calls `f` over and over for `RUN`, returning how many times and the
+/// median time a call took.
+fn measure(f: &mut dyn FnMut()) -> (usize, Duration) {
+    let warm_up = Instant::now();
+    while warm_up.elapsed() < RUN / 10 {
+        f();
+    }
+    let mut times = vec![];
+    let started = Instant::now();
+    while started.elapsed() < RUN {
+        let call = Instant::now();
+        f();
+        times.push(call.elapsed());
+    }
+    times.sort();
+    (times.len(), times[times.len() / 2])
+}
+
+fn report(name: &str, samples: usize, (calls, median): (usize, Duration)) {
+    println!(
+        "{:<36} {:>8} {:>12} {:>14.0}",
+        name,
+        calls,
+        format!("{median:.1?}"),
+        samples as f64 / median.as_secs_f64()
+    );
+}
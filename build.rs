@@ -0,0 +1,33 @@
+//! Passes `git describe` to the build as `GIT_DESCRIBE`, for the exports to
+//! say which commit made them, when building from a git checkout.
+
+use std::{fs, path::Path, process::Command};
+
+fn main() {
+    // A new commit moves the branch HEAD points to, not HEAD itself.
+    let branch = fs::read_to_string(".git/HEAD")
+        .ok()
+        .and_then(|head| Some(format!(".git/{}", head.strip_prefix("ref: ")?.trim())));
+    let watched = [".git/HEAD".to_string(), ".git/index".to_string()]
+        .into_iter()
+        .chain(branch)
+        .filter(|path| Path::new(path).exists())
+        .collect::<Vec<_>>();
+    // Without a checkout there's nothing to watch but this script.
+    if watched.is_empty() {
+        println!("cargo:rerun-if-changed=build.rs");
+    }
+    for path in watched {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+
+    let describe = Command::new("git")
+        .args(["describe", "--always", "--dirty"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    if let Some(describe) = describe {
+        println!("cargo:rustc-env=GIT_DESCRIBE={}", describe.trim());
+    }
+}
@@ -0,0 +1,115 @@
+//! Walks of a step other than the coin's: -1 half the time, 0 a quarter of
+//! the time and +2 the rest. It's lopsided, but its sums still tend to the
+//! normal curve, as the central limit theorem says any step with a finite
+//! variance does.
+//!
+//! ```text
+//! cargo run --example custom_distribution
+//! ```
+
+use central_limit::{
+    rng::Generator,
+    sampling::{self, StepSource},
+    stats::Stats,
+};
+use rand::Rng;
+use std::collections::BTreeMap;
+
+const SAMPLES: usize = 20_000;
+
+const SEED: u64 = 42;
+
+struct ThreePoint;
+
+impl StepSource for ThreePoint {
+    fn step<R: Rng + ?Sized>(&mut self, rng: &mut R) -> i32 {
+        match rng.gen_range(0..4) {
+            0 | 1 => -1,
+            2 => 0,
+            _ => 2,
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        0.0
+    }
+
+    fn variance(&self) -> f64 {
+        0.5 * 1.0 + 0.25 * 4.0
+    }
+}
+
+/// How close the sums of `steps`-step walks come to the normal curve.
+fn ks(steps: u32, seed: u64) -> f64 {
+    let mut source = ThreePoint;
+    let mut rng = Generator::Std.seeded(seed);
+    let mut counts = BTreeMap::new();
+    for sum in sampling::walk_sums(&mut source, &mut rng, SAMPLES, steps) {
+        *counts.entry(sum).or_insert(0) += 1;
+    }
+    // Every sum in between can be reached, so the buckets are 1 apart.
+    let buckets = counts
+        .into_iter()
+        .map(|(sum, count)| (f64::from(sum), count))
+        .collect::<Vec<_>>();
+    let n = f64::from(steps);
+    Stats::compute(
+        &buckets,
+        n * source.mean(),
+        (n * source.variance()).sqrt(),
+        1.0,
+    )
+    .ks
+}
+
+/// The KS distance of walks of each length.
+fn distances(seed: u64) -> Vec<(u32, f64)> {
+    [1, 5, 25, 100]
+        .into_iter()
+        .map(|steps| (steps, ks(steps, seed)))
+        .collect()
+}
+
+fn main() {
+    let distances = distances(SEED);
+
+    println!("{:>5}  KS distance", "steps");
+    for (steps, ks) in &distances {
+        println!("{:>5}  {:.4}", steps, ks);
+    }
+
+    check(&distances);
+}
+
+/// The longer the walks, the closer their sums come to the curve.
+fn check(distances: &[(u32, f64)]) {
+    let (_, first) = distances[0];
+    let (_, last) = distances[distances.len() - 1];
+    assert!(last < first / 4.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longer_walks_come_closer_to_the_curve() {
+        check(&distances(SEED));
+    }
+
+    #[test]
+    fn the_steps_have_the_mean_and_variance_given() {
+        let mut rng = Generator::Std.seeded(SEED);
+        let steps = (0..100_000)
+            .map(|_| f64::from(ThreePoint.step(&mut rng)))
+            .collect::<Vec<_>>();
+        let mean = steps.iter().sum::<f64>() / steps.len() as f64;
+        let variance = steps.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / steps.len() as f64;
+        assert!((mean - ThreePoint.mean()).abs() < 0.02, "{}", mean);
+        assert!(
+            (variance - ThreePoint.variance()).abs() < 0.03,
+            "{}",
+            variance
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! The terminal UI's histogram view, bars over the counts against the normal
+//! curve, as one panel of a `tui` layout of your own beside a table of
+//! statistics and a sparkline of the KS distance tick by tick. The frame is
+//! drawn off-screen and printed, so it runs anywhere; a real app would draw
+//! with `CrosstermBackend`.
+//!
+//! ```text
+//! cargo run --example embed_tui
+//! ```
+
+use central_limit::{
+    histogram::{HistogramSnapshot, HistogramState, HistogramView},
+    simulation::Simulation,
+};
+use std::error::Error;
+use tui::{
+    backend::TestBackend,
+    layout::{Constraint, Direction, Layout},
+    widgets::{Block, Borders, Paragraph, Sparkline},
+    Terminal,
+};
+
+const STEPS: i32 = 9;
+
+const SAMPLES_PER_TICK: usize = 1_000;
+
+const TICKS: usize = 40;
+
+/// The bucket picked out, the one at a sum of 1.
+const SELECTED: usize = 5;
+
+const SEED: u64 = 42;
+
+/// Runs `TICKS` ticks from `seed` and draws the layout, returning the
+/// frame's lines and what the histogram left in its state.
+fn draw(seed: u64) -> Result<(Vec<String>, HistogramState), Box<dyn Error>> {
+    let mut sim = Simulation::new(STEPS, seed)?;
+    let mut distances = vec![];
+    for _ in 0..TICKS {
+        sim.advance(SAMPLES_PER_TICK);
+        distances.push(sim.stats().ks);
+    }
+    let snapshot = HistogramSnapshot::of(&sim);
+    let mut state = HistogramState {
+        selected: Some(SELECTED),
+        ..HistogramState::default()
+    };
+    // The sparkline takes integers, so the distances are in ten-thousandths.
+    let sparkline = distances
+        .iter()
+        .map(|ks| (ks * 10_000.0).round() as u64)
+        .collect::<Vec<_>>();
+    let stats = sim.stats();
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 30))?;
+    terminal.draw(|f| {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(40), Constraint::Length(28)])
+            .split(f.size());
+        let side = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(6), Constraint::Min(3)])
+            .split(columns[1]);
+
+        f.render_stateful_widget(
+            HistogramView::new(&snapshot)
+                .title("Histogram".to_string())
+                .model(Some("normal".to_string())),
+            columns[0],
+            &mut state,
+        );
+
+        let table = format!(
+            "samples {}\nmean    {:.3}\nsd      {:.3}\nKS      {:.4}",
+            stats.total, stats.mean, stats.sd, stats.ks
+        );
+        f.render_widget(
+            Paragraph::new(table).block(Block::default().title("Statistics").borders(Borders::ALL)),
+            side[0],
+        );
+        f.render_widget(
+            Sparkline::default()
+                .block(Block::default().title("KS by tick").borders(Borders::ALL))
+                .data(&sparkline),
+            side[1],
+        );
+    })?;
+
+    let buffer = terminal.backend().buffer();
+    let area = buffer.area;
+    let screen = (area.top()..area.bottom())
+        .map(|y| {
+            (area.left()..area.right())
+                .map(|x| buffer.get(x, y).symbol.as_str())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+    Ok((screen, state))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let (screen, state) = draw(SEED)?;
+    for line in &screen {
+        println!("{}", line.trim_end());
+    }
+    check(&screen, &state)
+}
+
+/// Each panel is drawn with its title, the line chart's legend names the
+/// curve, and the render left where the chart plotted for mapping the mouse
+/// back to sums.
+fn check(screen: &[String], state: &HistogramState) -> Result<(), Box<dyn Error>> {
+    for title in ["Histogram", "Statistics", "KS by tick", "normal"] {
+        assert!(screen.iter().any(|line| line.contains(title)));
+    }
+    let plot = state.plot.as_ref().ok_or("the line chart wasn't drawn")?;
+    assert!(plot.x_at(plot.area.x, plot.area.y) == Some(plot.x_bounds[0]));
+    assert!(state.selected_column.is_some());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_panel_is_drawn() {
+        let (screen, state) = draw(SEED).unwrap();
+        assert_eq!(screen.len(), 30);
+        check(&screen, &state).unwrap();
+    }
+}
@@ -0,0 +1,88 @@
+//! Draws a histogram and writes it as CSV, one row per bucket with its count,
+//! its share of the samples and the count the binomial distribution expects,
+//! to a file if one is named and to stdout otherwise.
+//!
+//! ```text
+//! cargo run --example export_csv -- histogram.csv
+//! ```
+
+use central_limit::simulation::Simulation;
+use std::{
+    env,
+    error::Error,
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+const STEPS: i32 = 19;
+
+const SAMPLES: usize = 10_000;
+
+const SEED: u64 = 42;
+
+fn write_csv<W: Write>(out: &mut W, sim: &Simulation) -> io::Result<()> {
+    writeln!(out, "sum,count,frequency,expected")?;
+    for bucket in sim.buckets() {
+        writeln!(
+            out,
+            "{},{},{},{}",
+            bucket.label(),
+            bucket.count(),
+            bucket.frequency(),
+            // Sums always have a model, but an aggregate of positions doesn't.
+            bucket
+                .expected()
+                .map_or(String::new(), |count| count.to_string())
+        )?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut sim = Simulation::new(STEPS, SEED)?;
+    sim.advance(SAMPLES);
+
+    let mut csv = vec![];
+    write_csv(&mut csv, &sim)?;
+    match env::args().nth(1) {
+        Some(path) => {
+            let mut out = BufWriter::new(File::create(&path)?);
+            out.write_all(&csv)?;
+            out.flush()?;
+            eprintln!("wrote {} buckets to {}", sim.buckets().len(), path);
+        }
+        None => io::stdout().write_all(&csv)?,
+    }
+    check(csv)
+}
+
+/// A header, then a row for each reachable sum, with every sample in one.
+fn check(csv: Vec<u8>) -> Result<(), Box<dyn Error>> {
+    let text = String::from_utf8(csv)?;
+    assert_eq!(text.lines().count(), STEPS as usize + 2);
+    let counted = text
+        .lines()
+        .skip(1)
+        .map(|row| {
+            row.split(',')
+                .nth(1)
+                .and_then(|count| count.parse::<u64>().ok())
+        })
+        .sum::<Option<u64>>();
+    assert_eq!(counted, Some(SAMPLES as u64));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_sample_is_in_a_row() {
+        let mut sim = Simulation::new(STEPS, SEED).unwrap();
+        sim.advance(SAMPLES);
+        let mut csv = vec![];
+        write_csv(&mut csv, &sim).unwrap();
+        check(csv).unwrap();
+    }
+}
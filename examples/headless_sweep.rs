@@ -0,0 +1,78 @@
+//! Sweeps the walk length and the number of samples, and prints how close
+//! each histogram comes to the normal curve, using the library without the
+//! terminal UI.
+//!
+//! ```text
+//! cargo run --example headless_sweep
+//! ```
+
+use central_limit::simulation::Simulation;
+
+const STEPS: [i32; 7] = [1, 2, 5, 10, 20, 50, 100];
+
+/// The samples counted when each column's KS distance is taken.
+const SAMPLES: [usize; 3] = [1_000, 10_000, 100_000];
+
+const SEED: u64 = 42;
+
+/// The KS distance of each walk length after each number of samples.
+fn sweep(seed: u64) -> Vec<(i32, Vec<f64>)> {
+    let mut sim = Simulation::new(1, seed).expect("1 step is valid");
+    STEPS
+        .iter()
+        .map(|&steps| {
+            // Changing the walk length clears the counts, so each row
+            // counts up from nothing.
+            sim.set_steps(steps).expect("the steps are positive");
+            let mut counted = 0;
+            let distances = SAMPLES
+                .iter()
+                .map(|&samples| {
+                    sim.advance(samples - counted);
+                    counted = samples;
+                    sim.stats().ks
+                })
+                .collect();
+            (steps, distances)
+        })
+        .collect()
+}
+
+fn main() {
+    let rows = sweep(SEED);
+
+    print!("{:>5}", "steps");
+    for samples in SAMPLES {
+        print!("  {:>9}", format!("KS @ {}k", samples / 1_000));
+    }
+    println!();
+    for (steps, distances) in &rows {
+        print!("{:>5}", steps);
+        for ks in distances {
+            print!("  {:>9.4}", ks);
+        }
+        println!();
+    }
+
+    check(&rows);
+}
+
+/// With more samples, the histogram of the longest walks settles onto the
+/// curve.
+fn check(rows: &[(i32, Vec<f64>)]) {
+    let (_, longest) = rows.last().expect("there are walk lengths");
+    assert!(longest[SAMPLES.len() - 1] < longest[0]);
+    assert!(longest[SAMPLES.len() - 1] < 0.01);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_longest_walks_settle_onto_the_curve() {
+        let rows = sweep(SEED);
+        assert_eq!(rows.len(), STEPS.len());
+        check(&rows);
+    }
+}
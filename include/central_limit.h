@@ -0,0 +1,48 @@
+/* The C API to Central Limit's simulation, from src/ffi.rs. Link against
+ * libcentral_limit (the cdylib or staticlib `cargo build --release` makes). */
+
+#ifndef CENTRAL_LIMIT_H
+#define CENTRAL_LIMIT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct clt_simulation clt_simulation;
+
+typedef struct clt_stats {
+    uint64_t total;
+    double mean;
+    double sd;
+    double ks;
+} clt_stats;
+
+/* Null if steps is less than 1. The same seed draws the same histogram. */
+clt_simulation *clt_simulation_new(int32_t steps, uint64_t seed);
+void clt_simulation_free(clt_simulation *sim);
+
+/* 0, or -1 if p isn't between 0 and 1. */
+int clt_simulation_set_bias(clt_simulation *sim, double p);
+/* 0 sum, 1 mean, 2 max, 3 min, 4 median: 0, or -1 if refused. */
+int clt_simulation_set_aggregate(clt_simulation *sim, int aggregate);
+
+void clt_simulation_advance(clt_simulation *sim, size_t samples);
+void clt_simulation_reset(clt_simulation *sim);
+
+/* Each copies up to len values into out and returns how many buckets there
+ * are; out may be null to ask. */
+size_t clt_simulation_buckets(const clt_simulation *sim);
+size_t clt_simulation_counts(const clt_simulation *sim, uint64_t *out, size_t len);
+size_t clt_simulation_centers(const clt_simulation *sim, double *out, size_t len);
+
+uint64_t clt_simulation_samples(const clt_simulation *sim);
+clt_stats clt_simulation_stats(const clt_simulation *sim);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,209 @@
+//! `--notify-when`: a one-shot alert when a statistic crosses a threshold.
+//! The conditions are shared with `--until`.
+
+use crate::{history::Metric, i18n::Strings};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Less => "<",
+            Op::LessOrEqual => "<=",
+            Op::Greater => ">",
+            Op::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+/// A comparison such as `ks<0.01`, `sd>=4.3` or `buckets_converged>=95%`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Condition {
+    pub metric: Metric,
+    pub op: Op,
+    pub threshold: f64,
+}
+
+impl Condition {
+    pub fn parse(text: &str) -> Result<Condition, String> {
+        let at = text
+            .find(['<', '>'])
+            .ok_or(format!("'{}' has no < or > comparison", text))?;
+        let (name, rest) = text.split_at(at);
+        let (op, value) = match rest.split_at(1) {
+            ("<", value) => match value.strip_prefix('=') {
+                Some(value) => (Op::LessOrEqual, value),
+                None => (Op::Less, value),
+            },
+            (_, value) => match value.strip_prefix('=') {
+                Some(value) => (Op::GreaterOrEqual, value),
+                None => (Op::Greater, value),
+            },
+        };
+        let metric = Metric::parse(name.trim()).ok_or(format!(
+            "unknown statistic '{}', expected ks, peak, sd or buckets_converged",
+            name.trim()
+        ))?;
+        // buckets_converged is a percentage, with or without the sign.
+        let value = match (metric, value.trim().strip_suffix('%')) {
+            (Metric::Converged, Some(value)) => value,
+            (_, Some(_)) => {
+                return Err(format!("{} is not a percentage", metric.key()));
+            }
+            (_, None) => value,
+        };
+        let threshold = value
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .ok_or(format!("invalid threshold '{}'", value.trim()))?;
+        let condition = Condition {
+            metric,
+            op,
+            threshold,
+        };
+        // A condition no value can meet would wait forever.
+        let (min, max) = metric.bounds();
+        if !condition.holds(min) && !condition.holds(max) {
+            let (side, bound) = match op {
+                Op::Less | Op::LessOrEqual => ("below", min),
+                Op::Greater | Op::GreaterOrEqual => ("above", max),
+            };
+            return Err(format!(
+                "'{}' can never hold: {} is never {} {}",
+                condition,
+                metric.key(),
+                side,
+                bound
+            ));
+        }
+        Ok(condition)
+    }
+
+    pub fn holds(&self, value: f64) -> bool {
+        match self.op {
+            Op::Less => value < self.threshold,
+            Op::LessOrEqual => value <= self.threshold,
+            Op::Greater => value > self.threshold,
+            Op::GreaterOrEqual => value >= self.threshold,
+        }
+    }
+
+    /// What to tell the user when the condition first holds at `tick`.
+    pub fn message(&self, strings: &Strings, tick: u64, value: f64) -> String {
+        strings.fill(
+            "toast.alert",
+            &[
+                ("condition", self),
+                ("tick", &tick),
+                ("metric", &strings.get(self.metric.name_key())),
+                ("value", &self.metric.format(value)),
+            ],
+        )
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unit = match self.metric {
+            Metric::Converged => "%",
+            _ => "",
+        };
+        write!(
+            f,
+            "{}{}{}{}",
+            self.metric.key(),
+            self.op.symbol(),
+            self.threshold,
+            unit
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub condition: Condition,
+    /// The tick the condition first held on.
+    pub fired: Option<u64>,
+}
+
+impl Alert {
+    pub fn new(condition: Condition) -> Alert {
+        Alert {
+            condition,
+            fired: None,
+        }
+    }
+
+    /// Returns true on the tick the condition first holds. Going back to
+    /// before that tick, by a reset or by rewinding a replay, re-arms it.
+    pub fn check(&mut self, tick: u64, value: f64) -> bool {
+        if self.fired.is_some_and(|fired| tick < fired) {
+            self.fired = None;
+        }
+        if self.fired.is_none() && value.is_finite() && self.condition.holds(value) {
+            self.fired = Some(tick);
+            return true;
+        }
+        false
+    }
+}
+
+/// Asks the desktop to show a notification, with whatever the platform
+/// provides on the command line. Failures are only logged.
+#[cfg(feature = "notify")]
+pub fn notify_desktop(message: &str) {
+    use std::process::Command;
+
+    let result = if cfg!(target_os = "macos") {
+        Command::new("osascript")
+            .arg("-e")
+            .arg(format!(
+                "display notification {:?} with title \"Central Limit\"",
+                message
+            ))
+            .status()
+    } else {
+        Command::new("notify-send")
+            .arg("Central Limit")
+            .arg(message)
+            .status()
+    };
+    if let Err(err) = result {
+        log::warn!("desktop notification failed: {}", err);
+    }
+}
+
+#[cfg(not(feature = "notify"))]
+pub fn notify_desktop(_message: &str) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_converged_takes_a_percentage() {
+        let condition = Condition::parse("buckets_converged>=95%").unwrap();
+        assert_eq!(
+            condition,
+            Condition {
+                metric: Metric::Converged,
+                op: Op::GreaterOrEqual,
+                threshold: 95.0,
+            }
+        );
+        assert_eq!(Condition::parse("buckets_converged >= 95 "), Ok(condition));
+        assert!(Condition::parse("ks<1%").is_err());
+        // No more than every bucket can converge.
+        assert!(Condition::parse("buckets_converged>100%").is_err());
+        assert!(Condition::parse("buckets_converged<=100%").is_ok());
+    }
+}
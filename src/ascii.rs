@@ -0,0 +1,135 @@
+//! `--ascii`: the TUI drawn in plain ASCII, for terminals and fonts that
+//! show the block, Braille and line-drawing characters as question marks.
+//! The bars and sparklines are filled from `bars::ASCII_BARS` and the theme
+//! leaves out the Braille markers, but tui has no ASCII borders or markers
+//! of its own, so what's left is swapped cell by cell at the end of each
+//! frame: lines and corners for `-`, `|` and `+`, dots for `*`, blocks for
+//! `#`, and the odd symbol in the text, such as `σ`, for a letter like it.
+
+use std::env;
+use tui::{buffer::Buffer, layout::Rect, widgets::Widget};
+use unicode_width::UnicodeWidthStr;
+
+/// Whether the locale says the terminal takes something other than UTF-8,
+/// looked up as the C library does, `LC_ALL` before `LC_CTYPE` before
+/// `LANG`. With none of them set there's nothing to go on, so the answer
+/// is no.
+pub fn locale_lacks_utf8() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty());
+    locale.is_some_and(|locale| {
+        let locale = locale.to_lowercase();
+        !(locale.contains("utf-8") || locale.contains("utf8"))
+    })
+}
+
+/// Swaps every character outside ASCII in the area for one in it.
+pub struct Ascii;
+
+impl Widget for Ascii {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                if cell.symbol.is_ascii() {
+                    continue;
+                }
+                let wide = cell.symbol.width() > 1;
+                cell.set_symbol(substitute(&cell.symbol));
+                // The cell a wide character covered would otherwise be
+                // left empty.
+                if wide && x + 1 < area.right() {
+                    buf.get_mut(x + 1, y).set_symbol(" ");
+                }
+            }
+        }
+    }
+}
+
+fn substitute(symbol: &str) -> &'static str {
+    let Some(c) = symbol.chars().next() else {
+        return " ";
+    };
+    match c {
+        '─' | '━' | '═' | '┄' | '┅' | '╌' | '╍' => "-",
+        '│' | '┃' | '║' | '┆' | '┇' | '╎' | '╏' => "|",
+        '\u{2500}'..='\u{257f}' => "+",
+        // Blocks as `bars::ASCII_BARS` has them, by how much they fill,
+        // but for the half block, which is the block marker.
+        '█' | '▇' | '▉' | '▊' | '▀' | '▄' | '▓' | '▒' => "#",
+        '▆' | '▅' | '▋' | '▌' | '▐' | '░' => ":",
+        '▃' | '▂' | '▁' | '▍' | '▎' | '▏' => ".",
+        '\u{2800}'..='\u{28ff}' => braille(c),
+        '•' | '◆' | '●' => "*",
+        // The thin space between thousands.
+        '\u{2009}' | '\u{202f}' | '\u{a0}' => " ",
+        'ä' | 'à' | 'á' | 'â' => "a",
+        'Ä' => "A",
+        'ö' | 'ó' | 'ô' => "o",
+        'Ö' => "O",
+        'ü' | 'ú' => "u",
+        'Ü' => "U",
+        'é' | 'è' => "e",
+        'ß' => "s",
+        'σ' => "s",
+        'λ' => "l",
+        'χ' | '×' => "x",
+        'µ' => "u",
+        '√' => "V",
+        '±' => "~",
+        '²' => "2",
+        'ᵏ' => "k",
+        '…' | '·' => ".",
+        '–' | '—' | '−' => "-",
+        '←' | '≤' => "<",
+        '→' | '≥' => ">",
+        _ => "?",
+    }
+}
+
+/// A Braille cell by where its dots are: `'` for the top half, `.` for the
+/// bottom and `:` for both.
+fn braille(c: char) -> &'static str {
+    let dots = c as u32 - 0x2800;
+    // Dots 1, 2, 4 and 5 are the top two rows; 3, 6, 7 and 8 the bottom two.
+    let top = dots & 0b0001_1011 != 0;
+    let bottom = dots & 0b1110_0100 != 0;
+    match (top, bottom) {
+        (false, false) => " ",
+        (true, false) => "'",
+        (false, true) => ".",
+        (true, true) => ":",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn braille_goes_by_where_its_dots_are() {
+        assert_eq!(braille('\u{2800}'), " ");
+        assert_eq!(braille('⠁'), "'");
+        assert_eq!(braille('⣀'), ".");
+        assert_eq!(braille('⣿'), ":");
+    }
+
+    #[test]
+    fn every_cell_is_left_ascii() {
+        let area = Rect::new(0, 0, 8, 1);
+        let mut buf = Buffer::empty(area);
+        buf.set_string(0, 0, "┌─σ²█✓", Default::default());
+        buf.set_string(6, 0, "世", Default::default());
+        Ascii.render(area, &mut buf);
+        let text = buf
+            .content
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect::<String>();
+        // Anything without a likeness is a question mark, and the cell a
+        // wide character covered is blank.
+        assert_eq!(text, "+-s2#?? ");
+    }
+}
@@ -0,0 +1,167 @@
+//! `attach <SOCKET>`: following a run served with `--socket` from another
+//! terminal, such as a second screen in a lecture hall. The ticks are shown
+//! as a replay's are, parameters and all, and only the keys that change
+//! the view do anything. A lost connection is retried, waiting twice as
+//! long each time it fails again.
+
+use crate::{replay::Frame, App};
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// How often the socket is read.
+pub const POLL: Duration = Duration::from_millis(50);
+
+const FIRST_RETRY: Duration = Duration::from_millis(250);
+const MAX_RETRY: Duration = Duration::from_secs(8);
+
+#[cfg(unix)]
+type Connection = std::os::unix::net::UnixStream;
+
+#[cfg(unix)]
+fn connect(path: &Path) -> io::Result<Connection> {
+    let stream = Connection::connect(path)?;
+    stream.set_nonblocking(true)?;
+    Ok(stream)
+}
+
+#[cfg(not(unix))]
+type Connection = std::fs::File;
+
+#[cfg(not(unix))]
+fn connect(_path: &Path) -> io::Result<Connection> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "attach is not supported on this platform",
+    ))
+}
+
+pub struct AttachSource {
+    path: PathBuf,
+    connection: Option<Connection>,
+    /// What's been read of a line that hasn't ended yet.
+    partial: Vec<u8>,
+    /// Why the last connection ended or couldn't be made, while there's none.
+    lost: Option<String>,
+    retry_at: Instant,
+    retry: Duration,
+    /// Whether a tick has come in yet.
+    shown: bool,
+}
+
+impl AttachSource {
+    /// Connects on the first step, so a run that hasn't started serving yet
+    /// is waited for like a lost one.
+    pub fn new(path: &Path) -> AttachSource {
+        AttachSource {
+            path: path.to_path_buf(),
+            connection: None,
+            partial: vec![],
+            lost: None,
+            retry_at: Instant::now(),
+            retry: FIRST_RETRY,
+            shown: false,
+        }
+    }
+
+    /// Reads whatever has come in since the last step and shows the latest
+    /// tick, or tries to connect again once it's time.
+    pub fn step(&mut self, app: &mut App) {
+        if self.connection.is_none() && Instant::now() >= self.retry_at {
+            match connect(&self.path) {
+                Ok(connection) => {
+                    log::info!("attached to {}", self.path.display());
+                    self.connection = Some(connection);
+                    self.lost = None;
+                    self.retry = FIRST_RETRY;
+                }
+                Err(err) => self.lose(err.to_string()),
+            }
+        }
+        if let Some(frame) = self.read() {
+            frame.show(app);
+            self.shown = true;
+        }
+        self.show_status(app);
+    }
+
+    /// The last tick in the lines read, if any.
+    fn read(&mut self) -> Option<Frame> {
+        let connection = self.connection.as_mut()?;
+        let mut buffer = [0; 64 * 1024];
+        let ended = loop {
+            match connection.read(&mut buffer) {
+                Ok(0) => break Some("the run stopped serving".to_string()),
+                Ok(n) => self.partial.extend_from_slice(&buffer[..n]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break None,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => break Some(err.to_string()),
+            }
+        };
+
+        // The lines that came in whole are shown even if the connection
+        // ended after them.
+        let end = self.partial.iter().rposition(|&b| b == b'\n');
+        let lines = match end {
+            Some(end) => self.partial.drain(..=end).collect::<Vec<_>>(),
+            None => vec![],
+        };
+        if let Some(reason) = ended {
+            self.lose(reason);
+        }
+        String::from_utf8_lossy(&lines)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match Frame::parse(line) {
+                Ok(frame) => frame,
+                Err(err) => {
+                    log::warn!(
+                        "skipping a tick event from {}: {}",
+                        self.path.display(),
+                        err
+                    );
+                    None
+                }
+            })
+            .next_back()
+    }
+
+    /// Drops the connection, and any line it left unfinished, and waits
+    /// longer than last time before trying again.
+    fn lose(&mut self, reason: String) {
+        if self.connection.take().is_some() {
+            log::info!("lost {}: {}", self.path.display(), reason);
+        }
+        self.partial.clear();
+        self.lost = Some(reason);
+        self.retry_at = Instant::now() + self.retry;
+        self.retry = (self.retry * 2).min(MAX_RETRY);
+    }
+
+    pub fn show_status(&self, app: &mut App) {
+        let strings = &app.strings;
+        let path = self.path.display().to_string();
+        let state = match (&self.lost, self.shown) {
+            (Some(_), _) => strings.get("attach.lost"),
+            (None, true) => strings.get("attach.live"),
+            (None, false) => strings.get("attach.waiting"),
+        };
+        app.replay_status = Some(strings.fill(
+            "attach.status",
+            &[("path", &path), ("tick", &app.tick), ("state", &state)],
+        ));
+        app.connection_lost = self.lost.as_ref().map(|reason| {
+            let left = self.retry_at.saturating_duration_since(Instant::now());
+            strings.fill(
+                "banner.connection_lost",
+                &[
+                    ("path", &path),
+                    ("reason", reason),
+                    ("secs", &left.as_secs_f64().ceil()),
+                ],
+            )
+        });
+    }
+}
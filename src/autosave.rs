@@ -0,0 +1,219 @@
+//! `--autosave`: the cumulative counts written out every so often, so a long
+//! run lost to a crash, a laptop going to sleep or a closed terminal can be
+//! carried on with. The state is the JSON export, followed by a checksum
+//! line, written to `autosave.clt.tmp`, synced to disk and renamed over
+//! `autosave.clt`, so a run that dies part-way through a write, or a machine
+//! that loses power just after one, leaves the last one whole.
+//!
+//! The export is made between ticks and written on a thread of its own, so
+//! a slow disk doesn't hold up the screen; an autosave that comes due while
+//! the last is still being written is skipped. On the next start with
+//! `--autosave`, the run offers to warm-start from it, or does with
+//! `--auto-resume`. As with `--warm-start`, only the counts carry over, not
+//! the random sequence.
+
+use crate::{export, App};
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+pub const FILE: &str = "autosave.clt";
+
+const CHECKSUM: &str = "# checksum ";
+
+pub struct Autosave {
+    path: PathBuf,
+    every: Duration,
+    last: Instant,
+    writing: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl Autosave {
+    /// Autosaving to `FILE` in `dir` every `every`, the first `every` from now.
+    pub fn new(dir: &Path, every: Duration) -> Autosave {
+        Autosave {
+            path: dir.join(FILE),
+            every,
+            last: Instant::now(),
+            writing: None,
+        }
+    }
+
+    /// Puts the next autosave off by `by`, time the run wasn't running for.
+    pub fn delay(&mut self, by: Duration) {
+        self.last += by;
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Starts writing `app`'s state if an autosave is due and the last has
+    /// been written. Returns why the last write failed, if it did.
+    pub fn tick(&mut self, app: &App) -> Option<String> {
+        if self.last.elapsed() < self.every {
+            return None;
+        }
+        if self.writing.as_ref().is_some_and(|w| !w.is_finished()) {
+            log::info!("skipping an autosave, the last is still being written");
+            self.last = Instant::now();
+            return None;
+        }
+        let failed = self.join();
+        let contents = seal(&export::to_json(app).to_string());
+        let path = self.path.clone();
+        self.writing = Some(thread::spawn(move || write(&path, &contents)));
+        self.last = Instant::now();
+        failed
+    }
+
+    /// Waits for a write in progress, as when the run ends, and returns why
+    /// it failed, if it did.
+    pub fn join(&mut self) -> Option<String> {
+        match self.writing.take()?.join() {
+            Ok(Ok(())) => None,
+            Ok(Err(err)) => Some(err.to_string()),
+            Err(_) => Some("the autosave thread panicked".to_string()),
+        }
+    }
+}
+
+/// Replaces the file at `path` with `contents` all at once. The new file is
+/// on disk before the rename, and the rename is before this returns, so
+/// neither can be lost to a crash after the other.
+fn write(path: &Path, contents: &str) -> io::Result<()> {
+    let tmp = path.with_extension("clt.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp, path)?;
+    // The rename is an entry in the directory, which is synced for it on
+    // Unix; elsewhere a directory can't be opened as a file.
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// The export in the autosave at `path` if there's one to carry on from:
+/// with `auto_resume`, or if asked and the answer is yes. An autosave that
+/// can't be read is warned about and left for the next to replace.
+pub fn offer(path: &Path, auto_resume: bool) -> Option<export::Histogram> {
+    if !path.exists() {
+        return None;
+    }
+    let export = match open(path) {
+        Ok(export) => export,
+        Err(err) => {
+            eprintln!(
+                "warning: ignoring the autosave at {}: {}",
+                path.display(),
+                err
+            );
+            return None;
+        }
+    };
+    let samples = export.counts.iter().copied().fold(0, u64::saturating_add);
+    if auto_resume {
+        eprintln!(
+            "resuming from the autosave at {} ({} samples)",
+            path.display(),
+            samples
+        );
+        return Some(export);
+    }
+    if !(io::stdin().is_terminal() && io::stderr().is_terminal()) {
+        eprintln!(
+            "note: there's an autosave at {} ({} samples); pass --auto-resume to carry on from it, \
+             or it'll be replaced by this run's",
+            path.display(),
+            samples
+        );
+        return None;
+    }
+    eprint!(
+        "carry on from the autosave at {} ({} samples)? [Y/n] ",
+        path.display(),
+        samples
+    );
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return None;
+    }
+    match answer.trim().to_lowercase().as_str() {
+        "" | "y" | "yes" => Some(export),
+        _ => {
+            eprintln!("starting afresh; the autosave will be replaced by this run's");
+            None
+        }
+    }
+}
+
+/// `json` with the checksum line that `open` checks.
+fn seal(json: &str) -> String {
+    format!("{}\n{}{:016x}\n", json, CHECKSUM, fnv1a(json.as_bytes()))
+}
+
+/// The export in the autosave at `path`, if its checksum holds.
+pub fn open(path: &Path) -> Result<export::Histogram, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let (json, sum) = contents
+        .trim_end()
+        .rsplit_once('\n')
+        .and_then(|(json, last)| Some((json, last.strip_prefix(CHECKSUM)?)))
+        .ok_or("it has no checksum")?;
+    let sum = u64::from_str_radix(sum, 16).map_err(|_| "its checksum is unreadable")?;
+    if sum != fnv1a(json.as_bytes()) {
+        return Err("its checksum doesn't match, so it's been cut short or changed".to_string());
+    }
+    export::parse(json)
+}
+
+/// The 64-bit FNV-1a hash, enough to tell a torn or damaged file.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use central_limit::rng::Generator;
+
+    #[test]
+    fn an_autosave_reads_back_as_written() {
+        let dir =
+            std::env::temp_dir().join(format!("central-limit-autosave-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(FILE);
+        let mut app = App::new(1, Generator::Std);
+        app.set_params(50, 10).unwrap();
+        app.on_tick();
+        let counts = export::parse(&export::to_json(&app).to_string())
+            .unwrap()
+            .counts;
+        write(&path, &seal(&export::to_json(&app).to_string())).unwrap();
+        assert!(!path.with_extension("clt.tmp").exists());
+        assert_eq!(open(&path).map(|read| read.counts), Ok(counts));
+
+        let torn = fs::read_to_string(&path)
+            .unwrap()
+            .replacen("\"counts\"", "\"count\" ", 1);
+        fs::write(&path, torn).unwrap();
+        assert!(open(&path).is_err_and(|err| err.contains("checksum")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+//! Axis scaling helpers.
+
+/// Rounds `x` up to the nearest 1, 2 or 5 × 10^k.
+pub fn nice_ceil(x: f64) -> f64 {
+    if x <= 0.0 || !x.is_finite() {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(x.log10().floor());
+    let fraction = x / magnitude;
+    let nice = if fraction <= 1.0 {
+        1.0
+    } else if fraction <= 2.0 {
+        2.0
+    } else if fraction <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+/// Headroom above the tallest bucket before the axis grows.
+pub const HEADROOM: f64 = 1.1;
+
+/// Ticks the data must fit under a smaller bound before the axis shrinks.
+const SHRINK_AFTER: u32 = 5;
+
+/// An upper axis bound that follows the data but doesn't jitter: it grows as
+/// soon as the data needs it and shrinks only after the data has stayed
+/// smaller for a few ticks.
+#[derive(Debug, Clone, Copy)]
+pub struct AutoBound {
+    value: f64,
+    shrink_ticks: u32,
+}
+
+impl AutoBound {
+    pub fn new() -> AutoBound {
+        AutoBound {
+            value: 1.0,
+            shrink_ticks: 0,
+        }
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn update(&mut self, max: f64) {
+        let target = nice_ceil(max * HEADROOM);
+        if target > self.value {
+            self.value = target;
+            self.shrink_ticks = 0;
+        } else if target < self.value {
+            self.shrink_ticks += 1;
+            if self.shrink_ticks >= SHRINK_AFTER {
+                self.value = target;
+                self.shrink_ticks = 0;
+            }
+        } else {
+            self.shrink_ticks = 0;
+        }
+    }
+}
+
+impl Default for AutoBound {
+    fn default() -> AutoBound {
+        AutoBound::new()
+    }
+}
+
+/// Evenly spaced axis labels at multiples of a nice step. tui spreads labels
+/// evenly between the bounds, so the bounds are widened to multiples of the
+/// step to keep each label over its value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ticks {
+    pub bounds: [f64; 2],
+    pub step: f64,
+    pub values: Vec<f64>,
+    /// With ticks at multiples of some other unit than 1, its size and the
+    /// suffix the labels count it in.
+    pub unit: Option<(f64, &'static str)>,
+}
+
+impl Ticks {
+    pub fn new(lo: f64, hi: f64, max_labels: usize) -> Ticks {
+        let (lo, hi) = if hi > lo {
+            (lo, hi)
+        } else {
+            (lo - 1.0, lo + 1.0)
+        };
+        let intervals = max_labels.max(2) - 1;
+        let step = nice_ceil((hi - lo) / intervals as f64);
+
+        let start = (lo / step).floor();
+        let end = (hi / step).ceil();
+        let values = (0..=(end - start) as usize)
+            .map(|i| (start + i as f64) * step)
+            .collect();
+
+        Ticks {
+            bounds: [start * step, end * step],
+            step,
+            values,
+            unit: None,
+        }
+    }
+
+    /// Like `new`, at nice multiples of `unit` rather than of 1, labelled
+    /// in units with `suffix` after all but 0: in σ, `-2σ`, `0`, `2σ`.
+    pub fn per(lo: f64, hi: f64, unit: f64, suffix: &'static str, max_labels: usize) -> Ticks {
+        if unit <= 0.0 || unit == 1.0 && suffix.is_empty() {
+            return Ticks::new(lo, hi, max_labels);
+        }
+        let ticks = Ticks::new(lo / unit, hi / unit, max_labels);
+        Ticks {
+            bounds: ticks.bounds.map(|bound| bound * unit),
+            step: ticks.step * unit,
+            values: ticks.values.iter().map(|value| value * unit).collect(),
+            unit: Some((unit, suffix)),
+        }
+    }
+
+    /// Like `new`, at nice multiples of `sigma`: `-2σ`, `0`, `2σ`.
+    pub fn sigmas(lo: f64, hi: f64, sigma: f64, max_labels: usize) -> Ticks {
+        Ticks::per(lo, hi, sigma, "σ", max_labels)
+    }
+
+    /// Formats a tick value with just enough decimals for the step size.
+    pub fn label(&self, value: f64) -> String {
+        let Some((unit, suffix)) = self.unit else {
+            let decimals = (-self.step.log10().floor()).max(0.0) as usize;
+            // Adding 0.0 turns -0.0 into 0.0 so the origin isn't labelled "-0".
+            return format!("{:.*}", decimals, value + 0.0);
+        };
+        let step = self.step / unit;
+        let decimals = (-step.log10().floor()).max(0.0) as usize;
+        let value = ((value / unit) / step).round() * step;
+        match format!("{:.*}", decimals, value + 0.0) {
+            zero if value == 0.0 => zero,
+            multiple => multiple + suffix,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_single_value_gets_an_axis_around_it() {
+        let ticks = Ticks::new(4.0, 4.0, 5);
+        assert!(
+            ticks.bounds[0] < 4.0 && 4.0 < ticks.bounds[1],
+            "{:?}",
+            ticks.bounds
+        );
+        assert!(ticks.values.iter().all(|value| value.is_finite()));
+        // No spread to count in: the ticks fall back to plain numbers.
+        assert_eq!(Ticks::sigmas(4.0, 4.0, 0.0, 5), ticks);
+    }
+}
@@ -0,0 +1,88 @@
+//! A tripwire for sampler regressions: the mean of every step drawn so far,
+//! against the mean the steps are meant to have. A slip such as `<= 5` for
+//! `< 5` in `sampling::step` moves the mean by far more than chance would
+//! within a few ticks, while the histogram still looks like a bell curve.
+//!
+//! The mean is worked out from the sums, so it costs nothing per step. It's
+//! compared against the mean of the bias each sample was drawn with rather
+//! than an even split, so a sampler meant to be biased only trips it if it's
+//! biased by the wrong amount.
+
+use crate::sampling;
+
+/// Standard errors from the expected mean that count as drift. A fair
+/// sampler gets this far by chance about once in 1.7 million checks.
+pub const SIGMAS: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Balance {
+    steps: u64,
+    /// The sum of every step.
+    total: i64,
+    /// What the sum of every step is expected to be, and its variance.
+    expected: f64,
+    variance: f64,
+}
+
+impl Balance {
+    /// Counts a sample of `steps` fair steps that summed to `sum`.
+    pub fn add(&mut self, sum: i32, steps: i32) {
+        self.add_biased(sum, steps, sampling::FAIR);
+    }
+
+    /// Counts a sample of `steps` steps that summed to `sum`, each +1 with
+    /// probability `p`.
+    pub fn add_biased(&mut self, sum: i32, steps: i32, p: f64) {
+        self.steps += steps as u64;
+        self.total += sum as i64;
+        match p == sampling::FAIR {
+            true => {
+                self.expected += sampling::STEP_MEAN * steps as f64;
+                self.variance += sampling::STEP_VARIANCE * steps as f64;
+            }
+            false => {
+                self.expected += (2.0 * p - 1.0) * steps as f64;
+                self.variance += 4.0 * p * (1.0 - p) * steps as f64;
+            }
+        }
+    }
+
+    /// Steps drawn so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.total as f64 / self.steps.max(1) as f64
+    }
+
+    /// The fraction of steps that were +1.
+    pub fn up_fraction(&self) -> f64 {
+        (1.0 + self.mean()) / 2.0
+    }
+
+    /// The fraction of +1 steps the sampler is meant to draw, from the
+    /// biases the steps so far were drawn with; an even split before any.
+    pub fn expected_fraction(&self) -> f64 {
+        match self.steps {
+            0 => (1.0 + sampling::STEP_MEAN) / 2.0,
+            steps => (1.0 + self.expected / steps as f64) / 2.0,
+        }
+    }
+
+    /// How many standard errors the mean is from the expected one.
+    pub fn z(&self) -> f64 {
+        if self.steps == 0 || self.variance <= 0.0 {
+            return 0.0;
+        }
+        let steps = self.steps as f64;
+        let se = (self.variance / steps / steps).sqrt();
+        (self.mean() - self.expected / steps) / se
+    }
+
+    /// Whether the mean is further from the expected one than chance
+    /// explains.
+    pub fn drifted(&self) -> bool {
+        self.z().abs() > SIGMAS
+    }
+}
@@ -0,0 +1,550 @@
+//! A bar chart like tui's `BarChart`, but with value and bucket labels that
+//! adapt to the available bar width instead of overlapping or vanishing.
+
+use crate::format;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    symbols,
+    widgets::{
+        canvas::{Canvas, Line},
+        Block, Widget,
+    },
+};
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RenderSettings {
+    pub max_bar_width: u16,
+    pub bar_gap: u16,
+    /// Bars narrower than this never show value labels.
+    pub min_value_width: u16,
+    pub renderer: Renderer,
+    /// Draw in plain ASCII, for terminals that can't show the block and
+    /// Braille characters.
+    pub ascii: bool,
+}
+
+impl RenderSettings {
+    /// The renderer the bars are drawn with: Braille is drawn as blocks in
+    /// ASCII, as there's no ASCII for a dot.
+    pub fn shown_renderer(&self) -> Renderer {
+        match self.ascii {
+            true => Renderer::Blocks,
+            false => self.renderer,
+        }
+    }
+
+    /// The characters block bars and sparklines are filled in with.
+    pub fn bar_set(&self) -> symbols::bar::Set {
+        match self.ascii {
+            true => ASCII_BARS,
+            false => symbols::bar::NINE_LEVELS,
+        }
+    }
+}
+
+/// `#` for a whole or nearly whole cell, with `:` and `.` for the less
+/// filled tops of bars.
+pub const ASCII_BARS: symbols::bar::Set = symbols::bar::Set {
+    full: "#",
+    seven_eighths: "#",
+    three_quarters: ":",
+    five_eighths: ":",
+    half: ":",
+    three_eighths: ".",
+    one_quarter: ".",
+    one_eighth: ".",
+    empty: " ",
+};
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings {
+            max_bar_width: 7,
+            bar_gap: 1,
+            min_value_width: 2,
+            renderer: Renderer::Blocks,
+            ascii: false,
+        }
+    }
+}
+
+/// How the bars themselves are filled in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Renderer {
+    /// Block characters, eight levels per row.
+    Blocks,
+    /// Braille dots on a canvas, four levels per row. Finer, but some fonts
+    /// draw Braille badly, so it's opt-in.
+    Braille,
+}
+
+impl Renderer {
+    pub fn parse(name: &str) -> Option<Renderer> {
+        match name {
+            "blocks" => Some(Renderer::Blocks),
+            "braille" => Some(Renderer::Braille),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> Renderer {
+        match self {
+            Renderer::Blocks => Renderer::Braille,
+            Renderer::Braille => Renderer::Blocks,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueText {
+    Full,
+    Short,
+    Hidden,
+}
+
+/// What the bar values count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Count,
+    /// Hundredths of a percent, shown as `12.4%`.
+    PerTenThousand,
+}
+
+impl Unit {
+    fn full(self, value: u64) -> String {
+        match self {
+            Unit::Count => value.to_string() + saturated(value),
+            Unit::PerTenThousand => format!("{:.1}%", value as f64 / 100.0),
+        }
+    }
+
+    fn short(self, value: u64) -> String {
+        match self {
+            Unit::Count => format::compact(value) + saturated(value),
+            Unit::PerTenThousand => format!("{:.0}%", value as f64 / 100.0),
+        }
+    }
+}
+
+/// Marks a value that was clamped to fit a bar: the real count is larger.
+fn saturated(value: u64) -> &'static str {
+    if value == u64::MAX {
+        "+"
+    } else {
+        ""
+    }
+}
+
+/// Bar width and label decisions for one frame, computed in one place so the
+/// value and bucket labels agree with the bars they belong to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarLayout {
+    pub bar_width: u16,
+    pub bar_gap: u16,
+    pub values: ValueText,
+    pub unit: Unit,
+    /// Draw every nth bucket label.
+    pub label_every: usize,
+}
+
+impl BarLayout {
+    pub fn new(
+        width: u16,
+        data: &[(&str, u64)],
+        unit: Unit,
+        settings: &RenderSettings,
+    ) -> BarLayout {
+        let n = data.len().max(1) as u16;
+        // Bars too many to fit with gaps between them go without.
+        let gap = match width / (1 + settings.bar_gap) < n {
+            true => 0,
+            false => settings.bar_gap,
+        };
+        let bar_width = ((width + gap) / n)
+            .saturating_sub(gap)
+            .clamp(1, settings.max_bar_width);
+
+        let widest = |f: &dyn Fn(u64) -> String| {
+            data.iter()
+                .map(|(_, value)| f(*value).width() as u16)
+                .max()
+                .unwrap_or(0)
+        };
+        let values = if bar_width < settings.min_value_width {
+            ValueText::Hidden
+        } else if widest(&|v| unit.full(v)) <= bar_width {
+            ValueText::Full
+        } else if widest(&|v| unit.short(v)) <= bar_width {
+            ValueText::Short
+        } else {
+            ValueText::Hidden
+        };
+
+        let label_width = data
+            .iter()
+            .map(|(label, _)| label.width())
+            .max()
+            .unwrap_or(0);
+        let slot = (bar_width + gap) as usize;
+        let label_every = if label_width <= bar_width as usize {
+            1
+        } else {
+            [1, 2, 5, 10, 20, 50, 100, 200, 500]
+                .into_iter()
+                .find(|k| k * slot > label_width)
+                .unwrap_or(1000)
+        };
+
+        BarLayout {
+            bar_width,
+            bar_gap: gap,
+            values,
+            unit,
+            label_every,
+        }
+    }
+
+    fn value_text(&self, value: u64) -> Option<String> {
+        match self.values {
+            ValueText::Full => Some(self.unit.full(value)),
+            ValueText::Short => Some(self.unit.short(value)),
+            ValueText::Hidden => None,
+        }
+    }
+}
+
+/// How many buckets each bar has to stand for to fit `buckets` bars in
+/// `width` cells: 1 while there's a cell for each, otherwise the smallest
+/// power of two that's enough.
+pub fn display_factor(width: u16, buckets: usize) -> usize {
+    let mut factor = 1;
+    while buckets.div_ceil(factor) > (width as usize).max(1) {
+        factor *= 2;
+    }
+    factor
+}
+
+/// Every `factor` bars in turn merged into one as tall as their average,
+/// so the heights keep to the same scale, and labelled by the first.
+pub fn binned<'a>(data: &[(&'a str, u64)], factor: usize) -> Vec<(&'a str, u64)> {
+    data.chunks(factor.max(1))
+        .map(|bars| {
+            let sum = bars
+                .iter()
+                .map(|(_, value)| u128::from(*value))
+                .sum::<u128>();
+            (bars[0].0, (sum / bars.len() as u128) as u64)
+        })
+        .collect()
+}
+
+/// Every `factor` counts in turn summed, to go with `binned`.
+pub fn binned_sums(counts: &[u64], factor: usize) -> Vec<u64> {
+    counts
+        .chunks(factor.max(1))
+        .map(|counts| counts.iter().sum())
+        .collect()
+}
+
+pub struct Bars<'a> {
+    block: Option<Block<'a>>,
+    data: &'a [(&'a str, u64)],
+    layout: BarLayout,
+    renderer: Renderer,
+    bar_set: symbols::bar::Set,
+    max: Option<u64>,
+    bar_style: Style,
+    value_style: Style,
+    label_style: Style,
+    highlight: Option<(usize, Style)>,
+    styles: Option<&'a [Style]>,
+    reference: Option<(&'a [u64], Style)>,
+    spans: Option<&'a [u16]>,
+}
+
+impl<'a> Bars<'a> {
+    pub fn new(data: &'a [(&'a str, u64)], layout: BarLayout) -> Bars<'a> {
+        Bars {
+            block: None,
+            data,
+            layout,
+            renderer: Renderer::Blocks,
+            bar_set: symbols::bar::NINE_LEVELS,
+            max: None,
+            bar_style: Style::default(),
+            value_style: Style::default(),
+            label_style: Style::default(),
+            highlight: None,
+            styles: None,
+            reference: None,
+            spans: None,
+        }
+    }
+
+    pub fn block(mut self, block: Block<'a>) -> Bars<'a> {
+        self.block = Some(block);
+        self
+    }
+
+    pub fn renderer(mut self, renderer: Renderer) -> Bars<'a> {
+        self.renderer = renderer;
+        self
+    }
+
+    /// The characters for `Renderer::Blocks`, as for tui's `BarChart`.
+    pub fn bar_set(mut self, bar_set: symbols::bar::Set) -> Bars<'a> {
+        self.bar_set = bar_set;
+        self
+    }
+
+    pub fn max(mut self, max: u64) -> Bars<'a> {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn bar_style(mut self, style: Style) -> Bars<'a> {
+        self.bar_style = style;
+        self
+    }
+
+    pub fn value_style(mut self, style: Style) -> Bars<'a> {
+        self.value_style = style;
+        self
+    }
+
+    pub fn label_style(mut self, style: Style) -> Bars<'a> {
+        self.label_style = style;
+        self
+    }
+
+    /// Draws a second row of bars, one beside each bar, in `style`. Bars one
+    /// cell wide have no room for them.
+    pub fn reference(mut self, values: &'a [u64], style: Style) -> Bars<'a> {
+        self.reference = Some((values, style));
+        self
+    }
+
+    /// Makes bar `i` as wide as `spans[i]` bars of the layout, gaps included,
+    /// for bars that stand for several buckets. The layout should be worked
+    /// out for the total of the spans.
+    pub fn spans(mut self, spans: &'a [u16]) -> Bars<'a> {
+        self.spans = Some(spans);
+        self
+    }
+
+    /// Draws bar `i` in `styles[i]` over the bar style.
+    pub fn styles(mut self, styles: &'a [Style]) -> Bars<'a> {
+        self.styles = Some(styles);
+        self
+    }
+
+    /// Draws the bar at `index`, if any, in `style` instead of the bar style.
+    pub fn highlight(mut self, index: Option<usize>, style: Style) -> Bars<'a> {
+        self.highlight = index.map(|index| (index, style));
+        self
+    }
+}
+
+impl Widget for Bars<'_> {
+    fn render(mut self, area: Rect, buf: &mut Buffer) {
+        let area = match self.block.take() {
+            Some(block) => {
+                let inner = block.inner(area);
+                block.render(area, buf);
+                inner
+            }
+            None => area,
+        };
+
+        if area.height < 2 {
+            return;
+        }
+
+        let BarLayout {
+            bar_width,
+            bar_gap,
+            label_every,
+            ..
+        } = self.layout;
+        let slot = bar_width + bar_gap;
+        let slots = (area.width / slot) as usize;
+        // Each bar's first slot and its width in slots.
+        let mut start = 0;
+        let bars = (0..self.data.len())
+            .map(|i| {
+                let span = self.spans.and_then(|spans| spans.get(i)).copied();
+                let span = span.unwrap_or(1).max(1) as usize;
+                start += span;
+                (start - span, span)
+            })
+            .take_while(|(first, span)| first + span <= slots)
+            .collect::<Vec<_>>();
+        let count = bars.len();
+        let total_slots = start;
+        let max = self
+            .max
+            .unwrap_or_else(|| self.data.iter().map(|(_, v)| *v).max().unwrap_or(0))
+            .max(1);
+        let bars_area = Rect::new(area.x, area.y, area.width, area.height - 1);
+        let data = &self.data[..count];
+
+        // A reference takes the right half of each bar's width.
+        let reference = self.reference.filter(|_| bar_width >= 2);
+        let columns = |right: bool| {
+            bars.iter()
+                .map(|(first, span)| {
+                    let x = *first as u16 * slot;
+                    let width = *span as u16 * slot - bar_gap;
+                    match (reference, right) {
+                        (None, _) => (x, width),
+                        (Some(_), false) => (x, width - width / 2),
+                        (Some(_), true) => (x + width - width / 2, width / 2),
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let render = |buf: &mut Buffer, data: &[(&str, u64)], columns: &[(u16, u16)]| match self
+            .renderer
+        {
+            Renderer::Blocks => render_blocks(buf, bars_area, data, columns, max, &self.bar_set),
+            Renderer::Braille => render_braille(buf, bars_area, data, columns, max),
+        };
+        let bar_columns = columns(false);
+        render(buf, data, &bar_columns);
+        buf.set_style(bars_area, self.bar_style);
+        for (style, (x, width)) in self.styles.into_iter().flatten().zip(&bar_columns) {
+            let area = Rect::new(bars_area.x + x, bars_area.y, *width, bars_area.height);
+            buf.set_style(area, *style);
+        }
+        if let Some((values, style)) = reference {
+            let values = values
+                .iter()
+                .take(count)
+                .map(|value| ("", *value))
+                .collect::<Vec<_>>();
+            let reference_columns = columns(true);
+            render(buf, &values, &reference_columns);
+            for (x, width) in reference_columns.iter().take(values.len()) {
+                let area = Rect::new(bars_area.x + x, bars_area.y, *width, bars_area.height);
+                buf.set_style(area, style);
+            }
+        }
+        if let Some((index, style)) = self.highlight.filter(|(index, _)| *index < count) {
+            let (x, width) = bar_columns[index];
+            buf.set_style(
+                Rect::new(area.left() + x, bars_area.y, width, bars_area.height),
+                style,
+            );
+        }
+
+        for (i, (label, value)) in data.iter().enumerate() {
+            let (x, width) = bar_columns[i];
+            let x = area.left() + x;
+            let (first, span) = bars[i];
+
+            if *value != 0 {
+                if let Some(text) = self.layout.value_text(*value) {
+                    let text_width = text.width() as u16;
+                    if text_width <= width {
+                        buf.set_string(
+                            x + (width - text_width) / 2,
+                            area.bottom() - 2,
+                            text,
+                            self.value_style,
+                        );
+                    }
+                }
+            }
+
+            // A wide bar's label goes under it if it fits. Thinned labels are
+            // anchored on the middle slot so the center stays labelled.
+            if span > 1 {
+                if label.width() <= (span as u16 * slot - bar_gap) as usize {
+                    buf.set_string(x, area.bottom() - 1, label, self.label_style);
+                }
+            } else if first % label_every == (total_slots / 2) % label_every {
+                let room = (label_every as u16 * slot)
+                    .saturating_sub(bar_gap)
+                    .max(bar_width);
+                let room = room.min(area.right() - x);
+                buf.set_stringn(x, area.bottom() - 1, label, room as usize, self.label_style);
+            }
+        }
+    }
+}
+
+/// `value * steps / max`, without overflowing for counts near `u64::MAX`.
+fn scale(value: u64, steps: u64, max: u64) -> u64 {
+    (u128::from(value) * u128::from(steps) / u128::from(max)) as u64
+}
+
+/// Draws each value as a bar at the offset and width in `columns`.
+fn render_blocks(
+    buf: &mut Buffer,
+    area: Rect,
+    data: &[(&str, u64)],
+    columns: &[(u16, u16)],
+    max: u64,
+    set: &symbols::bar::Set,
+) {
+    for ((_, value), (x, bar_width)) in data.iter().zip(columns) {
+        let (x, bar_width) = (area.left() + x, *bar_width);
+        let mut eighths = scale(*value, u64::from(area.height) * 8, max);
+
+        for row in (0..area.height).rev() {
+            let symbol = match eighths {
+                0 => set.empty,
+                1 => set.one_eighth,
+                2 => set.one_quarter,
+                3 => set.three_eighths,
+                4 => set.half,
+                5 => set.five_eighths,
+                6 => set.three_quarters,
+                7 => set.seven_eighths,
+                _ => set.full,
+            };
+            for dx in 0..bar_width {
+                buf.get_mut(x + dx, area.top() + row).set_symbol(symbol);
+            }
+            eighths = eighths.saturating_sub(8);
+        }
+    }
+}
+
+/// Fills each bar with vertical Braille lines, two dot columns per cell.
+fn render_braille(
+    buf: &mut Buffer,
+    area: Rect,
+    data: &[(&str, u64)],
+    columns: &[(u16, u16)],
+    max: u64,
+) {
+    // Canvas units are dots, so every line lands on its own dot column (lining
+    // up with the labels below) and heights round the same way as block bars.
+    Canvas::default()
+        .marker(symbols::Marker::Braille)
+        .x_bounds([0.0, (area.width * 2).saturating_sub(1) as f64])
+        .y_bounds([0.0, (area.height * 4).saturating_sub(1) as f64])
+        .paint(|ctx| {
+            for ((_, value), (x, bar_width)) in data.iter().zip(columns) {
+                let dots = scale(*value, u64::from(area.height) * 4, max);
+                if dots == 0 {
+                    continue;
+                }
+                for dot in 0..bar_width * 2 {
+                    let x = (x * 2 + dot) as f64;
+                    ctx.draw(&Line {
+                        x1: x,
+                        y1: 0.0,
+                        x2: x,
+                        y2: (dots.min(u64::from(area.height) * 4) - 1) as f64,
+                        color: Color::Reset,
+                    });
+                }
+            }
+        })
+        .render(area, buf);
+}
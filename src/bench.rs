@@ -0,0 +1,193 @@
+//! `central-limit bench`: sampling throughput without the UI.
+
+use crate::{export::Format, json::Value};
+use central_limit::sampling;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    io::{self, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_SECONDS: u64 = 10;
+
+type Sampler = fn(&mut StdRng, usize, u32) -> Vec<i32>;
+
+/// Every way of drawing a tick's sums, each measured in turn.
+const IMPLEMENTATIONS: &[(&str, Sampler)] = &[
+    ("naive", sampling::generate_sums),
+    ("normal", sampling::approximate_sums),
+    ("binomial", binomial_sums),
+    ("threaded", threaded_sums),
+];
+
+/// Each sum from the count of its up steps, drawn as one Binomial(n, ½).
+fn binomial_sums(rng: &mut StdRng, n_samples: usize, n_steps: u32) -> Vec<i32> {
+    (0..n_samples)
+        .map(|_| 2 * sampling::binomial(rng, n_steps as u64, 0.5) as i32 - n_steps as i32)
+        .collect()
+}
+
+/// The naive walks, split evenly across the available threads. Each thread
+/// gets its own generator, seeded from `rng`.
+fn threaded_sums(rng: &mut StdRng, n_samples: usize, n_steps: u32) -> Vec<i32> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = n_samples.div_ceil(threads).max(1);
+    let seeds = (0..n_samples.div_ceil(chunk))
+        .map(|_| rng.gen::<u64>())
+        .collect::<Vec<_>>();
+    thread::scope(|scope| {
+        let handles = seeds
+            .iter()
+            .enumerate()
+            .map(|(i, &seed)| {
+                let samples = chunk.min(n_samples - i * chunk);
+                scope.spawn(move || {
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    sampling::generate_sums(&mut rng, samples, n_steps)
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    })
+}
+
+pub struct Config {
+    pub samples: usize,
+    pub steps: u32,
+    pub duration: Duration,
+    pub seed: u64,
+    pub format: Format,
+}
+
+struct Measurement {
+    name: &'static str,
+    ticks: usize,
+    samples_per_sec: f64,
+    steps_per_sec: f64,
+    /// Tick latency at the 50th, 90th and 99th percentiles.
+    latency: [Duration; 3],
+}
+
+pub fn run<W: Write>(out: &mut W, config: &Config) -> io::Result<()> {
+    let results = IMPLEMENTATIONS
+        .iter()
+        .map(|(name, sampler)| measure(name, *sampler, config))
+        .collect::<Vec<_>>();
+    match config.format {
+        Format::Text => write_table(out, config, &results),
+        Format::Csv => write_csv(out, &results),
+        Format::Json => writeln!(out, "{}", to_json(config, &results)),
+    }
+}
+
+/// Draws ticks until `config.duration` has passed, starting from the same
+/// seed for every implementation so their runs are comparable.
+fn measure(name: &'static str, sampler: Sampler, config: &Config) -> Measurement {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut latencies = vec![];
+    let started = Instant::now();
+    while started.elapsed() < config.duration {
+        let tick_start = Instant::now();
+        let sums = sampler(&mut rng, config.samples, config.steps);
+        latencies.push(tick_start.elapsed());
+        std::hint::black_box(sums);
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let samples = (latencies.len() * config.samples) as f64;
+    latencies.sort();
+    Measurement {
+        name,
+        ticks: latencies.len(),
+        samples_per_sec: samples / elapsed,
+        steps_per_sec: samples * config.steps as f64 / elapsed,
+        latency: [0.5, 0.9, 0.99].map(|p| percentile(&latencies, p)),
+    }
+}
+
+/// Nearest-rank percentile of sorted durations.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn write_table<W: Write>(out: &mut W, config: &Config, results: &[Measurement]) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} samples of {} steps per tick, {}s per implementation (seed {})",
+        config.samples,
+        config.steps,
+        config.duration.as_secs(),
+        config.seed
+    )?;
+    writeln!(
+        out,
+        "{:<10} {:>7} {:>14} {:>14} {:>10} {:>10} {:>10}",
+        "impl", "ticks", "samples/s", "steps/s", "p50", "p90", "p99"
+    )?;
+    for result in results {
+        let [p50, p90, p99] = result.latency.map(crate::format::duration);
+        writeln!(
+            out,
+            "{:<10} {:>7} {:>14.0} {:>14.0} {:>10} {:>10} {:>10}",
+            result.name, result.ticks, result.samples_per_sec, result.steps_per_sec, p50, p90, p99
+        )?;
+    }
+    out.flush()
+}
+
+fn write_csv<W: Write>(out: &mut W, results: &[Measurement]) -> io::Result<()> {
+    writeln!(
+        out,
+        "impl,ticks,samples_per_sec,steps_per_sec,p50_ms,p90_ms,p99_ms"
+    )?;
+    for result in results {
+        let [p50, p90, p99] = result.latency.map(|d| d.as_secs_f64() * 1000.0);
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            result.name, result.ticks, result.samples_per_sec, result.steps_per_sec, p50, p90, p99
+        )?;
+    }
+    out.flush()
+}
+
+fn to_json(config: &Config, results: &[Measurement]) -> Value {
+    let millis = |d: Duration| Value::from(d.as_secs_f64() * 1000.0);
+    Value::object(vec![
+        ("samples", config.samples.into()),
+        ("steps", (config.steps as u64).into()),
+        ("seconds", config.duration.as_secs().into()),
+        ("seed", config.seed.to_string().into()),
+        (
+            "results",
+            Value::Array(
+                results
+                    .iter()
+                    .map(|result| {
+                        Value::object(vec![
+                            ("impl", result.name.into()),
+                            ("ticks", result.ticks.into()),
+                            ("samples_per_sec", result.samples_per_sec.into()),
+                            ("steps_per_sec", result.steps_per_sec.into()),
+                            ("p50_ms", millis(result.latency[0])),
+                            ("p90_ms", millis(result.latency[1])),
+                            ("p99_ms", millis(result.latency[2])),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+    ])
+}
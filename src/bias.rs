@@ -0,0 +1,126 @@
+//! `--bias-sweep`: the coin's bias swept back and forth as the run goes, for
+//! watching the bell slide and narrow or widen as the mean and spread of the
+//! sums follow it. The chance of a +1 step eases from one end of the range
+//! to the other and back once a period, counted in ticks so a run can be
+//! repeated, and `a` holds it where it is for a while.
+//!
+//! Each tick is drawn at a single bias, and the histogram starts over every
+//! tick, so the model drawn over it is exactly that tick's. The buckets
+//! reach every sum, so the x-axis takes in the whole sweep.
+
+use std::f64::consts::TAU;
+
+/// Ticks for the bias to go there and back, unless `--bias-period` says.
+pub const DEFAULT_PERIOD: u64 = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sweep {
+    from: f64,
+    to: f64,
+    period: u64,
+    /// Ticks into the sweep of the one drawn last, not counting those held.
+    swept: u64,
+    started: bool,
+    held: bool,
+}
+
+impl Sweep {
+    /// A sweep from `from` to `to` and back every `period` ticks, starting
+    /// at `from`. Both ends are chances of a +1 step, above 0 and below 1.
+    pub fn new(from: f64, to: f64, period: u64) -> Result<Sweep, String> {
+        let mut sweep = Sweep {
+            from: 0.0,
+            to: 0.0,
+            period: 0,
+            swept: 0,
+            started: false,
+            held: false,
+        };
+        sweep.set_schedule(from, to, period)?;
+        Ok(sweep)
+    }
+
+    /// Reads a range such as `0.5:0.9`.
+    pub fn parse_range(text: &str) -> Result<(f64, f64), String> {
+        let invalid = || {
+            format!(
+                "invalid bias range '{}', expected FROM:TO such as 0.5:0.9",
+                text
+            )
+        };
+        let (from, to) = text.split_once(':').ok_or_else(invalid)?;
+        let from = from.trim().parse::<f64>().map_err(|_| invalid())?;
+        let to = to.trim().parse::<f64>().map_err(|_| invalid())?;
+        Ok((from, to))
+    }
+
+    /// Changes the range and period, carrying on from the same point in
+    /// the period, for a config file that changes them.
+    pub fn set_schedule(&mut self, from: f64, to: f64, period: u64) -> Result<(), String> {
+        for p in [from, to] {
+            if !(p > 0.0 && p < 1.0) {
+                return Err(format!("the bias must be between 0 and 1, not {}", p));
+            }
+        }
+        if period < 2 {
+            return Err("the bias sweep's period must be at least 2 ticks".to_string());
+        }
+        self.from = from;
+        self.to = to;
+        self.period = period;
+        Ok(())
+    }
+
+    pub fn from(&self) -> f64 {
+        self.from
+    }
+
+    pub fn to(&self) -> f64 {
+        self.to
+    }
+
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    /// The least and greatest bias the sweep reaches.
+    pub fn range(&self) -> (f64, f64) {
+        (self.from.min(self.to), self.from.max(self.to))
+    }
+
+    /// The bias of the tick drawn last, or of the first before there's been one.
+    pub fn bias(&self) -> f64 {
+        let phase = (self.swept % self.period) as f64 / self.period as f64;
+        self.from + (self.to - self.from) * (1.0 - (TAU * phase).cos()) / 2.0
+    }
+
+    /// Moves on to the bias of the next tick, or stays at this one while
+    /// held, and returns it.
+    pub fn next(&mut self) -> f64 {
+        if self.started && !self.held {
+            self.swept += 1;
+        }
+        self.started = true;
+        self.bias()
+    }
+
+    pub fn held(&self) -> bool {
+        self.held
+    }
+
+    pub fn toggle_hold(&mut self) {
+        self.held = !self.held;
+    }
+
+    /// Goes back to the start of the sweep, as for a new run.
+    pub fn restart(&mut self) {
+        self.swept = 0;
+        self.started = false;
+    }
+
+    /// The schedule as `--bias-sweep` and `--bias-period` give it, for the
+    /// exports' parameters.
+    pub fn describe(&self) -> String {
+        format!("{}:{} over {} ticks", self.from, self.to, self.period)
+    }
+}
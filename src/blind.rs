@@ -0,0 +1,53 @@
+//! `x`: a blind comparison for a class. The histogram and one drawn from the
+//! exact model with as many samples are shown as "A" and "B", in an order
+//! drawn at random, with nothing else on the chart to give them away; `x`
+//! again says which was the simulation, and how far apart the two are.
+//!
+//! Both are drawn from the presentation stream, so a blind comparison
+//! doesn't move the run's samples.
+
+use crate::presentation::PresentationRng;
+use central_limit::stats::{self, ChiSquare};
+
+#[derive(Debug)]
+pub struct Blind {
+    real: Vec<u64>,
+    synthetic: Vec<u64>,
+    real_is_a: bool,
+}
+
+impl Blind {
+    /// Blinds `real` against a draw of as many samples from `probabilities`,
+    /// one per bucket.
+    pub fn new(real: Vec<u64>, probabilities: &[f64], rng: &mut PresentationRng) -> Blind {
+        let total = stats::total(real.iter().copied());
+        Blind {
+            synthetic: rng.multinomial(total, probabilities),
+            real,
+            real_is_a: rng.coin(),
+        }
+    }
+
+    pub fn a(&self) -> &[u64] {
+        match self.real_is_a {
+            true => &self.real,
+            false => &self.synthetic,
+        }
+    }
+
+    pub fn b(&self) -> &[u64] {
+        match self.real_is_a {
+            true => &self.synthetic,
+            false => &self.real,
+        }
+    }
+
+    pub fn real_is_a(&self) -> bool {
+        self.real_is_a
+    }
+
+    /// The chi-square distance between the two.
+    pub fn distance(&self) -> ChiSquare {
+        stats::chi_square_between(&self.real, &self.synthetic)
+    }
+}
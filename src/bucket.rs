@@ -0,0 +1,483 @@
+//! One bar of the histogram.
+
+use crate::{
+    sampling::Regime,
+    stats::{self, Model},
+};
+
+/// A histogram bucket. The center is kept as a number so nothing has to
+/// parse the label back, which is free to be any text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    pub x: f64,
+    pub label: String,
+    pub count: u64,
+}
+
+impl Bucket {
+    pub fn new(x: i32, count: u64) -> Bucket {
+        Bucket {
+            x: x as f64,
+            label: x.to_string(),
+            count,
+        }
+    }
+}
+
+/// What each sample's steps are shown as: their sum, or their mean, which
+/// is the sum scaled down by the steps, so the counts are the same either
+/// way and only the labels change.
+///
+/// Or the highest, lowest or median position the walk passes through, from
+/// 0 before its first step to its sum after the last, which are counted
+/// instead of the sum. Of the ±1 steps themselves those would nearly always
+/// be -1 or +1, but the positions have a shape, and not a normal one: the
+/// central limit theorem is about sums.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Mean,
+    Max,
+    Min,
+    Median,
+}
+
+impl Aggregate {
+    pub fn parse(name: &str) -> Result<Aggregate, String> {
+        match name {
+            "sum" => Ok(Aggregate::Sum),
+            "mean" => Ok(Aggregate::Mean),
+            "max" => Ok(Aggregate::Max),
+            "min" => Ok(Aggregate::Min),
+            "median" => Ok(Aggregate::Median),
+            _ => Err(format!(
+                "unknown aggregate {:?}: use sum, mean, max, min or median",
+                name
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Aggregate::Sum => "sum",
+            Aggregate::Mean => "mean",
+            Aggregate::Max => "max",
+            Aggregate::Min => "min",
+            Aggregate::Median => "median",
+        }
+    }
+
+    /// Whether it's taken over the walk's positions rather than its sum, so
+    /// every walk has to be drawn step by step, and there's no model.
+    pub fn of_positions(self) -> bool {
+        matches!(self, Aggregate::Max | Aggregate::Min | Aggregate::Median)
+    }
+
+    /// The aggregate of a walk's positions, from 0 before its first step: its
+    /// sum is the last. Of an even number of positions, the median is the
+    /// lower of the middle two, so it's a whole number.
+    pub fn of(self, walk: &[i32]) -> i32 {
+        match self {
+            Aggregate::Sum | Aggregate::Mean => walk[walk.len() - 1],
+            Aggregate::Max => walk.iter().copied().max().unwrap_or(0),
+            Aggregate::Min => walk.iter().copied().min().unwrap_or(0),
+            Aggregate::Median => {
+                let mut sorted = walk.to_vec();
+                let middle = (sorted.len().max(1) - 1) / 2;
+                *sorted.select_nth_unstable(middle).1
+            }
+        }
+    }
+
+    /// The least and greatest it can be of a walk of `steps` steps.
+    pub fn support(self, steps: i32) -> (i32, i32) {
+        match self {
+            Aggregate::Sum | Aggregate::Mean => (-steps, steps),
+            Aggregate::Max => (0, steps),
+            Aggregate::Min => (-steps, 0),
+            // All of one sign, the lower middle position is halfway along.
+            Aggregate::Median => (-(steps + 1) / 2, steps / 2),
+        }
+    }
+
+    /// How far apart in sums two samples are whose aggregates are 1 apart.
+    /// Those of positions aren't sums, so are counted as they are.
+    pub fn unit(self, steps: u32) -> f64 {
+        match self {
+            Aggregate::Mean => f64::from(steps.max(1)),
+            _ => 1.0,
+        }
+    }
+
+    /// The aggregate of a sample of `steps` steps adding up to `sum`, with
+    /// just enough decimals to tell neighbouring buckets apart.
+    pub fn label(self, sum: f64, steps: u32) -> String {
+        let unit = self.unit(steps);
+        // Neighbouring buckets are 2 / unit apart.
+        let decimals = (unit / 2.0).log10().ceil().max(0.0) as usize;
+        // Adding 0.0 turns -0.0 into 0.0 so the middle isn't labelled "-0".
+        format!("{:.*}", decimals, sum / unit + 0.0)
+    }
+}
+
+/// Buckets at most across the sums of a walk drawn from the normal
+/// approximation, an odd number so one sits on the mean.
+pub const BINS: usize = 101;
+
+/// Standard deviations either side of the mean those buckets reach.
+pub const SIGMAS: f64 = 4.0;
+
+/// Where the buckets of a histogram of `steps`-step walks lie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// One bucket per reachable sum, two apart from `-steps`.
+    Sums { steps: i32 },
+    /// `count` buckets of `width / 2` neighbouring sums each, the first
+    /// starting at `start`. The end buckets also take every sum beyond them,
+    /// so no sample is lost.
+    Bins {
+        steps: i32,
+        start: i32,
+        width: i32,
+        count: usize,
+    },
+    /// One bucket per whole number from `start`, for an aggregate of the
+    /// positions, which can be any of them.
+    Values {
+        steps: i32,
+        start: i32,
+        count: usize,
+    },
+}
+
+impl Layout {
+    /// The buckets for `steps`-step walks that are each +1 with probability
+    /// `p`: one per sum for walks drawn exactly, and at most `BINS` across
+    /// `SIGMAS` either side of the mean for those drawn from the normal
+    /// approximation, which would otherwise need a bucket for each of
+    /// millions of sums.
+    pub fn for_walks(steps: i32, p: f64, regime: Regime) -> Layout {
+        let sums = Layout::Sums { steps };
+        if regime == Regime::Exact {
+            return sums;
+        }
+        let n = steps as u32;
+        let (mean, sd) = (stats::walk_mean(n, p), stats::walk_sd(n, p));
+        // Sums are two apart, so an even width holds the same number of
+        // them in every bin and the bars don't alias.
+        let width = 2 * (SIGMAS * sd / BINS as f64).ceil().max(1.0) as i32;
+        let half = (SIGMAS * sd / f64::from(width) - 0.5).ceil().max(1.0) as i32;
+        let count = 2 * half + 1;
+        if count * width / 2 > steps {
+            return sums;
+        }
+        // The middle bin is centered on the mean, and every bin starts on a
+        // reachable sum.
+        let first = mean - f64::from(half * width + width / 2 - 1);
+        let start = 2 * ((first + f64::from(steps)) / 2.0).round() as i32 - steps;
+        Layout::Bins {
+            steps,
+            start,
+            width,
+            count: count as usize,
+        }
+    }
+
+    /// The buckets for `aggregate` of `steps`-step walks, across its
+    /// support.
+    pub fn for_positions(aggregate: Aggregate, steps: i32) -> Layout {
+        let (low, high) = aggregate.support(steps);
+        Layout::Values {
+            steps,
+            start: low,
+            count: (high - low) as usize + 1,
+        }
+    }
+
+    pub fn steps(self) -> i32 {
+        match self {
+            Layout::Sums { steps } | Layout::Bins { steps, .. } | Layout::Values { steps, .. } => {
+                steps
+            }
+        }
+    }
+
+    /// Whether there's a model of the buckets' counts to compare them with,
+    /// which there isn't for an aggregate of positions.
+    pub fn modelled(self) -> bool {
+        !matches!(self, Layout::Values { .. })
+    }
+
+    /// How many buckets there are.
+    pub fn buckets(self) -> usize {
+        match self {
+            Layout::Sums { steps } => steps as usize + 1,
+            Layout::Bins { count, .. } | Layout::Values { count, .. } => count,
+        }
+    }
+
+    /// The bucket `sum`, or an aggregate's value, is counted in.
+    pub fn index(self, sum: i32) -> usize {
+        match self {
+            Layout::Sums { steps } => ((sum + steps) / 2) as usize,
+            Layout::Bins {
+                start,
+                width,
+                count,
+                ..
+            } => (sum - start).div_euclid(width).clamp(0, count as i32 - 1) as usize,
+            Layout::Values { start, count, .. } => {
+                (sum - start).clamp(0, count as i32 - 1) as usize
+            }
+        }
+    }
+
+    /// The sum in the middle of bucket `index`.
+    pub fn center(self, index: usize) -> i32 {
+        match self {
+            Layout::Sums { steps } => 2 * index as i32 - steps,
+            Layout::Bins { start, width, .. } => start + index as i32 * width + width / 2 - 1,
+            Layout::Values { start, .. } => start + index as i32,
+        }
+    }
+
+    /// How far apart neighbouring buckets' centers are.
+    pub fn spacing(self) -> f64 {
+        match self {
+            Layout::Sums { .. } => 2.0,
+            Layout::Bins { width, .. } => f64::from(width),
+            Layout::Values { .. } => 1.0,
+        }
+    }
+
+    /// Each bucket's probability under `model`, for steps that are +1 with
+    /// probability `p`, unless it's not `modelled`. Bins are only used where
+    /// the two models agree, so theirs come from the normal approximation
+    /// either way.
+    pub fn probabilities(self, model: Model, p: f64) -> Option<Vec<f64>> {
+        match self {
+            Layout::Sums { steps } => Some(model.biased_probabilities(steps as u32, p)),
+            Layout::Bins { count, .. } => Some((0..count).map(|i| self.bin(i, p)).collect()),
+            Layout::Values { .. } => None,
+        }
+    }
+
+    /// The normal approximation's share of bin `index`, which reaches one
+    /// either side of its end sums, or out to infinity at the ends.
+    fn bin(self, index: usize, p: f64) -> f64 {
+        let Layout::Bins {
+            steps,
+            start,
+            width,
+            count,
+        } = self
+        else {
+            return 0.0;
+        };
+        let n = steps as u32;
+        let (mean, sd) = (stats::walk_mean(n, p), stats::walk_sd(n, p));
+        let below = |i: usize| match i {
+            0 => 0.0,
+            i if i == count => 1.0,
+            i => stats::normal_cdf((f64::from(start + i as i32 * width - 1) - mean) / sd),
+        };
+        below(index + 1) - below(index)
+    }
+}
+
+/// One bucket of a histogram of walk sums, borrowed from its counts rather
+/// than copied out of them.
+#[derive(Debug, Clone, Copy)]
+pub struct View<'a> {
+    counts: &'a [u64],
+    index: usize,
+    layout: Layout,
+    /// The chance of each step being +1.
+    p: f64,
+    total: u64,
+}
+
+impl<'a> View<'a> {
+    /// The sum the bucket counts, or the middle of those it counts.
+    pub fn center(&self) -> f64 {
+        self.layout.center(self.index) as f64
+    }
+
+    pub fn label(&self) -> String {
+        self.layout.center(self.index).to_string()
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts[self.index]
+    }
+
+    /// The bucket's share of the samples, or 0 before there are any.
+    pub fn frequency(&self) -> f64 {
+        stats::share(self.count(), self.total)
+    }
+
+    /// The count the exact binomial distribution expects of this many
+    /// samples, or the normal approximation for a bin of several sums, unless
+    /// the layout isn't `modelled`.
+    pub fn expected(&self) -> Option<f64> {
+        let probability = match self.layout {
+            Layout::Sums { steps } => {
+                stats::biased_binomial_pmf(steps as u32, self.index as u32, self.p)
+            }
+            Layout::Bins { .. } => self.layout.bin(self.index, self.p),
+            Layout::Values { .. } => return None,
+        };
+        Some(probability * self.total as f64)
+    }
+}
+
+impl From<View<'_>> for Bucket {
+    fn from(view: View) -> Bucket {
+        Bucket::new(view.layout.center(view.index), view.count())
+    }
+}
+
+/// A view of each of `counts`, one per bucket of `layout`, of walks whose
+/// steps are each +1 with probability `p`.
+pub fn views(
+    counts: &[u64],
+    layout: Layout,
+    p: f64,
+) -> impl ExactSizeIterator<Item = View<'_>> + Clone {
+    let total = stats::total(counts.iter().copied());
+    (0..counts.len()).map(move |index| View {
+        counts,
+        index,
+        layout,
+        p,
+        total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AGGREGATES: [Aggregate; 5] = [
+        Aggregate::Sum,
+        Aggregate::Mean,
+        Aggregate::Max,
+        Aggregate::Min,
+        Aggregate::Median,
+    ];
+
+    #[test]
+    fn aggregates_by_name() {
+        for aggregate in AGGREGATES {
+            assert_eq!(Aggregate::parse(aggregate.name()), Ok(aggregate));
+        }
+        assert!(Aggregate::parse("mode").unwrap_err().contains("median"));
+    }
+
+    #[test]
+    fn positions_are_aggregated_within_their_support() {
+        let walk = [0, 1, 2, 1, 0, -1, -2, -3];
+        assert_eq!(Aggregate::Sum.of(&walk), -3);
+        assert_eq!(Aggregate::Max.of(&walk), 2);
+        assert_eq!(Aggregate::Min.of(&walk), -3);
+        // The lower of the middle two of -3, -2, -1, 0, 0, 1, 1, 2.
+        assert_eq!(Aggregate::Median.of(&walk), 0);
+        assert_eq!(Aggregate::Median.of(&walk[..5]), 1);
+
+        // The walks at the ends of the support reach its ends.
+        for steps in [1, 2, 7, 10] {
+            let up = (0..=steps).collect::<Vec<_>>();
+            let down = up.iter().map(|x| -x).collect::<Vec<_>>();
+            for aggregate in AGGREGATES {
+                let (low, high) = aggregate.support(steps);
+                let values = [aggregate.of(&up), aggregate.of(&down)];
+                assert_eq!(values.iter().max(), Some(&high), "{:?}", aggregate);
+                assert_eq!(values.iter().min(), Some(&low), "{:?}", aggregate);
+            }
+        }
+
+        let layout = Layout::for_positions(Aggregate::Max, 10);
+        assert!(!layout.modelled());
+        assert_eq!(layout.buckets(), 11);
+        assert_eq!((layout.center(0), layout.center(10)), (0, 10));
+        assert_eq!((layout.index(0), layout.index(7)), (0, 7));
+        assert_eq!(layout.spacing(), 1.0);
+        assert_eq!(layout.probabilities(Model::Binomial, 0.5), None);
+        let layout = Layout::for_positions(Aggregate::Median, 5);
+        assert_eq!((layout.center(0), layout.buckets()), (-3, 6));
+    }
+
+    #[test]
+    fn means_are_told_apart_by_their_labels() {
+        assert_eq!(Aggregate::Sum.label(-3.0, 19), "-3");
+        assert_eq!(Aggregate::Mean.label(0.0, 2), "0");
+        assert_eq!(Aggregate::Mean.label(-1.0, 1), "-1");
+        for steps in [1, 2, 19, 20, 199, 1_000] {
+            let labels = (0..=steps)
+                .map(|i| Aggregate::Mean.label(f64::from(2 * i) - f64::from(steps), steps))
+                .collect::<Vec<_>>();
+            let mut distinct = labels.clone();
+            distinct.dedup();
+            assert_eq!(distinct, labels, "{} steps", steps);
+            // The middle is 0, not -0.
+            if steps % 2 == 0 {
+                assert!(!labels[steps as usize / 2].starts_with('-'));
+            }
+        }
+        assert_eq!(Aggregate::Mean.label(-1.0, 19), "-0.1");
+    }
+
+    #[test]
+    fn exact_walks_have_a_bucket_per_sum() {
+        let layout = Layout::for_walks(10_000, 0.5, Regime::Exact);
+        assert_eq!(layout, Layout::Sums { steps: 10_000 });
+        assert_eq!(layout.buckets(), 10_001);
+        // Too short for bins to save anything.
+        let layout = Layout::for_walks(10, 0.5, Regime::Normal);
+        assert_eq!(layout, Layout::Sums { steps: 10 });
+    }
+
+    #[test]
+    fn long_walks_are_binned_across_their_middle() {
+        for steps in [1_000, 10_000, 1_000_000, 100_000_000] {
+            for p in [0.5, 0.3, 1e-6] {
+                let layout = Layout::for_walks(steps, p, Regime::Normal);
+                let Layout::Bins { width, count, .. } = layout else {
+                    panic!("{} steps at {}: {:?}", steps, p, layout);
+                };
+                let case = format!("{} steps at {}: {:?}", steps, p, layout);
+                assert!((3..=BINS).contains(&count) && count % 2 == 1, "{}", case);
+                assert_eq!(width % 2, 0, "{}", case);
+
+                let (mean, sd) = (
+                    stats::walk_mean(steps as u32, p),
+                    stats::walk_sd(steps as u32, p),
+                );
+                let middle = f64::from(layout.center(count / 2));
+                assert!((middle - mean).abs() <= f64::from(width) / 2.0, "{}", case);
+                let reach = f64::from(layout.center(count - 1)) - mean;
+                assert!(reach + f64::from(width) >= SIGMAS * sd, "{}", case);
+
+                // Every sum has a bin, those beyond the ends the end bins.
+                let (first, last) = (layout.center(0), layout.center(count - 1));
+                assert_eq!(layout.index(first - 2 * width), 0, "{}", case);
+                assert_eq!(layout.index(last + 2 * width), count - 1, "{}", case);
+                let sum = 2 * (mean / 2.0).round() as i32 + steps % 2;
+                let center = f64::from(layout.center(layout.index(sum)));
+                assert!(
+                    (center - f64::from(sum)).abs() < f64::from(width) / 2.0,
+                    "{}",
+                    case
+                );
+
+                let total = layout
+                    .probabilities(Model::Binomial, p)
+                    .unwrap()
+                    .iter()
+                    .sum::<f64>();
+                assert!((total - 1.0).abs() < 1e-9, "{}", case);
+            }
+        }
+    }
+}
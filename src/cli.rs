@@ -0,0 +1,612 @@
+//! Command line arguments.
+
+use crate::{
+    alert::Condition, bars::Renderer, bias::Sweep, export::Format, i18n, overlay::Source, raw,
+    schedule::StartAt, selftest, theme::Palette,
+};
+use central_limit::{bucket::Aggregate, rng::Generator};
+use std::{path::PathBuf, time::Duration};
+
+pub const USAGE: &str = "Usage: central-limit [OPTIONS]
+       central-limit replay <FILE>
+       central-limit replay-events <FILE>
+       central-limit attach <SOCKET>
+       central-limit bench [--samples <N>] [--steps <N>] [--seconds <N>] [--format <FORMAT>]
+       central-limit sweep [--steps <N,N,...>] [--samples <N>] [--threads <N>] [--rng <NAME>] [--format <FORMAT>]
+       central-limit selftest [--samples <N>] [--steps <N>] [--rng <NAME>] [--only <CHECKS>]
+       central-limit diff <A> <B> [--alpha <P>] [--format <FORMAT>]
+
+Commands:
+  replay <FILE>       Play back a session recorded with --stream
+  replay-events <FILE>
+                      Run a session logged with --event-log again, key for key
+  attach <SOCKET>     Follow a run served with --socket, view only
+  bench               Measure sampling throughput without the UI
+  sweep               Tabulate KS distance, skewness and kurtosis against the steps
+  selftest            Check the sampler and statistics against theory
+  diff <A> <B>        Compare two exports, exiting with 1 if they differ
+
+Options:
+  --headless          Print the histogram as text instead of starting the TUI
+  --ticks <N>         Number of ticks to run headless (default: 10)
+  --force-tui         Start the TUI even when stdout or stdin is not a terminal
+  --no-altscreen      Draw the TUI in rows at the bottom of the terminal instead of
+                      the alternate screen, leaving the shell's output above it
+  --accessible        Describe the histogram in text instead of drawing charts
+  --layout <A:B>      Relative heights of the bar chart and line chart (default: 1:1)
+  --minimal           Show only the chart, for presenting (toggle with F)
+  --theme <NAME>      Color theme: classic, colorblind or mono
+  --no-color          Draw without colors, as the mono theme does; NO_COLOR does
+                      the same unless --theme is given
+  --renderer <NAME>   Bar renderer: blocks (default) or braille
+  --ascii             Draw the TUI in plain ASCII, as is done when the locale
+                      isn't UTF-8
+  --lang <CODE>       Language of the TUI's text: en (default) or de
+  --cumulative        Add each tick's samples to the histogram instead of replacing it
+  --decay <LAMBDA>    Fade the counts so far by LAMBDA, such as 0.98, before adding
+                      each tick's, for a histogram of recent ticks (adjust with [ ])
+  --bias-sweep <FROM:TO>
+                      Sweep the chance of a +1 step from FROM to TO and back, such
+                      as 0.5:0.9, drawing each tick at one bias (hold with a)
+  --bias-period <N>   Ticks for the --bias-sweep to go there and back (default: 40)
+  --warm-start <FILE> Continue counting, cumulatively, from a histogram.json export;
+                      only the counts carry over, not the random sequence
+  --reference <FILE>  Draw a histogram.json export behind the live histogram, by
+                      frequency, re-binned if its buckets differ
+  --timeline <N>      Ticks kept to step back through while paused (default: 240,
+                      fewer if they'd take more than 64 MiB)
+  --merge-tails <K>   Merge the bars more than K standard deviations out into
+                      bars four buckets wide (toggle with m; K defaults to 3)
+  --aggregate <NAME>  Show each sample's steps as their sum (default) or mean,
+                      or the max, min or median of the walk's positions
+  --clip-percentile <P>
+                      Chart only the sums between the P and 100-P percentiles,
+                      such as 0.5, with the rest in a bar at either end (0, the
+                      default, shows every bucket)
+  --no-intro          Start straight away, without first sweeping the steps up
+                      from 1 to --steps
+  --start-at <TIME>   Open the TUI with a countdown and start the run at TIME, local
+                      HH:MM or RFC 3339 such as 2024-06-01T14:05:00+02:00 (press
+                      s to start early)
+  --no-animate        Jump straight to each tick's counts instead of animating
+  --target <N>        Show progress toward N samples in total
+  --notify-when <CONDITION>
+                      Ring the bell when a statistic first crosses a threshold, e.g. ks<0.01
+  --until <CONDITION> Count cumulatively until a condition such as ks<0.005 holds
+                      for a few ticks in a row, then pause (headless: stop)
+  --until-ticks <K>   Ticks in a row the --until condition has to hold (default: 3)
+  --until-exit        Quit once the --until condition is met, writing the
+                      --output-dir exports
+  --precision <PERCENT>
+                      Show how many buckets' shares have a relative standard error
+                      within PERCENT, such as 1 (the default for buckets_converged)
+  --fast-forward <N>  Start with N samples, such as 1e8, drawn at once from the
+                      binomial model, counting cumulatively on from them; f adds
+                      as many again
+  --samples <N>       Samples drawn per tick (default: 5000)
+  --steps <N>         Steps in each sample's random walk (default: 19); a
+                      comma-separated list for sweep (default: 1,2,5,10,20,50,100)
+  --exact-below <N>   Draw walks of N steps or more from the normal approximation
+                      instead of step by step, and chart them within ±4σ
+                      (default: 1000)
+  --seed <N>          Seed for the random number generator
+  --seeds <N>         Draw N runs from seeds derived from --seed and show each
+                      bucket's spread across them (cycle the runs with r)
+  --overlay <LIST>    Walk up to three step sources alongside the run, coin, dieN
+                      such as die6, or uniform01, and chart their z-scores on one
+                      chart in place of the histogram
+  --rng <NAME>        Random number generator: std (default), pcg32, xoshiro256++,
+                      or bad-lcg, which is flawed on purpose to show what that does
+  --startup-check     Test the generator with 100k steps before starting, and warn
+                      if it fails
+  --watch-config <FILE>
+                      Apply a JSON config (samples, r_max, tick_ms, cumulative,
+                      theme) and re-apply it whenever it changes; only a change
+                      of r_max starts the counts over
+  --raw-out <FILE>    Write every sum as it's drawn to FILE, or stdout for -
+                      (headless only), before it's counted
+  --raw-format <FORMAT>
+                      Raw sums as binary, little-endian i32s (default), or text,
+                      one per line
+  --raw-limit <N>     Stop writing raw sums after N of them
+  --status-file <FILE>
+                      Keep a small JSON summary of the run in FILE, for scripts
+                      to watch; it's removed when the run ends
+  --status-every <N>  Ticks between status file updates (default: 10)
+  --export-gif <FILE> Save the histogram as an animated GIF (needs a build with
+                      --features image-export)
+  --gif-every <N>     Ticks between GIF frames (default: 5)
+  --gif-delay <MS>    Time each GIF frame is shown (default: 100)
+  --gif-size <WxH>    GIF size in pixels (default: 640x360)
+  --gif-max-frames <N>
+                      Stop adding GIF frames after N of them (default: 1000)
+  --sonify            Click every tick, higher the further from normal (needs a
+                      build with --features audio)
+  --volume <0-100>    Loudness of the --sonify clicks (default: 50)
+  --output-dir <DIR>  Save the config, exports and stream in a new directory under DIR
+  --daemon            Run headless until SIGTERM or Ctrl+C, exporting into
+                      --output-dir every so often and logging progress to
+                      --log-file (default: daemon.log in the run's directory)
+  --export-every <DURATION>
+                      Time between daemon exports, e.g. 90s, 10m or 6h (default: 10m)
+  --keep-exports <N>  Daemon exports kept before the oldest are removed; 0 keeps
+                      them all (default: 288)
+  --cycles <N>        Run N cycles of --ticks-per-cycle ticks, each from a fresh seed
+                      derived from --seed, exporting each into --output-dir and
+                      summarizing their KS distances at the end
+  --ticks-per-cycle <N>
+                      Ticks in each of the --cycles
+  --resume            Warm-start from the newest export under --output-dir
+  --autosave <DURATION>
+                      Save the cumulative counts to autosave.clt every DURATION,
+                      e.g. 5m, and offer to carry on from it on the next start
+  --auto-resume       Carry on from the --autosave without asking
+  --stream <FILE>     Write a JSON-lines event for every tick to FILE
+  --event-log <FILE>  Log every key pressed, what it changed, and every mode change,
+                      export and error as JSON lines in FILE, for replay-events
+  --socket <PATH>     Serve the tick events on a Unix domain socket at PATH
+  --seconds <N>       How long bench runs each implementation (default: 10)
+  --threads <N>       Step counts sweep runs in parallel (default: all cores)
+  --format <FORMAT>   Bench, sweep and diff output: text (default), csv or json
+  --alpha <P>         Significance level below which diff calls two exports
+                      different (default: 0.05)
+  --only <CHECKS>     Comma-separated selftest checks: binomial, symmetry, balance,
+                      walks, stats
+  --max-cpu <PERCENT> Sleep between sampling chunks to keep to about PERCENT of a
+                      core (5 to 100; 100, the default, doesn't limit it)
+  --max-memory <SIZE> Keep the run's growing buffers within SIZE, such as 256MB, by
+                      dropping the oldest ticks kept for stepping back through
+  --debug-hud         Show tick and frame timings (toggle with F2)
+  --log-file <FILE>   Append log messages to FILE
+  --verbose           Log debug messages too, such as the terminal checks made
+                      before the TUI starts
+  --print-config      Print the run's parameters and where each came from, then exit
+  --smoke-test        Check that this build draws and counts samples as it should,
+                      with a small seeded run and the selftest checks, then exit
+  -h, --help          Print this help";
+
+#[derive(Debug, Default)]
+pub enum Command {
+    #[default]
+    Run,
+    Replay(PathBuf),
+    ReplayEvents(PathBuf),
+    Attach(PathBuf),
+    Bench,
+    Sweep,
+    Selftest,
+    Diff(PathBuf, PathBuf),
+}
+
+impl Command {
+    /// Whether the ticks shown were drawn somewhere else: recorded, or
+    /// followed over a socket.
+    pub fn is_playback(&self) -> bool {
+        matches!(self, Command::Replay(_) | Command::Attach(_))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Args {
+    pub command: Command,
+    pub headless: bool,
+    pub ticks: Option<u64>,
+    pub force_tui: bool,
+    pub no_altscreen: bool,
+    pub accessible: bool,
+    pub minimal: bool,
+    pub layout: Option<(u32, u32)>,
+    pub theme: Option<Palette>,
+    pub no_color: bool,
+    pub renderer: Option<Renderer>,
+    pub ascii: bool,
+    pub lang: Option<String>,
+    pub cumulative: bool,
+    pub decay: Option<f64>,
+    pub bias_sweep: Option<(f64, f64)>,
+    pub bias_period: Option<u64>,
+    pub warm_start: Option<PathBuf>,
+    pub reference: Option<PathBuf>,
+    pub timeline: Option<usize>,
+    pub merge_tails: Option<f64>,
+    pub aggregate: Option<Aggregate>,
+    pub clip_percentile: Option<f64>,
+    pub no_animate: bool,
+    pub no_intro: bool,
+    pub start_at: Option<StartAt>,
+    pub target: Option<u64>,
+    pub notify_when: Option<Condition>,
+    pub until: Option<Condition>,
+    pub precision: Option<f64>,
+    pub fast_forward: Option<u64>,
+    pub until_ticks: Option<u64>,
+    pub until_exit: bool,
+    pub samples: Option<usize>,
+    pub steps: Option<i32>,
+    pub exact_below: Option<u32>,
+    /// `--steps` as given to sweep.
+    pub sweep_steps: Vec<i32>,
+    pub threads: Option<usize>,
+    pub seed: Option<u64>,
+    pub seeds: Option<usize>,
+    pub overlay: Option<Vec<Source>>,
+    pub rng: Option<Generator>,
+    pub startup_check: bool,
+    pub watch_config: Option<PathBuf>,
+    pub raw_out: Option<PathBuf>,
+    pub raw_format: Option<raw::Format>,
+    pub raw_limit: Option<u64>,
+    pub status_file: Option<PathBuf>,
+    pub status_every: Option<u64>,
+    pub export_gif: Option<PathBuf>,
+    pub gif_every: Option<u64>,
+    pub gif_delay: Option<Duration>,
+    pub gif_size: Option<(u16, u16)>,
+    pub gif_max_frames: Option<u64>,
+    pub sonify: bool,
+    pub volume: Option<u8>,
+    pub output_dir: Option<PathBuf>,
+    pub daemon: bool,
+    pub export_every: Option<Duration>,
+    pub keep_exports: Option<usize>,
+    pub cycles: Option<u64>,
+    pub ticks_per_cycle: Option<u64>,
+    pub resume: bool,
+    pub autosave: Option<Duration>,
+    pub auto_resume: bool,
+    pub stream: Option<PathBuf>,
+    pub event_log: Option<PathBuf>,
+    pub socket: Option<PathBuf>,
+    pub seconds: Option<u64>,
+    pub format: Option<Format>,
+    pub alpha: Option<f64>,
+    pub only: Vec<String>,
+    pub max_cpu: Option<u32>,
+    pub max_memory: Option<usize>,
+    pub debug_hud: bool,
+    pub log_file: Option<PathBuf>,
+    pub verbose: bool,
+    pub print_config: bool,
+    pub smoke_test: bool,
+    pub help: bool,
+}
+
+impl Args {
+    pub fn parse() -> Result<Args, String> {
+        Args::parse_from(std::env::args().skip(1))
+    }
+
+    /// The run parameters given on the command line, by their names in the
+    /// exports.
+    pub fn params(&self) -> Vec<&'static str> {
+        let given = [
+            ("samples", self.samples.is_some()),
+            ("r_max", self.steps.is_some()),
+            ("exact_below", self.exact_below.is_some()),
+            ("seed", self.seed.is_some()),
+            ("rng", self.rng.is_some()),
+            (
+                "mode",
+                self.cumulative
+                    || self.decay.is_some()
+                    || self.warm_start.is_some()
+                    || self.resume
+                    || self.autosave.is_some()
+                    || self.until.is_some()
+                    || self.fast_forward.is_some()
+                    || self.daemon,
+            ),
+            ("lambda", self.decay.is_some()),
+            ("bias", self.bias_sweep.is_some()),
+            ("theme", self.theme.is_some() || self.no_color),
+        ];
+        given
+            .into_iter()
+            .filter(|(_, given)| *given)
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    pub fn parse_from<I: IntoIterator<Item = String>>(args: I) -> Result<Args, String> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter().peekable();
+
+        if args.next_if(|arg| arg == "replay").is_some() {
+            let path = args.next().ok_or("replay requires a file")?;
+            parsed.command = Command::Replay(path.into());
+        } else if args.next_if(|arg| arg == "replay-events").is_some() {
+            let path = args.next().ok_or("replay-events requires an event log")?;
+            parsed.command = Command::ReplayEvents(path.into());
+        } else if args.next_if(|arg| arg == "attach").is_some() {
+            let path = args.next().ok_or("attach requires a socket")?;
+            parsed.command = Command::Attach(path.into());
+        } else if args.next_if(|arg| arg == "bench").is_some() {
+            parsed.command = Command::Bench;
+        } else if args.next_if(|arg| arg == "sweep").is_some() {
+            parsed.command = Command::Sweep;
+        } else if args.next_if(|arg| arg == "selftest").is_some() {
+            parsed.command = Command::Selftest;
+        } else if args.next_if(|arg| arg == "diff").is_some() {
+            let a = args.next().ok_or("diff requires two exports")?;
+            let b = args.next().ok_or("diff requires two exports")?;
+            parsed.command = Command::Diff(a.into(), b.into());
+        }
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("{} requires a value", name))
+            };
+
+            match arg.as_str() {
+                "--headless" => parsed.headless = true,
+                "--ticks" => parsed.ticks = Some(parse_number(&arg, &value(&arg)?)?),
+                "--force-tui" => parsed.force_tui = true,
+                "--no-altscreen" => parsed.no_altscreen = true,
+                "--accessible" => parsed.accessible = true,
+                "--minimal" => parsed.minimal = true,
+                "--layout" => parsed.layout = Some(parse_ratio(&arg, &value(&arg)?)?),
+                "--theme" => {
+                    let name = value(&arg)?;
+                    parsed.theme =
+                        Some(Palette::parse(&name).ok_or(format!("unknown theme '{}'", name))?);
+                }
+                "--no-color" => parsed.no_color = true,
+                "--ascii" => parsed.ascii = true,
+                "--renderer" => {
+                    let name = value(&arg)?;
+                    parsed.renderer =
+                        Some(Renderer::parse(&name).ok_or(format!("unknown renderer '{}'", name))?);
+                }
+                "--lang" => {
+                    let code = value(&arg)?;
+                    if !i18n::is_language(&code) {
+                        return Err(format!(
+                            "unknown language '{}' (available: {})",
+                            code,
+                            i18n::languages()
+                        ));
+                    }
+                    parsed.lang = Some(code);
+                }
+                "--cumulative" => parsed.cumulative = true,
+                "--decay" => {
+                    let text = value(&arg)?;
+                    let lambda: f64 = parse_number(&arg, &text)?;
+                    if !(0.0..=1.0).contains(&lambda) {
+                        return Err(format!(
+                            "invalid value '{}' for {}, expected 0 to 1",
+                            text, arg
+                        ));
+                    }
+                    parsed.decay = Some(lambda);
+                }
+                "--bias-sweep" => parsed.bias_sweep = Some(Sweep::parse_range(&value(&arg)?)?),
+                "--bias-period" => parsed.bias_period = Some(parse_number(&arg, &value(&arg)?)?),
+                "--warm-start" => parsed.warm_start = Some(value(&arg)?.into()),
+                "--merge-tails" => {
+                    let text = value(&arg)?;
+                    let k: f64 = parse_number(&arg, &text)?;
+                    if !k.is_finite() || k < 0.0 {
+                        return Err(format!("invalid value '{}' for {}", text, arg));
+                    }
+                    parsed.merge_tails = Some(k);
+                }
+                "--aggregate" => parsed.aggregate = Some(Aggregate::parse(&value(&arg)?)?),
+                "--clip-percentile" => {
+                    let text = value(&arg)?;
+                    let p: f64 = parse_number(&arg, &text)?;
+                    if !(0.0..50.0).contains(&p) {
+                        return Err(format!(
+                            "invalid value '{}' for {}, expected 0 up to but not including 50",
+                            text, arg
+                        ));
+                    }
+                    parsed.clip_percentile = Some(p);
+                }
+                "--no-animate" => parsed.no_animate = true,
+                "--no-intro" => parsed.no_intro = true,
+                "--start-at" => parsed.start_at = Some(StartAt::parse(&value(&arg)?)?),
+                "--reference" => parsed.reference = Some(value(&arg)?.into()),
+                "--timeline" => parsed.timeline = Some(parse_number(&arg, &value(&arg)?)?),
+                "--target" => parsed.target = Some(parse_number(&arg, &value(&arg)?)?),
+                "--notify-when" => parsed.notify_when = Some(Condition::parse(&value(&arg)?)?),
+                "--until" => parsed.until = Some(Condition::parse(&value(&arg)?)?),
+                "--fast-forward" => {
+                    let text = value(&arg)?;
+                    let n: f64 = parse_number(&arg, &text)?;
+                    if n.fract() != 0.0 || !(1.0..=u64::MAX as f64).contains(&n) {
+                        return Err(format!(
+                            "invalid value '{}' for {}, expected a whole number of samples such as 1e8",
+                            text, arg
+                        ));
+                    }
+                    parsed.fast_forward = Some(n as u64);
+                }
+                "--until-ticks" => parsed.until_ticks = Some(parse_number(&arg, &value(&arg)?)?),
+                "--until-exit" => parsed.until_exit = true,
+                "--precision" => {
+                    let text = value(&arg)?;
+                    let percent: f64 = parse_number(&arg, &text)?;
+                    if !(percent > 0.0 && percent < 100.0) {
+                        return Err(format!(
+                            "invalid value '{}' for {}, expected a percentage above 0 and below 100",
+                            text, arg
+                        ));
+                    }
+                    parsed.precision = Some(percent);
+                }
+                "--samples" => parsed.samples = Some(parse_number(&arg, &value(&arg)?)?),
+                "--steps" if matches!(parsed.command, Command::Sweep) => {
+                    for n in value(&arg)?.split(',') {
+                        parsed.sweep_steps.push(parse_number(&arg, n.trim())?);
+                    }
+                }
+                "--steps" => parsed.steps = Some(parse_number(&arg, &value(&arg)?)?),
+                "--exact-below" => parsed.exact_below = Some(parse_number(&arg, &value(&arg)?)?),
+                "--rng" => {
+                    let name = value(&arg)?;
+                    parsed.rng = Some(
+                        Generator::parse(&name)
+                            .ok_or(format!("unknown random number generator '{}'", name))?,
+                    );
+                }
+                "--startup-check" => parsed.startup_check = true,
+                "--threads" => parsed.threads = Some(parse_number(&arg, &value(&arg)?)?),
+                "--seed" => parsed.seed = Some(parse_number(&arg, &value(&arg)?)?),
+                "--seeds" => parsed.seeds = Some(parse_number(&arg, &value(&arg)?)?),
+                "--overlay" => parsed.overlay = Some(Source::parse_list(&value(&arg)?)?),
+                "--watch-config" => parsed.watch_config = Some(value(&arg)?.into()),
+                "--raw-out" => parsed.raw_out = Some(value(&arg)?.into()),
+                "--raw-format" => {
+                    let name = value(&arg)?;
+                    parsed.raw_format = Some(
+                        raw::Format::parse(&name)
+                            .ok_or(format!("unknown raw format '{}'", name))?,
+                    );
+                }
+                "--raw-limit" => parsed.raw_limit = Some(parse_number(&arg, &value(&arg)?)?),
+                "--status-file" => parsed.status_file = Some(value(&arg)?.into()),
+                "--status-every" => parsed.status_every = Some(parse_number(&arg, &value(&arg)?)?),
+                "--daemon" => parsed.daemon = true,
+                "--export-every" => {
+                    parsed.export_every = Some(parse_duration(&arg, &value(&arg)?)?)
+                }
+                "--keep-exports" => parsed.keep_exports = Some(parse_number(&arg, &value(&arg)?)?),
+                "--cycles" => parsed.cycles = Some(parse_number(&arg, &value(&arg)?)?),
+                "--ticks-per-cycle" => {
+                    parsed.ticks_per_cycle = Some(parse_number(&arg, &value(&arg)?)?)
+                }
+                "--resume" => parsed.resume = true,
+                "--autosave" => parsed.autosave = Some(parse_duration(&arg, &value(&arg)?)?),
+                "--auto-resume" => parsed.auto_resume = true,
+                "--export-gif" => parsed.export_gif = Some(value(&arg)?.into()),
+                "--gif-every" => parsed.gif_every = Some(parse_number(&arg, &value(&arg)?)?),
+                "--gif-delay" => {
+                    let ms = parse_number(&arg, &value(&arg)?)?;
+                    parsed.gif_delay = Some(Duration::from_millis(ms));
+                }
+                "--gif-size" => parsed.gif_size = Some(parse_size(&arg, &value(&arg)?)?),
+                "--gif-max-frames" => {
+                    parsed.gif_max_frames = Some(parse_number(&arg, &value(&arg)?)?)
+                }
+                "--sonify" => parsed.sonify = true,
+                "--volume" => {
+                    let volume = parse_number(&arg, &value(&arg)?)?;
+                    if volume > 100 {
+                        return Err(format!("--volume must be 0 to 100, not {}", volume));
+                    }
+                    parsed.volume = Some(volume);
+                }
+                "--output-dir" => parsed.output_dir = Some(value(&arg)?.into()),
+                "--stream" => parsed.stream = Some(value(&arg)?.into()),
+                "--event-log" => parsed.event_log = Some(value(&arg)?.into()),
+                "--socket" => parsed.socket = Some(value(&arg)?.into()),
+                "--alpha" => parsed.alpha = Some(parse_number(&arg, &value(&arg)?)?),
+                "--seconds" => parsed.seconds = Some(parse_number(&arg, &value(&arg)?)?),
+                "--format" => {
+                    let name = value(&arg)?;
+                    parsed.format =
+                        Some(Format::parse(&name).ok_or(format!("unknown format '{}'", name))?);
+                }
+                "--only" => {
+                    for name in value(&arg)?.split(',') {
+                        if !selftest::is_check(name) {
+                            return Err(format!("unknown check '{}'", name));
+                        }
+                        parsed.only.push(name.to_string());
+                    }
+                }
+                "--max-cpu" => parsed.max_cpu = Some(parse_number(&arg, &value(&arg)?)?),
+                "--max-memory" => parsed.max_memory = Some(parse_bytes(&arg, &value(&arg)?)?),
+                "--debug-hud" => parsed.debug_hud = true,
+                "--log-file" => parsed.log_file = Some(value(&arg)?.into()),
+                "--verbose" => parsed.verbose = true,
+                "--print-config" => parsed.print_config = true,
+                "--smoke-test" => parsed.smoke_test = true,
+                "-h" | "--help" => parsed.help = true,
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for {}", value, name))
+}
+
+/// Parses a positive number of seconds, minutes, hours or days, e.g. `10m`;
+/// a bare number is seconds.
+fn parse_duration(name: &str, value: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "invalid value '{}' for {}, expected e.g. 90s, 10m or 6h",
+            value, name
+        )
+    };
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    match number.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(Duration::from_secs(n.saturating_mul(seconds))),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses a positive amount of memory in bytes, KB, MB or GB, e.g. `256MB`;
+/// the units are binary, so `1KB` is 1024 bytes.
+fn parse_bytes(name: &str, value: &str) -> Result<usize, String> {
+    let invalid = || {
+        format!(
+            "invalid value '{}' for {}, expected e.g. 512KB, 256MB or 2GB",
+            value, name
+        )
+    };
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let shift = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 10,
+        "M" | "MB" | "MIB" => 20,
+        "G" | "GB" | "GIB" => 30,
+        _ => return Err(invalid()),
+    };
+    match number.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n.saturating_mul(1 << shift)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses `WxH` with both parts positive, e.g. `640x360`.
+fn parse_size(name: &str, value: &str) -> Result<(u16, u16), String> {
+    let invalid = || format!("invalid value '{}' for {}, expected WxH", value, name);
+    let (w, h) = value.split_once('x').ok_or_else(invalid)?;
+    match (w.parse(), h.parse()) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses `A:B` with both parts positive, e.g. `30:70`.
+fn parse_ratio(name: &str, value: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("invalid value '{}' for {}, expected A:B", value, name);
+    let (a, b) = value.split_once(':').ok_or_else(invalid)?;
+    match (a.parse(), b.parse()) {
+        (Ok(a), Ok(b)) if a > 0 && b > 0 => Ok((a, b)),
+        _ => Err(invalid()),
+    }
+}
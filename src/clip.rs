@@ -0,0 +1,112 @@
+//! `--clip-percentile`: showing the histogram between its `p` and `100 - p`
+//! percentiles, so a few far-out buckets can't squash the middle. What lies
+//! outside is summed into a bar at either end of the bar chart, and the line
+//! chart's x-axis is narrowed to match.
+//!
+//! The percentiles are worked out again each tick and eased towards, so the
+//! range doesn't jump about from one tick's noise to the next. Only the
+//! charts are clipped; the counts, the statistics and the exports aren't.
+
+use crate::{bucket::Bucket, merge::Bins, stats};
+use std::ops::Range;
+
+/// How far each tick moves the range towards the latest percentiles.
+const SMOOTHING: f64 = 0.3;
+
+#[derive(Debug)]
+pub struct Clip {
+    percentile: f64,
+    /// The eased percentiles, as sums.
+    bounds: Option<(f64, f64)>,
+    /// The buckets shown, and the bars they're drawn as, while some are
+    /// clipped.
+    keep: Option<Range<usize>>,
+    bins: Option<Bins>,
+    /// The share of the samples outside the range shown.
+    clipped: f64,
+}
+
+impl Clip {
+    /// Clipping at `percentile`, from above 0 to below 50.
+    pub fn new(percentile: f64) -> Clip {
+        Clip {
+            percentile,
+            bounds: None,
+            keep: None,
+            bins: None,
+            clipped: 0.0,
+        }
+    }
+
+    pub fn percentile(&self) -> f64 {
+        self.percentile
+    }
+
+    /// Moves the range towards the percentiles of `data`, after a tick.
+    pub fn update(&mut self, data: &[Bucket]) {
+        let buckets = data
+            .iter()
+            .map(|bucket| (bucket.x, bucket.count))
+            .collect::<Vec<_>>();
+        let p = self.percentile / 100.0;
+        let (Some(low), Some(high)) = (
+            stats::quantile(&buckets, p),
+            stats::quantile(&buckets, 1.0 - p),
+        ) else {
+            return self.clear();
+        };
+        let (low, high) = match self.bounds {
+            Some((l, h)) => (l + (low - l) * SMOOTHING, h + (high - h) * SMOOTHING),
+            None => (low, high),
+        };
+        self.bounds = Some((low, high));
+
+        // Each bucket reaches halfway to its neighbours, so the range always
+        // takes in at least one.
+        let reach = match data {
+            [first, second, ..] => (second.x - first.x) / 2.0,
+            _ => 1.0,
+        };
+        let start = data
+            .iter()
+            .position(|bucket| bucket.x + reach >= low)
+            .unwrap_or(0);
+        let end = data
+            .iter()
+            .rposition(|bucket| bucket.x - reach <= high)
+            .map_or(data.len(), |i| i + 1);
+        if start >= end || (start == 0 && end == data.len()) {
+            self.keep = None;
+            self.bins = None;
+            self.clipped = 0.0;
+            return;
+        }
+        let total = stats::total(data.iter().map(|bucket| bucket.count));
+        let kept = stats::total(data[start..end].iter().map(|bucket| bucket.count));
+        self.clipped = (total - kept) as f64 / total.max(1) as f64;
+        self.bins = Some(Bins::clipped(data, start..end));
+        self.keep = Some(start..end);
+    }
+
+    /// Forgets the range, for when the buckets change.
+    pub fn clear(&mut self) {
+        self.bounds = None;
+        self.keep = None;
+        self.bins = None;
+        self.clipped = 0.0;
+    }
+
+    /// The bars to draw, while some buckets are clipped.
+    pub fn bins(&self) -> Option<&Bins> {
+        self.bins.as_ref()
+    }
+
+    /// The buckets shown, while some are clipped.
+    pub fn keep(&self) -> Option<Range<usize>> {
+        self.keep.clone()
+    }
+
+    pub fn clipped(&self) -> f64 {
+        self.clipped
+    }
+}
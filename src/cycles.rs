@@ -0,0 +1,379 @@
+//! `--cycles` and `--ticks-per-cycle`: one run repeated from fresh seeds, for
+//! burn-in style experiments. Each cycle runs its ticks, is exported into
+//! the session directory as `exports/cycle-<n>`, and the run starts over
+//! from the next cycle's seed. Once the last is done, `cycles.json` gives
+//! each cycle's final KS distance, with their mean and spread. A run stopped
+//! part-way still exports the cycle it was in, marked incomplete.
+
+use crate::{events, interrupt, json::Value, session::Session, App};
+use central_limit::rng;
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+/// The `derive_seed` indices of the cycles after the first, clear of the
+/// `--seeds` runs and the sweep's step counts.
+const STREAM: u64 = 1 << 63;
+
+/// The seed of cycle `cycle`, counting from 1. The first is `seed` itself,
+/// so it draws what a run without `--cycles` would.
+pub fn seed(seed: u64, cycle: u64) -> u64 {
+    match cycle {
+        1 => seed,
+        _ => rng::derive_seed(seed, STREAM + cycle),
+    }
+}
+
+#[derive(Debug)]
+pub struct Cycles {
+    count: u64,
+    ticks: u64,
+    /// The `--seed` the cycles' seeds are derived from.
+    seed: u64,
+    /// The cycle running, counting from 1.
+    current: u64,
+    ended: Vec<Ended>,
+}
+
+/// A cycle that's been exported.
+#[derive(Debug)]
+struct Ended {
+    cycle: u64,
+    seed: u64,
+    tick: u64,
+    samples: u64,
+    ks: f64,
+}
+
+impl Cycles {
+    pub fn new(count: u64, ticks: u64, seed: u64) -> Cycles {
+        Cycles {
+            count,
+            ticks,
+            seed,
+            current: 1,
+            ended: vec![],
+        }
+    }
+
+    pub fn current(&self) -> u64 {
+        self.current
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Whether the cycle running has had all its ticks at `tick`.
+    pub fn due(&self, tick: u64) -> bool {
+        tick >= self.ticks
+    }
+
+    /// The name of the cycle running's exports, numbered to the same width
+    /// so they list in order.
+    fn name(&self) -> String {
+        let width = self.count.to_string().len().max(2);
+        format!("cycle-{:0width$}", self.current, width = width)
+    }
+
+    /// Which cycle an export is of, for the exports themselves.
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("cycle", self.current.into()),
+            ("of", self.count.into()),
+            ("ticks_per_cycle", self.ticks.into()),
+            ("base_seed", self.seed.to_string().into()),
+        ])
+    }
+
+    /// The cycles exported so far, and the mean and spread of the final KS
+    /// distances of those that ran all their ticks.
+    fn summary(&self) -> Value {
+        let runs = self
+            .ended
+            .iter()
+            .map(|ended| {
+                Value::object(vec![
+                    ("cycle", ended.cycle.into()),
+                    ("seed", ended.seed.to_string().into()),
+                    ("ticks", ended.tick.into()),
+                    ("samples", ended.samples.into()),
+                    ("complete", (ended.tick >= self.ticks).into()),
+                    ("ks", ended.ks.into()),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let spread = self.spread();
+        Value::object(vec![
+            ("cycles", self.count.into()),
+            ("ticks_per_cycle", self.ticks.into()),
+            ("seed", self.seed.to_string().into()),
+            (
+                "completed",
+                spread.as_ref().map_or(0, |spread| spread.count).into(),
+            ),
+            ("runs", runs.into()),
+            (
+                "ks",
+                spread.map_or(Value::Null, |spread| {
+                    Value::object(vec![
+                        ("mean", spread.mean.into()),
+                        ("sd", spread.sd.into()),
+                        ("min", spread.min.into()),
+                        ("max", spread.max.into()),
+                    ])
+                }),
+            ),
+        ])
+    }
+
+    /// The spread of the final KS distances of the cycles that ran all
+    /// their ticks, if any have; one cut short had fewer samples to settle.
+    fn spread(&self) -> Option<Spread> {
+        let ks = self
+            .ended
+            .iter()
+            .filter(|ended| ended.tick >= self.ticks)
+            .map(|ended| ended.ks)
+            .collect::<Vec<_>>();
+        let count = ks.len();
+        if count == 0 {
+            return None;
+        }
+        let mean = ks.iter().sum::<f64>() / count as f64;
+        let sd = match count {
+            1 => 0.0,
+            _ => (ks.iter().map(|ks| (ks - mean).powi(2)).sum::<f64>() / (count - 1) as f64).sqrt(),
+        };
+        Some(Spread {
+            count,
+            mean,
+            sd,
+            min: ks.iter().copied().fold(f64::INFINITY, f64::min),
+            max: ks.iter().copied().fold(0.0, f64::max),
+        })
+    }
+}
+
+struct Spread {
+    count: usize,
+    mean: f64,
+    /// The sample standard deviation.
+    sd: f64,
+    min: f64,
+    max: f64,
+}
+
+/// Exports the cycle `app` is in, and starts the next if there's one to go.
+/// Returns false once the last has been exported.
+pub fn end_cycle(app: &mut App, session: &Session) -> io::Result<bool> {
+    export(app, session)?;
+    let Some(cycles) = &mut app.cycles else {
+        return Ok(false);
+    };
+    if cycles.current >= cycles.count {
+        return Ok(false);
+    }
+    cycles.current += 1;
+    let seed = seed(cycles.seed, cycles.current);
+    log::info!(
+        "cycle {} of {}: seed {}",
+        cycles.current,
+        cycles.count,
+        seed
+    );
+    app.restart(seed);
+    Ok(true)
+}
+
+/// Exports the cycle `app` is in, unless it already has been or hasn't had
+/// a tick yet, and writes `cycles.json`, for when the run ends.
+pub fn finish(app: &mut App, session: &Session) -> io::Result<PathBuf> {
+    let Some(cycles) = &app.cycles else {
+        return Err(io::Error::other("not a --cycles run"));
+    };
+    let exported = cycles
+        .ended
+        .last()
+        .is_some_and(|ended| ended.cycle == cycles.current);
+    if !exported && app.tick > 0 {
+        export(app, session)?;
+    }
+    let path = session.path().join("cycles.json");
+    let summary = app.cycles.as_ref().map_or(Value::Null, Cycles::summary);
+    fs::write(&path, format!("{}\n", summary))?;
+    Ok(path)
+}
+
+fn export(app: &mut App, session: &Session) -> io::Result<()> {
+    let Some(name) = app.cycles.as_ref().map(Cycles::name) else {
+        return Ok(());
+    };
+    let [json, _] = session.write_snapshot(app, &name)?;
+    events::export(app, &json);
+    let ks = app.stats().ks;
+    let (seed, tick, samples) = (app.seed, app.tick, app.samples());
+    if let Some(cycles) = &mut app.cycles {
+        log::info!("exported {}: {} ticks, KS {:.5}", name, tick, ks);
+        cycles.ended.push(Ended {
+            cycle: cycles.current,
+            seed,
+            tick,
+            samples,
+            ks,
+        });
+    }
+    Ok(())
+}
+
+/// Runs every cycle headless, printing a line as each ends, and the spread
+/// of their KS distances after the last. Ctrl+C stops it between ticks,
+/// exporting the cycle it was in; returns false if it was stopped.
+pub fn run<W: Write>(out: &mut W, app: &mut App, session: &Session) -> io::Result<bool> {
+    interrupt::install();
+    while !interrupt::requested() {
+        app.on_tick();
+        if let Some(err) = app.take_feedback() {
+            return Err(io::Error::other(err));
+        }
+        if !app.cycle_over() {
+            continue;
+        }
+        if let Some(cycles) = &app.cycles {
+            writeln!(
+                out,
+                "cycle {}/{}: seed {}, {} ticks, KS {:.5}",
+                cycles.current,
+                cycles.count,
+                app.seed,
+                app.tick,
+                app.stats().ks
+            )?;
+        }
+        if !end_cycle(app, session)? {
+            break;
+        }
+    }
+    let interrupted = interrupt::requested();
+    if interrupted {
+        eprintln!(
+            "interrupted at tick {} of cycle {}",
+            app.tick,
+            app.cycles.as_ref().map_or(0, Cycles::current)
+        );
+    }
+    let path = finish(app, session)?;
+    if let Some(spread) = app.cycles.as_ref().and_then(Cycles::spread) {
+        writeln!(
+            out,
+            "KS over {} complete cycles: mean {:.5}, sd {:.5}, min {:.5}, max {:.5}",
+            spread.count, spread.mean, spread.sd, spread.min, spread.max
+        )?;
+    }
+    out.flush()?;
+    eprintln!("cycle summary written to {}", path.display());
+    Ok(!interrupted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+    use central_limit::rng::Generator;
+    use std::{collections::HashSet, env, process};
+
+    #[test]
+    fn each_cycle_has_its_own_seed() {
+        assert_eq!(seed(42, 1), 42);
+        let seeds = (1..=100)
+            .map(|cycle| seed(42, cycle))
+            .collect::<HashSet<_>>();
+        assert_eq!(seeds.len(), 100);
+        // None of them is one of the `--seeds` runs'.
+        assert!((1..100).all(|i| !seeds.contains(&rng::derive_seed(42, i))));
+        assert_eq!(seed(42, 3), seed(42, 3));
+        assert_ne!(seed(42, 3), seed(43, 3));
+    }
+
+    #[test]
+    fn a_cycle_draws_what_a_run_from_its_seed_would() {
+        let mut app = App::new(42, Generator::Std);
+        app.set_params(100, 10).unwrap();
+        app.on_tick();
+        app.restart(seed(42, 2));
+        app.on_tick();
+        let mut fresh = App::new(seed(42, 2), Generator::Std);
+        fresh.set_params(100, 10).unwrap();
+        fresh.on_tick();
+        assert_eq!(app.data, fresh.data);
+    }
+
+    #[test]
+    fn exports_are_numbered_to_the_same_width() {
+        let mut cycles = Cycles::new(3, 1, 42);
+        assert_eq!(cycles.name(), "cycle-01");
+        cycles.current = 3;
+        assert_eq!(cycles.name(), "cycle-03");
+        let mut cycles = Cycles::new(250, 1, 42);
+        assert_eq!(cycles.name(), "cycle-001");
+        cycles.current = 250;
+        assert_eq!(cycles.name(), "cycle-250");
+    }
+
+    #[test]
+    fn each_cycle_is_exported_into_the_session() {
+        let root = env::temp_dir().join(format!("central-limit-cycles-{}", process::id()));
+        let session = Session::create(&root, 42).unwrap();
+        let mut app = App::new(42, Generator::Std);
+        app.set_params(100, 10).unwrap();
+        app.cycles = Some(Cycles::new(3, 2, 42));
+        let mut out = vec![];
+        assert!(run(&mut out, &mut app, &session).unwrap());
+
+        let exports = session.path().join("exports");
+        let mut names = fs::read_dir(&exports)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "cycle-01.csv",
+                "cycle-01.json",
+                "cycle-02.csv",
+                "cycle-02.json",
+                "cycle-03.csv",
+                "cycle-03.json"
+            ]
+        );
+        for cycle in 1..=3 {
+            let path = exports.join(format!("cycle-{:02}.json", cycle));
+            let export = json::parse(&fs::read_to_string(path).unwrap()).unwrap();
+            let seed_of = export.get("seed").and_then(json::Value::as_str);
+            assert_eq!(seed_of, Some(seed(42, cycle).to_string().as_str()));
+            let of = export.get("cycle").and_then(|cycle| cycle.get("cycle"));
+            assert_eq!(of.and_then(json::Value::as_u64), Some(cycle));
+        }
+
+        let summary = fs::read_to_string(session.path().join("cycles.json")).unwrap();
+        let summary = json::parse(&summary).unwrap();
+        assert_eq!(
+            summary.get("completed").and_then(json::Value::as_u64),
+            Some(3)
+        );
+        let runs = summary.get("runs").and_then(json::Value::as_array).unwrap();
+        for (cycle, run) in (1..).zip(runs) {
+            let seed_of = run.get("seed").and_then(json::Value::as_str);
+            assert_eq!(seed_of, Some(seed(42, cycle).to_string().as_str()));
+            assert_eq!(run.get("samples").and_then(json::Value::as_u64), Some(200));
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
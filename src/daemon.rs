@@ -0,0 +1,131 @@
+//! `--daemon`: a headless run with no set end, for leaving going for days.
+//! The histogram is exported every so often into the session directory, the
+//! oldest exports are removed, and SIGTERM stops the run after a last export.
+//! Progress goes to the log rather than to stdout.
+
+use crate::{datetime::DateTime, interrupt, session::Session, App};
+use std::{
+    collections::VecDeque,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+pub const DEFAULT_EVERY: Duration = Duration::from_secs(10 * 60);
+
+/// Exports kept before the oldest are removed: two days' worth at the
+/// default interval.
+pub const DEFAULT_KEEP: usize = 288;
+
+pub struct Config {
+    pub every: Duration,
+    /// Exports kept, or 0 to keep them all.
+    pub keep: usize,
+    /// Ticks to stop after, if any.
+    pub ticks: Option<u64>,
+}
+
+pub fn run(app: &mut App, session: &Session, config: &Config) -> io::Result<()> {
+    interrupt::install_terminate();
+    log::info!(
+        "daemon started: {} samples per tick, {} steps, exporting every {}s to {}",
+        app.b_count,
+        app.r_max,
+        config.every.as_secs(),
+        session.path().display()
+    );
+    let mut kept = VecDeque::new();
+    let mut next = Instant::now() + config.every;
+    while !interrupt::requested()
+        && !app.until_met()
+        && config.ticks.is_none_or(|ticks| app.tick < ticks)
+    {
+        app.on_tick();
+        // A stream falling over isn't worth stopping a long run for.
+        if let Some(err) = app.take_feedback() {
+            log::warn!("{}", err);
+        }
+        if std::mem::take(&mut app.bell) {
+            if let Some(alert) = app.alert.as_ref().filter(|a| a.fired == Some(app.tick)) {
+                log::info!("{} at tick {}", alert.condition, app.tick);
+            }
+        }
+        if Instant::now() >= next {
+            export(app, session, &mut kept, config.keep);
+            next = Instant::now() + config.every;
+        }
+    }
+    match (interrupt::requested(), &app.until) {
+        (true, _) => log::info!("stopping on request after {} ticks", app.tick),
+        (false, Some(until)) if app.until_met() => log::info!(
+            "stopping after {} ticks: {} held for {}",
+            app.tick,
+            until.condition,
+            until.needed
+        ),
+        _ => log::info!("stopping after {} ticks", app.tick),
+    }
+    export(app, session, &mut kept, config.keep);
+    Ok(())
+}
+
+/// Writes an export named for the time and tick, then removes the oldest
+/// beyond `keep`. Failures are logged, and the run carries on.
+fn export(app: &App, session: &Session, kept: &mut VecDeque<[PathBuf; 2]>, keep: usize) {
+    let name = format!("{}_tick{}", DateTime::now().file_stamp(), app.tick);
+    match session.write_snapshot(app, &name) {
+        Ok(paths) => {
+            log::info!(
+                "exported {}: {} samples, KS {:.5}",
+                name,
+                app.samples(),
+                app.stats().ks
+            );
+            // The final export can land on the same name as the last one.
+            if kept.back() != Some(&paths) {
+                kept.push_back(paths);
+            }
+        }
+        Err(err) => log::error!("export {} failed: {}", name, err),
+    }
+    while keep > 0 && kept.len() > keep {
+        for path in kept.pop_front().into_iter().flatten() {
+            if let Err(err) = fs::remove_file(&path) {
+                log::warn!("could not remove {}: {}", path.display(), err);
+            }
+        }
+    }
+}
+
+/// The most recently written JSON export in any run under `root`, periodic
+/// or final, for `--resume`.
+pub fn newest_export(root: &Path) -> io::Result<Option<PathBuf>> {
+    let mut newest: Option<(SystemTime, PathBuf)> = None;
+    let mut consider = |path: PathBuf| {
+        let Ok(modified) = fs::metadata(&path).and_then(|meta| meta.modified()) else {
+            return;
+        };
+        if newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+            newest = Some((modified, path));
+        }
+    };
+    for run in fs::read_dir(root)? {
+        let run = run?;
+        // Not through `latest`, which is about to point at the new run.
+        if !run.file_type()?.is_dir() {
+            continue;
+        }
+        let run = run.path();
+        consider(run.join("histogram.json"));
+        let Ok(exports) = fs::read_dir(run.join("exports")) else {
+            continue;
+        };
+        for export in exports.flatten() {
+            let path = export.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                consider(path);
+            }
+        }
+    }
+    Ok(newest.map(|(_, path)| path))
+}
@@ -0,0 +1,120 @@
+//! Local wall-clock time, for naming files and showing timestamps.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl DateTime {
+    pub fn now() -> DateTime {
+        DateTime::at(SystemTime::now())
+    }
+
+    pub fn at(time: SystemTime) -> DateTime {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        DateTime::local(secs)
+    }
+
+    #[cfg(unix)]
+    pub fn local(secs: i64) -> DateTime {
+        let time = secs as libc::time_t;
+        let mut tm = unsafe { std::mem::zeroed::<libc::tm>() };
+        // SAFETY: both pointers are valid for the duration of the call.
+        if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+            return DateTime::utc(secs);
+        }
+        DateTime {
+            year: tm.tm_year + 1900,
+            month: tm.tm_mon as u32 + 1,
+            day: tm.tm_mday as u32,
+            hour: tm.tm_hour as u32,
+            minute: tm.tm_min as u32,
+            second: tm.tm_sec as u32,
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn local(secs: i64) -> DateTime {
+        DateTime::utc(secs)
+    }
+
+    pub fn utc(secs: i64) -> DateTime {
+        let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+        // Howard Hinnant's civil_from_days.
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+
+        DateTime {
+            year,
+            month,
+            day,
+            hour: (rem / 3600) as u32,
+            minute: (rem % 3600 / 60) as u32,
+            second: (rem % 60) as u32,
+        }
+    }
+
+    /// Seconds since the Unix epoch, reading the fields as UTC: the inverse
+    /// of `utc`.
+    pub fn unix_utc(&self) -> i64 {
+        // Howard Hinnant's days_from_civil.
+        let month = self.month as i64;
+        let year = self.year as i64 - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let doy =
+            (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146_097 + doe - 719_468;
+        days * 86_400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
+
+    /// `2024-06-01T13:05:22+02:00`: local time, with its offset from UTC.
+    pub fn rfc3339(time: SystemTime) -> String {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let local = DateTime::local(secs);
+        let offset = (local.unix_utc() - secs) / 60;
+        format!(
+            "{:04}-{:02}-{:02}T{}{}{:02}:{:02}",
+            local.year,
+            local.month,
+            local.day,
+            local.clock(),
+            if offset < 0 { '-' } else { '+' },
+            offset.abs() / 60,
+            offset.abs() % 60
+        )
+    }
+
+    /// `13:05:22`.
+    pub fn clock(&self) -> String {
+        format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+
+    /// `2024-06-01_13-05-22`, safe to use in file names.
+    pub fn file_stamp(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
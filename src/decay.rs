@@ -0,0 +1,71 @@
+//! `--decay`: a histogram of recent ticks, weighted to fade exponentially.
+//! Each tick scales the counts so far by λ before adding the new samples, so
+//! a tick `k` ticks old counts λᵏ as much as the latest. λ = 1 is cumulative
+//! counting and λ = 0 regenerates every tick; in between, the histogram keeps
+//! moving but much more smoothly than a single tick's.
+
+use central_limit::bucket::Bucket;
+
+/// How far `[` and `]` move λ.
+const STEP: f64 = 0.01;
+
+#[derive(Debug)]
+pub struct Decay {
+    lambda: f64,
+    /// Each bucket's decayed count.
+    weights: Vec<f64>,
+}
+
+impl Decay {
+    pub fn new(lambda: f64) -> Decay {
+        Decay {
+            lambda: lambda.clamp(0.0, 1.0),
+            weights: vec![],
+        }
+    }
+
+    pub fn lambda(&self) -> f64 {
+        self.lambda
+    }
+
+    /// Moves λ a step up or down, within 0 to 1.
+    pub fn adjust(&mut self, up: bool) {
+        let step = if up { STEP } else { -STEP };
+        // Rounded to the step, so repeated steps don't drift off it.
+        self.lambda = (((self.lambda + step) / STEP).round() * STEP).clamp(0.0, 1.0);
+    }
+
+    /// Fades the weights and adds a tick's counts. Buckets that differ from
+    /// the last tick's, after a change of steps, start the weights over.
+    pub fn add(&mut self, batch: &[Bucket]) {
+        if self.weights.len() != batch.len() {
+            self.weights = vec![0.0; batch.len()];
+        }
+        for (weight, bucket) in self.weights.iter_mut().zip(batch) {
+            *weight = *weight * self.lambda + bucket.count as f64;
+        }
+    }
+
+    /// Starts the weights from `counts`, for a switch from another mode that
+    /// keeps its counts.
+    pub fn start_from(&mut self, counts: &[u64]) {
+        self.weights = counts.iter().map(|count| *count as f64).collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.weights.clear();
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// The weights rounded to counts, for what needs whole ones: the bars
+    /// and the exports.
+    pub fn counts(&self) -> Vec<u64> {
+        self.weights
+            .iter()
+            .map(|weight| weight.round() as u64)
+            .collect()
+    }
+}
@@ -0,0 +1,431 @@
+//! `central-limit diff <A> <B>`: whether two exports are draws from one
+//! distribution, for checking that a change to the sampler left its output
+//! as it was. The report gives each bucket's share of the samples in both,
+//! with the difference in standard errors, the KS distance between them, a
+//! two-sample chi-square test, and the parameters that differ.
+//!
+//! The exit status is 0 if the chi-square test can't tell the two apart at
+//! `--alpha`, 1 if it can, and 2 if an export can't be read or has no
+//! samples.
+//!
+//! Exports of different walk lengths are compared over the sums either
+//! reaches. Sums of an odd and an even number of steps never coincide, so
+//! those are compared in buckets four wide, each holding two of either's.
+
+use crate::{
+    export::{self, Format},
+    json::{self, Value},
+};
+use central_limit::stats::{self, ChiSquare};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+pub const DEFAULT_ALPHA: f64 = 0.05;
+
+/// Buckets at least this many standard errors apart are marked in the text.
+const MARKED_Z: f64 = 3.0;
+
+/// The run's fields compared along with `meta.params`, which older exports
+/// don't have.
+const FIELDS: [&str; 8] = [
+    "tick", "samples", "r_max", "seed", "rng", "mode", "sampling", "bias",
+];
+
+pub struct Config {
+    pub a: PathBuf,
+    pub b: PathBuf,
+    pub alpha: f64,
+    pub format: Format,
+}
+
+/// An export as read back, with its buckets as the sums they count.
+struct Run {
+    path: PathBuf,
+    export: Value,
+    sums: Vec<i32>,
+    counts: Vec<u64>,
+}
+
+impl Run {
+    fn load(path: &Path) -> Result<Run, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let histogram = export::parse(&contents)?;
+        let sums = histogram
+            .labels
+            .iter()
+            .map(|label| {
+                label
+                    .parse::<i32>()
+                    .map_err(|_| format!("bucket label '{}' is not a sum", label))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if sums.is_empty() {
+            return Err("it has no buckets".to_string());
+        }
+        if histogram.counts.iter().all(|&count| count == 0) {
+            return Err("it has no samples to compare".to_string());
+        }
+        Ok(Run {
+            path: path.to_path_buf(),
+            export: json::parse(&contents)?,
+            sums,
+            counts: histogram.counts,
+        })
+    }
+
+    fn samples(&self) -> u64 {
+        stats::total(self.counts.iter().copied())
+    }
+
+    /// The run's parameters by name, as text.
+    fn params(&self) -> Vec<(String, String)> {
+        let text = |value: &Value| match value.as_str() {
+            Some(s) => s.to_string(),
+            None => value.to_string(),
+        };
+        let mut params = FIELDS
+            .iter()
+            .filter_map(|&name| Some((name.to_string(), text(self.export.get(name)?))))
+            .collect::<Vec<_>>();
+        let given = self.export.get("meta").and_then(|meta| meta.get("params"));
+        if let Some(Value::Object(given)) = given {
+            for (name, param) in given {
+                if params.iter().any(|(seen, _)| seen == name) {
+                    continue;
+                }
+                if let Some(value) = param.get("value") {
+                    params.push((name.clone(), text(value)));
+                }
+            }
+        }
+        params
+    }
+}
+
+/// Both runs' counts over buckets they share.
+struct Layout {
+    labels: Vec<String>,
+    a: Vec<u64>,
+    b: Vec<u64>,
+    /// How the buckets differ from either export's, if they do.
+    rebinned: Option<String>,
+}
+
+impl Layout {
+    fn common(a: &Run, b: &Run) -> Layout {
+        if a.sums == b.sums {
+            return Layout {
+                labels: a.sums.iter().map(i32::to_string).collect(),
+                a: a.counts.clone(),
+                b: b.counts.clone(),
+                rebinned: None,
+            };
+        }
+        let low = a.sums[0].min(b.sums[0]);
+        let high = a.sums[a.sums.len() - 1].max(b.sums[b.sums.len() - 1]);
+        let width = match (a.sums[0] - b.sums[0]) % 2 {
+            0 => 2,
+            _ => 4,
+        };
+        let buckets = ((high - low) / width + 1) as usize;
+        let bin = |run: &Run| {
+            let mut counts = vec![0; buckets];
+            for (sum, count) in run.sums.iter().zip(&run.counts) {
+                counts[((sum - low) / width) as usize] += count;
+            }
+            counts
+        };
+        let labels = (0..buckets as i32)
+            .map(|i| match width {
+                2 => (low + 2 * i).to_string(),
+                _ => format!("{}..{}", low + 4 * i, low + 4 * i + 3),
+            })
+            .collect::<Vec<_>>();
+        let rebinned = match width {
+            2 => format!(
+                "the buckets differ, so both are compared over the sums from {} to {}",
+                low, high
+            ),
+            _ => format!(
+                "one walks an odd and the other an even number of steps, so both are compared \
+                 in buckets of four sums from {} to {}",
+                low,
+                low + 4 * buckets as i32 - 1
+            ),
+        };
+        Layout {
+            a: bin(a),
+            b: bin(b),
+            labels,
+            rebinned: Some(rebinned),
+        }
+    }
+}
+
+struct Report {
+    a: Run,
+    b: Run,
+    layout: Layout,
+    frequencies: (Vec<f64>, Vec<f64>),
+    /// How far b's share of each bucket is from a's, in standard errors.
+    residuals: Vec<Option<f64>>,
+    ks: f64,
+    ks_p: f64,
+    chi_square: ChiSquare,
+    /// The parameters either gives that the other doesn't give the same.
+    params: Vec<(String, Option<String>, Option<String>)>,
+    alpha: f64,
+}
+
+impl Report {
+    fn new(a: Run, b: Run, alpha: f64) -> Report {
+        let layout = Layout::common(&a, &b);
+        let frequencies = |counts: &[u64]| {
+            let total = stats::total(counts.iter().copied());
+            counts
+                .iter()
+                .map(|&count| stats::share(count, total))
+                .collect::<Vec<_>>()
+        };
+        let frequencies = (frequencies(&layout.a), frequencies(&layout.b));
+        let ks = stats::ks_between(&frequencies.0, &frequencies.1);
+        let (params_a, params_b) = (a.params(), b.params());
+        let find = |params: &[(String, String)], name: &str| {
+            params
+                .iter()
+                .find(|(given, _)| given == name)
+                .map(|(_, value)| value.clone())
+        };
+        let mut seen = HashSet::new();
+        let params = params_a
+            .iter()
+            .chain(&params_b)
+            .filter(|(name, _)| seen.insert(name.clone()))
+            .map(|(name, _)| (name.clone(), find(&params_a, name), find(&params_b, name)))
+            .filter(|(_, a, b)| a != b)
+            .collect();
+        Report {
+            residuals: stats::two_sample_residuals(&layout.b, &layout.a),
+            ks_p: stats::ks_two_sample_p(ks, a.samples(), b.samples()),
+            chi_square: stats::chi_square_between(&layout.a, &layout.b),
+            ks,
+            frequencies,
+            params,
+            layout,
+            a,
+            b,
+            alpha,
+        }
+    }
+
+    /// Whether the chi-square test can't tell the two apart.
+    fn indistinguishable(&self) -> bool {
+        self.chi_square.p_value >= self.alpha
+    }
+
+    /// The buckets with samples in either run.
+    fn rows(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.layout.labels.len()).filter(|&i| self.layout.a[i] + self.layout.b[i] > 0)
+    }
+
+    fn write_text<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for (name, run) in [("a", &self.a), ("b", &self.b)] {
+            writeln!(
+                out,
+                "{}: {} ({} samples)",
+                name,
+                run.path.display(),
+                run.samples()
+            )?;
+        }
+        if let Some(rebinned) = &self.layout.rebinned {
+            writeln!(out, "note: {}", rebinned)?;
+        }
+        if !self.params.is_empty() {
+            writeln!(out, "\nParameters that differ:")?;
+            let width = self.params.iter().map(|(name, ..)| name.len()).max();
+            for (name, a, b) in &self.params {
+                writeln!(
+                    out,
+                    "  {:<width$}  {} → {}",
+                    name,
+                    a.as_deref().unwrap_or("(not given)"),
+                    b.as_deref().unwrap_or("(not given)"),
+                    width = width.unwrap_or(0)
+                )?;
+            }
+        }
+
+        let width = self
+            .layout
+            .labels
+            .iter()
+            .map(String::len)
+            .max()
+            .unwrap_or(0);
+        writeln!(
+            out,
+            "\n{:>width$}  {:>9}  {:>9}  {:>9}  {:>7}",
+            "bucket",
+            "a",
+            "b",
+            "b - a",
+            "z",
+            width = width.max(6)
+        )?;
+        let mut marked = false;
+        for i in self.rows() {
+            let (a, b) = (self.frequencies.0[i], self.frequencies.1[i]);
+            let z = self.residuals[i];
+            let mark = z.is_some_and(|z| z.abs() >= MARKED_Z);
+            marked |= mark;
+            writeln!(
+                out,
+                "{:>width$}  {:>8.3}%  {:>8.3}%  {:>+8.3}%  {:>7}{}",
+                self.layout.labels[i],
+                a * 100.0,
+                b * 100.0,
+                (b - a) * 100.0,
+                z.map_or_else(String::new, |z| format!("{:+.2}", z)),
+                if mark { " *" } else { "" },
+                width = width.max(6)
+            )?;
+        }
+        if marked {
+            writeln!(
+                out,
+                "* at least {} standard errors apart, which chance alone rarely gives",
+                MARKED_Z
+            )?;
+        }
+
+        writeln!(out, "\nKS distance {:.5} (p = {:.3})", self.ks, self.ks_p)?;
+        writeln!(
+            out,
+            "chi-square {:.2} on {} degrees of freedom (p = {:.3})",
+            self.chi_square.statistic, self.chi_square.dof, self.chi_square.p_value
+        )?;
+        match self.indistinguishable() {
+            true => writeln!(
+                out,
+                "The two are indistinguishable at alpha = {}.",
+                self.alpha
+            ),
+            false => writeln!(
+                out,
+                "The two differ at alpha = {}: they're unlikely to be draws from one distribution.",
+                self.alpha
+            ),
+        }
+    }
+
+    fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "bucket,count_a,count_b,frequency_a,frequency_b,difference,z"
+        )?;
+        for i in 0..self.layout.labels.len() {
+            let (a, b) = (self.frequencies.0[i], self.frequencies.1[i]);
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                self.layout.labels[i],
+                self.layout.a[i],
+                self.layout.b[i],
+                a,
+                b,
+                b - a,
+                self.residuals[i].map_or_else(String::new, |z| z.to_string())
+            )?;
+        }
+        Ok(())
+    }
+
+    fn to_json(&self) -> Value {
+        let run = |run: &Run| {
+            Value::object(vec![
+                ("path", run.path.display().to_string().into()),
+                ("samples", run.samples().into()),
+            ])
+        };
+        let buckets = (0..self.layout.labels.len())
+            .map(|i| {
+                let (a, b) = (self.frequencies.0[i], self.frequencies.1[i]);
+                Value::object(vec![
+                    ("bucket", self.layout.labels[i].as_str().into()),
+                    ("count_a", self.layout.a[i].into()),
+                    ("count_b", self.layout.b[i].into()),
+                    ("frequency_a", a.into()),
+                    ("frequency_b", b.into()),
+                    ("difference", (b - a).into()),
+                    ("z", self.residuals[i].map_or(Value::Null, Value::from)),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let text = |value: &Option<String>| value.as_deref().map_or(Value::Null, Value::from);
+        let params = self
+            .params
+            .iter()
+            .map(|(name, a, b)| {
+                Value::object(vec![
+                    ("name", name.as_str().into()),
+                    ("a", text(a)),
+                    ("b", text(b)),
+                ])
+            })
+            .collect::<Vec<_>>();
+        Value::object(vec![
+            ("a", run(&self.a)),
+            ("b", run(&self.b)),
+            (
+                "rebinned",
+                self.layout
+                    .rebinned
+                    .as_deref()
+                    .map_or(Value::Null, Value::from),
+            ),
+            ("params", params.into()),
+            ("buckets", buckets.into()),
+            (
+                "ks",
+                Value::object(vec![
+                    ("distance", self.ks.into()),
+                    ("p_value", self.ks_p.into()),
+                ]),
+            ),
+            (
+                "chi_square",
+                Value::object(vec![
+                    ("statistic", self.chi_square.statistic.into()),
+                    ("dof", self.chi_square.dof.into()),
+                    ("p_value", self.chi_square.p_value.into()),
+                ]),
+            ),
+            ("alpha", self.alpha.into()),
+            ("indistinguishable", self.indistinguishable().into()),
+        ])
+    }
+}
+
+/// Reads both exports and writes the report. Returns whether the two are
+/// indistinguishable, or why an export couldn't be read.
+pub fn run<W: Write>(out: &mut W, config: &Config) -> Result<bool, String> {
+    let load = |path: &Path| {
+        Run::load(path).map_err(|err| format!("can't read {}: {}", path.display(), err))
+    };
+    let report = Report::new(load(&config.a)?, load(&config.b)?, config.alpha);
+    let written = match config.format {
+        Format::Text => report.write_text(out),
+        Format::Csv => report.write_csv(out),
+        Format::Json => writeln!(out, "{}", report.to_json()),
+    };
+    match written.and_then(|()| out.flush()) {
+        // A report piped to `head` still has an answer to give.
+        Err(err) if err.kind() != io::ErrorKind::BrokenPipe => Err(err.to_string()),
+        _ => Ok(report.indistinguishable()),
+    }
+}
@@ -0,0 +1,45 @@
+//! What drives `run_app`: the terminal's events and the time. A session
+//! reads crossterm's and `Instant::now`; the test harness runs the same loop
+//! on a script of events and a clock that only moves while the loop waits.
+
+use crossterm::event::{self, Event};
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+/// Where the loop's input comes from.
+pub trait Events {
+    /// Whether an event is ready, waiting up to `timeout` for one.
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
+
+    /// The next event, once `poll` has said there is one.
+    fn read(&mut self) -> io::Result<Event>;
+}
+
+/// Where the loop gets the time from.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The terminal's events.
+pub struct Crossterm;
+
+impl Events for Crossterm {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
+}
+
+/// The monotonic clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
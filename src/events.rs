@@ -0,0 +1,921 @@
+//! `--event-log` and `replay-events`: what happened in a TUI session, as
+//! JSON lines, and the session driven again from them.
+//!
+//! The log starts with the command line and the seed, then has a line for
+//! every key pressed and what it did, every change of a parameter or the
+//! mode, every `--watch-config` reload, every export and every error, each
+//! with the tick it came at and the seconds since the start. Only the
+//! counts' shape is ever written, never samples, so it stays small. Every
+//! line is flushed as it's written, so a session that panics leaves the keys
+//! that led up to it.
+//!
+//! `replay-events` runs the same command line from the same seed, without
+//! the TUI and without writing anything but the exports, and applies each
+//! key at the point in the run it came, down to the tick, or the samples
+//! into a tick it interrupted. A key's effect is replayed as the session
+//! resolved it, so a key that dismissed an error does that again even if
+//! the replay's toasts came and went at other times. The frames are drawn
+//! off-screen at the session's size, so a panic in drawing happens again too.
+
+use crate::{
+    cli::Args, cycles, datetime::DateTime, json, json::Value, provenance, session::Session, ui,
+    watch, App, CHUNK,
+};
+use crossterm::event::KeyCode;
+use std::{
+    env, fs,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+    time::{Instant, SystemTime},
+};
+use tui::{backend::TestBackend, Terminal};
+
+/// Where in the run something happened: the ticks finished, counting the
+/// intro's and those of earlier cycles, and the samples drawn into the one
+/// under way, if a key came part-way through it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    step: u64,
+    drawn: u64,
+}
+
+/// What a key press did, as `handle_event` resolved it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    /// Started a `--start-at` run early.
+    Start,
+    SkipIntro,
+    /// Went to the settings panel, open at the time.
+    Settings,
+    Dismiss,
+    Key,
+    /// Did nothing, as during the countdown.
+    Ignored,
+}
+
+impl Action {
+    const ALL: [Action; 7] = [
+        Action::Quit,
+        Action::Start,
+        Action::SkipIntro,
+        Action::Settings,
+        Action::Dismiss,
+        Action::Key,
+        Action::Ignored,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Start => "start",
+            Action::SkipIntro => "skip_intro",
+            Action::Settings => "settings",
+            Action::Dismiss => "dismiss",
+            Action::Key => "key",
+            Action::Ignored => "ignored",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+pub struct EventLog {
+    out: BufWriter<File>,
+    started: Instant,
+    step: u64,
+    /// How many of the provenance's changes have been written.
+    changes: usize,
+    mode: String,
+    selected: Option<f64>,
+    /// Why a write first failed, for a toast.
+    failed: Option<String>,
+    warned: bool,
+}
+
+impl EventLog {
+    /// Creates the log at `path` and writes its first line: the command line
+    /// `args` as given, the seed, and the `--watch-config` file as it was
+    /// read, if there's one.
+    pub fn create(
+        path: &Path,
+        app: &App,
+        args: &[String],
+        config: Option<String>,
+        resumed: bool,
+    ) -> io::Result<EventLog> {
+        let mut log = EventLog {
+            out: BufWriter::new(File::create(path)?),
+            started: Instant::now(),
+            step: 0,
+            changes: app.provenance.changes().len(),
+            mode: app.mode.name().to_string(),
+            selected: app.selected,
+            failed: None,
+            warned: false,
+        };
+        let mut fields = vec![
+            ("event", "start".into()),
+            ("time", DateTime::rfc3339(SystemTime::now()).into()),
+            ("args", args.to_vec().into()),
+            ("seed", app.seed.to_string().into()),
+        ];
+        if let Some(config) = config {
+            fields.push(("config", config.into()));
+        }
+        if resumed {
+            fields.push(("resumed_autosave", true.into()));
+        }
+        log.write(Value::object(fields))?;
+        Ok(log)
+    }
+
+    /// Where `app` is in the run, for an event that's about to change it.
+    pub fn position(&self, app: &App) -> Position {
+        Position {
+            step: self.step,
+            drawn: app
+                .pending
+                .map_or(0, |before| app.sim.samples().saturating_sub(before)),
+        }
+    }
+
+    fn write(&mut self, event: Value) -> io::Result<()> {
+        writeln!(self.out, "{}", event)?;
+        self.out.flush()
+    }
+
+    /// Writes `fields` after `event`, `tick` and `at`, and then whatever
+    /// changed in `app` because of it.
+    fn record(&mut self, app: &mut App, at: Position, event: &str, fields: Vec<(&str, Value)>) {
+        let line = self.line(app, at, event, fields);
+        self.try_write(line);
+        self.note(app);
+    }
+
+    /// Writes the parameter changes, the mode changes and the errors since
+    /// the last look.
+    fn note(&mut self, app: &mut App) {
+        let at = self.position(app);
+        let mut lines = vec![];
+        for change in &app.provenance.changes()[self.changes..] {
+            lines.push(self.line(
+                app,
+                at,
+                "change",
+                vec![
+                    ("name", change.name.into()),
+                    ("value", change.value.clone().into()),
+                    ("source", change.source.name().into()),
+                ],
+            ));
+        }
+        self.changes = app.provenance.changes().len();
+        if app.mode.name() != self.mode {
+            let to = app.mode.name().to_string();
+            lines.push(self.line(
+                app,
+                at,
+                "mode",
+                vec![
+                    ("from", self.mode.clone().into()),
+                    ("to", to.clone().into()),
+                ],
+            ));
+            self.mode = to;
+        }
+        for message in app.toasts.take_raised() {
+            lines.push(self.line(app, at, "error", vec![("message", message.into())]));
+        }
+        for line in lines {
+            self.try_write(line);
+        }
+    }
+
+    /// `fields` after the event's name and where it came.
+    fn line(&self, app: &App, at: Position, event: &str, fields: Vec<(&str, Value)>) -> Value {
+        let mut line = vec![
+            ("event", event.into()),
+            ("tick", app.tick.into()),
+            ("step", at.step.into()),
+            ("drawn", at.drawn.into()),
+            ("t", self.seconds().into()),
+        ];
+        line.extend(fields);
+        Value::object(line)
+    }
+
+    fn try_write(&mut self, line: Value) {
+        if let Err(err) = self.write(line) {
+            self.failed.get_or_insert(err.to_string());
+        }
+    }
+
+    fn seconds(&self) -> f64 {
+        (self.started.elapsed().as_secs_f64() * 1000.0).round() / 1000.0
+    }
+}
+
+/// Takes `app`'s log out for `f`, so it can write about `app` as well.
+fn with_log(app: &mut App, f: impl FnOnce(&mut EventLog, &mut App)) {
+    let Some(mut log) = app.events.take() else {
+        return;
+    };
+    f(&mut log, app);
+    if let (false, Some(err)) = (log.warned, &log.failed) {
+        log.warned = true;
+        let message = app
+            .strings
+            .fill("toast.event_log_failed", &[("error", &err)]);
+        log::warn!("{}", message);
+        app.toasts.error(message);
+    }
+    app.events = Some(log);
+}
+
+/// Where `app` is in the run, if it's keeping a log.
+pub fn position(app: &App) -> Option<Position> {
+    app.events.as_ref().map(|log| log.position(app))
+}
+
+/// Logs a key pressed at `at`, after it's done `action`.
+pub fn key(app: &mut App, at: Option<Position>, code: KeyCode, action: Action) {
+    let (Some(at), Some(name)) = (at, key_name(code)) else {
+        return;
+    };
+    with_log(app, |log, app| {
+        log.record(
+            app,
+            at,
+            "key",
+            vec![("key", name.into()), ("action", action.name().into())],
+        );
+    });
+}
+
+/// Logs the bucket selected with the mouse at `at`, if it's changed.
+pub fn select(app: &mut App, at: Option<Position>) {
+    let Some(at) = at else {
+        return;
+    };
+    with_log(app, |log, app| {
+        if app.selected == log.selected {
+            return;
+        }
+        log.selected = app.selected;
+        let x = app.selected.map_or(Value::Null, Value::from);
+        log.record(app, at, "select", vec![("x", x)]);
+    });
+}
+
+/// Logs the terminal's size, at the start and whenever it changes.
+pub fn resize(app: &mut App, width: u16, height: u16) {
+    with_log(app, |log, app| {
+        let at = log.position(app);
+        log.record(
+            app,
+            at,
+            "resize",
+            vec![
+                ("width", u64::from(width).into()),
+                ("height", u64::from(height).into()),
+            ],
+        );
+    });
+}
+
+/// Logs a `--watch-config` reload, with the file as it was applied.
+pub fn config(app: &mut App, contents: &str) {
+    with_log(app, |log, app| {
+        let at = log.position(app);
+        log.record(app, at, "config", vec![("contents", contents.into())]);
+    });
+}
+
+/// Logs an export written into `path`.
+pub fn export(app: &mut App, path: &Path) {
+    with_log(app, |log, app| {
+        let at = log.position(app);
+        let path = path.display().to_string();
+        log.record(app, at, "export", vec![("path", path.into())]);
+    });
+}
+
+/// Counts a tick that's finished, and logs what it changed.
+pub fn ticked(app: &mut App) {
+    with_log(app, |log, app| {
+        log.step += 1;
+        log.note(app);
+    });
+}
+
+/// Logs the end of the session, with enough of where it got to for
+/// `replay-events` to check it got to the same place.
+pub fn finish(app: &mut App) {
+    with_log(app, |log, app| {
+        let at = log.position(app);
+        let ks = app.stats().ks;
+        let samples = app.samples();
+        log.record(
+            app,
+            at,
+            "end",
+            vec![("samples", samples.into()), ("ks", ks.into())],
+        );
+    });
+    app.events = None;
+}
+
+/// The name a key is logged by: the character, or the key's name.
+fn key_name(code: KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Char(' ') => "Space",
+        KeyCode::Char(c) => return Some(c.to_string()),
+        KeyCode::F(n) => return Some(format!("F{}", n)),
+        KeyCode::Left => "Left",
+        KeyCode::Right => "Right",
+        KeyCode::Up => "Up",
+        KeyCode::Down => "Down",
+        KeyCode::Esc => "Esc",
+        KeyCode::Tab => "Tab",
+        KeyCode::BackTab => "BackTab",
+        KeyCode::Enter => "Enter",
+        KeyCode::Backspace => "Backspace",
+        KeyCode::Delete => "Delete",
+        KeyCode::Home => "Home",
+        KeyCode::End => "End",
+        KeyCode::PageUp => "PageUp",
+        KeyCode::PageDown => "PageDown",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let code = match name {
+        "Space" => KeyCode::Char(' '),
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Esc" => KeyCode::Esc,
+        "Tab" => KeyCode::Tab,
+        "BackTab" => KeyCode::BackTab,
+        "Enter" => KeyCode::Enter,
+        "Backspace" => KeyCode::Backspace,
+        "Delete" => KeyCode::Delete,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        _ => KeyCode::F(name.strip_prefix('F')?.parse().ok()?),
+    };
+    Some(code)
+}
+
+/// A logged session, to be run again.
+pub struct Script {
+    path: PathBuf,
+    args: Vec<String>,
+    seed: u64,
+    config: Option<String>,
+    resumed: bool,
+    events: Vec<Scripted>,
+    end: Option<End>,
+    /// Where the session's `--watch-config` file is written for the replay
+    /// to start from.
+    config_file: Option<PathBuf>,
+}
+
+/// An event the replay acts on, and where.
+struct Scripted {
+    at: Position,
+    line: usize,
+    kind: Kind,
+}
+
+enum Kind {
+    Key(KeyCode, Action),
+    Select(Option<f64>),
+    Resize(u16, u16),
+    Config(String),
+}
+
+/// Where the session ended.
+struct End {
+    at: Position,
+    tick: u64,
+    samples: u64,
+    ks: Option<f64>,
+}
+
+impl Script {
+    pub fn read(path: &Path) -> Result<Script, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("can't read {}: {}", path.display(), err))?;
+        Script::parse(path, &contents)
+    }
+
+    /// The log in `contents`, read from `path`, which errors are reported
+    /// against.
+    pub fn parse(path: &Path, contents: &str) -> Result<Script, String> {
+        let mut lines = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let at_line = |n: usize, err: String| format!("{}:{}: {}", path.display(), n + 1, err);
+
+        let (n, first) = lines.next().ok_or("the event log is empty")?;
+        let start = json::parse(first).map_err(|err| at_line(n, err))?;
+        if start.get("event").and_then(Value::as_str) != Some("start") {
+            return Err(at_line(
+                n,
+                "the event log doesn't begin with its start".into(),
+            ));
+        }
+        let args = start
+            .get("args")
+            .and_then(Value::as_array)
+            .and_then(|args| {
+                args.iter()
+                    .map(|arg| arg.as_str().map(String::from))
+                    .collect()
+            })
+            .ok_or_else(|| at_line(n, "the start has no command line".into()))?;
+        let seed = start
+            .get("seed")
+            .and_then(Value::as_str)
+            .and_then(|seed| seed.parse().ok())
+            .ok_or_else(|| at_line(n, "the start has no seed".into()))?;
+        let mut script = Script {
+            path: path.to_path_buf(),
+            args,
+            seed,
+            config: start
+                .get("config")
+                .and_then(Value::as_str)
+                .map(String::from),
+            resumed: start.get("resumed_autosave") == Some(&Value::Bool(true)),
+            events: vec![],
+            end: None,
+            config_file: None,
+        };
+
+        for (n, line) in lines {
+            let event = json::parse(line).map_err(|err| at_line(n, err))?;
+            let number = |name: &str| {
+                event
+                    .get(name)
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| at_line(n, format!("'{}' is missing", name)))
+            };
+            let text = |name: &str| {
+                event
+                    .get(name)
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| at_line(n, format!("'{}' is missing", name)))
+            };
+            let at = Position {
+                step: number("step")?,
+                drawn: number("drawn")?,
+            };
+            let kind = match text("event")? {
+                "key" => {
+                    let name = text("key")?;
+                    let code = parse_key(name)
+                        .ok_or_else(|| at_line(n, format!("unknown key '{}'", name)))?;
+                    let action = text("action")?;
+                    let action = Action::parse(action)
+                        .ok_or_else(|| at_line(n, format!("unknown action '{}'", action)))?;
+                    Kind::Key(code, action)
+                }
+                "select" => Kind::Select(event.get("x").and_then(Value::as_f64)),
+                "resize" => Kind::Resize(
+                    number("width")?.min(u64::from(u16::MAX)) as u16,
+                    number("height")?.min(u64::from(u16::MAX)) as u16,
+                ),
+                "config" => Kind::Config(text("contents")?.to_string()),
+                "end" => {
+                    script.end = Some(End {
+                        at,
+                        tick: number("tick")?,
+                        samples: number("samples")?,
+                        ks: event.get("ks").and_then(Value::as_f64),
+                    });
+                    break;
+                }
+                // What the session did, which the replay does for itself.
+                _ => continue,
+            };
+            script.events.push(Scripted {
+                at,
+                line: n + 1,
+                kind,
+            });
+        }
+        Ok(script)
+    }
+
+    /// The session's command line, from the logged seed, without what would
+    /// write anywhere but the exports, wait for a start time, or watch a
+    /// file that may have changed since.
+    pub fn args(&mut self) -> Result<Args, String> {
+        let mut args = Args::parse_from(self.args.clone())
+            .map_err(|err| format!("the logged command line is invalid: {}", err))?;
+        args.seed = Some(self.seed);
+        args.event_log = None;
+        args.start_at = None;
+        // The autosave's counts were never anything but cumulative.
+        if args.autosave.take().is_some() {
+            args.cumulative = true;
+        }
+        args.auto_resume = false;
+        args.status_file = None;
+        args.stream = None;
+        args.socket = None;
+        args.raw_out = None;
+        args.export_gif = None;
+        args.sonify = false;
+        args.volume = None;
+        args.log_file = None;
+        args.verbose = false;
+        if args.watch_config.is_some() {
+            // The file as it was read at the start; the reloads are applied
+            // from the log.
+            let contents = self.config.as_deref().unwrap_or("{}");
+            let path = env::temp_dir().join(format!("central-limit-events-{}.json", process::id()));
+            fs::write(&path, contents)
+                .map_err(|err| format!("can't write {}: {}", path.display(), err))?;
+            args.watch_config = Some(path.clone());
+            self.config_file = Some(path);
+        }
+        Ok(args)
+    }
+}
+
+/// Runs `app` through `script`'s session, printing a line for every key
+/// applied. Returns whether it ended where the session did.
+pub fn replay<W: Write>(
+    out: &mut W,
+    app: &mut App,
+    script: Script,
+    session: Option<&Session>,
+) -> io::Result<bool> {
+    if let Some(path) = &script.config_file {
+        let _ = fs::remove_file(path);
+    }
+    // The reloads come from the log, not the file.
+    app.watch = None;
+    app.progress = 1.0;
+    if script.resumed {
+        eprintln!(
+            "warning: the session carried on from an autosave, which the replay doesn't, \
+             so its counts will differ"
+        );
+    }
+    let mut terminal = Terminal::new(TestBackend::new(80, 24))?;
+    let mut step = 0;
+    let mut ended = false;
+    for scripted in &script.events {
+        if !advance(app, &mut terminal, session, &mut step, scripted.at)? {
+            ended = true;
+            break;
+        }
+        let key = match scripted.kind {
+            Kind::Key(code, action) => Some((code, action)),
+            _ => None,
+        };
+        match &scripted.kind {
+            Kind::Key(code, action) => {
+                if *action == Action::Quit {
+                    ended = true;
+                    break;
+                }
+                apply(app, *code, *action);
+            }
+            Kind::Select(x) => app.selected = *x,
+            Kind::Resize(width, height) => terminal.backend_mut().resize(*width, *height),
+            Kind::Config(contents) => {
+                match watch::parse(contents).and_then(|config| app.apply_config(&config)) {
+                    Ok(()) => app.note_changes(provenance::Source::Config),
+                    Err(err) => log::warn!("a logged config doesn't apply: {}", err),
+                }
+            }
+        }
+        if let Some((code, action)) = key {
+            writeln!(
+                out,
+                "{}:{}: tick {}: {} ({})",
+                script.path.display(),
+                scripted.line,
+                app.tick,
+                key_name(code).unwrap_or_default(),
+                action.name()
+            )?;
+        }
+        terminal.draw(|f| ui::draw(f, app))?;
+    }
+    if let (false, Some(end)) = (ended, &script.end) {
+        advance(app, &mut terminal, session, &mut step, end.at)?;
+    }
+    let ks = Some(app.stats().ks).filter(|ks| ks.is_finite());
+    let matched = script
+        .end
+        .as_ref()
+        .is_some_and(|end| end.tick == app.tick && end.samples == app.samples() && end.ks == ks);
+    match &script.end {
+        Some(_) if matched => writeln!(
+            out,
+            "replayed to tick {} with {} samples, where the session ended",
+            app.tick,
+            app.samples()
+        )?,
+        Some(end) => writeln!(
+            out,
+            "replayed to tick {} with {} samples and a KS distance of {}, but the session ended \
+             at tick {} with {} and {}",
+            app.tick,
+            app.samples(),
+            ks.map_or("none".to_string(), |ks| format!("{:.6}", ks)),
+            end.tick,
+            end.samples,
+            end.ks.map_or("none".to_string(), |ks| format!("{:.6}", ks))
+        )?,
+        None => writeln!(
+            out,
+            "replayed to tick {}; the log has no end, so the session didn't finish",
+            app.tick
+        )?,
+    }
+    out.flush()?;
+    Ok(matched)
+}
+
+/// Does with `code` what `handle_event` did in the session.
+fn apply(app: &mut App, code: KeyCode, action: Action) {
+    match action {
+        Action::SkipIntro => app.end_intro(),
+        Action::Settings => app.on_settings_key(code),
+        Action::Dismiss => {
+            app.toasts.dismiss_next_error();
+        }
+        Action::Key => app.on_key(code),
+        Action::Quit | Action::Start | Action::Ignored => {}
+    }
+    app.note_changes(provenance::Source::Interactive);
+}
+
+/// Runs ticks until `step` reaches `at`, stopping part-way through the last
+/// if `at` came during it, as `run_app` does. Returns false if the run ends
+/// before then.
+pub fn advance(
+    app: &mut App,
+    terminal: &mut Terminal<TestBackend>,
+    session: Option<&Session>,
+    step: &mut u64,
+    at: Position,
+) -> io::Result<bool> {
+    let drawn = |app: &App| {
+        app.pending
+            .map_or(0, |before| app.sim.samples().saturating_sub(before))
+    };
+    loop {
+        if *step > at.step || (*step == at.step && drawn(app) >= at.drawn) {
+            return Ok(true);
+        }
+        if app.pending.is_none() {
+            app.begin_tick();
+        }
+        let max = match *step == at.step {
+            true => at.drawn.saturating_sub(drawn(app)) as usize,
+            false => CHUNK,
+        };
+        if !app.continue_tick(max) {
+            continue;
+        }
+        *step += 1;
+        if let Some(err) = app.take_feedback() {
+            app.toasts.error(err);
+        }
+        terminal.draw(|f| ui::draw(f, app))?;
+        if app.until_exit && app.until_met() {
+            return Ok(false);
+        }
+        if let (true, Some(session)) = (app.cycle_over(), session) {
+            if !cycles::end_cycle(app, session)? {
+                return Ok(false);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{harness::Harness, mode::Mode};
+    use central_limit::rng::Generator;
+
+    /// Commits 10 samples a tick from the settings panel, half-way through
+    /// a tick of 100,000, which used to underflow the samples still to draw.
+    const FEWER_SAMPLES: &str = r#"{"event":"start","args":["--samples","100000","--no-intro"],"seed":"1"}
+{"event":"key","step":1,"drawn":50000,"key":"o","action":"key"}
+{"event":"key","step":1,"drawn":50000,"key":"Enter","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Backspace","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Backspace","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Backspace","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Backspace","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Backspace","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"0","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Enter","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Esc","action":"settings"}
+{"event":"end","step":4,"drawn":0,"tick":4,"samples":100030}
+"#;
+
+    /// Commits 20 steps from the settings panel half-way through a
+    /// cumulative tick, which used to underflow where the log was.
+    const MORE_STEPS: &str = r#"{"event":"start","args":["--samples","100000","--no-intro","--cumulative"],"seed":"1"}
+{"event":"key","step":1,"drawn":50000,"key":"o","action":"key"}
+{"event":"key","step":1,"drawn":50000,"key":"Down","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Enter","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Backspace","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Backspace","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"2","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"0","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Enter","action":"settings"}
+{"event":"key","step":1,"drawn":50000,"key":"Esc","action":"settings"}
+{"event":"end","step":4,"drawn":0,"tick":4,"samples":400000}
+"#;
+
+    /// The app `main` would make for the script's command line, as far as
+    /// the logs here need: the seed, the samples, the steps and the mode.
+    fn app_for(script: &mut Script) -> App {
+        let args = script.args().unwrap();
+        let mut app = App::new(args.seed.unwrap(), Generator::Std);
+        app.set_params(
+            args.samples.unwrap_or(app.b_count),
+            args.steps.unwrap_or(app.r_max),
+        )
+        .unwrap();
+        if args.cumulative {
+            app.mode = Mode::Cumulative;
+        }
+        app
+    }
+
+    /// Replays `log`, returning the app at the end and what was printed.
+    fn replay_log(log: &str) -> (App, bool, String) {
+        let mut script = Script::parse(Path::new("test.jsonl"), log).unwrap();
+        let mut app = app_for(&mut script);
+        let mut out = vec![];
+        let matched = replay(&mut out, &mut app, script, None).unwrap();
+        (app, matched, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn fewer_samples_part_way_through_a_tick() {
+        let (app, _, out) = replay_log(FEWER_SAMPLES);
+        assert_eq!(app.b_count, 10);
+        // A whole tick of 100,000, and three of 10 after the change.
+        assert_eq!((app.tick, app.samples()), (4, 100_030));
+        assert_eq!(app.pending, None);
+        assert!(
+            out.contains("test.jsonl:11: tick 2: Esc (settings)"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn more_steps_part_way_through_a_tick() {
+        let (app, _, out) = replay_log(MORE_STEPS);
+        assert_eq!(app.r_max, 20);
+        assert_eq!((app.tick, app.samples()), (4, 400_000));
+        assert_eq!(app.pending, None);
+        assert!(
+            out.contains("test.jsonl:10: tick 1: Esc (settings)"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn a_logged_session_replays_to_where_it_ended() {
+        let path =
+            env::temp_dir().join(format!("central-limit-events-test-{}.jsonl", process::id()));
+        // Ticks of 40,000 samples are drawn in chunks, so some keys come
+        // part-way through one.
+        let mut app = App::new(7, Generator::Std);
+        app.b_count = 40_000;
+        let mut harness = Harness::with(app);
+        harness.log_to(&path, &["--seed", "7", "--samples", "40000", "--no-intro"]);
+        harness
+            .advance_ticks(2)
+            .press(KeyCode::Char('c'))
+            .part_tick(20_000)
+            .press(KeyCode::Right)
+            .press(KeyCode::Char('o'))
+            .press(KeyCode::Down)
+            .press(KeyCode::Enter)
+            .press(KeyCode::Backspace)
+            .press(KeyCode::Backspace)
+            .press(KeyCode::Char('9'))
+            .press(KeyCode::Enter)
+            .press(KeyCode::Esc)
+            .advance_ticks(3)
+            .press(KeyCode::Char(' '))
+            .press(KeyCode::Char(' '))
+            .advance_ticks(1);
+        harness.finish();
+        let log = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let (app, matched, out) = replay_log(&log);
+        assert!(matched, "{}\n{}", log, out);
+        assert_eq!(app.r_max, 9);
+        assert!(matches!(app.mode, Mode::Cumulative));
+        assert_eq!(app.sim.counts_slice(), harness.app.sim.counts_slice());
+    }
+
+    #[test]
+    fn a_session_logs_what_it_did_and_no_samples() {
+        let path = env::temp_dir().join(format!(
+            "central-limit-events-lines-test-{}.jsonl",
+            process::id()
+        ));
+        let mut harness = Harness::new(3);
+        harness.log_to(&path, &["--seed", "3"]);
+        harness
+            .advance_ticks(1)
+            .press(KeyCode::Char('c'))
+            .press(KeyCode::Char('o'))
+            .press(KeyCode::Enter)
+            .press(KeyCode::Backspace)
+            .press(KeyCode::Enter)
+            .press(KeyCode::Esc)
+            .advance_ticks(1)
+            .press(KeyCode::Char('q'));
+        harness.finish();
+        let log = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let lines = log
+            .lines()
+            .map(|line| json::parse(line).unwrap())
+            .collect::<Vec<_>>();
+        fn event(line: &Value) -> &str {
+            line.get("event").and_then(Value::as_str).unwrap()
+        }
+        let find = |name: &str| lines.iter().find(|line| event(line) == name);
+        assert_eq!(event(&lines[0]), "start");
+        assert_eq!(event(&lines[lines.len() - 1]), "end");
+        for line in &lines[1..] {
+            for field in ["tick", "step", "drawn", "t"] {
+                assert!(
+                    line.get(field).and_then(Value::as_f64).is_some(),
+                    "{}",
+                    line
+                );
+            }
+        }
+        let mode = find("mode").unwrap();
+        assert_eq!(mode.get("to").and_then(Value::as_str), Some("cumulative"));
+        let changed = |name: &str| {
+            lines.iter().find(|line| {
+                event(line) == "change" && line.get("name").and_then(Value::as_str) == Some(name)
+            })
+        };
+        let samples = changed("samples").expect(&log);
+        assert_eq!(samples.get("value").and_then(Value::as_str), Some("500"));
+        assert!(changed("mode").is_some(), "{}", log);
+        let quit = lines
+            .iter()
+            .rev()
+            .find(|line| event(line) == "key")
+            .unwrap();
+        assert_eq!(quit.get("action").and_then(Value::as_str), Some("quit"));
+        // A few lines of keys and changes, however many samples were drawn.
+        assert!(log.len() < 4_000, "{}", log);
+    }
+
+    #[test]
+    fn a_log_without_its_start_is_refused() {
+        let log = r#"{"event":"key","step":1,"drawn":0,"key":"q","action":"quit"}"#;
+        let err = Script::parse(Path::new("bad.jsonl"), log).err().unwrap();
+        assert_eq!(
+            err,
+            "bad.jsonl:1: the event log doesn't begin with its start"
+        );
+        let log = "{\"event\":\"start\",\"args\":[],\"seed\":\"1\"}\n\
+                   {\"event\":\"key\",\"step\":0,\"drawn\":0,\"key\":\"Nope\",\"action\":\"key\"}";
+        let err = Script::parse(Path::new("bad.jsonl"), log).err().unwrap();
+        assert_eq!(err, "bad.jsonl:2: unknown key 'Nope'");
+    }
+}
@@ -0,0 +1,763 @@
+//! Histogram exports.
+
+use crate::{
+    bucket::{Aggregate, Bucket},
+    datetime::DateTime,
+    json::{self, Value},
+    overlay::Overlay,
+    pin::Pin,
+    seeds::{SeedRuns, Spread},
+    App,
+};
+use central_limit::{sampling, stats};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    time::SystemTime,
+};
+
+/// What each sample is, for the exports to say what the counts are of.
+const DISTRIBUTION: &str = "sum of a random walk of ±1 steps";
+
+/// The commit the binary was built from, if it was built from a checkout.
+fn git() -> Option<&'static str> {
+    option_env!("GIT_DESCRIBE").filter(|describe| !describe.is_empty())
+}
+
+/// `# ` lines heading a CSV export, saying what made it and under which
+/// parameters, so two exports that differ can be told apart.
+fn write_meta<W: Write>(out: &mut W, app: &App) -> io::Result<()> {
+    writeln!(
+        out,
+        "# central-limit {} ({})",
+        env!("CARGO_PKG_VERSION"),
+        git().unwrap_or("unknown commit")
+    )?;
+    let started = app.provenance.started().map(DateTime::rfc3339);
+    writeln!(
+        out,
+        "# started {}, exported {}",
+        started.as_deref().unwrap_or("-"),
+        DateTime::rfc3339(SystemTime::now())
+    )?;
+    writeln!(out, "# tick {}, {}", app.tick, DISTRIBUTION)?;
+    writeln!(out, "# sampling: {}", app.sim.regime().name())?;
+    writeln!(out, "# aggregate: {}", app.sim.aggregate().name())?;
+    if app.bias_sweep.is_some() || app.bias() != sampling::FAIR {
+        writeln!(out, "# chance of a +1 step: {}", app.bias())?;
+    }
+    if let Some(cycles) = &app.cycles {
+        writeln!(
+            out,
+            "# cycle {} of {}, {} ticks each, seed {}",
+            cycles.current(),
+            cycles.count(),
+            cycles.ticks(),
+            app.seed
+        )?;
+    }
+    if let Some(forwarded) = fast_forwarded(app) {
+        writeln!(
+            out,
+            "# fast-forwarded: {} of the counts, drawn at once from the binomial model",
+            stats::total(forwarded.iter().copied())
+        )?;
+    }
+    if app.precision_shown() {
+        let (converged, occupied) = app.converged();
+        writeln!(
+            out,
+            "# buckets converged: {} of {} with samples within {}% relative standard error",
+            converged,
+            occupied,
+            app.precision_percent()
+        )?;
+    }
+    let balance = app.sim.balance();
+    writeln!(
+        out,
+        "# +1 steps: {:.6} of {}, expected {}, z = {:.2}",
+        balance.up_fraction(),
+        balance.steps(),
+        balance.expected_fraction(),
+        balance.z()
+    )?;
+    for param in app.provenance.params() {
+        writeln!(
+            out,
+            "# {} = {} ({})",
+            param.name,
+            param.value,
+            param.source.name()
+        )?;
+    }
+    for (i, pin) in app.pins.iter().enumerate() {
+        writeln!(out, "# pin_{}: {}", i + 1, pin.label)?;
+    }
+    for change in app.provenance.changes() {
+        writeln!(
+            out,
+            "# at tick {}: {} = {} ({})",
+            change.tick,
+            change.name,
+            change.value,
+            change.source.name()
+        )?;
+    }
+    Ok(())
+}
+
+/// Each bucket's share's relative standard error, infinite for an empty one.
+fn relative_errors(app: &App) -> Vec<f64> {
+    let total = stats::total(app.data.iter().map(|bucket| bucket.count));
+    app.data
+        .iter()
+        .map(|bucket| stats::relative_error(bucket.count, total))
+        .collect()
+}
+
+/// Each bucket's mean step, while the buckets are shown as means.
+fn means(app: &App) -> Option<Vec<f64>> {
+    let unit = app.sim.aggregate().unit(app.r_max as u32);
+    (app.sim.aggregate() == Aggregate::Mean)
+        .then(|| app.data.iter().map(|bucket| bucket.x / unit).collect())
+}
+
+/// The fast-forwarded part of each count, if there is one.
+fn fast_forwarded(app: &App) -> Option<&[u64]> {
+    let forwarded = app.sim.fast_forwarded();
+    (forwarded.len() == app.data.len() && forwarded.iter().any(|&count| count > 0))
+        .then_some(forwarded)
+}
+
+/// The version, commit and parameters the JSON export was made under, with
+/// where each parameter came from and how they changed during the run.
+fn meta(app: &App) -> Value {
+    let (params, changes) = app.provenance.to_json();
+    Value::object(vec![
+        ("version", env!("CARGO_PKG_VERSION").into()),
+        ("git", git().map_or(Value::Null, Value::from)),
+        ("distribution", DISTRIBUTION.into()),
+        (
+            "started",
+            app.provenance
+                .started()
+                .map_or(Value::Null, |time| DateTime::rfc3339(time).into()),
+        ),
+        ("exported", DateTime::rfc3339(SystemTime::now()).into()),
+        ("params", params),
+        ("changes", changes),
+    ])
+}
+
+/// How the bench and sweep subcommands print their results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "text" => Some(Format::Text),
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// One row per bucket, with the count the model shown expects beside the
+/// observed one, or nothing there for an aggregate with no model. Shown as means, a column gives each bucket's mean step
+/// after its sum. With merged tails, a column names the bar each bucket is
+/// drawn in, with `--precision`, one gives its share's relative standard
+/// error, with `--seeds`, three more give its spread across them, after
+/// a fast-forward, one gives the part of the count it added, and a column
+/// per pin gives its share of the samples in each bucket.
+pub fn write_csv<W: Write>(out: &mut W, app: &App) -> io::Result<()> {
+    write_meta(out, app)?;
+    let spread = app.seeds.as_ref().and_then(|seeds| seeds.spread(&app.data));
+    let expected = app.expected();
+    let means = means(app);
+    let mut header = "bucket,count,expected".to_string();
+    if means.is_some() {
+        header += ",mean";
+    }
+    if app.bins.is_some() {
+        header += ",bin";
+    }
+    let errors = app.precision_shown().then(|| relative_errors(app));
+    if errors.is_some() {
+        header += ",relative_se";
+    }
+    if spread.is_some() {
+        header += ",seeds_min,seeds_median,seeds_max";
+    }
+    let forwarded = fast_forwarded(app);
+    if forwarded.is_some() {
+        header += ",fast_forwarded";
+    }
+    for i in 1..=app.pins.len() {
+        header += &format!(",pin_{}", i);
+    }
+    let pins = app
+        .pins
+        .iter()
+        .map(|pin| pin.frequencies(&app.data))
+        .collect::<Vec<_>>();
+    writeln!(out, "{}", header)?;
+    for (i, Bucket { label, count, .. }) in app.data.iter().enumerate() {
+        match &expected {
+            Some(expected) => write!(out, "{},{},{}", label, count, expected[i])?,
+            None => write!(out, "{},{},", label, count)?,
+        }
+        if let Some(means) = &means {
+            write!(out, ",{}", means[i])?;
+        }
+        if let Some(bins) = &app.bins {
+            let bin = bins.bin_of(i).map_or("", |bin| bins.labels()[bin].as_str());
+            write!(out, ",{}", bin)?;
+        }
+        // Left empty for a bucket without samples, whose error is unbounded.
+        if let Some(errors) = &errors {
+            match errors[i].is_finite() {
+                true => write!(out, ",{}", errors[i])?,
+                false => write!(out, ",")?,
+            }
+        }
+        if let Some(spread) = &spread {
+            let Spread { min, median, max } = spread[i];
+            write!(out, ",{},{},{}", min, median, max)?;
+        }
+        if let Some(forwarded) = forwarded {
+            write!(out, ",{}", forwarded[i])?;
+        }
+        for pin in &pins {
+            write!(out, ",{}", pin[i])?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// One row per bucket and a column per seed, the run's own seed first, for
+/// looking at the `--seeds` runs one by one.
+pub fn write_seeds_csv<W: Write>(out: &mut W, app: &App, seeds: &SeedRuns) -> io::Result<()> {
+    write_meta(out, app)?;
+    write!(out, "bucket,seed_{}", app.seed)?;
+    for (seed, _) in seeds.runs() {
+        write!(out, ",seed_{}", seed)?;
+    }
+    writeln!(out)?;
+    let runs = seeds
+        .runs()
+        .map(|(_, sim)| sim.counts_slice())
+        .collect::<Vec<_>>();
+    for (i, Bucket { label, count, .. }) in app.data.iter().enumerate() {
+        write!(out, "{},{}", label, count)?;
+        for data in &runs {
+            // Left empty while a change of steps hasn't reached the runs.
+            match data.get(i).filter(|_| data.len() == app.data.len()) {
+                Some(count) => write!(out, ",{}", count)?,
+                None => write!(out, ",")?,
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// A row per sum each `--overlay` source's walks came to, or for a
+/// continuous source the middle of each bucket of them, with its z-score by
+/// the source's own mean and variance and its count.
+pub fn write_overlay_csv<W: Write>(out: &mut W, app: &App, overlay: &Overlay) -> io::Result<()> {
+    write_meta(out, app)?;
+    writeln!(out, "source,sum,z,count")?;
+    for series in overlay.series() {
+        let name = series.source.name();
+        for (sum, count) in series.counts() {
+            writeln!(out, "{},{},{},{}", name, sum, series.z(sum), count)?;
+        }
+    }
+    Ok(())
+}
+
+/// The export has the same shape as a stream tick event, so it can be replayed.
+/// The model's expected counts come with the observed ones, so a goodness of
+/// fit can be worked out without the model's formulas; both are null for an
+/// aggregate with no model.
+pub fn to_json(app: &App) -> Value {
+    let expected = app.expected();
+    let model = app.model();
+    let mut fields = vec![
+        ("tick", app.tick.into()),
+        ("samples", app.b_count.into()),
+        ("r_max", app.r_max.into()),
+        ("seed", app.seed.to_string().into()),
+        ("rng", app.sim.generator().name().into()),
+        ("cumulative", app.mode.is_cumulative().into()),
+        ("mode", app.mode.name().into()),
+        ("sampling", app.sim.regime().name().into()),
+        ("aggregate", app.sim.aggregate().name().into()),
+        (
+            "buckets",
+            app.data
+                .iter()
+                .map(|bucket| bucket.label.as_str())
+                .collect::<Vec<_>>()
+                .into(),
+        ),
+        (
+            "counts",
+            app.data
+                .iter()
+                .map(|bucket| bucket.count)
+                .collect::<Vec<_>>()
+                .into(),
+        ),
+        (
+            "expected",
+            expected.clone().map_or(Value::Null, Value::from),
+        ),
+        (
+            "model",
+            match &expected {
+                Some(_) => Value::object(vec![
+                    ("name", model.name().into()),
+                    ("steps", app.r_max.into()),
+                    ("p", app.bias().into()),
+                    ("mean", app.mean().into()),
+                    ("sd", app.sd().into()),
+                ]),
+                None => Value::Null,
+            },
+        ),
+    ];
+    // The buckets stay sums, so the export still replays.
+    if let Some(means) = means(app) {
+        fields.push(("means", means.into()));
+    }
+    // Replayed, the model follows the bias.
+    if app.bias() != sampling::FAIR {
+        fields.push(("bias", app.bias().into()));
+    }
+    if let Some(sweep) = &app.bias_sweep {
+        fields.push((
+            "bias_sweep",
+            Value::object(vec![
+                ("from", sweep.from().into()),
+                ("to", sweep.to().into()),
+                ("period", sweep.period().into()),
+                ("held", sweep.held().into()),
+            ]),
+        ));
+    }
+    if let Some(warm) = &app.warm {
+        fields.push(("warm_start", warm.files.clone().into()));
+    }
+    if let Some(cycles) = &app.cycles {
+        fields.push(("cycle", cycles.to_json()));
+    }
+    if app.precision_shown() {
+        let (converged, occupied) = app.converged();
+        // An empty bucket's unbounded error is written as null.
+        fields.push((
+            "precision",
+            Value::object(vec![
+                ("target_percent", app.precision_percent().into()),
+                ("converged", converged.into()),
+                ("occupied", occupied.into()),
+                (
+                    "percent",
+                    match occupied {
+                        0 => Value::Null,
+                        _ => (converged as f64 / occupied as f64 * 100.0).into(),
+                    },
+                ),
+                ("relative_se", relative_errors(app).into()),
+            ]),
+        ));
+    }
+    // Already in the counts; the part of them that wasn't simulated.
+    if let Some(forwarded) = fast_forwarded(app) {
+        fields.push((
+            "fast_forwarded",
+            Value::object(vec![
+                ("samples", stats::total(forwarded.iter().copied()).into()),
+                ("counts", forwarded.to_vec().into()),
+            ]),
+        ));
+    }
+    // The buckets stay as they are, so the export still replays; the merged
+    // bars are described alongside them.
+    if let Some(bins) = &app.bins {
+        let counts = app
+            .data
+            .iter()
+            .map(|bucket| bucket.count)
+            .collect::<Vec<_>>();
+        fields.push((
+            "bins",
+            Value::object(vec![
+                ("edges", bins.edges().to_vec().into()),
+                ("counts", bins.sums(&counts).into()),
+                (
+                    "expected",
+                    expected
+                        .as_deref()
+                        .map_or(Value::Null, |expected| bins.sums_f64(expected).into()),
+                ),
+            ]),
+        ));
+    }
+    if let Some(seeds) = &app.seeds {
+        let mut names = vec![app.seed.to_string()];
+        names.extend(seeds.runs().map(|(seed, _)| seed.to_string()));
+        let mut spread = vec![("seeds", names.into())];
+        if let Some(buckets) = seeds.spread(&app.data) {
+            spread.extend([
+                (
+                    "min",
+                    buckets.iter().map(|s| s.min).collect::<Vec<_>>().into(),
+                ),
+                (
+                    "median",
+                    buckets.iter().map(|s| s.median).collect::<Vec<_>>().into(),
+                ),
+                (
+                    "max",
+                    buckets.iter().map(|s| s.max).collect::<Vec<_>>().into(),
+                ),
+            ]);
+        }
+        fields.push(("seeds", Value::object(spread)));
+    }
+    if let Some(overlay) = &app.overlay {
+        let series = overlay
+            .series()
+            .iter()
+            .map(|series| {
+                let (sums, counts): (Vec<_>, Vec<_>) = series.counts().unzip();
+                Value::object(vec![
+                    ("source", series.source.name().into()),
+                    ("mean", series.source.mean().into()),
+                    ("variance", series.source.variance().into()),
+                    ("samples", series.samples().into()),
+                    ("sums", sums.into()),
+                    ("counts", counts.into()),
+                ])
+            })
+            .collect::<Vec<_>>();
+        fields.push(("overlay", series.into()));
+    }
+    if let Some(check) = &app.rng_check {
+        fields.push((
+            "rng_check",
+            Value::object(vec![
+                ("frequency_p", check.frequency.into()),
+                ("runs_p", check.runs.into()),
+                ("passed", check.passed().into()),
+            ]),
+        ));
+    }
+    if !app.pins.is_empty() {
+        fields.push((
+            "pins",
+            app.pins.iter().map(Pin::to_json).collect::<Vec<_>>().into(),
+        ));
+    }
+    // Every step drawn, as a check on the sampler, not only those counted
+    // in this histogram.
+    let balance = app.sim.balance();
+    fields.push((
+        "balance",
+        Value::object(vec![
+            ("up_fraction", balance.up_fraction().into()),
+            ("expected", balance.expected_fraction().into()),
+            ("steps", balance.steps().into()),
+            ("z", balance.z().into()),
+            ("drifted", app.drifted.into()),
+        ]),
+    ));
+    // How long the ticks took to sample, for telling jitter from load.
+    if app.perf.tick_times().ticks() > 0 {
+        fields.push(("tick_times", app.perf.tick_times().to_json()));
+    }
+    if let Some(alert) = &app.alert {
+        fields.push((
+            "alert",
+            Value::object(vec![
+                ("condition", alert.condition.to_string().into()),
+                ("tick", alert.fired.map_or(Value::Null, Value::from)),
+            ]),
+        ));
+    }
+    fields.push(("meta", meta(app)));
+    Value::object(fields)
+}
+
+/// The parts of a JSON export that are read back in.
+pub struct Histogram {
+    pub r_max: i32,
+    pub labels: Vec<String>,
+    pub counts: Vec<u64>,
+    /// The exports the run was warm-started from, if any.
+    pub warm_start: Vec<String>,
+}
+
+/// Reads an export written by `--output-dir`.
+pub fn read(path: &Path) -> Result<Histogram, String> {
+    parse(&fs::read_to_string(path).map_err(|err| err.to_string())?)
+}
+
+/// Reads the parts of a JSON export that are read back in.
+pub fn parse(contents: &str) -> Result<Histogram, String> {
+    let export = json::parse(contents)?;
+    let field = |name: &str| export.get(name).ok_or(format!("missing field '{}'", name));
+
+    let r_max = field("r_max")?.as_f64().ok_or("'r_max' is not a number")? as i32;
+    let labels = field("buckets")?
+        .as_array()
+        .ok_or("'buckets' is not an array")?
+        .iter()
+        .map(|label| {
+            label
+                .as_str()
+                .map(str::to_string)
+                .ok_or("bucket label is not a string")
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let counts = field("counts")?
+        .as_array()
+        .ok_or("'counts' is not an array")?
+        .iter()
+        .map(|count| count.as_u64().ok_or("bucket count is not a count"))
+        .collect::<Result<Vec<_>, _>>()?;
+    if counts.len() != labels.len() {
+        return Err("'buckets' and 'counts' differ in length".to_string());
+    }
+    let warm_start = match export.get("warm_start").and_then(Value::as_array) {
+        Some(files) => files
+            .iter()
+            .filter_map(|file| file.as_str().map(str::to_string))
+            .collect(),
+        None => vec![],
+    };
+    Ok(Histogram {
+        r_max,
+        labels,
+        counts,
+        warm_start,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mode::Mode, provenance};
+    use central_limit::rng::Generator;
+    use crossterm::event::KeyCode;
+
+    /// Seeded runs with each model, fair and biased, with tails merged.
+    fn apps() -> Vec<App> {
+        let mut apps = vec![];
+        for keys in [&[][..], &[KeyCode::Char('e')], &[KeyCode::Char('m')]] {
+            for bias in [sampling::FAIR, 0.3] {
+                let mut app = App::new(173, Generator::Std);
+                app.sim.set_bias(bias).unwrap();
+                app.on_key(KeyCode::Char('c'));
+                for _ in 0..3 {
+                    app.on_tick();
+                }
+                for &key in keys {
+                    app.on_key(key);
+                }
+                apps.push(app);
+            }
+        }
+        assert_eq!(apps.iter().filter(|app| app.bins.is_some()).count(), 2);
+        apps
+    }
+
+    fn assert_sums_to(expected: impl Iterator<Item = f64>, total: u64) {
+        let sum = expected.sum::<f64>();
+        assert!(
+            (sum - total as f64).abs() <= 1e-9 * total as f64,
+            "{} expected of {}",
+            sum,
+            total
+        );
+    }
+
+    #[test]
+    fn the_csv_expects_as_many_samples_as_it_counts() {
+        for app in apps() {
+            let mut out = vec![];
+            write_csv(&mut out, &app).unwrap();
+            let csv = String::from_utf8(out).unwrap();
+            let rows = csv
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .skip(1)
+                .map(|line| line.split(',').collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            let total = rows.iter().map(|row| row[1].parse::<u64>().unwrap()).sum();
+            assert_eq!(total, app.sim.samples());
+            assert_sums_to(rows.iter().map(|row| row[2].parse().unwrap()), total);
+        }
+    }
+
+    #[test]
+    fn the_json_expects_as_many_samples_as_it_counts() {
+        for app in apps() {
+            let json = json::parse(&to_json(&app).to_string()).unwrap();
+            let numbers = |value: &Value| {
+                value
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|n| n.as_f64().unwrap())
+                    .collect::<Vec<_>>()
+            };
+            let total = numbers(json.get("counts").unwrap()).iter().sum::<f64>() as u64;
+            assert_sums_to(numbers(json.get("expected").unwrap()).into_iter(), total);
+            assert_eq!(json.get("bins").is_some(), app.bins.is_some());
+            if let Some(bins) = json.get("bins") {
+                let counts = numbers(bins.get("counts").unwrap());
+                assert_eq!(counts.iter().sum::<f64>() as u64, total);
+                assert_sums_to(numbers(bins.get("expected").unwrap()).into_iter(), total);
+            }
+        }
+    }
+
+    #[test]
+    fn every_overlaid_source_is_exported() {
+        let mut app = App::new(171, Generator::Std);
+        let sources = crate::overlay::Source::parse_list("coin,die6,uniform01").unwrap();
+        app.overlay = Some(Overlay::new(&sources, 171, Generator::Std));
+        app.set_params(1_000, 3).unwrap();
+        app.on_tick();
+
+        let json = json::parse(&to_json(&app).to_string()).unwrap();
+        let series = json.get("overlay").unwrap().as_array().unwrap();
+        let names = series
+            .iter()
+            .map(|s| s.get("source").and_then(Value::as_str).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["coin", "die6", "uniform01"]);
+        for s in series {
+            let counts = s.get("counts").unwrap().as_array().unwrap();
+            let total = counts.iter().map(|n| n.as_f64().unwrap()).sum::<f64>();
+            assert_eq!(total, 1_000.0);
+            assert_eq!(s.get("samples").and_then(Value::as_f64), Some(1_000.0));
+        }
+        let die = series[1].get("sums").unwrap().as_array().unwrap();
+        assert!(die
+            .iter()
+            .all(|sum| (3.0..=18.0).contains(&sum.as_f64().unwrap())));
+        // Three uniform steps sum to anything from 0 to 3, counted into
+        // buckets a tenth of a standard deviation wide.
+        let uniform = series[2].get("sums").unwrap().as_array().unwrap();
+        assert!(uniform.len() > 20);
+        assert!(uniform
+            .iter()
+            .all(|sum| (-0.1..=3.1).contains(&sum.as_f64().unwrap())));
+
+        let mut out = vec![];
+        write_overlay_csv(&mut out, &app, app.overlay.as_ref().unwrap()).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let rows = csv
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect::<Vec<_>>();
+        assert_eq!(rows[0], "source,sum,z,count");
+        // A coin's 3 steps sum to -3, -1, 1 or 3, a z of -√3 to √3.
+        assert!(rows[1].starts_with("coin,-3,-1.73"));
+        let coin = rows.iter().filter(|row| row.starts_with("coin,")).count();
+        assert_eq!(coin, 4);
+    }
+
+    #[test]
+    fn means_are_exported_beside_the_sums() {
+        let mut app = App::new(173, Generator::Std);
+        app.set_params(1_000, 4).unwrap();
+        app.sim.set_aggregate(Aggregate::Mean).unwrap();
+        app.on_tick();
+
+        let json = json::parse(&to_json(&app).to_string()).unwrap();
+        assert_eq!(json.get("aggregate").and_then(Value::as_str), Some("mean"));
+        let buckets = json.get("buckets").unwrap().as_array().unwrap();
+        assert_eq!(buckets[0].as_str(), Some("-4"));
+        let means = json.get("means").unwrap().as_array().unwrap();
+        let means = means
+            .iter()
+            .map(|mean| mean.as_f64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(means, [-1.0, -0.5, 0.0, 0.5, 1.0]);
+
+        let mut out = vec![];
+        write_csv(&mut out, &app).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        assert!(csv.contains("# aggregate: mean\n"));
+        assert!(csv.contains("bucket,count,expected,mean\n"));
+        assert!(csv.contains("\n-2,") && csv.contains(",-0.5\n"));
+
+        app.sim.set_aggregate(Aggregate::Sum).unwrap();
+        let json = json::parse(&to_json(&app).to_string()).unwrap();
+        assert_eq!(json.get("aggregate").and_then(Value::as_str), Some("sum"));
+        assert!(json.get("means").is_none());
+    }
+
+    #[test]
+    fn an_export_has_what_it_takes_to_draw_it_again() {
+        let mut app = App::new(180, Generator::Pcg32);
+        app.set_params(700, 15).unwrap();
+        app.sim.set_exact_below(10);
+        app.on_key(KeyCode::Char('c'));
+        app.note_changes(provenance::Source::Cli);
+        for _ in 0..4 {
+            app.on_tick();
+        }
+        let written = to_json(&app).to_string();
+
+        let export = json::parse(&written).unwrap();
+        let params = export
+            .get("meta")
+            .and_then(|meta| meta.get("params"))
+            .unwrap();
+        let param = |name: &str| {
+            let value = params.get(name).and_then(|param| param.get("value"));
+            value.and_then(Value::as_str).unwrap().to_string()
+        };
+        let ticks = export.get("tick").and_then(Value::as_u64).unwrap();
+        let mut again = App::new(
+            param("seed").parse().unwrap(),
+            Generator::parse(&param("rng")).unwrap(),
+        );
+        again
+            .set_params(
+                param("samples").parse().unwrap(),
+                param("r_max").parse().unwrap(),
+            )
+            .unwrap();
+        again
+            .sim
+            .set_exact_below(param("exact_below").parse().unwrap());
+        if param("mode") == Mode::Cumulative.name() {
+            again.set_mode(Mode::Cumulative);
+        }
+        for _ in 0..ticks {
+            again.on_tick();
+        }
+        let counts = again
+            .data
+            .iter()
+            .map(|bucket| bucket.count)
+            .collect::<Vec<_>>();
+        assert_eq!(counts, parse(&written).unwrap().counts);
+        assert_eq!(counts.iter().sum::<u64>(), 2_800);
+    }
+}
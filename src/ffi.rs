@@ -0,0 +1,277 @@
+//! A C API to the simulation, for embedding it where Rust can't be linked
+//! directly: a `Simulation` behind an opaque pointer, seeded explicitly, and
+//! its counts copied out into a caller's buffer. `include/central_limit.h`
+//! declares it, and the library builds as a `cdylib` and `staticlib` to link
+//! against.
+//!
+//! Every function takes the pointer `clt_simulation_new` returned, and does
+//! nothing, or returns 0, when given a null one. Passing it to any function
+//! after `clt_simulation_free` is undefined behavior.
+
+use crate::{bucket::Aggregate, simulation::Simulation};
+use std::{ffi::c_int, slice};
+
+/// `Stats`, laid out for C.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CltStats {
+    pub total: u64,
+    pub mean: f64,
+    pub sd: f64,
+    pub ks: f64,
+}
+
+/// A simulation of `steps`-step walks seeded from `seed`, or null if
+/// `steps` is less than 1. Free it with `clt_simulation_free`.
+#[no_mangle]
+pub extern "C" fn clt_simulation_new(steps: i32, seed: u64) -> *mut Simulation {
+    match Simulation::new(steps, seed) {
+        Ok(sim) => Box::into_raw(Box::new(sim)),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Frees a simulation from `clt_simulation_new`.
+///
+/// # Safety
+///
+/// `sim` is null or from `clt_simulation_new`, and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn clt_simulation_free(sim: *mut Simulation) {
+    if !sim.is_null() {
+        drop(Box::from_raw(sim));
+    }
+}
+
+/// Draws steps that are +1 with probability `p`: 0, or -1 if `p` isn't
+/// between 0 and 1.
+///
+/// # Safety
+///
+/// `sim` is null or a live pointer from `clt_simulation_new`.
+#[no_mangle]
+pub unsafe extern "C" fn clt_simulation_set_bias(sim: *mut Simulation, p: f64) -> c_int {
+    match sim.as_mut().map(|sim| sim.set_bias(p)) {
+        Some(Ok(())) => 0,
+        _ => -1,
+    }
+}
+
+/// Counts each sample's `aggregate` from now on: 0 for the sum, 1 the mean,
+/// 2 the max, 3 the min and 4 the median of its positions. Returns 0, or -1
+/// for an unknown aggregate or one the walks are too long for.
+///
+/// # Safety
+///
+/// `sim` is null or a live pointer from `clt_simulation_new`.
+#[no_mangle]
+pub unsafe extern "C" fn clt_simulation_set_aggregate(
+    sim: *mut Simulation,
+    aggregate: c_int,
+) -> c_int {
+    let aggregate = match aggregate {
+        0 => Aggregate::Sum,
+        1 => Aggregate::Mean,
+        2 => Aggregate::Max,
+        3 => Aggregate::Min,
+        4 => Aggregate::Median,
+        _ => return -1,
+    };
+    match sim.as_mut().map(|sim| sim.set_aggregate(aggregate)) {
+        Some(Ok(())) => 0,
+        _ => -1,
+    }
+}
+
+/// Draws `samples` more samples and ends the tick.
+///
+/// # Safety
+///
+/// `sim` is null or a live pointer from `clt_simulation_new`.
+#[no_mangle]
+pub unsafe extern "C" fn clt_simulation_advance(sim: *mut Simulation, samples: usize) {
+    if let Some(sim) = sim.as_mut() {
+        sim.advance(samples);
+    }
+}
+
+/// Clears the counts, keeping the random sequence.
+///
+/// # Safety
+///
+/// `sim` is null or a live pointer from `clt_simulation_new`.
+#[no_mangle]
+pub unsafe extern "C" fn clt_simulation_reset(sim: *mut Simulation) {
+    if let Some(sim) = sim.as_mut() {
+        sim.reset();
+    }
+}
+
+/// How many buckets there are, which is as long as `out` must be for
+/// `clt_simulation_counts` and `clt_simulation_centers` to fill it.
+///
+/// # Safety
+///
+/// `sim` is null or a live pointer from `clt_simulation_new`.
+#[no_mangle]
+pub unsafe extern "C" fn clt_simulation_buckets(sim: *const Simulation) -> usize {
+    sim.as_ref().map_or(0, |sim| sim.counts_slice().len())
+}
+
+/// Copies up to `len` of the bucket counts into `out`, lowest bucket first,
+/// and returns how many buckets there are.
+///
+/// # Safety
+///
+/// `sim` is null or a live pointer from `clt_simulation_new`, and `out` is
+/// null or has room for `len` counts.
+#[no_mangle]
+pub unsafe extern "C" fn clt_simulation_counts(
+    sim: *const Simulation,
+    out: *mut u64,
+    len: usize,
+) -> usize {
+    let Some(sim) = sim.as_ref() else {
+        return 0;
+    };
+    let counts = sim.counts_slice();
+    if !out.is_null() {
+        let n = len.min(counts.len());
+        slice::from_raw_parts_mut(out, n).copy_from_slice(&counts[..n]);
+    }
+    counts.len()
+}
+
+/// Like `clt_simulation_counts`, for the sum or value in the middle of each
+/// bucket.
+///
+/// # Safety
+///
+/// As for `clt_simulation_counts`.
+#[no_mangle]
+pub unsafe extern "C" fn clt_simulation_centers(
+    sim: *const Simulation,
+    out: *mut f64,
+    len: usize,
+) -> usize {
+    let Some(sim) = sim.as_ref() else {
+        return 0;
+    };
+    let buckets = sim.buckets();
+    let count = buckets.len();
+    if !out.is_null() {
+        let out = slice::from_raw_parts_mut(out, len.min(count));
+        for (x, bucket) in out.iter_mut().zip(buckets) {
+            *x = bucket.center();
+        }
+    }
+    count
+}
+
+/// Samples counted since the last reset.
+///
+/// # Safety
+///
+/// `sim` is null or a live pointer from `clt_simulation_new`.
+#[no_mangle]
+pub unsafe extern "C" fn clt_simulation_samples(sim: *const Simulation) -> u64 {
+    sim.as_ref().map_or(0, Simulation::samples)
+}
+
+/// The histogram's statistics, all NaN but the total before there are any
+/// samples, or for a null `sim`.
+///
+/// # Safety
+///
+/// `sim` is null or a live pointer from `clt_simulation_new`.
+#[no_mangle]
+pub unsafe extern "C" fn clt_simulation_stats(sim: *const Simulation) -> CltStats {
+    match sim.as_ref().map(Simulation::stats) {
+        Some(stats) => CltStats {
+            total: stats.total,
+            mean: stats.mean,
+            sd: stats.sd,
+            ks: stats.ks,
+        },
+        None => CltStats {
+            total: 0,
+            mean: f64::NAN,
+            sd: f64::NAN,
+            ks: f64::NAN,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What a C caller gets from `ticks` ticks of `samples` on `seed`.
+    fn run(seed: u64, ticks: usize, samples: usize) -> (Vec<u64>, Vec<f64>, CltStats) {
+        unsafe {
+            let sim = clt_simulation_new(19, seed);
+            assert!(!sim.is_null());
+            for _ in 0..ticks {
+                clt_simulation_advance(sim, samples);
+            }
+            let n = clt_simulation_buckets(sim);
+            let (mut counts, mut centers) = (vec![0; n], vec![0.0; n]);
+            assert_eq!(clt_simulation_counts(sim, counts.as_mut_ptr(), n), n);
+            assert_eq!(clt_simulation_centers(sim, centers.as_mut_ptr(), n), n);
+            let stats = clt_simulation_stats(sim);
+            clt_simulation_free(sim);
+            (counts, centers, stats)
+        }
+    }
+
+    #[test]
+    fn a_fixed_seed_draws_the_same_histogram_every_time() {
+        let (counts, centers, stats) = run(194, 5, 2_000);
+        assert_eq!(run(194, 5, 2_000), (counts.clone(), centers.clone(), stats));
+        assert_ne!(run(195, 5, 2_000).0, counts);
+
+        // The same as the simulation draws from Rust.
+        let mut sim = Simulation::new(19, 194).unwrap();
+        for _ in 0..5 {
+            sim.advance(2_000);
+        }
+        assert_eq!(counts, sim.counts_slice());
+        assert_eq!(
+            centers,
+            sim.buckets().map(|b| b.center()).collect::<Vec<_>>()
+        );
+        assert_eq!(stats.total, 10_000);
+        let expected = sim.stats();
+        assert_eq!(
+            (stats.mean, stats.sd, stats.ks),
+            (expected.mean, expected.sd, expected.ks)
+        );
+    }
+
+    #[test]
+    fn bad_arguments_are_refused_rather_than_trusted() {
+        assert!(clt_simulation_new(0, 1).is_null());
+        unsafe {
+            let none = std::ptr::null_mut();
+            clt_simulation_advance(none, 10);
+            assert_eq!(clt_simulation_buckets(none), 0);
+            assert_eq!(clt_simulation_set_bias(none, 0.5), -1);
+            assert!(clt_simulation_stats(none).mean.is_nan());
+            clt_simulation_free(none);
+
+            let sim = clt_simulation_new(4, 1);
+            assert_eq!(clt_simulation_set_bias(sim, 1.5), -1);
+            assert_eq!(clt_simulation_set_aggregate(sim, 9), -1);
+            assert_eq!(clt_simulation_set_aggregate(sim, 2), 0);
+            clt_simulation_advance(sim, 100);
+            assert_eq!(clt_simulation_samples(sim), 100);
+            // A short buffer gets what fits, and the count says what's missing.
+            let mut counts = [0; 2];
+            assert_eq!(clt_simulation_counts(sim, counts.as_mut_ptr(), 2), 5);
+            assert_eq!(clt_simulation_counts(sim, std::ptr::null_mut(), 0), 5);
+            clt_simulation_reset(sim);
+            assert_eq!(clt_simulation_samples(sim), 0);
+            clt_simulation_free(sim);
+        }
+    }
+}
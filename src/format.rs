@@ -0,0 +1,254 @@
+//! Numbers as people read them, in the TUI and the text output. Exports and
+//! streams keep the raw numbers; nothing here is meant to be parsed back.
+//!
+//! The output is the same whatever the language or the system's locale: the
+//! decimal point is always a point, and digits are grouped with thin spaces,
+//! which no locale mistakes for a decimal point.
+
+use std::time::Duration;
+
+/// Shown for a value there isn't one of, such as the ETA while paused.
+pub const UNDEFINED: &str = "—";
+
+const PREFIXES: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+
+/// A count to three significant digits with an SI prefix: `999`, `1k`,
+/// `1.05M`, `18.4E`.
+pub fn si(n: u64) -> String {
+    match n {
+        0..=999 => n.to_string(),
+        _ => prefixed(n as f64),
+    }
+}
+
+/// Like `si`, for a rate or another value with a fraction: `2.5`, `2.49M`.
+pub fn si_f64(x: f64) -> String {
+    if !x.is_finite() {
+        return UNDEFINED.to_string();
+    }
+    match x.abs() {
+        magnitude if magnitude < 999.5 => trim(sig(x, 3)),
+        _ => prefixed(x),
+    }
+}
+
+/// `x` divided down to the largest prefix that leaves at least 1.
+fn prefixed(x: f64) -> String {
+    let mut value = x;
+    for prefix in PREFIXES {
+        value /= 1000.0;
+        // Rounding can carry 999.96k up to 1000k, which is 1M.
+        let decimals = match value.abs() {
+            v if v < 9.995 => 2,
+            v if v < 99.95 => 1,
+            v if v < 999.5 => 0,
+            _ => continue,
+        };
+        return trim(format!("{:.*}", decimals, value)) + prefix;
+    }
+    format!("{:.0}E", value * 1000.0)
+}
+
+/// Drops a fraction's trailing zeros, and the point if nothing's left.
+fn trim(s: String) -> String {
+    match s.contains('.') {
+        true => s.trim_end_matches('0').trim_end_matches('.').to_string(),
+        false => s,
+    }
+}
+
+/// A count in at most four characters, for labels that have no more room:
+/// `999`, `1.2k`, `12k`, `3.4M`.
+pub fn compact(n: u64) -> String {
+    if n < 1000 {
+        return n.to_string();
+    }
+    let mut value = n as f64;
+    for prefix in PREFIXES {
+        value /= 1000.0;
+        if value < 9.95 {
+            return format!("{:.1}{}", value, prefix);
+        } else if value < 999.5 {
+            return format!("{:.0}{}", value, prefix);
+        }
+    }
+    format!("{:.0}E", value)
+}
+
+/// Every digit of a count, in groups of three: `1 048 576`.
+pub fn grouped(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() * 2);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push('\u{2009}');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+/// `x` to `digits` significant digits, but never in exponent form, so a
+/// large number keeps all of its integer digits: `0.00123`, `1.23`, `12346`.
+pub fn sig(x: f64, digits: u32) -> String {
+    if !x.is_finite() {
+        return UNDEFINED.to_string();
+    }
+    if x == 0.0 {
+        return format!("{:.*}", digits.saturating_sub(1) as usize, 0.0);
+    }
+    let magnitude = x.abs().log10().floor() as i32;
+    let decimals = (digits as i32 - 1 - magnitude).max(0) as usize;
+    let rounded = format!("{:.*}", decimals, x);
+    // Rounding up to the next power of ten leaves one digit too many.
+    let carried = rounded
+        .trim_start_matches('-')
+        .trim_start_matches(['0', '.'])
+        .chars()
+        .filter(char::is_ascii_digit)
+        .count()
+        > digits as usize;
+    match carried && decimals > 0 {
+        true => format!("{:.*}", decimals - 1, x),
+        false => rounded,
+    }
+}
+
+/// A duration in the most readable of µs, ms and s.
+pub fn duration(d: Duration) -> String {
+    let micros = d.as_secs_f64() * 1e6;
+    if micros < 1000.0 {
+        format!("{:.0}µs", micros)
+    } else if micros < 1e6 {
+        format!("{:.1}ms", micros / 1000.0)
+    } else {
+        format!("{:.2}s", micros / 1e6)
+    }
+}
+
+/// `500ms`, or seconds from one second up: `1.5s`.
+pub fn tick_rate(rate: Duration) -> String {
+    if rate.as_millis() < 1000 {
+        format!("{}ms", rate.as_millis())
+    } else {
+        format!("{:.1}s", rate.as_secs_f64())
+    }
+}
+
+/// A duration still to go, as `42s`, `3m 05s` or `2h 10m`.
+pub fn eta(eta: Duration) -> String {
+    let secs = eta.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    }
+}
+
+/// A countdown as a clock reads, in whole seconds rounded up: `3:42`,
+/// `1:03:42`.
+pub fn countdown(left: Duration) -> String {
+    let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+    match secs {
+        0..3600 => format!("{}:{:02}", secs / 60, secs % 60),
+        _ => format!("{}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60),
+    }
+}
+
+/// A byte count as B, KiB, MiB or GiB.
+pub fn bytes(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{}B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1}KiB", bytes as f64 / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1}MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1}GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn si_across_the_boundaries() {
+        assert_eq!(si(0), "0");
+        assert_eq!(si(999), "999");
+        assert_eq!(si(1_000), "1k");
+        assert_eq!(si(1_049), "1.05k");
+        assert_eq!(si(999_499), "999k");
+        assert_eq!(si(999_999), "1M");
+        assert_eq!(si(1_000_000), "1M");
+        assert_eq!(si(1_048_576), "1.05M");
+        assert_eq!(si(u64::MAX), "18.4E");
+    }
+
+    #[test]
+    fn si_f64_across_the_boundaries() {
+        assert_eq!(si_f64(2.5), "2.5");
+        assert_eq!(si_f64(999.0), "999");
+        assert_eq!(si_f64(999.7), "1k");
+        assert_eq!(si_f64(2_493_081.3), "2.49M");
+        assert_eq!(si_f64(-1_500.0), "-1.5k");
+        assert_eq!(si_f64(f64::NAN), UNDEFINED);
+        assert_eq!(si_f64(f64::INFINITY), UNDEFINED);
+    }
+
+    #[test]
+    fn compact_fits_in_four_characters() {
+        assert_eq!(compact(999), "999");
+        assert_eq!(compact(1_000), "1.0k");
+        assert_eq!(compact(9_949), "9.9k");
+        assert_eq!(compact(9_950), "10k");
+        assert_eq!(compact(999_999), "1.0M");
+        assert_eq!(compact(1_000_000), "1.0M");
+        assert_eq!(compact(u64::MAX), "18E");
+        for n in [999, 1_000, 99_999, 999_999, 1_000_000, u64::MAX] {
+            assert!(compact(n).len() <= 4, "{} is {}", n, compact(n));
+        }
+    }
+
+    #[test]
+    fn grouped_keeps_every_digit() {
+        assert_eq!(grouped(999), "999");
+        assert_eq!(grouped(1_000), "1\u{2009}000");
+        assert_eq!(grouped(999_999), "999\u{2009}999");
+        assert_eq!(grouped(1_000_000), "1\u{2009}000\u{2009}000");
+        assert_eq!(
+            grouped(u64::MAX).replace('\u{2009}', ""),
+            u64::MAX.to_string()
+        );
+    }
+
+    #[test]
+    fn sig_rounds_to_its_digits() {
+        assert_eq!(sig(0.0, 3), "0.00");
+        assert_eq!(sig(0.001234, 3), "0.00123");
+        assert_eq!(sig(1.234, 3), "1.23");
+        assert_eq!(sig(9.996, 3), "10.0");
+        assert_eq!(sig(12_345.6, 3), "12346");
+        assert_eq!(sig(-0.5, 2), "-0.50");
+        assert_eq!(sig(f64::NAN, 3), UNDEFINED);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(duration(Duration::from_micros(999)), "999µs");
+        assert_eq!(duration(Duration::from_micros(1_000)), "1.0ms");
+        assert_eq!(duration(Duration::from_secs(1)), "1.00s");
+        assert_eq!(tick_rate(Duration::from_millis(999)), "999ms");
+        assert_eq!(tick_rate(Duration::from_millis(1_000)), "1.0s");
+        assert_eq!(eta(Duration::from_secs(59)), "59s");
+        assert_eq!(eta(Duration::from_secs(60)), "1m 00s");
+        assert_eq!(eta(Duration::from_secs(3_600)), "1h 00m");
+        assert_eq!(countdown(Duration::from_millis(59_001)), "1:00");
+        assert_eq!(countdown(Duration::from_secs(3_600)), "1:00:00");
+        assert_eq!(bytes(1_023), "1023B");
+        assert_eq!(bytes(1_024), "1.0KiB");
+        assert_eq!(bytes(1024 * 1024 * 1024), "1.0GiB");
+    }
+}
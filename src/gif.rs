@@ -0,0 +1,352 @@
+//! `--export-gif`: the histogram as an animated GIF, a frame every few ticks,
+//! drawn straight to pixels rather than captured from the terminal.
+//!
+//! Frames are encoded as they're captured, so memory use doesn't grow with
+//! the length of the run.
+
+use crate::bucket::Bucket;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+pub const DEFAULT_EVERY: u64 = 5;
+pub const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+pub const DEFAULT_SIZE: (u16, u16) = (640, 360);
+
+/// Frames after which capturing stops: about 20 MB at the default size.
+pub const DEFAULT_MAX_FRAMES: u64 = 1000;
+
+const BACKGROUND: u8 = 0;
+const BAR: u8 = 1;
+const AXIS: u8 = 2;
+const TEXT: u8 = 3;
+/// Pinned histograms are drawn in the axis gray, dim beside the bars.
+const PIN: u8 = AXIS;
+
+/// Colors by the indices above, after the classic theme.
+const PALETTE: [[u8; 3]; 4] = [[0, 0, 0], [0, 205, 0], [128, 128, 128], [255, 255, 255]];
+
+/// Bits per pixel in the LZW data; GIF needs at least 2.
+const MIN_CODE_SIZE: u8 = 2;
+
+/// The largest code GIF allows.
+const MAX_CODE: u16 = 4095;
+
+pub struct GifExport {
+    path: PathBuf,
+    out: Option<BufWriter<File>>,
+    width: u16,
+    height: u16,
+    every: u64,
+    max_frames: u64,
+    /// Delay between frames, in the hundredths of a second GIF counts in.
+    delay: u16,
+    frames: u64,
+    /// The bars' full height, in counts. It only grows, so the bars don't
+    /// jump about as the peak wobbles from tick to tick.
+    ceiling: u64,
+    pixels: Vec<u8>,
+    error: Option<io::Error>,
+}
+
+impl GifExport {
+    /// Starts a `width` by `height` GIF at `path`, capturing every `every`
+    /// ticks, with `delay` between frames and at most `max_frames` of them.
+    pub fn create(
+        path: &Path,
+        (width, height): (u16, u16),
+        every: u64,
+        delay: Duration,
+        max_frames: u64,
+    ) -> io::Result<GifExport> {
+        let mut out = BufWriter::new(File::create(path)?);
+        write_header(&mut out, width, height)?;
+        // Most viewers don't honour delays under 2/100 s.
+        let delay = (delay.as_millis() / 10).clamp(2, u16::MAX as u128) as u16;
+        Ok(GifExport {
+            path: path.to_path_buf(),
+            out: Some(out),
+            width,
+            height,
+            every: every.max(1),
+            max_frames,
+            delay,
+            frames: 0,
+            ceiling: 0,
+            pixels: vec![BACKGROUND; width as usize * height as usize],
+            error: None,
+        })
+    }
+
+    /// Captures a frame of `data` after tick `tick` if one is due, with
+    /// `pins` as their shares of the samples in each of its buckets. The
+    /// first error stops capturing, and is kept for `finish`.
+    pub fn record(&mut self, tick: u64, samples: u64, data: &[Bucket], pins: &[Vec<f64>]) {
+        if !tick.is_multiple_of(self.every) || self.capped() {
+            return;
+        }
+        let Some(out) = &mut self.out else {
+            return;
+        };
+        let peak = data.iter().map(|bucket| bucket.count).max().unwrap_or(0);
+        self.ceiling = self.ceiling.max(peak);
+        draw(
+            &mut self.pixels,
+            self.width,
+            self.height,
+            data,
+            pins,
+            self.ceiling,
+            &format!("TICK {}  SAMPLES {}", tick, samples),
+        );
+        match write_frame(out, self.width, self.height, self.delay, &self.pixels) {
+            Ok(()) => self.frames += 1,
+            Err(err) => {
+                self.error = Some(err);
+                self.out = None;
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Frames written so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Whether `--gif-max-frames` stopped the capture.
+    pub fn capped(&self) -> bool {
+        self.frames >= self.max_frames
+    }
+
+    /// Ends the GIF and flushes it, returning the first error there was.
+    pub fn finish(&mut self) -> io::Result<()> {
+        let closed = match self.out.take() {
+            Some(mut out) => out.write_all(&[0x3B]).and_then(|()| out.flush()),
+            None => Ok(()),
+        };
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => closed,
+        }
+    }
+}
+
+/// The signature, the screen size and palette, and a loop-forever extension.
+fn write_header<W: Write>(out: &mut W, width: u16, height: u16) -> io::Result<()> {
+    out.write_all(b"GIF89a")?;
+    out.write_all(&width.to_le_bytes())?;
+    out.write_all(&height.to_le_bytes())?;
+    // A global palette of 2^(1 + 1) colors, 8 bits a channel.
+    out.write_all(&[0xF1, BACKGROUND, 0])?;
+    for color in PALETTE {
+        out.write_all(&color)?;
+    }
+    out.write_all(&[0x21, 0xFF, 0x0B])?;
+    out.write_all(b"NETSCAPE2.0")?;
+    out.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])
+}
+
+fn write_frame<W: Write>(
+    out: &mut W,
+    width: u16,
+    height: u16,
+    delay: u16,
+    pixels: &[u8],
+) -> io::Result<()> {
+    // Graphic control: the delay, no transparency.
+    out.write_all(&[0x21, 0xF9, 0x04, 0x00])?;
+    out.write_all(&delay.to_le_bytes())?;
+    out.write_all(&[0x00, 0x00])?;
+    // The image covers the whole screen and uses the global palette.
+    out.write_all(&[0x2C, 0, 0, 0, 0])?;
+    out.write_all(&width.to_le_bytes())?;
+    out.write_all(&height.to_le_bytes())?;
+    out.write_all(&[0x00, MIN_CODE_SIZE])?;
+
+    let data = compress(pixels);
+    for block in data.chunks(255) {
+        out.write_all(&[block.len() as u8])?;
+        out.write_all(block)?;
+    }
+    out.write_all(&[0x00])
+}
+
+/// GIF's variable-width LZW, starting over with a clear code whenever the
+/// code table fills up.
+fn compress(pixels: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut bits = Bits::default();
+    // The code for each known string extended by each pixel value, 0 if
+    // there's none yet; no string's code is ever 0.
+    let mut table = vec![[0u16; PALETTE.len()]; MAX_CODE as usize + 1];
+    let mut next = end + 1;
+    let mut size = MIN_CODE_SIZE + 1;
+
+    bits.push(clear, size);
+    let Some((&first, rest)) = pixels.split_first() else {
+        bits.push(end, size);
+        return bits.finish();
+    };
+    let mut current = first as u16;
+    for &pixel in rest {
+        let code = table[current as usize][pixel as usize];
+        if code != 0 {
+            current = code;
+            continue;
+        }
+        bits.push(current, size);
+        if next <= MAX_CODE {
+            table[current as usize][pixel as usize] = next;
+            if next >= 1 << size && size < 12 {
+                size += 1;
+            }
+            next += 1;
+        } else {
+            bits.push(clear, size);
+            table
+                .iter_mut()
+                .for_each(|codes| *codes = [0; PALETTE.len()]);
+            next = end + 1;
+            size = MIN_CODE_SIZE + 1;
+        }
+        current = pixel as u16;
+    }
+    bits.push(current, size);
+    bits.push(end, size);
+    bits.finish()
+}
+
+/// Codes packed least significant bit first, as GIF wants them.
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    pending: u32,
+    count: u8,
+}
+
+impl Bits {
+    fn push(&mut self, code: u16, size: u8) {
+        self.pending |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.bytes.push(self.pending as u8);
+            self.pending >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.pending as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Draws the bars over a baseline, with a line across each for every pin,
+/// and `caption` in the top left corner.
+fn draw(
+    pixels: &mut [u8],
+    width: u16,
+    height: u16,
+    data: &[Bucket],
+    pins: &[Vec<f64>],
+    ceiling: u64,
+    caption: &str,
+) {
+    let (width, height) = (width as usize, height as usize);
+    pixels.fill(BACKGROUND);
+    let scale = (height / 120).max(1);
+    let margin = 4 * scale;
+    let top = margin * 3 + GLYPH_HEIGHT * scale;
+    let base = height.saturating_sub(margin);
+    let mut fill = |x0: usize, y0: usize, x1: usize, y1: usize, color: u8| {
+        for y in y0..y1.min(height) {
+            let row = y * width;
+            pixels[row + x0.min(width)..row + x1.min(width)].fill(color);
+        }
+    };
+
+    let plot = width.saturating_sub(2 * margin);
+    if !data.is_empty() && base > top {
+        let slot = plot as f64 / data.len() as f64;
+        // A gap between bars once they're wide enough to spare one.
+        let gap = match slot >= 4.0 {
+            true => (slot / 5.0).max(1.0),
+            false => 0.0,
+        };
+        for (i, bucket) in data.iter().enumerate() {
+            let x0 = margin + (i as f64 * slot + gap / 2.0).round() as usize;
+            let x1 = margin + ((i + 1) as f64 * slot - gap / 2.0).round() as usize;
+            let share = bucket.count as f64 / ceiling.max(1) as f64;
+            let bar = ((base - top) as f64 * share).round() as usize;
+            fill(x0, base - bar, x1.max(x0 + 1), base, BAR);
+        }
+        // Pins are scaled to the live histogram's samples, as in the TUI.
+        let total = data.iter().map(|bucket| bucket.count).sum::<u64>() as f64;
+        for pin in pins {
+            for (i, frequency) in pin.iter().enumerate() {
+                let x0 = margin + (i as f64 * slot).round() as usize;
+                let x1 = margin + ((i + 1) as f64 * slot).round() as usize;
+                let share = (frequency * total / ceiling.max(1) as f64).min(1.0);
+                let y = base - ((base - top) as f64 * share).round() as usize;
+                fill(x0, y.saturating_sub(scale), x1.max(x0 + 1), y, PIN);
+            }
+        }
+    }
+    fill(margin, base, margin + plot, base + scale, AXIS);
+
+    let mut x = margin;
+    for c in caption.chars() {
+        if let Some(rows) = glyph(c) {
+            for (dy, row) in rows.iter().enumerate() {
+                for dx in 0..GLYPH_WIDTH {
+                    if row & (1 << (GLYPH_WIDTH - 1 - dx)) != 0 {
+                        let (px, py) = (x + dx * scale, margin + dy * scale);
+                        fill(px, py, px + scale, py + scale, TEXT);
+                    }
+                }
+            }
+        }
+        x += (GLYPH_WIDTH + 1) * scale;
+    }
+}
+
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+/// A 3 by 5 pixel font of the digits and the caption's letters, a row to
+/// a byte with the leftmost pixel in the highest bit. Spaces are blank.
+fn glyph(c: char) -> Option<[u8; GLYPH_HEIGHT]> {
+    Some(match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'C' => [0b111, 0b100, 0b100, 0b100, 0b111],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        _ => return None,
+    })
+}
@@ -0,0 +1,32 @@
+//! Golden files for the tests that draw frames, shared by the library's
+//! tests and the binary's. Each frame's expected text is kept in
+//! `tests/golden/<dir>/<name>.txt`; running the tests with
+//! `UPDATE_GOLDEN=1` writes them afresh from what's drawn.
+
+use std::{env, fs, path::PathBuf};
+
+fn path(dir: &str, name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(dir)
+        .join(format!("{}.txt", name))
+}
+
+/// Checks `frame` against the golden file `name` in `dir`.
+pub fn check(dir: &str, name: &str, frame: &str) {
+    let path = path(dir, name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, frame).unwrap();
+        return;
+    }
+    let expected =
+        fs::read_to_string(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+    assert!(
+        frame == expected,
+        "{} differs from {}:\n{}",
+        name,
+        path.display(),
+        frame
+    );
+}
@@ -0,0 +1,447 @@
+//! A TUI session driven from a test, frame by frame, through `run_app`'s own
+//! loop. Keys and resizes are queued as the terminal's events, and the clock
+//! only moves while the loop waits for them, so ticks come when the loop
+//! would run them and never while it's paused. Every frame is drawn on a
+//! `TestBackend`, so a panic in drawing is a test failure.
+
+use crate::{
+    driver::{Clock, Events},
+    events::{self, EventLog},
+    provenance, ui, App, Frames, Source,
+};
+use central_limit::rng::Generator;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::{
+    collections::VecDeque,
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tui::{backend::TestBackend, buffer::Buffer, Terminal};
+
+/// Frames run at most waiting for something, before the wait is taken to be
+/// one that never ends.
+const MAX_FRAMES: usize = 100_000;
+
+/// A clock that only moves when the loop waits, or a test moves it, such
+/// as from a tick hook to make ticks slow.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl MockClock {
+    pub fn new() -> MockClock {
+        MockClock {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
+
+/// The events a test has queued. With none, waiting for one passes the
+/// time on the clock instead.
+struct Script {
+    queue: VecDeque<Event>,
+    clock: MockClock,
+    /// Times the loop has checked for input.
+    polls: u64,
+}
+
+impl Events for Script {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        self.polls += 1;
+        if self.queue.is_empty() {
+            self.clock.advance(timeout);
+        }
+        Ok(!self.queue.is_empty())
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        self.queue
+            .pop_front()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::WouldBlock, "no event queued"))
+    }
+}
+
+pub struct Harness {
+    pub app: App,
+    terminal: Terminal<TestBackend>,
+    frames: Frames,
+    script: Script,
+    clock: MockClock,
+    quit: bool,
+}
+
+impl Harness {
+    /// A run from `seed` with the defaults, on an 80×24 screen.
+    pub fn new(seed: u64) -> Harness {
+        Harness::with(App::new(seed, Generator::Std))
+    }
+
+    /// A run of `app`, started as `main` starts one: what's set so far is
+    /// its starting point, and changes from here on are kept.
+    pub fn with(mut app: App) -> Harness {
+        app.note_changes(provenance::Source::Default);
+        app.provenance.start();
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let clock = MockClock::new();
+        let frames = Frames::new(&mut terminal, &mut app, Source::Live, &clock).unwrap();
+        let mut harness = Harness {
+            app,
+            terminal,
+            frames,
+            script: Script {
+                queue: VecDeque::new(),
+                clock: clock.clone(),
+                polls: 0,
+            },
+            clock,
+            quit: false,
+        };
+        harness.draw();
+        harness
+    }
+
+    /// Logs the session to `path`, as `--event-log` would with `args`.
+    pub fn log_to(&mut self, path: &Path, args: &[&str]) -> &mut Harness {
+        let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        self.app.events = Some(EventLog::create(path, &self.app, &args, None, false).unwrap());
+        self
+    }
+
+    pub fn press(&mut self, code: KeyCode) -> &mut Harness {
+        self.chord(code, KeyModifiers::NONE)
+    }
+
+    pub fn chord(&mut self, code: KeyCode, modifiers: KeyModifiers) -> &mut Harness {
+        self.event(Event::Key(KeyEvent::new(code, modifiers)))
+    }
+
+    /// Sends a key as Windows and the kitty protocol report it: pressed,
+    /// held down or let go.
+    pub fn key(&mut self, code: KeyCode, kind: KeyEventKind) -> &mut Harness {
+        self.event(Event::Key(KeyEvent::new_with_kind(
+            code,
+            KeyModifiers::NONE,
+            kind,
+        )))
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) -> &mut Harness {
+        self.terminal.backend_mut().resize(width, height);
+        self.event(Event::Resize(width, height))
+    }
+
+    /// Queues `event` and runs frames until the loop has read it. A tick
+    /// being drawn in chunks gets a chunk further in that frame, as it
+    /// would between two keys.
+    fn event(&mut self, event: Event) -> &mut Harness {
+        assert!(!self.quit, "the session has quit");
+        self.script.queue.push_back(event);
+        while !self.script.queue.is_empty() && !self.quit {
+            self.frame();
+        }
+        self.draw();
+        self
+    }
+
+    /// Runs frames until `n` more ticks have ended, counting one drawn
+    /// part-way, or until the loop holds them back while paused.
+    pub fn advance_ticks(&mut self, n: u64) -> &mut Harness {
+        let target = self.app.tick + n;
+        self.run_until(|app| app.pending.is_none() && (app.tick >= target || app.paused));
+        self
+    }
+
+    /// Runs frames until the next tick is at least `drawn` samples in, as
+    /// if a key had come part-way through it. Ticks are drawn a chunk a
+    /// frame, so it's as far as the first chunk to reach it.
+    pub fn part_tick(&mut self, drawn: u64) -> &mut Harness {
+        let next = self.app.tick + 1;
+        self.run_until(|app| {
+            assert!(app.tick < next, "the tick ended before {} samples", drawn);
+            app.pending
+                .is_some_and(|before| app.sim.samples().saturating_sub(before) >= drawn)
+        });
+        self
+    }
+
+    /// Runs frames until `by` has passed on the clock.
+    pub fn run_for(&mut self, by: Duration) -> &mut Harness {
+        let until = self.clock.now() + by;
+        let clock = self.clock.clone();
+        self.run_until(move |_| clock.now() >= until);
+        self
+    }
+
+    pub fn clock(&self) -> &MockClock {
+        &self.clock
+    }
+
+    /// Times the loop has checked for input so far.
+    pub fn polls(&self) -> u64 {
+        self.script.polls
+    }
+
+    fn run_until(&mut self, done: impl Fn(&App) -> bool) {
+        for _ in 0..MAX_FRAMES {
+            if self.quit || done(&self.app) {
+                self.draw();
+                return;
+            }
+            self.frame();
+        }
+        panic!("still waiting after {} frames", MAX_FRAMES);
+    }
+
+    fn frame(&mut self) {
+        self.quit = self
+            .frames
+            .frame(
+                &mut self.terminal,
+                &mut self.app,
+                None,
+                &mut self.script,
+                &self.clock,
+            )
+            .unwrap();
+    }
+
+    /// Draws what the loop's next frame would, so the last frame always
+    /// shows what's been done.
+    fn draw(&mut self) {
+        let app = &self.app;
+        self.terminal.draw(|f| ui::draw(f, app)).unwrap();
+    }
+
+    pub fn state(&self) -> &App {
+        &self.app
+    }
+
+    /// Whether a key has quit the session.
+    pub fn quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        self.terminal.backend().buffer()
+    }
+
+    /// The last frame's characters, a line per row.
+    pub fn frame_text(&self) -> String {
+        let buffer = self.buffer();
+        let area = buffer.area;
+        (area.top()..area.bottom())
+            .map(|y| {
+                let line = (area.left()..area.right())
+                    .map(|x| buffer.get(x, y).symbol.as_str())
+                    .collect::<String>();
+                line.trim_end().to_string() + "\n"
+            })
+            .collect()
+    }
+
+    /// Ends the session, writing the log's last line if it's logged.
+    pub fn finish(&mut self) {
+        events::finish(&mut self.app);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mode::Mode, ui::View};
+    use central_limit::stats;
+
+    #[test]
+    fn q_and_ctrl_c_quit() {
+        assert!(Harness::new(1).press(KeyCode::Char('q')).quit());
+        let mut harness = Harness::new(1);
+        harness.chord(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert!(harness.quit());
+        assert!(matches!(harness.app.mode, Mode::Regenerate));
+    }
+
+    #[test]
+    fn other_chords_do_nothing() {
+        let mut harness = Harness::new(1);
+        harness
+            .chord(KeyCode::Char('p'), KeyModifiers::CONTROL)
+            .chord(KeyCode::Char('c'), KeyModifiers::ALT);
+        assert!(!harness.quit());
+        assert!(!harness.app.percent);
+        assert!(matches!(harness.app.mode, Mode::Regenerate));
+        // Windows sends AltGr as Ctrl+Alt, for a character typed.
+        harness.chord(
+            KeyCode::Char('p'),
+            KeyModifiers::CONTROL | KeyModifiers::ALT,
+        );
+        assert!(harness.app.percent);
+    }
+
+    #[test]
+    fn only_presses_act() {
+        use KeyEventKind::{Press, Release, Repeat};
+        let mut harness = Harness::new(1);
+        harness.advance_ticks(1);
+        harness.key(KeyCode::Char('p'), Press);
+        assert!(harness.app.percent);
+        harness
+            .key(KeyCode::Char('p'), Repeat)
+            .key(KeyCode::Char('p'), Release);
+        assert!(harness.app.percent);
+        harness.key(KeyCode::Char('q'), Release);
+        assert!(!harness.quit());
+        harness.key(KeyCode::Char('q'), Press);
+        assert!(harness.quit());
+    }
+
+    #[test]
+    fn held_arrows_repeat() {
+        use KeyEventKind::{Press, Release, Repeat};
+        let mut harness = Harness::new(1);
+        harness.advance_ticks(1).key(KeyCode::Right, Press);
+        let middle = harness.app.data.len() / 2;
+        assert_eq!(harness.app.selected_index(), Some(middle));
+        harness
+            .key(KeyCode::Right, Repeat)
+            .key(KeyCode::Right, Repeat)
+            .key(KeyCode::Right, Release);
+        assert_eq!(harness.app.selected_index(), Some(middle + 2));
+    }
+
+    #[test]
+    fn space_holds_the_ticks() {
+        let mut harness = Harness::new(1);
+        harness
+            .advance_ticks(2)
+            .press(KeyCode::Char(' '))
+            .advance_ticks(3);
+        assert_eq!(harness.state().tick, 2);
+        // The tick held back is due as soon as they're let go.
+        harness.press(KeyCode::Char(' '));
+        assert_eq!(harness.state().tick, 3);
+        harness.advance_ticks(3);
+        assert_eq!(harness.state().tick, 6);
+    }
+
+    #[test]
+    fn c_cycles_the_modes() {
+        let mut harness = Harness::new(1);
+        harness.advance_ticks(1).press(KeyCode::Char('c'));
+        assert!(matches!(harness.app.mode, Mode::Cumulative));
+        harness.advance_ticks(2);
+        assert_eq!(harness.app.sim.samples(), 3 * harness.app.b_count as u64);
+    }
+
+    #[test]
+    fn tab_and_the_digits_switch_views() {
+        let mut harness = Harness::new(1);
+        harness.advance_ticks(1).press(KeyCode::Tab);
+        assert_eq!(harness.app.view, View::Convergence);
+        harness.press(KeyCode::Char('5'));
+        assert_eq!(harness.app.view, View::Stats);
+        assert!(harness.frame_text().contains("Samples in total"));
+        harness.press(KeyCode::BackTab).press(KeyCode::Char('1'));
+        assert_eq!(harness.app.view, View::Histogram);
+    }
+
+    #[test]
+    fn minimal_mode_drops_the_header() {
+        let mut harness = Harness::new(1);
+        harness.advance_ticks(1);
+        assert!(harness.frame_text().contains("Central Limit"));
+        harness.press(KeyCode::F(11));
+        assert!(!harness.frame_text().contains("Central Limit"));
+        harness.press(KeyCode::F(11));
+        assert!(harness.frame_text().contains("Central Limit"));
+    }
+
+    #[test]
+    fn the_settings_panel_has_the_keys_while_open() {
+        let mut harness = Harness::new(1);
+        harness.advance_ticks(1).press(KeyCode::Char('o'));
+        assert!(harness.app.settings.is_some() && harness.app.paused);
+        assert!(harness.frame_text().contains("Settings"));
+        // q still quits, but other keys are the panel's.
+        harness.press(KeyCode::Char('p'));
+        assert!(!harness.app.percent);
+        harness.press(KeyCode::Esc);
+        assert!(harness.app.settings.is_none() && !harness.app.paused);
+        assert!(!harness.frame_text().contains("Settings"));
+    }
+
+    /// A run of ticks of 200,000 samples, drawn in twenty chunks, so a few
+    /// keys still come part-way through one.
+    fn large_ticks() -> Harness {
+        let mut app = App::new(1, Generator::Std);
+        app.b_count = 200_000;
+        Harness::with(app)
+    }
+
+    /// Edits the samples per tick from 200000 down to 20 in the settings
+    /// panel.
+    fn edit_samples_to_20(harness: &mut Harness) {
+        harness.press(KeyCode::Char('o')).press(KeyCode::Enter);
+        for _ in 0..4 {
+            harness.press(KeyCode::Backspace);
+        }
+        harness.press(KeyCode::Enter);
+    }
+
+    #[test]
+    fn fewer_samples_committed_part_way_through_a_tick() {
+        let mut harness = large_ticks();
+        // 20,000 of a tick's 200,000 samples are in when the panel opens,
+        // and a chunk more for each key, and 200000 is edited down to 20.
+        harness.advance_ticks(1).part_tick(20_000);
+        edit_samples_to_20(&mut harness);
+        assert_eq!(harness.state().b_count, 20);
+        assert_eq!(harness.state().pending, None);
+        // Closing the panel runs the tick it held back, then one more.
+        harness.press(KeyCode::Esc).advance_ticks(1);
+        assert_eq!(harness.state().sim.samples(), 20);
+        assert_eq!(harness.state().samples(), 200_000 + 20 + 20);
+
+        // Counting cumulatively, the part-drawn tick is finished at 200,000
+        // instead, and every sample counted is in the total.
+        let mut harness = large_ticks();
+        harness.press(KeyCode::Char('c'));
+        harness.advance_ticks(1).part_tick(20_000);
+        edit_samples_to_20(&mut harness);
+        assert_eq!(harness.state().pending, None);
+        harness.press(KeyCode::Esc).advance_ticks(1);
+        let app = harness.state();
+        let total = stats::total(app.data.iter().map(|bucket| bucket.count));
+        assert_eq!(app.tick, 4);
+        assert_eq!(app.samples(), 200_000 + 200_000 + 20 + 20);
+        assert_eq!(total, app.samples());
+    }
+
+    #[test]
+    fn every_screen_size_draws() {
+        let mut harness = Harness::new(1);
+        harness.advance_ticks(2);
+        for (width, height) in [(1, 1), (20, 5), (40, 12), (200, 60), (80, 24)] {
+            harness.resize(width, height);
+            assert_eq!(harness.buffer().area.width, width);
+        }
+        for key in ['2', '3', '4', '5', '6', '1'] {
+            harness
+                .resize(30, 8)
+                .press(KeyCode::Char(key))
+                .resize(80, 24);
+        }
+    }
+}
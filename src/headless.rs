@@ -0,0 +1,130 @@
+//! Plain-text driver used when there is no terminal to draw on.
+
+use crate::{bucket::Bucket, format, interrupt, summary::Summary, App};
+use central_limit::{bucket::Aggregate, sampling::Regime};
+use std::io::{self, Write};
+
+pub const DEFAULT_TICKS: u64 = 10;
+
+const BAR_WIDTH: u64 = 50;
+
+/// Runs `ticks` ticks, or until the `--until` condition is met if that's
+/// sooner or `ticks` is `None`, and prints the histogram. Ctrl+C stops it
+/// between ticks, printing what there is so far; returns false if it was
+/// stopped.
+pub fn run<W: Write>(out: &mut W, app: &mut App, ticks: Option<u64>) -> io::Result<bool> {
+    interrupt::install();
+    while ticks.is_none_or(|ticks| app.tick < ticks) && !app.until_met() {
+        if interrupt::requested() {
+            break;
+        }
+        app.on_tick();
+        if let Some(err) = app.take_feedback() {
+            return Err(io::Error::other(err));
+        }
+        if std::mem::take(&mut app.bell) {
+            if let Some(alert) = app.alert.as_ref().filter(|a| a.fired == Some(app.tick)) {
+                eprintln!("note: {} at tick {}", alert.condition, app.tick);
+            }
+        }
+    }
+    if app.sim.regime() == Regime::Normal {
+        eprintln!(
+            "note: walks of {} steps or more are drawn from the normal approximation (--exact-below)",
+            app.sim.exact_below()
+        );
+    }
+    let forwarded = app.sim.fast_forwarded_samples();
+    if forwarded > 0 {
+        eprintln!(
+            "note: {} of the samples were fast-forwarded, drawn at once from the binomial model",
+            format::grouped(forwarded)
+        );
+    }
+    if let Some(drift) = app.drift_text() {
+        eprintln!("warning: {}", drift);
+    }
+    if let Some(degenerate) = app.degenerate_text() {
+        eprintln!("warning: {}", degenerate);
+    }
+    if let Some(until) = app.until.as_ref().filter(|_| app.until_met()) {
+        eprintln!(
+            "note: {} held for {} ticks, stopped at tick {}",
+            until.condition, until.needed, app.tick
+        );
+    }
+    if let Some(sweep) = &app.bias_sweep {
+        eprintln!(
+            "note: the bias swept {}; the last tick was drawn at p = {:.3}",
+            sweep.describe(),
+            app.bias()
+        );
+    }
+
+    let aggregate = match app.sim.aggregate() {
+        Aggregate::Sum => String::new(),
+        aggregate => format!(" | aggregate: {}", aggregate.name()),
+    };
+    writeln!(
+        out,
+        "Central Limit | ticks: {} | samples per tick: {} | steps: {}{}",
+        format::grouped(app.tick),
+        format::grouped(app.b_count as u64),
+        app.r_max,
+        aggregate
+    )?;
+    if !app.accessible {
+        write_histogram(out, &app.shown_data())?;
+    }
+    for line in Summary::new(&app.shown_data(), app.shown_stats(app.stats())).lines(&app.strings) {
+        writeln!(out, "{}", line)?;
+    }
+    if let (Some(seeds), Some([min, median, max])) = (&app.seeds, app.seeds_ks()) {
+        let line = app.strings.fill(
+            "summary.seeds",
+            &[
+                ("count", &seeds.count()),
+                ("median", &format!("{:.4}", median)),
+                ("min", &format!("{:.4}", min)),
+                ("max", &format!("{:.4}", max)),
+            ],
+        );
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()?;
+    if interrupt::requested() {
+        match ticks {
+            Some(ticks) => eprintln!("interrupted after {} of {} ticks", app.tick, ticks),
+            None => eprintln!("interrupted after {} ticks", app.tick),
+        }
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+pub fn write_histogram<W: Write>(out: &mut W, data: &[Bucket]) -> io::Result<()> {
+    let max = data
+        .iter()
+        .map(|bucket| bucket.count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let label_width = data
+        .iter()
+        .map(|bucket| bucket.label.len())
+        .max()
+        .unwrap_or(0);
+
+    for Bucket { label, count, .. } in data {
+        let bar = "#".repeat((count * BAR_WIDTH / max) as usize);
+        let line = format!(
+            "{:>w$} {:>8} {}",
+            label,
+            format::grouped(*count),
+            bar,
+            w = label_width
+        );
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,1232 @@
+//! The histogram view of the terminal UI as a widget, for a `tui` app of your
+//! own: the bars, and below them a line chart of the same counts against the
+//! model, with axes, a legend and the selected bucket picked out.
+//!
+//! `HistogramView` draws a `HistogramSnapshot`, taken from a `Simulation` or
+//! made by hand, as a `HistogramState` says to: in counts or frequencies,
+//! in sums or means, zoomed in on some buckets, with one selected. Each
+//! render leaves where it drew in the state, so mouse positions can be mapped
+//! back to sums.
+
+use crate::{
+    axis::{self, Ticks},
+    bars::{self, BarLayout, Bars, RenderSettings, Unit},
+    bucket::{self, Aggregate},
+    sampling::Regime,
+    simulation::Simulation,
+    stats::{self, Change, Model},
+};
+use std::ops::Range;
+use tui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+    text::{Span, Spans},
+    widgets::{
+        Axis, Block, Borders, Chart, Clear, Dataset, GraphType, Paragraph, StatefulWidget, Widget,
+    },
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Points the normal curve is drawn with across the x-axis.
+const CURVE_POINTS: usize = 200;
+
+/// The buckets of a run and the walks they were drawn from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramSnapshot {
+    /// Each bucket's sum, label and count on screen, which may be part-way
+    /// between two ticks' while animating.
+    pub xs: Vec<f64>,
+    pub labels: Vec<String>,
+    pub counts: Vec<f64>,
+    /// The samples the buckets hold.
+    pub total: u64,
+    pub steps: u32,
+    pub bias: f64,
+    /// The model the counts are drawn against.
+    pub model: Model,
+    /// Sums from the normal approximation are labelled in standard
+    /// deviations, since they're too many to read individually.
+    pub regime: Regime,
+    /// Where the buckets lie along the sums.
+    pub layout: bucket::Layout,
+}
+
+impl HistogramSnapshot {
+    pub fn of(sim: &Simulation) -> HistogramSnapshot {
+        let (xs, labels) = sim
+            .buckets()
+            .map(|bucket| (bucket.center(), bucket.label()))
+            .unzip();
+        let steps = sim.steps() as u32;
+        HistogramSnapshot {
+            xs,
+            labels,
+            counts: sim
+                .counts_slice()
+                .iter()
+                .map(|&count| count as f64)
+                .collect(),
+            total: sim.samples(),
+            steps,
+            bias: sim.bias(),
+            model: Model::for_steps(steps),
+            regime: sim.regime(),
+            layout: sim.layout(),
+        }
+    }
+
+    /// The snapshot with each bucket labelled by its `aggregate`, for a
+    /// state counting in it.
+    pub fn labelled(mut self, aggregate: Aggregate) -> HistogramSnapshot {
+        let steps = self.steps;
+        self.labels = self.xs.iter().map(|&x| aggregate.label(x, steps)).collect();
+        self
+    }
+
+    /// The model's mean, or for an aggregate of positions, which has no
+    /// model, the counts' own.
+    pub fn mean(&self) -> f64 {
+        match self.layout.modelled() {
+            true => stats::walk_mean(self.steps, self.bias),
+            false => self.fitted().mean,
+        }
+    }
+
+    pub fn sd(&self) -> f64 {
+        match self.layout.modelled() {
+            true => stats::walk_sd(self.steps, self.bias),
+            false => self.fitted().sd,
+        }
+    }
+
+    fn fitted(&self) -> stats::Stats {
+        let buckets = self
+            .xs
+            .iter()
+            .copied()
+            .zip(self.counts.iter().copied())
+            .collect::<Vec<_>>();
+        stats::Stats::weighted(&buckets, 0.0, 1.0, self.layout.spacing())
+    }
+
+    /// The counts the model expects across `bounds`: the normal curve
+    /// sampled finely enough to be smooth, or the exact binomial as a step
+    /// across each bucket's slice, since it only has values at the sums.
+    /// Without a model, there are none.
+    pub fn expected(&self, bounds: [f64; 2]) -> Vec<(f64, f64)> {
+        let Some(probabilities) = self.layout.probabilities(Model::Binomial, self.bias) else {
+            return vec![];
+        };
+        let total = self.total as f64;
+        let spacing = self.layout.spacing();
+        match self.model {
+            Model::Normal => {
+                let (mean, sd) = (self.mean(), self.sd());
+                // Each bucket collects a slice as wide as the distance
+                // between neighbouring buckets.
+                let scale = total * spacing / sd;
+                (0..=CURVE_POINTS)
+                    .map(|i| {
+                        let x =
+                            bounds[0] + (bounds[1] - bounds[0]) * i as f64 / CURVE_POINTS as f64;
+                        (x, scale * stats::normal_pdf((x - mean) / sd))
+                    })
+                    .collect()
+            }
+            Model::Binomial => self
+                .xs
+                .iter()
+                .zip(probabilities)
+                .flat_map(|(x, probability)| {
+                    let y = total * probability;
+                    [(x - spacing / 2.0, y), (x + spacing / 2.0, y)]
+                })
+                .collect(),
+        }
+    }
+}
+
+/// How a `HistogramView` is looked at, and where it last drew.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistogramState {
+    /// Frequencies in percent rather than counts.
+    pub percent: bool,
+    /// The buckets the line chart's x-axis is narrowed to.
+    pub zoom: Option<Range<usize>>,
+    pub selected: Option<usize>,
+    /// The top of the y-axis in counts, if not a little above the tallest.
+    pub y_max: Option<f64>,
+    /// What the x-axis counts in, while the buckets are exact: sums or
+    /// means of the steps.
+    pub aggregate: Aggregate,
+    /// Where the line chart plotted on the last render.
+    pub plot: Option<Plot>,
+    /// Where the bars were drawn on the last render, and how far into them
+    /// the selected bucket's bar starts.
+    pub bars_area: Rect,
+    pub selected_column: Option<u16>,
+}
+
+/// Where a line chart plotted its data, so mouse positions can be mapped
+/// back to sums.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plot {
+    pub area: Rect,
+    pub x_bounds: [f64; 2],
+}
+
+impl Plot {
+    /// The x value under a terminal cell, if the cell is inside the plot.
+    pub fn x_at(&self, column: u16, row: u16) -> Option<f64> {
+        let area = self.area;
+        if column < area.left()
+            || column >= area.right()
+            || row < area.top()
+            || row >= area.bottom()
+        {
+            return None;
+        }
+        let [min, max] = self.x_bounds;
+        let t = (column - area.left()) as f64 / (area.width.max(2) - 1) as f64;
+        Some(min + (max - min) * t)
+    }
+}
+
+/// The styles and markers a `HistogramView` draws with. The default is the
+/// terminal UI's classic palette.
+#[derive(Debug, Clone, Copy)]
+pub struct HistogramStyle {
+    pub bar: Style,
+    pub bar_value: Style,
+    pub bar_label: Style,
+    pub axis: Style,
+    pub empirical: Style,
+    pub empirical_marker: Marker,
+    pub model: Style,
+    pub model_marker: Marker,
+    /// The reference beside each bar.
+    pub reference: Style,
+    pub grid: Style,
+    pub grid_marker: Marker,
+    pub band: Style,
+    pub band_marker: Marker,
+    /// The selected bucket.
+    pub accent: Style,
+    pub point_marker: Marker,
+    /// Bars by how their count changed.
+    pub grew: Style,
+    pub shrank: Style,
+    pub steady: Style,
+}
+
+impl Default for HistogramStyle {
+    fn default() -> HistogramStyle {
+        HistogramStyle {
+            bar: Style::default().fg(Color::Green),
+            bar_value: Style::default().fg(Color::Black).bg(Color::Green),
+            bar_label: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::DIM),
+            axis: Style::default().fg(Color::Gray),
+            empirical: Style::default().fg(Color::Yellow),
+            empirical_marker: Marker::Dot,
+            model: Style::default().fg(Color::Cyan),
+            model_marker: Marker::Dot,
+            reference: Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
+            grid: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::DIM),
+            grid_marker: Marker::Braille,
+            band: Style::default().fg(Color::DarkGray),
+            band_marker: Marker::Dot,
+            accent: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            point_marker: Marker::Block,
+            grew: Style::default().fg(Color::LightGreen),
+            shrank: Style::default().fg(Color::LightRed),
+            steady: Style::default().fg(Color::DarkGray),
+        }
+    }
+}
+
+/// Runs of buckets drawn as one bar each.
+pub trait Grouping {
+    /// One label per bar.
+    fn labels(&self) -> &[String];
+    /// How many buckets each bar spans.
+    fn spans(&self) -> Vec<u16>;
+    /// The bar a bucket is drawn in, if it's drawn at all.
+    fn bar_of(&self, bucket: usize) -> Option<usize>;
+    /// Each bar's height, from a value per bucket.
+    fn heights(&self, values: &[f64]) -> Vec<f64>;
+    /// Each bar's total, from a count per bucket.
+    fn sums(&self, counts: &[u64]) -> Vec<u64>;
+}
+
+/// A line on the chart, named in the legend.
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub name: String,
+    pub style: Style,
+    pub marker: Marker,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// The histogram's bars above a line chart of the same counts, or the bars
+/// alone.
+pub struct HistogramView<'a> {
+    snapshot: &'a HistogramSnapshot,
+    settings: RenderSettings,
+    style: HistogramStyle,
+    block: Block<'a>,
+    bars_only: bool,
+    split: (u32, u32),
+    empirical: String,
+    model: Option<String>,
+    title: Option<String>,
+    binned_title: Option<Box<dyn Fn(usize) -> String + 'a>>,
+    grouped_title: Option<String>,
+    merged: Option<&'a dyn Grouping>,
+    grouped: Option<&'a dyn Grouping>,
+    reference: Option<&'a [f64]>,
+    changes: Option<(&'a [u64], &'a [u64])>,
+    series: Vec<Series>,
+    guides: Vec<Series>,
+    grid: bool,
+    bands: bool,
+    legend: bool,
+}
+
+impl<'a> HistogramView<'a> {
+    pub fn new(snapshot: &'a HistogramSnapshot) -> HistogramView<'a> {
+        HistogramView {
+            snapshot,
+            settings: RenderSettings::default(),
+            style: HistogramStyle::default(),
+            block: Block::default().borders(Borders::ALL),
+            bars_only: false,
+            split: (1, 1),
+            empirical: "empirical".to_string(),
+            model: Some("model".to_string()),
+            title: None,
+            binned_title: None,
+            grouped_title: None,
+            merged: None,
+            grouped: None,
+            reference: None,
+            changes: None,
+            series: vec![],
+            guides: vec![],
+            grid: false,
+            bands: false,
+            legend: true,
+        }
+    }
+
+    pub fn settings(mut self, settings: RenderSettings) -> HistogramView<'a> {
+        self.settings = settings;
+        self
+    }
+
+    pub fn style(mut self, style: HistogramStyle) -> HistogramView<'a> {
+        self.style = style;
+        self
+    }
+
+    /// The frame drawn around the bars and around the chart.
+    pub fn block(mut self, block: Block<'a>) -> HistogramView<'a> {
+        self.block = block;
+        self
+    }
+
+    pub fn bars_only(mut self, bars_only: bool) -> HistogramView<'a> {
+        self.bars_only = bars_only;
+        self
+    }
+
+    /// How the height is shared between the bars and the chart.
+    pub fn split(mut self, bars: u32, chart: u32) -> HistogramView<'a> {
+        self.split = (bars, chart);
+        self
+    }
+
+    /// What the legend calls the counts.
+    pub fn empirical(mut self, name: String) -> HistogramView<'a> {
+        self.empirical = name;
+        self
+    }
+
+    /// What the legend calls the model, which isn't drawn without a name.
+    pub fn model(mut self, name: Option<String>) -> HistogramView<'a> {
+        self.model = name;
+        self
+    }
+
+    pub fn title(mut self, title: String) -> HistogramView<'a> {
+        self.title = Some(title);
+        self
+    }
+
+    /// The title when there are too many buckets for a bar each, from how
+    /// many each bar stands for.
+    pub fn binned_title(mut self, title: impl Fn(usize) -> String + 'a) -> HistogramView<'a> {
+        self.binned_title = Some(Box::new(title));
+        self
+    }
+
+    /// Bars spanning runs of buckets, as wide as the buckets they span, so
+    /// only drawn when there's room for every bucket.
+    pub fn merged(mut self, grouping: &'a dyn Grouping) -> HistogramView<'a> {
+        self.merged = Some(grouping);
+        self
+    }
+
+    /// Bars spanning runs of buckets, laid out bar by bar whenever they fit,
+    /// in place of the merged ones.
+    pub fn grouped(mut self, grouping: &'a dyn Grouping) -> HistogramView<'a> {
+        self.grouped = Some(grouping);
+        self
+    }
+
+    /// The title when the grouped bars are drawn.
+    pub fn grouped_title(mut self, title: String) -> HistogramView<'a> {
+        self.grouped_title = Some(title);
+        self
+    }
+
+    /// A count per bucket drawn beside each bar.
+    pub fn reference(mut self, counts: &'a [f64]) -> HistogramView<'a> {
+        self.reference = Some(counts);
+        self
+    }
+
+    /// Colors each bar by whether its count grew, shrank or held steady,
+    /// from a count per bucket before and after.
+    pub fn changes(mut self, previous: &'a [u64], current: &'a [u64]) -> HistogramView<'a> {
+        self.changes = Some((previous, current));
+        self
+    }
+
+    /// More lines in counts, named in the legend after the model.
+    pub fn series(mut self, series: Vec<Series>) -> HistogramView<'a> {
+        self.series = series;
+        self
+    }
+
+    /// Lines in counts drawn beneath the others and left out of the legend.
+    pub fn guides(mut self, guides: Vec<Series>) -> HistogramView<'a> {
+        self.guides = guides;
+        self
+    }
+
+    /// Grid lines at the y-axis ticks and at ±1σ, ±2σ and ±3σ.
+    pub fn grid(mut self, grid: bool) -> HistogramView<'a> {
+        self.grid = grid;
+        self
+    }
+
+    /// Lines at ±1σ, ±2σ and ±3σ.
+    pub fn bands(mut self, bands: bool) -> HistogramView<'a> {
+        self.bands = bands;
+        self
+    }
+
+    pub fn legend(mut self, legend: bool) -> HistogramView<'a> {
+        self.legend = legend;
+        self
+    }
+}
+
+impl<'a> StatefulWidget for HistogramView<'a> {
+    type State = HistogramState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut HistogramState) {
+        let snapshot = self.snapshot;
+        let style = self.style;
+        let constraints = if self.bars_only {
+            vec![Constraint::Percentage(100)]
+        } else {
+            let (bars, chart) = self.split;
+            vec![
+                Constraint::Ratio(bars, bars + chart),
+                Constraint::Ratio(chart, bars + chart),
+            ]
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(constraints)
+            .split(area);
+        let chart_area = chunks.get(1).copied().unwrap_or(chunks[0]);
+        let selected = state.selected.filter(|&i| i < snapshot.counts.len());
+
+        // Zooming narrows the x-axis to the buckets shown, each reaching
+        // halfway to its neighbours. Bins of a long walk only cover the
+        // middle of its sums, and an aggregate of positions only its own
+        // values, so the axis ends with them.
+        let zoom = state
+            .zoom
+            .clone()
+            .filter(|zoom| !zoom.is_empty() && zoom.end <= snapshot.xs.len());
+        let reach = snapshot.layout.spacing() / 2.0;
+        let [x_min, x_max] = match (zoom, snapshot.layout) {
+            (Some(zoom), _) => [
+                snapshot.xs[zoom.start] - reach,
+                snapshot.xs[zoom.end - 1] + reach,
+            ],
+            (None, bucket::Layout::Bins { .. } | bucket::Layout::Values { .. }) => [
+                snapshot.xs[0] - reach,
+                snapshot.xs[snapshot.xs.len() - 1] + reach,
+            ],
+            (None, bucket::Layout::Sums { .. }) => {
+                [-f64::from(snapshot.steps), f64::from(snapshot.steps)]
+            }
+        };
+        let x_ticks = match snapshot.regime {
+            Regime::Exact => Ticks::per(
+                x_min,
+                x_max,
+                state.aggregate.unit(snapshot.steps),
+                "",
+                chart_area.width as usize / 8,
+            ),
+            Regime::Normal => {
+                Ticks::sigmas(x_min, x_max, snapshot.sd(), chart_area.width as usize / 8)
+            }
+        };
+
+        // Both charts share one unit so they never disagree: counts, or
+        // percent of the total (per-10,000 on the bars to keep them
+        // integral).
+        let total = snapshot.total;
+        let (scale, unit, bar_scale) = if state.percent && total > 0 {
+            (100.0 / total as f64, Unit::PerTenThousand, 100.0)
+        } else {
+            (1.0, Unit::Count, 1.0)
+        };
+        let y_top = state.y_max.unwrap_or_else(|| {
+            let peak = snapshot.counts.iter().copied().fold(0.0, f64::max);
+            axis::nice_ceil(peak * axis::HEADROOM)
+        });
+        let y_ticks = Ticks::new(0.0, y_top * scale, chart_area.height as usize / 3);
+        let y_max = y_ticks.bounds[1];
+        // Interpolated values are clamped so an animating bar never leaves
+        // the axis.
+        let on_chart = |count: f64| (count * scale).clamp(0.0, y_max);
+
+        let counts = snapshot
+            .counts
+            .iter()
+            .map(|&count| on_chart(count))
+            .collect::<Vec<_>>();
+        let bar_data = snapshot
+            .labels
+            .iter()
+            .zip(&counts)
+            .map(|(label, count)| (label.as_str(), (count * bar_scale).round() as u64))
+            .collect::<Vec<_>>();
+        // With more buckets than cells, runs of them are drawn as one bar,
+        // for the bars alone: the line chart keeps every bucket. Merged bars
+        // give way to that; grouped ones take their place, unless even
+        // they don't fit.
+        let width = self.block.inner(chunks[0]).width;
+        let grouped = self
+            .grouped
+            .filter(|grouping| bars::display_factor(width, grouping.labels().len()) == 1);
+        let factor = match grouped {
+            Some(_) => 1,
+            None => bars::display_factor(width, bar_data.len()),
+        };
+        let binned_data = (factor > 1).then(|| bars::binned(&bar_data, factor));
+        let grouping = grouped.or(self.merged.filter(|_| factor == 1));
+        let grouped_data = grouping.map(|grouping| {
+            grouping
+                .labels()
+                .iter()
+                .zip(grouping.heights(&counts))
+                .map(|(label, count)| (label.as_str(), (count * bar_scale).round() as u64))
+                .collect::<Vec<_>>()
+        });
+
+        let reference = self
+            .reference
+            .map(|reference| reference.iter().map(|&count| on_chart(count)).collect());
+        let reference_bars = reference
+            .as_ref()
+            .map(|values: &Vec<f64>| match grouping {
+                Some(grouping) => grouping.heights(values),
+                None => values.clone(),
+            })
+            .into_iter()
+            .flatten()
+            .map(|value| ("", (value * bar_scale).round() as u64))
+            .collect::<Vec<_>>();
+        let reference_bars = bars::binned(&reference_bars, factor)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>();
+        let spans = grouping.map(|grouping| grouping.spans());
+
+        let title = match (factor, grouped) {
+            (_, Some(_)) => self.grouped_title.clone(),
+            (1, None) => self.title.clone(),
+            _ => self.binned_title.as_ref().map(|title| title(factor)),
+        };
+        let block = match title {
+            Some(title) => self.block.clone().title(title),
+            None => self.block.clone(),
+        };
+        // The layout is for the buckets, which merged bars span whole
+        // numbers of, or for the grouped bars.
+        let layout_data = match (grouped, &grouped_data) {
+            (Some(_), Some(data)) => data.as_slice(),
+            _ => binned_data.as_deref().unwrap_or(&bar_data),
+        };
+        let bar_layout = BarLayout::new(
+            block.inner(chunks[0]).width,
+            layout_data,
+            unit,
+            &self.settings,
+        );
+        let highlight = match grouping {
+            Some(grouping) => selected.and_then(|i| grouping.bar_of(i)),
+            None => selected.map(|i| i / factor),
+        };
+        // Changes are judged on whole counts, summed over what each bar
+        // stands for, so merged and binned bars are judged on all their
+        // samples.
+        let change_styles = self.changes.map(|(previous, current)| {
+            let (previous, current) = match grouping {
+                Some(grouping) => (grouping.sums(previous), grouping.sums(current)),
+                None => (
+                    bars::binned_sums(previous, factor),
+                    bars::binned_sums(current, factor),
+                ),
+            };
+            stats::changes(&previous, &current, stats::CHANGE_Z)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|change| match change {
+                    Change::Grew => style.grew,
+                    Change::Shrank => style.shrank,
+                    Change::Steady => style.steady,
+                })
+                .collect::<Vec<_>>()
+        });
+        let shown_data = grouped_data.as_deref().unwrap_or(layout_data);
+        let bar_chart = Bars::new(shown_data, bar_layout)
+            .block(block)
+            .renderer(self.settings.shown_renderer())
+            .bar_set(self.settings.bar_set())
+            .max((y_max * bar_scale) as u64)
+            .bar_style(style.bar)
+            .label_style(style.bar_label)
+            .value_style(style.bar_value)
+            .highlight(highlight, style.accent);
+        let bar_chart = match reference {
+            Some(_) => bar_chart.reference(&reference_bars, style.reference),
+            None => bar_chart,
+        };
+        let bar_chart = match &spans {
+            Some(spans) => bar_chart.spans(spans),
+            None => bar_chart,
+        };
+        let bar_chart = match &change_styles {
+            Some(styles) => bar_chart.styles(styles),
+            None => bar_chart,
+        };
+        bar_chart.render(chunks[0], buf);
+        state.bars_area = chunks[0];
+        state.selected_column = selected.map(|i| {
+            let bar = match grouped {
+                Some(grouping) => grouping.bar_of(i).unwrap_or(0),
+                None => i / factor,
+            };
+            bar as u16 * (bar_layout.bar_width + bar_layout.bar_gap)
+        });
+        state.plot = None;
+        if self.bars_only {
+            return;
+        }
+
+        let line_data = snapshot.xs.iter().copied().zip(counts).collect::<Vec<_>>();
+        let highlight = selected
+            .and_then(|i| line_data.get(i))
+            .map(|point| vec![*point])
+            .unwrap_or_default();
+        let y_labels = axis_labels(&y_ticks, if state.percent { "%" } else { "" });
+
+        let mut series = vec![Series {
+            name: self.empirical,
+            style: style.empirical,
+            marker: style.empirical_marker,
+            points: line_data,
+        }];
+        if let Some(name) = self.model.filter(|_| snapshot.layout.modelled()) {
+            series.push(Series {
+                name,
+                style: style.model,
+                marker: style.model_marker,
+                points: snapshot
+                    .expected(x_ticks.bounds)
+                    .into_iter()
+                    .map(|(x, y)| (x, y * scale))
+                    .collect(),
+            });
+        }
+        let on_chart = |s: Series| Series {
+            points: s.points.iter().map(|&(x, y)| (x, on_chart(y))).collect(),
+            ..s
+        };
+        series.extend(self.series.into_iter().map(on_chart));
+        let extra = self.guides.into_iter().map(on_chart).collect::<Vec<_>>();
+
+        // ±1σ, ±2σ and ±3σ; ones outside the x-axis are left out.
+        let (mean, sd) = (snapshot.mean(), snapshot.sd());
+        let sigmas = (1..=3)
+            .flat_map(|k| [-1.0, 1.0].map(|side| mean + side * k as f64 * sd))
+            .filter(|x| (x_ticks.bounds[0]..=x_ticks.bounds[1]).contains(x))
+            .collect::<Vec<_>>();
+        let grid = if self.grid {
+            // The lowest tick is the x-axis itself.
+            guides(&sigmas, &y_ticks.values[1..], &x_ticks, &y_ticks)
+        } else {
+            vec![]
+        };
+        let bands = if self.bands {
+            guides(&sigmas, &[], &x_ticks, &y_ticks)
+        } else {
+            vec![]
+        };
+
+        let guide = |points, style, marker| {
+            Dataset::default()
+                .marker(marker)
+                .style(style)
+                .graph_type(GraphType::Line)
+                .data(points)
+        };
+        let datasets = grid
+            .iter()
+            .map(|points| guide(points, style.grid, style.grid_marker))
+            .chain(
+                bands
+                    .iter()
+                    .map(|points| guide(points, style.band, style.band_marker)),
+            )
+            .chain(extra.iter().map(|s| guide(&s.points, s.style, s.marker)))
+            .chain(series.iter().map(|s| {
+                Dataset::default()
+                    .name(s.name.as_str())
+                    .marker(s.marker)
+                    .style(s.style)
+                    .graph_type(GraphType::Line)
+                    .data(&s.points)
+            }))
+            .chain([Dataset::default()
+                .marker(style.point_marker)
+                .style(style.accent)
+                .graph_type(GraphType::Scatter)
+                .data(&highlight)])
+            .collect();
+
+        let block = self.block;
+        state.plot = Some(Plot {
+            area: graph_area(block.inner(chart_area), &x_ticks, &y_labels),
+            x_bounds: x_ticks.bounds,
+        });
+        Chart::new(datasets)
+            .block(block)
+            // The legend is drawn by hand so it can avoid the data.
+            .hidden_legend_constraints((Constraint::Length(0), Constraint::Length(0)))
+            .x_axis(
+                Axis::default()
+                    .style(style.axis)
+                    .labels(axis_labels(&x_ticks, ""))
+                    .bounds(x_ticks.bounds),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(style.axis)
+                    .labels(y_labels)
+                    .bounds(y_ticks.bounds),
+            )
+            .render(chart_area, buf);
+
+        if self.legend {
+            Legend::new(&series, &x_ticks, &y_ticks).render(chart_area, buf);
+        }
+    }
+}
+
+/// A line chart's legend, in whichever top corner the data leaves emptier.
+pub struct Legend<'a> {
+    series: &'a [Series],
+    x_ticks: &'a Ticks,
+    y_ticks: &'a Ticks,
+}
+
+impl<'a> Legend<'a> {
+    pub fn new(series: &'a [Series], x_ticks: &'a Ticks, y_ticks: &'a Ticks) -> Legend<'a> {
+        Legend {
+            series,
+            x_ticks,
+            y_ticks,
+        }
+    }
+}
+
+impl<'a> Widget for Legend<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (series, x_ticks, y_ticks) = (self.series, self.x_ticks, self.y_ticks);
+        let width = series.iter().map(|s| s.name.width()).max().unwrap_or(0) as u16 + 6;
+        let height = series.len() as u16 + 2;
+
+        let y_label_width = y_ticks
+            .values
+            .iter()
+            .map(|v| y_ticks.label(*v).len())
+            .max()
+            .unwrap_or(0) as u16;
+        let (left_x, right_x) = (
+            area.x + y_label_width + 3,
+            area.right().saturating_sub(width + 2),
+        );
+        if area.height < height + 4 || right_x < left_x {
+            return;
+        }
+
+        let [min, max] = x_ticks.bounds;
+        let quarter = (max - min) / 4.0;
+        let peak = |keep: &dyn Fn(f64) -> bool| {
+            series
+                .iter()
+                .flat_map(|s| &s.points)
+                .filter(|(x, _)| keep(*x))
+                .map(|(_, y)| *y)
+                .fold(0.0, f64::max)
+        };
+        let x = if peak(&|x| x <= min + quarter) < peak(&|x| x >= max - quarter) {
+            left_x
+        } else {
+            right_x
+        };
+
+        let lines = series
+            .iter()
+            .map(|s| {
+                Spans::from(vec![
+                    Span::styled("── ", s.style),
+                    Span::raw(s.name.as_str()),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let legend_area = Rect::new(x, area.y + 1, width, height);
+        Clear.render(legend_area, buf);
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL))
+            .render(legend_area, buf);
+    }
+}
+
+/// Two-point lines across the whole chart: vertical ones at `xs` and
+/// horizontal ones at `ys`.
+pub fn guides(xs: &[f64], ys: &[f64], x_ticks: &Ticks, y_ticks: &Ticks) -> Vec<Vec<(f64, f64)>> {
+    let [left, right] = x_ticks.bounds;
+    let [bottom, top] = y_ticks.bounds;
+    xs.iter()
+        .map(|x| vec![(*x, bottom), (*x, top)])
+        .chain(ys.iter().map(|y| vec![(left, *y), (right, *y)]))
+        .collect()
+}
+
+/// The ticks' labels, the first and last in bold.
+pub fn axis_labels(ticks: &Ticks, suffix: &str) -> Vec<Span<'static>> {
+    let last = ticks.values.len().saturating_sub(1);
+    ticks
+        .values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            if i == 0 || i == last {
+                Span::styled(
+                    ticks.label(*value) + suffix,
+                    Style::default().add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Span::raw(ticks.label(*value) + suffix)
+            }
+        })
+        .collect()
+}
+
+/// The area a `Chart` in `inner` plots into, worked out the way tui does:
+/// the y labels (or the overhang of the first x label) and the y-axis line
+/// on the left, the x labels and x-axis line at the bottom.
+pub fn graph_area(inner: Rect, x_ticks: &Ticks, y_labels: &[Span]) -> Rect {
+    let y_label_width = y_labels.iter().map(Span::width).max().unwrap_or(0) as u16;
+    let first_x_label = x_ticks
+        .values
+        .first()
+        .map_or(0, |value| x_ticks.label(*value).width() as u16)
+        .saturating_sub(1);
+    let left = y_label_width.max(first_x_label).min(inner.width / 3) + 1;
+    Rect::new(
+        inner.x + left,
+        inner.y,
+        inner.width.saturating_sub(left),
+        inner.height.saturating_sub(2),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden;
+
+    fn snapshot(steps: i32, ticks: usize) -> HistogramSnapshot {
+        let mut sim = Simulation::new(steps, 42).unwrap();
+        for _ in 0..ticks {
+            sim.advance(1_000);
+        }
+        HistogramSnapshot::of(&sim)
+    }
+
+    fn render(view: HistogramView, width: u16, height: u16, state: &mut HistogramState) -> Buffer {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        view.render(area, &mut buf, state);
+        buf
+    }
+
+    fn lines(buf: &Buffer) -> String {
+        let area = buf.area;
+        (area.top()..area.bottom())
+            .map(|y| {
+                let line = (area.left()..area.right())
+                    .map(|x| buf.get(x, y).symbol.as_str())
+                    .collect::<String>();
+                line.trim_end().to_string() + "\n"
+            })
+            .collect()
+    }
+
+    /// Checks the frame's characters against the golden file `name`.
+    fn assert_golden(name: &str, buf: &Buffer) {
+        golden::check("histogram", name, &lines(buf));
+    }
+
+    /// The rows and columns drawn in `style`'s foreground.
+    fn cells_in(buf: &Buffer, style: Style) -> impl Iterator<Item = (u16, u16)> + '_ {
+        let area = buf.area;
+        (area.top()..area.bottom())
+            .flat_map(move |y| (area.left()..area.right()).map(move |x| (x, y)))
+            .filter(move |&(x, y)| style.fg.is_some() && buf.get(x, y).fg == style.fg.unwrap())
+    }
+
+    /// Two runs of five buckets, and the one left over, drawn as wide as
+    /// they span, as merged tails are, or a bar each, as clipped ones are.
+    struct Halves {
+        labels: Vec<String>,
+        spans: Vec<u16>,
+    }
+
+    impl Halves {
+        fn new(spans: [u16; 3]) -> Halves {
+            Halves {
+                labels: vec!["-10..-2".to_string(), "0..8".to_string(), "10".to_string()],
+                spans: spans.to_vec(),
+            }
+        }
+    }
+
+    impl Grouping for Halves {
+        fn labels(&self) -> &[String] {
+            &self.labels
+        }
+        fn spans(&self) -> Vec<u16> {
+            self.spans.clone()
+        }
+        fn bar_of(&self, bucket: usize) -> Option<usize> {
+            Some(bucket / 5)
+        }
+        fn heights(&self, values: &[f64]) -> Vec<f64> {
+            values.chunks(5).map(|run| run.iter().sum()).collect()
+        }
+        fn sums(&self, counts: &[u64]) -> Vec<u64> {
+            counts.chunks(5).map(|run| run.iter().sum()).collect()
+        }
+    }
+
+    #[test]
+    fn the_default_view() {
+        let snapshot = snapshot(10, 10);
+        let mut state = HistogramState::default();
+        let buf = render(HistogramView::new(&snapshot), 80, 24, &mut state);
+        assert_golden("default", &buf);
+        let plot = state.plot.expect("the chart was drawn");
+        assert_eq!(plot.x_bounds, [-10.0, 10.0]);
+        assert_eq!(state.selected_column, None);
+    }
+
+    #[test]
+    fn a_selected_bucket() {
+        let snapshot = snapshot(10, 10);
+        let mut state = HistogramState {
+            selected: Some(5),
+            ..HistogramState::default()
+        };
+        let style = HistogramStyle::default();
+        let accent = style.accent;
+        let view = HistogramView::new(&snapshot).title("Histogram".to_string());
+        let buf = render(view, 80, 24, &mut state);
+        assert_golden("selected", &buf);
+        // The column is counted from inside the bars' border.
+        let column = state.selected_column.expect("the selected bar was found");
+        let bars = Block::default()
+            .borders(Borders::ALL)
+            .inner(state.bars_area);
+        let picked = Rect::new(bars.x + column, bars.y, 1, bars.height);
+        assert!(cells_in(&buf, accent).any(|(x, y)| picked.intersects(Rect::new(x, y, 1, 1))));
+    }
+
+    #[test]
+    fn frequencies_in_percent() {
+        let snapshot = snapshot(10, 10);
+        let mut state = HistogramState {
+            percent: true,
+            ..HistogramState::default()
+        };
+        assert_golden(
+            "percent",
+            &render(HistogramView::new(&snapshot), 80, 24, &mut state),
+        );
+    }
+
+    #[test]
+    fn zoomed_in() {
+        let snapshot = snapshot(10, 10);
+        let mut state = HistogramState {
+            zoom: Some(3..8),
+            ..HistogramState::default()
+        };
+        let buf = render(HistogramView::new(&snapshot), 80, 24, &mut state);
+        assert_golden("zoomed", &buf);
+        // The buckets reach from -5 to 5, widened to the ticks either side.
+        assert_eq!(state.plot.map(|plot| plot.x_bounds), Some([-6.0, 6.0]));
+    }
+
+    #[test]
+    fn in_means() {
+        let snapshot = snapshot(10, 10).labelled(Aggregate::Mean);
+        let mut state = HistogramState {
+            aggregate: Aggregate::Mean,
+            ..HistogramState::default()
+        };
+        let buf = render(HistogramView::new(&snapshot), 80, 24, &mut state);
+        assert_golden("means", &buf);
+        // Counted in means, the axis ends at a mean of ±1.
+        let text = lines(&buf);
+        assert!(text.contains("-1.0") && text.contains("0.5"));
+        assert_eq!(state.plot.map(|plot| plot.x_bounds), Some([-10.0, 10.0]));
+    }
+
+    #[test]
+    fn grid_and_bands() {
+        let snapshot = snapshot(10, 10);
+        let mut state = HistogramState::default();
+        let view = HistogramView::new(&snapshot).grid(true).bands(true);
+        assert_golden("grid", &render(view, 80, 24, &mut state));
+    }
+
+    #[test]
+    fn the_bars_alone() {
+        let snapshot = snapshot(10, 10);
+        let mut state = HistogramState::default();
+        let view = HistogramView::new(&snapshot).bars_only(true).legend(false);
+        assert_golden("bars_only", &render(view, 60, 12, &mut state));
+        assert_eq!(state.plot, None);
+    }
+
+    #[test]
+    fn too_many_buckets_for_a_bar_each() {
+        let snapshot = snapshot(100, 10);
+        let mut state = HistogramState::default();
+        let view = HistogramView::new(&snapshot)
+            .binned_title(|factor| format!("{} buckets a bar", factor));
+        let buf = render(view, 40, 20, &mut state);
+        assert_golden("binned", &buf);
+        assert!(lines(&buf).contains("buckets a bar"));
+    }
+
+    #[test]
+    fn every_sample_alike_draws() {
+        // Nearly certain to step down, every walk ends at -steps.
+        for steps in [10, 2_000] {
+            let mut sim = Simulation::new(steps, 42).unwrap();
+            sim.set_bias(1e-12).unwrap();
+            sim.advance(1_000);
+            assert!(sim.counts_slice().contains(&1_000));
+            let snapshot = HistogramSnapshot::of(&sim);
+            let buf = render(
+                HistogramView::new(&snapshot),
+                60,
+                20,
+                &mut HistogramState::default(),
+            );
+            let drawn = lines(&buf);
+            assert!(
+                !drawn.contains("NaN") && !drawn.contains("inf"),
+                "{}",
+                drawn
+            );
+            if steps == 10 {
+                assert_golden("single_value", &buf);
+            }
+        }
+    }
+
+    #[test]
+    fn gaps_go_before_buckets_are_binned() {
+        // 101 buckets in 118 cells: too few for a gap after every bar, but
+        // one each still fits.
+        let snapshot = snapshot(100, 10);
+        let view = HistogramView::new(&snapshot)
+            .binned_title(|factor| format!("{} buckets a bar", factor));
+        let buf = render(view, 120, 20, &mut HistogramState::default());
+        assert_golden("gapless", &buf);
+        assert!(!lines(&buf).contains("buckets a bar"));
+    }
+
+    #[test]
+    fn buckets_are_binned_a_cell_short() {
+        let snapshot = snapshot(100, 10);
+        for (width, binned) in [(103, false), (102, true)] {
+            let view = HistogramView::new(&snapshot)
+                .binned_title(|factor| format!("{} buckets a bar", factor));
+            let buf = render(view, width, 20, &mut HistogramState::default());
+            assert_eq!(lines(&buf).contains("2 buckets a bar"), binned, "{}", width);
+        }
+        assert_eq!(bars::display_factor(101, 101), 1);
+        assert_eq!(bars::display_factor(100, 101), 2);
+    }
+
+    #[test]
+    fn sums_in_standard_deviations() {
+        let snapshot = snapshot(2_000, 2);
+        assert_eq!(snapshot.regime, Regime::Normal);
+        let mut state = HistogramState::default();
+        assert_golden(
+            "normal",
+            &render(HistogramView::new(&snapshot), 80, 24, &mut state),
+        );
+    }
+
+    #[test]
+    fn merged_bars() {
+        let snapshot = snapshot(10, 10);
+        let merged = Halves::new([5, 5, 1]);
+        let mut state = HistogramState {
+            selected: Some(7),
+            ..HistogramState::default()
+        };
+        let view = HistogramView::new(&snapshot)
+            .merged(&merged)
+            .bars_only(true);
+        assert_golden("merged", &render(view, 80, 12, &mut state));
+        // The bars are laid out bucket by bucket, so the column is the
+        // bucket's own, part-way into the bar it's merged into.
+        assert_eq!(state.selected_column, Some(7 * 7));
+    }
+
+    #[test]
+    fn grouped_bars() {
+        let snapshot = snapshot(10, 10);
+        let grouped = Halves::new([1, 1, 1]);
+        let mut state = HistogramState::default();
+        let view = HistogramView::new(&snapshot)
+            .title("each bucket".to_string())
+            .grouped(&grouped)
+            .grouped_title("each run".to_string());
+        let buf = render(view, 80, 24, &mut state);
+        assert_golden("grouped", &buf);
+        assert!(lines(&buf).contains("each run"));
+    }
+
+    #[test]
+    fn bars_colored_by_change() {
+        let snapshot = snapshot(4, 10);
+        let previous = [1_000, 2_000, 3_000, 2_000, 1_000];
+        let current = [1_500, 2_000, 2_000, 2_000, 1_000];
+        let style = HistogramStyle::default();
+        let (grew, shrank) = (style.grew, style.shrank);
+        let mut state = HistogramState::default();
+        let view = HistogramView::new(&snapshot)
+            .bars_only(true)
+            .changes(&previous, &current);
+        let buf = render(view, 40, 10, &mut state);
+        assert_golden("changes", &buf);
+        assert!(cells_in(&buf, grew).next().is_some());
+        assert!(cells_in(&buf, shrank).next().is_some());
+    }
+
+    #[test]
+    fn no_samples_on_a_fixed_axis() {
+        let snapshot = snapshot(10, 0);
+        let mut state = HistogramState {
+            y_max: Some(100.0),
+            ..HistogramState::default()
+        };
+        let view = HistogramView::new(&snapshot).split(1, 2);
+        assert_golden("empty", &render(view, 60, 18, &mut state));
+    }
+
+    #[test]
+    fn ascii_bars() {
+        let snapshot = snapshot(10, 10);
+        let mut state = HistogramState::default();
+        let settings = RenderSettings {
+            ascii: true,
+            ..RenderSettings::default()
+        };
+        let view = HistogramView::new(&snapshot)
+            .settings(settings)
+            .bars_only(true);
+        let buf = render(view, 60, 12, &mut state);
+        assert_golden("ascii", &buf);
+        // The border is tui's, which the terminal UI swaps afterwards.
+        let inner = Block::default().borders(Borders::ALL).inner(buf.area);
+        for y in inner.top()..inner.bottom() {
+            for x in inner.left()..inner.right() {
+                assert!(
+                    buf.get(x, y).symbol.is_ascii(),
+                    "{:?} at {}, {}",
+                    buf.get(x, y).symbol,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn any_size_renders_without_panicking() {
+        let snapshot = snapshot(10, 10);
+        for width in 0..40 {
+            for height in 0..16 {
+                let mut state = HistogramState {
+                    selected: Some(3),
+                    ..HistogramState::default()
+                };
+                render(
+                    HistogramView::new(&snapshot).grid(true),
+                    width,
+                    height,
+                    &mut state,
+                );
+            }
+        }
+    }
+}
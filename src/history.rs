@@ -0,0 +1,145 @@
+//! Recent per-tick statistics for the header sparkline and convergence view.
+
+use crate::stats::Stats;
+use std::collections::VecDeque;
+
+pub const CAPACITY: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Ks,
+    Peak,
+    Sd,
+    /// The percentage of the occupied buckets whose relative standard error
+    /// is within the `--precision` target.
+    Converged,
+}
+
+impl Metric {
+    pub fn parse(key: &str) -> Option<Metric> {
+        match key {
+            "ks" => Some(Metric::Ks),
+            "peak" => Some(Metric::Peak),
+            "sd" => Some(Metric::Sd),
+            "buckets_converged" => Some(Metric::Converged),
+            _ => None,
+        }
+    }
+
+    /// Short name for the command line.
+    pub fn key(self) -> &'static str {
+        match self {
+            Metric::Ks => "ks",
+            Metric::Peak => "peak",
+            Metric::Sd => "sd",
+            Metric::Converged => "buckets_converged",
+        }
+    }
+
+    /// The strings table key for the name shown in the TUI.
+    pub fn name_key(self) -> &'static str {
+        match self {
+            Metric::Ks => "metric.ks",
+            Metric::Peak => "metric.peak",
+            Metric::Sd => "metric.sd",
+            Metric::Converged => "metric.converged",
+        }
+    }
+
+    /// The least and greatest values the statistic can take.
+    pub fn bounds(self) -> (f64, f64) {
+        match self {
+            Metric::Ks => (0.0, 1.0),
+            Metric::Peak | Metric::Sd => (0.0, f64::INFINITY),
+            Metric::Converged => (0.0, 100.0),
+        }
+    }
+
+    pub fn next(self) -> Metric {
+        match self {
+            Metric::Ks => Metric::Peak,
+            Metric::Peak => Metric::Sd,
+            Metric::Sd => Metric::Converged,
+            Metric::Converged => Metric::Ks,
+        }
+    }
+
+    pub fn of(self, point: &Point) -> f64 {
+        match self {
+            Metric::Ks => point.ks,
+            Metric::Peak => point.peak as f64,
+            Metric::Sd => point.sd,
+            Metric::Converged => point.converged,
+        }
+    }
+
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Metric::Ks => format!("{:.3}", value),
+            Metric::Peak => format!("{:.0}", value),
+            Metric::Sd => format!("{:.2}", value),
+            Metric::Converged => format!("{:.1}%", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub ks: f64,
+    pub peak: u64,
+    pub sd: f64,
+    /// `Metric::Converged`, in percent.
+    pub converged: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    points: VecDeque<Point>,
+}
+
+impl History {
+    pub fn push(&mut self, stats: &Stats, peak: u64, converged: f64) {
+        if self.points.len() == CAPACITY {
+            self.points.pop_front();
+        }
+        self.points.push_back(Point {
+            ks: stats.ks,
+            peak,
+            sd: stats.sd,
+            converged,
+        });
+    }
+
+    pub fn latest(&self, metric: Metric) -> Option<f64> {
+        self.points.back().map(|point| metric.of(point))
+    }
+
+    /// Bytes the points take. There are never more than `CAPACITY`, so
+    /// they're not worth pruning.
+    pub fn bytes(&self) -> usize {
+        self.points.capacity() * std::mem::size_of::<Point>()
+    }
+
+    /// The metric's recent values, oldest first, with undefined values (no
+    /// samples yet) as zero.
+    pub fn values(&self, metric: Metric) -> Vec<f64> {
+        self.points
+            .iter()
+            .map(|point| metric.of(point))
+            .map(|v| if v.is_finite() { v } else { 0.0 })
+            .collect()
+    }
+
+    /// The metric's recent values scaled to integers for tui's `Sparkline`.
+    pub fn sparkline(&self, metric: Metric) -> Vec<u64> {
+        let values = self.values(metric);
+        let max = values.iter().cloned().fold(0.0, f64::max);
+        if max <= 0.0 {
+            return vec![0; values.len()];
+        }
+        values
+            .iter()
+            .map(|v| (v / max * 1000.0).round() as u64)
+            .collect()
+    }
+}
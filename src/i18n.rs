@@ -0,0 +1,140 @@
+//! The text the TUI shows, looked up by key in a table per language.
+//!
+//! Tables are embedded TOML files of `key = "text"` lines. Text may name
+//! arguments in braces, `{tick}`, which `fill` replaces. Numbers are formatted
+//! before they're passed in, and stay ASCII in every language.
+
+use std::{collections::HashMap, fmt::Display};
+
+/// Every shipped language; the first is the fallback for missing keys.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("en", include_str!("lang/en.toml")),
+    ("de", include_str!("lang/de.toml")),
+];
+
+pub fn is_language(code: &str) -> bool {
+    LANGUAGES.iter().any(|(name, _)| *name == code)
+}
+
+/// The codes of the shipped languages, for error messages.
+pub fn languages() -> String {
+    LANGUAGES
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, Clone)]
+pub struct Strings {
+    table: HashMap<String, String>,
+}
+
+impl Default for Strings {
+    fn default() -> Strings {
+        Strings::load("en").expect("the English table parses")
+    }
+}
+
+impl Strings {
+    /// The table for `code`, with English for any key it leaves out.
+    pub fn load(code: &str) -> Result<Strings, String> {
+        let (_, source) = LANGUAGES
+            .iter()
+            .find(|(name, _)| *name == code)
+            .ok_or(format!("unknown language '{}'", code))?;
+        let mut table = parse(LANGUAGES[0].1).map_err(|err| format!("en.toml: {}", err))?;
+        table.extend(parse(source).map_err(|err| format!("{}.toml: {}", code, err))?);
+        Ok(Strings { table })
+    }
+
+    /// The text for `key`, or the key itself if no table has it, so a typo
+    /// shows up on screen instead of as a blank.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.table.get(key).map_or(key, String::as_str)
+    }
+
+    /// The text for `key` with each `{name}` replaced by its argument.
+    pub fn fill(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.get(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+}
+
+/// Reads `key = "text"` lines, skipping blank lines and `#` comments.
+fn parse(source: &str) -> Result<HashMap<String, String>, String> {
+    let mut table = HashMap::new();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |what: &str| format!("line {}: {}", number + 1, what);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected key = \"text\""))?;
+        let value = value
+            .trim()
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .ok_or_else(|| error("the text must be in double quotes"))?;
+        table.insert(
+            key.trim().to_string(),
+            unescape(value).map_err(|e| error(&e))?,
+        );
+    }
+    Ok(table)
+}
+
+fn unescape(text: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            other => return Err(format!("unknown escape \\{}", other.unwrap_or(' '))),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn keys(code: &str, source: &str) -> BTreeSet<String> {
+        parse(source)
+            .unwrap_or_else(|err| panic!("{}.toml: {}", code, err))
+            .into_keys()
+            .collect()
+    }
+
+    #[test]
+    fn every_language_has_every_key() {
+        let (fallback, source) = LANGUAGES[0];
+        let expected = keys(fallback, source);
+        for (code, source) in &LANGUAGES[1..] {
+            let found = keys(code, source);
+            let missing = expected.difference(&found).collect::<Vec<_>>();
+            let extra = found.difference(&expected).collect::<Vec<_>>();
+            assert!(missing.is_empty(), "{}.toml is missing {:?}", code, missing);
+            assert!(
+                extra.is_empty(),
+                "{}.toml has keys en.toml doesn't: {:?}",
+                code,
+                extra
+            );
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Ctrl+C as a request to stop early rather than a kill, for the batch modes
+//! that have partial results worth printing or files worth flushing, and
+//! SIGTERM too for a daemon run.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
+
+#[cfg(unix)]
+extern "C" fn handle(_signal: libc::c_int) {
+    REQUESTED.store(true, Ordering::Relaxed);
+}
+
+#[cfg(unix)]
+pub fn install() {
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        libc::signal(libc::SIGINT, handle as *const () as libc::sighandler_t);
+    }
+}
+
+/// Like `install`, and takes SIGTERM the same way, so a service manager
+/// stopping the run gets its results written first.
+#[cfg(unix)]
+pub fn install_terminate() {
+    install();
+    // SAFETY: as for `install`.
+    unsafe {
+        libc::signal(libc::SIGTERM, handle as *const () as libc::sighandler_t);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install() {}
+
+#[cfg(not(unix))]
+pub fn install_terminate() {}
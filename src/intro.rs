@@ -0,0 +1,78 @@
+//! The intro sweep: a few ticks of single steps, two spikes at ±1, then the
+//! steps growing to the configured count over a few seconds, to show the bell
+//! curve emerge before the run proper starts.
+//!
+//! The intro draws from a simulation of its own, seeded from the presentation
+//! stream, so the run's random sequence is the same with or without it.
+
+use central_limit::simulation::Simulation;
+use std::time::Duration;
+
+/// Interval between intro ticks, whatever the run's tick rate.
+pub const TICK: Duration = Duration::from_millis(250);
+
+/// Ticks spent on single steps before the sweep starts.
+const HOLD: u64 = 4;
+
+/// Ticks the sweep from one step to the configured count takes.
+const SWEEP: i32 = 20;
+
+#[derive(Debug)]
+pub struct Intro {
+    /// Steps and ticks at each stage, in order.
+    stages: Vec<(i32, u64)>,
+    stage: usize,
+    /// Ticks drawn at the current stage.
+    ticks: u64,
+    target: i32,
+    /// The run's simulation, put back when the intro ends.
+    run: Simulation,
+}
+
+impl Intro {
+    /// An intro sweeping from one step to `target`, holding on to `run`
+    /// meanwhile.
+    pub fn new(run: Simulation, target: i32) -> Intro {
+        let mut stages = vec![(1, HOLD)];
+        for i in 1..=SWEEP {
+            let steps = 1 + ((target - 1) as f64 * i as f64 / SWEEP as f64).round() as i32;
+            match stages.last_mut() {
+                Some((last, ticks)) if *last == steps => *ticks += 1,
+                _ => stages.push((steps, 1)),
+            }
+        }
+        Intro {
+            stages,
+            stage: 0,
+            ticks: 0,
+            target,
+            run,
+        }
+    }
+
+    /// The configured steps, which the sweep ends on.
+    pub fn target(&self) -> i32 {
+        self.target
+    }
+
+    /// Counts a finished tick. Returns the steps of the next stage if this
+    /// tick ended one, or `None` if the stage goes on or was the last.
+    pub fn advance(&mut self) -> Option<i32> {
+        self.ticks += 1;
+        if self.ticks < self.stages[self.stage].1 {
+            return None;
+        }
+        self.stage += 1;
+        self.ticks = 0;
+        self.stages.get(self.stage).map(|(steps, _)| *steps)
+    }
+
+    /// Whether every stage has been shown.
+    pub fn finished(&self) -> bool {
+        self.stage >= self.stages.len()
+    }
+
+    pub fn into_run(self) -> Simulation {
+        self.run
+    }
+}
@@ -0,0 +1,21 @@
+//! The simulation core of Central Limit, usable without the terminal UI.
+//! With the `tui` feature, `histogram` is the terminal UI's histogram view as
+//! a widget, and `bars` the bar chart it draws with, for drawing them in a
+//! `tui` app of your own. `ffi` is a C API over the simulation, declared in
+//! `include/central_limit.h`.
+
+pub mod axis;
+pub mod balance;
+#[cfg(feature = "tui")]
+pub mod bars;
+pub mod bucket;
+pub mod ffi;
+pub mod format;
+#[cfg(all(test, feature = "tui"))]
+mod golden;
+#[cfg(feature = "tui")]
+pub mod histogram;
+pub mod rng;
+pub mod sampling;
+pub mod simulation;
+pub mod stats;
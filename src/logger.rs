@@ -0,0 +1,56 @@
+//! A small file logger; the TUI owns the terminal, so logs can't go to stderr.
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+struct FileLogger {
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "{}.{:03} {:<5} {}",
+                now.as_secs(),
+                now.subsec_millis(),
+                record.level(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Logs to `path`, with debug messages too if `verbose`.
+pub fn init(path: &Path, verbose: bool) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    log::set_boxed_logger(Box::new(FileLogger {
+        file: Mutex::new(file),
+    }))
+    .map_err(|err| io::Error::other(err.to_string()))?;
+    log::set_max_level(match verbose {
+        true => LevelFilter::Debug,
+        false => LevelFilter::Info,
+    });
+    Ok(())
+}
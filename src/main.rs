@@ -7,223 +7,3295 @@
  * @link        https://afaan.dev
  *
  */
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+mod alert;
+mod ascii;
+mod attach;
+mod autosave;
+mod bench;
+mod bias;
+mod blind;
+mod cli;
+mod clip;
+mod cycles;
+mod daemon;
+mod datetime;
+mod decay;
+mod diff;
+mod driver;
+mod events;
+mod export;
+#[cfg(feature = "image-export")]
+mod gif;
+#[cfg(test)]
+mod golden;
+#[cfg(test)]
+mod harness;
+mod headless;
+mod history;
+mod i18n;
+mod interrupt;
+mod intro;
+mod json;
+mod logger;
+mod merge;
+mod mode;
+mod overlay;
+mod pace;
+mod perf;
+mod pin;
+mod presentation;
+mod provenance;
+mod raw;
+mod reference;
+mod replay;
+mod rngcheck;
+mod schedule;
+mod seeds;
+mod selftest;
+mod session;
+mod settings;
+mod smoke;
+#[cfg(feature = "audio")]
+mod sonify;
+mod status;
+mod stream;
+mod summary;
+mod suspend;
+mod sweep;
+mod target;
+mod term;
+mod theme;
+mod throttle;
+mod timeline;
+mod toast;
+mod ui;
+mod until;
+mod warm;
+mod watch;
+
+use alert::Alert;
+use attach::AttachSource;
+use autosave::Autosave;
+use bars::{RenderSettings, Renderer};
+use bias::Sweep;
+use blind::Blind;
+use bucket::{Aggregate, Bucket};
+use central_limit::{
+    axis::AutoBound,
+    bars, bucket, format,
+    histogram::Plot,
+    rng::Generator,
+    sampling::{self, Regime},
+    simulation::Simulation,
+    stats,
 };
+use cli::{Args, Command};
+use clip::Clip;
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers, MouseEventKind};
+use cycles::Cycles;
+use datetime::DateTime;
+use decay::Decay;
+use driver::{Clock, Events};
+use events::EventLog;
+use history::{History, Metric, Point};
+use i18n::Strings;
+use intro::Intro;
+use merge::Bins;
+use mode::{Mode, Transition};
+use overlay::Overlay;
+use pace::Pace;
+use perf::Perf;
+use pin::Pin;
+use presentation::PresentationRng;
+use provenance::Provenance;
 use rand::prelude::*;
+use reference::Reference;
+use replay::ReplaySource;
+use rngcheck::RngCheck;
+use seeds::SeedRuns;
+use session::Session;
+use settings::{Action, Field, Panel};
+use stats::{Model, Stats};
+use status::StatusFile;
 use std::{
+    borrow::Cow,
+    cell::Cell,
+    env,
     error::Error,
-    io,
-    time::{Duration, Instant},
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        mpsc::{self, Receiver},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
+use stream::Streams;
+use summary::Summary;
+use target::Target;
+use theme::{Palette, Theme};
+use throttle::Throttle;
+use timeline::Timeline;
+use toast::Toasts;
 use tui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    symbols,
-    text::Span,
-    widgets::{Axis, BarChart, Block, Borders, Chart, Dataset, GraphType, Paragraph},
-    Frame, Terminal,
+    Terminal,
 };
+use ui::View;
+use until::Until;
+use warm::WarmStart;
+use watch::Watch;
+
+const DEFAULT_TICK_RATE: Duration = Duration::from_millis(500);
+
+/// Samples per tick and steps per sample unless given on the command line.
+const DEFAULT_SAMPLES: usize = 5000;
+const DEFAULT_STEPS: i32 = 19;
+
+/// The most samples per tick and steps per sample a run takes: a tick is
+/// drawn in pieces, but each sum has a bucket to be counted in.
+const MAX_SAMPLES: usize = 1_000_000_000;
+const MAX_STEPS: i32 = 1_000_000;
+
+/// Buckets the model expects fewer samples in than this are left out of
+/// the errors view, where a single sample would be a huge error.
+const ERRORS_MIN_EXPECTED: f64 = 1.0;
+
+/// The relative standard error a bucket's share is counted as converged
+/// within, unless `--precision` says.
+const DEFAULT_PRECISION: f64 = 0.01;
+
+/// Samples `f` adds at once, unless `--fast-forward` says.
+const DEFAULT_FAST_FORWARD: u64 = 100_000_000;
+
+/// Sample paths kept for the walks view.
+const WALKS: usize = 8;
 
 struct App {
     b_count: usize,
     r_max: i32,
-    data: Vec<(String, u64)>,
+    seed: u64,
+    /// Draws the samples of live ticks.
+    sim: Simulation,
+    /// Draws whatever is only for show, so it never takes from `sim`'s
+    /// stream.
+    presentation: PresentationRng,
+    data: Vec<Bucket>,
+    /// Running sums of the first few samples of the last tick, step by step.
+    walks: Vec<Vec<i32>>,
+    /// Counts from the tick before `data`, to animate the transition from
+    /// and to color the bars by in the diff view.
+    previous: Vec<u64>,
+    animate: bool,
+    /// How far through the current tick interval the display is, from 0 to 1.
+    progress: f64,
+    /// The current interval between ticks.
+    tick_rate: Duration,
+    y_bound: AutoBound,
+    /// The errors view's bound, in percent either side of 0.
+    error_bound: AutoBound,
+    history: History,
+    metric: Metric,
+    show_normal: bool,
+    /// The model the overlay, chi-square test and residuals use, if chosen
+    /// with `e`; otherwise it depends on the steps.
+    model: Option<Model>,
+    show_legend: bool,
+    show_bands: bool,
+    /// ±1 standard error around each bucket on the line chart.
+    show_errors: bool,
+    /// Whether the bars are colored by how they changed since the last tick.
+    show_diff: bool,
+    show_grid: bool,
+    /// Whether the bar chart merges the tails, and beyond how many
+    /// standard deviations.
+    merge_tails: bool,
+    merge_sigmas: f64,
+    /// The bars the buckets are merged into, while `merge_tails` is on.
+    bins: Option<Bins>,
+    /// The range the charts are narrowed to by `--clip-percentile`, or to
+    /// the middle of a walk long enough to be drawn from the normal
+    /// approximation.
+    clip: Option<Clip>,
+    palette: Palette,
+    /// Whether colors are off, by `--no-color` or `NO_COLOR`, which keeps
+    /// the theme mono.
+    no_color: bool,
+    view: View,
+    /// Center of the bucket under the mouse or keyboard cursor. It's kept
+    /// by value, so it stays on the same sum when the buckets change.
+    selected: Option<f64>,
+    /// Written by the UI while drawing.
+    plot: Cell<Option<Plot>>,
+    /// Relative heights of the bars and the line chart.
+    split: (u32, u32),
+    /// Show the charts as percentages of the total instead of raw counts.
+    percent: bool,
+    render: RenderSettings,
+    tick: u64,
+    /// Whether each tick's samples replace the histogram, add to it, or
+    /// fade what's there.
+    mode: Mode,
+    /// λ the next switch to decaying starts from.
+    lambda: f64,
+    /// With `--bias-sweep`, the coin's bias from one tick to the next.
+    bias_sweep: Option<Sweep>,
+    /// The bias the counts in `data` were drawn at.
+    bias: f64,
+    warm: Option<WarmStart>,
+    /// An earlier export drawn alongside the live histogram.
+    reference: Option<Reference>,
+    /// Histograms pinned with `k`, oldest first, and how many there have
+    /// been, to number them.
+    pins: Vec<Pin>,
+    pinned: u64,
+    /// The blind comparison shown in place of the histogram, while `x` has
+    /// started one.
+    blind: Option<Blind>,
+    /// Samples each fast-forward adds, and how many have been added in all.
+    fast_forward: u64,
+    fast_forwarded: u64,
+    /// Samples the run's ticks have drawn, as many as each drew.
+    drawn: u64,
+    /// While a tick is still being drawn, the samples `sim` held before it.
+    pending: Option<u64>,
+    /// How long the pending tick has taken to sample so far.
+    tick_work: Duration,
+    /// No new ticks are drawn, and the arrow keys step through the timeline.
+    paused: bool,
+    timeline: Timeline,
+    target: Option<Target>,
+    toasts: Toasts,
+    alert: Option<Alert>,
+    /// Set when the alert fires; the front end rings the bell and clears it.
+    bell: bool,
+    /// The `--until` condition, and whether to quit once it's met.
+    until: Option<Until>,
+    until_exit: bool,
+    /// The `--precision` target, in percent.
+    precision: Option<f64>,
+    /// With `--cycles`, which cycle is running and how the others ended.
+    cycles: Option<Cycles>,
+    /// The result of `--startup-check`, if it was asked for.
+    rng_check: Option<RngCheck>,
+    /// With `--start-at`, when the run is to start, until it has.
+    start_at: Option<SystemTime>,
+    /// With `--start-at`, the wall-clock time the run started.
+    started: Option<DateTime>,
+    perf: Perf,
+    throttle: Throttle,
+    /// The `--max-memory` cap on `buffer_bytes`, and whether it has had the
+    /// timeline pruned yet.
+    max_memory: Option<usize>,
+    pruned: bool,
+    /// Whether the steps drawn have strayed from their expected mean, which
+    /// keeps a warning up for the rest of the run.
+    drifted: bool,
+    /// With every sample of the histogram shown in one bucket, its center.
+    degenerate: Option<f64>,
+    /// Where each parameter came from, and how it changed during the run.
+    provenance: Provenance,
+    strings: Strings,
+    status: Option<StatusFile>,
+    autosave: Option<Autosave>,
+    events: Option<EventLog>,
+    #[cfg(feature = "image-export")]
+    gif: Option<gif::GifExport>,
+    #[cfg(feature = "audio")]
+    sound: Option<sonify::Sonify>,
+    /// The `--watch-config` file, and when it was last applied.
+    watch: Option<Watch>,
+    reloaded: Option<Instant>,
+    /// The `--seeds` runs drawn alongside `sim`.
+    seeds: Option<SeedRuns>,
+    /// The `--overlay` sources walked alongside `sim`, drawn in place of
+    /// the histogram.
+    overlay: Option<Overlay>,
+    /// The intro sweep, while it plays.
+    intro: Option<Intro>,
+    /// The settings panel, while it's open.
+    settings: Option<Panel>,
+    show_hud: bool,
+    clients: Option<usize>,
+    /// What the tick hook reported, if one is installed.
+    feedback: Option<Receiver<Feedback>>,
+    accessible: bool,
+    /// Only the current view, with no header, borders or status line.
+    minimal: bool,
+    summary: Option<Summary>,
+    replay_status: Option<String>,
+    /// Whether the ticks are followed from another run's socket, and why
+    /// it's not getting them, while it isn't.
+    attached: bool,
+    connection_lost: Option<String>,
+    session: Option<String>,
 }
 
 impl App {
-    fn new() -> App {
-        App {
-            b_count: 5000,
-            r_max: 19, // must be odd
+    fn new(seed: u64, generator: Generator) -> App {
+        let mut app = App {
+            b_count: DEFAULT_SAMPLES,
+            r_max: DEFAULT_STEPS,
+            seed,
+            sim: Simulation::with_generator(DEFAULT_STEPS, seed, generator)
+                .expect("the default steps are valid"),
+            presentation: PresentationRng::new(seed),
             data: vec![],
-        }
+            walks: vec![],
+            previous: vec![],
+            animate: true,
+            progress: 1.0,
+            tick_rate: DEFAULT_TICK_RATE,
+            y_bound: AutoBound::new(),
+            error_bound: AutoBound::new(),
+            history: History::default(),
+            metric: Metric::Ks,
+            show_normal: true,
+            model: None,
+            show_legend: true,
+            show_bands: false,
+            show_errors: false,
+            show_diff: false,
+            show_grid: false,
+            merge_tails: false,
+            merge_sigmas: merge::DEFAULT_SIGMAS,
+            bins: None,
+            clip: None,
+            palette: Palette::Classic,
+            no_color: false,
+            view: View::Histogram,
+            selected: None,
+            plot: Cell::new(None),
+            split: (1, 1),
+            percent: false,
+            render: RenderSettings::default(),
+            tick: 0,
+            mode: Mode::Regenerate,
+            lambda: mode::DEFAULT_LAMBDA,
+            bias_sweep: None,
+            bias: sampling::FAIR,
+            warm: None,
+            reference: None,
+            pins: vec![],
+            pinned: 0,
+            blind: None,
+            fast_forward: DEFAULT_FAST_FORWARD,
+            fast_forwarded: 0,
+            drawn: 0,
+            pending: None,
+            tick_work: Duration::ZERO,
+            paused: false,
+            timeline: Timeline::new(timeline::DEFAULT_TICKS),
+            target: None,
+            toasts: Toasts::default(),
+            alert: None,
+            bell: false,
+            until: None,
+            until_exit: false,
+            precision: None,
+            cycles: None,
+            rng_check: None,
+            start_at: None,
+            started: None,
+            perf: Perf::default(),
+            throttle: Throttle::default(),
+            max_memory: None,
+            pruned: false,
+            drifted: false,
+            degenerate: None,
+            provenance: Provenance::default(),
+            strings: Strings::default(),
+            status: None,
+            autosave: None,
+            events: None,
+            #[cfg(feature = "image-export")]
+            gif: None,
+            #[cfg(feature = "audio")]
+            sound: None,
+            watch: None,
+            reloaded: None,
+            seeds: None,
+            overlay: None,
+            intro: None,
+            settings: None,
+            show_hud: false,
+            clients: None,
+            feedback: None,
+            accessible: false,
+            minimal: false,
+            summary: None,
+            replay_status: None,
+            attached: false,
+            connection_lost: None,
+            session: None,
+        };
+        app.reset_buckets();
+        app
     }
 
     fn on_tick(&mut self) {
-        let (b_min, b_max) = (-(self.r_max + 2), self.r_max + 2);
+        self.begin_tick();
+        // In pieces, so the sums of a large tick aren't all held at once.
+        while !self.continue_tick(CHUNK) {}
+    }
+
+    /// Rejects parameters the simulation can't draw: every tick needs at
+    /// least one sample of at least one step, and no more of either than
+    /// `MAX_SAMPLES` and `MAX_STEPS`.
+    fn check_params(b_count: usize, r_max: i32) -> Result<(), String> {
+        if b_count == 0 {
+            return Err("samples per tick must be at least 1".to_string());
+        }
+        if b_count > MAX_SAMPLES {
+            return Err(format!(
+                "samples per tick must be at most {}",
+                format::grouped(MAX_SAMPLES as u64)
+            ));
+        }
+        if r_max < 1 {
+            return Err("steps per sample must be at least 1".to_string());
+        }
+        if r_max > MAX_STEPS {
+            return Err(format!(
+                "steps per sample must be at most {}",
+                format::grouped(MAX_STEPS as u64)
+            ));
+        }
+        Ok(())
+    }
+
+    fn set_params(&mut self, b_count: usize, r_max: i32) -> Result<(), String> {
+        App::check_params(b_count, r_max)?;
+        if self.bias_sweep.is_some() {
+            self.check_bias_sweep(r_max, false)?;
+        }
+        self.sim.set_steps(r_max)?;
+        self.b_count = b_count;
+        self.r_max = r_max;
+        // A tick part-drawn for the old parameters is dropped, not finished.
+        self.pending = None;
+        self.reset_buckets();
+        self.timeline.clear();
+        if let Some(decay) = self.mode.decay_mut() {
+            decay.clear();
+        }
+        Ok(())
+    }
+
+    /// Applies a config file's settings, or none of them if any is invalid.
+    /// Only a change of steps starts the counts over, since it changes the
+    /// buckets.
+    fn apply_config(&mut self, config: &watch::Config) -> Result<(), String> {
+        let b_count = config.samples.unwrap_or(self.b_count);
+        let r_max = config.r_max.unwrap_or(self.r_max);
+        App::check_params(b_count, r_max)?;
+        let mut sweep = self.bias_sweep;
+        if config.bias.is_some() || config.bias_period.is_some() {
+            let (from, to) = match (config.bias, &sweep) {
+                (Some(range), _) => range,
+                (None, Some(sweep)) => (sweep.from(), sweep.to()),
+                (None, None) => {
+                    return Err("'bias_period' needs 'bias_from' and 'bias_to'".to_string())
+                }
+            };
+            let period = config
+                .bias_period
+                .or(sweep.map(|sweep| sweep.period()))
+                .unwrap_or(bias::DEFAULT_PERIOD);
+            match &mut sweep {
+                Some(sweep) => sweep.set_schedule(from, to, period)?,
+                None => sweep = Some(Sweep::new(from, to, period)?),
+            }
+        }
+        if sweep.is_some() {
+            self.check_bias_sweep(r_max, config.cumulative == Some(true))?;
+        }
+        self.bias_sweep = sweep;
+        // Before the first tick, the empty histogram's model is the sweep's.
+        if let Some(sweep) = sweep.filter(|_| self.tick == 0) {
+            self.bias = sweep.bias();
+        }
+        if r_max != self.r_max {
+            self.set_params(b_count, r_max)?;
+        } else if b_count != self.b_count {
+            // Counting cumulatively, a part-drawn tick's samples are in the
+            // counts already, so it's finished at the old size. Otherwise
+            // it's dropped and drawn again at the new one.
+            if self.mode.is_cumulative() {
+                while !self.continue_tick(CHUNK) {}
+            }
+            self.b_count = b_count;
+            self.pending = None;
+        }
+        if let Some(rate) = config.tick_rate {
+            self.tick_rate = rate;
+        }
+        match config.cumulative {
+            Some(true) => self.set_mode(Mode::Cumulative),
+            // Turning cumulative counting off leaves decaying alone.
+            Some(false) if self.mode.is_cumulative() => self.set_mode(Mode::Regenerate),
+            _ => {}
+        }
+        if let Some(palette) = config.theme {
+            self.set_palette(palette);
+        }
+        Ok(())
+    }
+
+    /// Switches how the ticks' samples go into the histogram, keeping as
+    /// much of the counts as the new mode can use, and says what it kept.
+    fn set_mode(&mut self, mut mode: Mode) {
+        let Some(transition) = self.mode.transition(&mode) else {
+            return;
+        };
+        // A replay's counts come from the recording.
+        if self.replay_status.is_some() || self.intro.is_some() {
+            return;
+        }
+        if self.bias_sweep.is_some() {
+            self.toasts
+                .info(self.strings.get("toast.mode_bias").to_string());
+            return;
+        }
+        if self
+            .until
+            .as_ref()
+            .is_some_and(|until| until.met().is_none())
+        {
+            self.toasts
+                .info(self.strings.get("toast.mode_until").to_string());
+            return;
+        }
+        if self.seeds.is_some() && mode.decay().is_some() {
+            self.toasts
+                .info(self.strings.get("toast.mode_seeds").to_string());
+            return;
+        }
+
+        // The latest tick's counts carry on, not those of one from the
+        // timeline.
+        if self.timeline.viewing().is_some() {
+            if let Some(latest) = self.timeline.resume() {
+                set_counts(&mut self.data, &latest.counts);
+            }
+        }
+        let counts = self
+            .data
+            .iter()
+            .map(|bucket| bucket.count)
+            .collect::<Vec<_>>();
+        let samples = stats::total(counts.iter().copied());
+        // A tick part-drawn for the old mode would add up wrongly in the new
+        // one, so it's dropped and drawn again.
+        self.pending = None;
+        self.sim.reset();
+        match (transition, &mut mode) {
+            // The histogram stays empty until the next tick, and the
+            // carried-over counts go with the rest.
+            (Transition::Discard, _) => {
+                self.data.iter_mut().for_each(|bucket| bucket.count = 0);
+                self.previous.clear();
+                self.warm = None;
+            }
+            (_, Mode::Decay(decay)) => decay.start_from(&counts),
+            _ => self
+                .sim
+                .add_counts(&counts)
+                .expect("the histogram has a count per bucket"),
+        }
+        if let Some(decay) = self.mode.decay() {
+            self.lambda = decay.lambda();
+        }
+        let key = match (transition, &mode) {
+            (Transition::Discard, _) => "toast.mode_regenerate",
+            (Transition::FromTick, Mode::Decay(_)) => "toast.mode_decay_from_tick",
+            (Transition::FromTick, _) => "toast.mode_cumulative_from_tick",
+            (Transition::Keep, _) => "toast.mode_decay_kept",
+            (Transition::Round, _) => "toast.mode_cumulative_rounded",
+        };
+        self.mode = mode;
+        // Before the first tick there's nothing to carry over, or to say.
+        if self.tick > 0 {
+            let message = self.strings.fill(
+                key,
+                &[
+                    ("samples", &format::si(samples)),
+                    ("lambda", &format!("{:.2}", self.lambda)),
+                ],
+            );
+            self.toasts.info(message);
+        }
+    }
+
+    /// Checks that a bias sweep can go with the run: every tick has to
+    /// start the histogram over, so it's all drawn at one bias, from walks
+    /// short enough to be drawn exactly.
+    fn check_bias_sweep(&self, r_max: i32, cumulative: bool) -> Result<(), String> {
+        if cumulative || !matches!(self.mode, Mode::Regenerate) {
+            return Err("a bias sweep needs each tick to start the histogram over".to_string());
+        }
+        if self.seeds.is_some() || self.until.is_some() {
+            return Err("a bias sweep can't be combined with --seeds or --until".to_string());
+        }
+        if Regime::for_steps(r_max as u32, self.sim.exact_below()) == Regime::Normal {
+            return Err(format!(
+                "a bias sweep needs walks of fewer than {} steps, which are drawn exactly",
+                self.sim.exact_below()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Holds the bias sweep where it is, or lets it carry on.
+    fn toggle_bias_hold(&mut self) {
+        let Some(sweep) = &mut self.bias_sweep else {
+            return;
+        };
+        sweep.toggle_hold();
+        let key = match sweep.held() {
+            true => "toast.bias_held",
+            false => "toast.bias_released",
+        };
+        let message = self
+            .strings
+            .fill(key, &[("p", &format!("{:.3}", sweep.bias()))]);
+        self.toasts.info(message);
+    }
+
+    /// Turns the `--sonify` clicks off or on.
+    #[cfg(feature = "audio")]
+    fn toggle_sound(&mut self) {
+        let Some(sound) = &mut self.sound else {
+            return;
+        };
+        sound.toggle();
+        let key = match sound.on() {
+            true => "toast.sound_on",
+            false => "toast.sound_off",
+        };
+        let message = self.strings.fill(key, &[("volume", &sound.volume())]);
+        self.toasts.info(message);
+    }
+
+    #[cfg(not(feature = "audio"))]
+    fn toggle_sound(&mut self) {}
+
+    /// Turns the `--sonify` clicks up or down.
+    #[cfg(feature = "audio")]
+    fn adjust_volume(&mut self, up: bool) {
+        let Some(sound) = &mut self.sound else {
+            return;
+        };
+        let volume = sound.adjust(up);
+        let message = self.strings.fill("toast.volume", &[("volume", &volume)]);
+        self.toasts.info(message);
+    }
+
+    #[cfg(not(feature = "audio"))]
+    fn adjust_volume(&mut self, _up: bool) {}
+
+    /// The colors and markers to draw with, in ASCII if that's all that can
+    /// be drawn.
+    fn theme(&self) -> Theme {
+        let theme = self.palette.theme();
+        match self.render.ascii {
+            true => theme.ascii(),
+            false => theme,
+        }
+    }
+
+    /// Switches to `palette`, unless colors are off.
+    fn set_palette(&mut self, palette: Palette) {
+        if self.no_color {
+            self.toasts
+                .info(self.strings.get("toast.no_color").to_string());
+        } else {
+            self.palette = palette;
+        }
+    }
+
+    /// Re-reads the watched config file if it changed since the last look.
+    /// Call between ticks, so the samples per tick don't change under one.
+    fn check_config(&mut self) {
+        let Some(watch) = &mut self.watch else {
+            return;
+        };
+        if !watch.changed() {
+            return;
+        }
+        let contents = fs::read_to_string(watch.path()).map_err(|err| err.to_string());
+        let applied = contents.and_then(|contents| {
+            let config = watch::parse(&contents)?;
+            self.apply_config(&config).map(|()| contents)
+        });
+        match applied {
+            Ok(contents) => {
+                self.reloaded = Some(Instant::now());
+                self.note_changes(provenance::Source::Config);
+                events::config(self, &contents);
+            }
+            Err(err) => {
+                let message = self.strings.fill("toast.config_error", &[("error", &err)]);
+                self.toasts.error(message);
+            }
+        }
+    }
+
+    /// Starts a tick whose samples are drawn by `continue_tick`.
+    fn begin_tick(&mut self) {
+        let started = Instant::now();
+        if !self.mode.is_cumulative() {
+            self.sim.reset();
+        }
+        if self.intro.is_none() {
+            if let Some(sweep) = &mut self.bias_sweep {
+                self.sim
+                    .set_bias(sweep.next())
+                    .expect("the sweep's biases are valid");
+            }
+        }
+        self.pending = Some(self.sim.samples());
+        // The first few samples are drawn step by step for the walks view;
+        // drawing them first keeps the random sequence the same either way.
+        self.walks = self.sim.advance_walks(WALKS.min(self.b_count));
+        // The overlaid sources draw each part of the tick alongside the run.
+        if let Some(overlay) = self.overlay.as_mut().filter(|_| self.intro.is_none()) {
+            overlay.begin_tick(self.r_max as u32, self.mode.is_cumulative());
+            overlay.draw(self.walks.len());
+        }
+        self.tick_work = started.elapsed();
+    }
+
+    /// Draws up to `max` more samples of the pending tick and returns true
+    /// once it is complete. Drawing a tick in chunks gives the same sums as
+    /// drawing it at once.
+    fn continue_tick(&mut self, max: usize) -> bool {
+        let Some(before) = self.pending else {
+            return true;
+        };
+        let drawn = self.sim.samples().saturating_sub(before) as usize;
+        let n = self.b_count.saturating_sub(drawn).min(max);
+        let started = Instant::now();
+        self.sim.draw(n);
+        if let Some(overlay) = self.overlay.as_mut().filter(|_| self.intro.is_none()) {
+            overlay.draw(n);
+        }
+        self.throttle.after_work(started.elapsed());
+        if drawn + n < self.b_count {
+            self.tick_work += started.elapsed();
+            return false;
+        }
+        self.pending = None;
+        self.sim.end_tick();
+
+        self.remember_previous();
+        self.data = self.sim.histogram();
+        self.bias = self.sim.bias();
+        if self.intro.is_some() {
+            self.refresh();
+            self.advance_intro();
+            return true;
+        }
+        if let Some(decay) = self.mode.decay_mut() {
+            decay.add(&self.data);
+            set_counts(&mut self.data, &decay.counts());
+        }
+        self.tick += 1;
+        self.drawn = self.drawn.saturating_add(self.b_count as u64);
+        self.timeline.push(self.tick, self.bias, &self.data);
+        self.check_memory();
+        self.check_balance();
+        if let Some(seeds) = &mut self.seeds {
+            seeds.tick(self.b_count, self.r_max, self.mode.is_cumulative());
+        }
+        self.refresh();
+        self.perf.record_work(self.tick_work + started.elapsed());
+        if let Some(mut status) = self.status.take() {
+            if let Some(err) = status.record(self) {
+                let message = self.strings.fill(
+                    "toast.status_failed",
+                    &[("path", &status.path().display()), ("error", &err)],
+                );
+                log::warn!("{}", message);
+                self.toasts.error(message);
+            }
+            self.status = Some(status);
+        }
+        if let Some(mut autosave) = self.autosave.take() {
+            if let Some(err) = autosave.tick(self) {
+                let message = self.strings.fill(
+                    "toast.autosave_failed",
+                    &[("path", &autosave.path().display()), ("error", &err)],
+                );
+                log::warn!("{}", message);
+                self.toasts.error(message);
+            }
+            self.autosave = Some(autosave);
+        }
+        #[cfg(feature = "image-export")]
+        {
+            let samples = self.samples();
+            let pins = self
+                .pins
+                .iter()
+                .map(|pin| pin.frequencies(&self.data))
+                .collect::<Vec<_>>();
+            if let Some(gif) = &mut self.gif {
+                gif.record(self.tick, samples, &self.data, &pins);
+            }
+        }
+        #[cfg(feature = "audio")]
+        if self.sound.as_ref().is_some_and(sonify::Sonify::on) {
+            let ks = self.stats().ks;
+            if let Some(err) = self.sound.as_mut().and_then(|sound| sound.click(ks)) {
+                let message = self.strings.fill("toast.sound_failed", &[("error", &err)]);
+                self.toasts.error(message);
+            }
+        }
+        true
+    }
+
+    /// Plays the intro sweep before the first tick, drawing from a
+    /// simulation seeded from the presentation stream.
+    fn start_intro(&mut self) -> Result<(), String> {
+        let seed = self.presentation.seed();
+        let mut sim = Simulation::with_generator(1, seed, self.sim.generator())?;
+        sim.set_exact_below(self.sim.exact_below());
+        let run = std::mem::replace(&mut self.sim, sim);
+        self.intro = Some(Intro::new(run, self.r_max));
+        self.r_max = 1;
+        self.reset_buckets();
+        Ok(())
+    }
+
+    /// Moves the intro on after one of its ticks, ending it after the last.
+    fn advance_intro(&mut self) {
+        let Some(intro) = &mut self.intro else {
+            return;
+        };
+        let next = intro.advance();
+        let finished = intro.finished();
+        if let Some(steps) = next {
+            // The peak falls with every stage, faster than the bound would
+            // usually shrink, so it starts over each time.
+            self.y_bound = AutoBound::new();
+            self.error_bound = AutoBound::new();
+            if let Err(err) = self.set_params(self.b_count, steps) {
+                log::warn!("intro stopped: {}", err);
+                self.end_intro();
+            }
+        } else if finished {
+            self.end_intro();
+        }
+    }
+
+    /// Puts the run's simulation back. The intro's last histogram stays on
+    /// screen until the first tick replaces it, so the one fades into the
+    /// other.
+    fn end_intro(&mut self) {
+        let Some(intro) = self.intro.take() else {
+            return;
+        };
+        self.pending = None;
+        self.r_max = intro.target();
+        self.sim = intro.into_run();
+        if self.data.len() != self.sim.counts_slice().len() {
+            // Skipped part-way, with other buckets on screen.
+            self.data = self.sim.histogram();
+            self.update_bins();
+        }
+        self.walks.clear();
+        self.history = History::default();
+        self.y_bound = AutoBound::new();
+        self.error_bound = AutoBound::new();
+        self.timeline.clear();
+    }
+
+    /// Starts the run over from `seed`, with the same parameters, for the
+    /// next of the `--cycles`. What's been pinned stays, to compare the
+    /// cycles by.
+    fn restart(&mut self, seed: u64) {
+        self.seed = seed;
+        self.sim.reseed(seed);
+        self.presentation = PresentationRng::new(seed);
+        self.tick = 0;
+        self.pending = None;
+        self.fast_forwarded = 0;
+        self.drawn = 0;
+        self.blind = None;
+        self.walks.clear();
+        self.previous.clear();
+        self.reset_buckets();
+        if let Some(decay) = self.mode.decay_mut() {
+            decay.clear();
+        }
+        self.history = History::default();
+        self.y_bound = AutoBound::new();
+        self.error_bound = AutoBound::new();
+        self.timeline.clear();
+        if let Some(sweep) = &mut self.bias_sweep {
+            sweep.restart();
+        }
+        self.drifted = false;
+        self.degenerate = None;
+        if let Some(alert) = &mut self.alert {
+            alert.fired = None;
+        }
+    }
+
+    /// Pausing stops new ticks; resuming goes back to the latest one.
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        if !self.paused {
+            if let Some(latest) = self.timeline.resume() {
+                set_counts(&mut self.data, &latest.counts);
+            }
+        }
+    }
+
+    /// Opens the settings panel, pausing the run while it's open. A replay's
+    /// settings come from the recording, so it has none to change.
+    fn open_settings(&mut self) {
+        if self.replay_status.is_some() || self.intro.is_some() {
+            return;
+        }
+        let resume = !self.paused;
+        if resume {
+            self.toggle_pause();
+        }
+        self.settings = Some(Panel::new(resume));
+    }
 
-        let mut buckets = vec![];
-        for r in b_min..=b_max {
-            if r % 2 != 0 {
-                buckets.push(r);
+    fn close_settings(&mut self) {
+        if let Some(panel) = self.settings.take() {
+            if panel.resume() && self.paused {
+                self.toggle_pause();
             }
         }
+    }
 
-        let mut sums = vec![];
-        for b in 0..self.b_count {
-            sums.push(0);
-            sums[b] = 0;
-            for _ in 0..self.r_max {
-                if thread_rng().gen_range(0..10) < 5 {
-                    sums[b] -= 1;
-                } else {
-                    sums[b] += 1;
+    fn on_settings_key(&mut self, code: KeyCode) {
+        let Some(field) = self.settings.as_ref().map(Panel::selected) else {
+            return;
+        };
+        let value = self.setting(field);
+        let Some(panel) = &mut self.settings else {
+            return;
+        };
+        match panel.on_key(code, &value) {
+            Action::None => {}
+            Action::Close => self.close_settings(),
+            Action::Commit(field, text) => {
+                let result = self.apply_setting(field, &text);
+                if let Some(panel) = &mut self.settings {
+                    panel.committed(result);
                 }
             }
         }
+    }
 
-        self.data.clear();
+    /// A field's current value, as the settings panel shows it.
+    fn setting(&self, field: Field) -> String {
+        match field {
+            Field::Samples => self.b_count.to_string(),
+            Field::Steps => self.r_max.to_string(),
+            Field::TickRate => self.tick_rate.as_millis().to_string(),
+            Field::Theme => self.palette.name().to_string(),
+        }
+    }
 
-        for b in buckets {
-            let sum = sums.iter().filter(|s| *s == &b).count() as u64;
-            self.data.push((format!("{}", b), sum));
+    /// Sets a field from the settings panel, through the same checks as a
+    /// config file; the theme moves on to the next one.
+    fn apply_setting(&mut self, field: Field, text: &str) -> Result<(), String> {
+        let number = || {
+            text.parse::<u64>()
+                .map_err(|_| format!("'{}' is not a number", text))
+        };
+        let mut config = watch::Config::default();
+        match field {
+            Field::Samples => config.samples = Some(number()? as usize),
+            Field::Steps => config.r_max = Some(number()?.min(i32::MAX as u64) as i32),
+            Field::TickRate => match number()? {
+                0 => return Err("the tick rate must be at least 1 ms".to_string()),
+                ms => config.tick_rate = Some(Duration::from_millis(ms)),
+            },
+            Field::Theme => config.theme = Some(self.palette.next()),
         }
+        self.apply_config(&config)
     }
-}
 
-fn main() -> Result<(), Box<dyn Error>> {
-    enable_raw_mode()?;
+    /// Shows the timeline's tick before or after the one on screen.
+    fn scrub(&mut self, back: bool) {
+        // A tick still being drawn would replace the shown counts.
+        if self.pending.is_some() {
+            return;
+        }
+        if let Some(snapshot) = self.timeline.step(back) {
+            set_counts(&mut self.data, &snapshot.counts);
+            self.previous.clear();
+        }
+    }
+
+    /// Takes in what the tick hook reported since the last call, returning
+    /// the first error, if any.
+    fn take_feedback(&mut self) -> Option<String> {
+        let mut error = self.sim.take_hook_error().map(|message| {
+            self.strings
+                .fill("toast.hook_panicked", &[("message", &message)])
+        });
+        for feedback in self.feedback.iter().flat_map(Receiver::try_iter) {
+            match feedback {
+                Ok(clients) => self.clients = clients,
+                Err(err) => {
+                    error.get_or_insert(
+                        self.strings.fill("toast.stream_failed", &[("error", &err)]),
+                    );
+                }
+            }
+        }
+        error
+    }
 
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    /// How much of the pending tick has been drawn, from 0 to 1.
+    fn computing(&self) -> Option<f64> {
+        self.pending
+            .map(|before| self.sim.samples().saturating_sub(before) as f64 / self.b_count as f64)
+    }
 
-    let tick_rate = Duration::from_millis(500);
-    let app = App::new();
-    let res = run_app(&mut terminal, app, tick_rate);
+    /// Shows empty buckets for the sums the simulation can reach. Call again
+    /// whenever the steps change.
+    fn reset_buckets(&mut self) {
+        self.data = self.sim.histogram();
+        self.data.iter_mut().for_each(|bucket| bucket.count = 0);
+        self.update_bins();
+        if let Some(clip) = &mut self.clip {
+            clip.clear();
+        }
+    }
 
-    disable_raw_mode()?;
+    /// Works out the merged bars again, for when the buckets or the
+    /// merging change.
+    fn update_bins(&mut self) {
+        self.bins = self.merge_tails.then(|| {
+            Bins::new(
+                &self.shown_data(),
+                self.mean(),
+                self.merge_sigmas * self.sd(),
+            )
+        });
+    }
 
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    fn remember_previous(&mut self) {
+        self.previous.clear();
+        self.previous
+            .extend(self.data.iter().map(|bucket| bucket.count));
+    }
 
-    terminal.show_cursor()?;
+    /// Counts as they should appear on screen: part-way between the previous
+    /// and current tick while animating.
+    /// What the histogram's frequencies are shares of: its samples, or
+    /// the decayed weights' total while decaying, so they add up to one.
+    fn frequency_total(&self) -> u64 {
+        match self.weights() {
+            Some(weights) => weights.iter().sum::<f64>().round() as u64,
+            None => stats::total(self.data.iter().map(|bucket| bucket.count)),
+        }
+    }
 
-    if let Err(err) = res {
-        println!("{:?}", err)
+    fn displayed_counts(&self) -> Vec<f64> {
+        let t = self.progress.clamp(0.0, 1.0);
+        let weights = self.weights();
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(i, bucket)| {
+                let count = weights.map_or(bucket.count as f64, |weights| weights[i]);
+                match self.previous.get(i) {
+                    Some(previous) if self.animate && self.previous.len() == self.data.len() => {
+                        *previous as f64 + (count - *previous as f64) * t
+                    }
+                    _ => count,
+                }
+            })
+            .collect()
     }
 
-    Ok(())
-}
+    /// With `--decay`, the unrounded counts behind `data`, unless an earlier
+    /// tick from the timeline is shown instead.
+    fn weights(&self) -> Option<&[f64]> {
+        self.mode
+            .decay()
+            .map(Decay::weights)
+            .filter(|weights| weights.len() == self.data.len())
+            .filter(|_| self.timeline.viewing().is_none())
+    }
 
-fn run_app<B: Backend>(
-    terminal: &mut Terminal<B>,
-    mut app: App,
-    tick_rate: Duration,
-) -> io::Result<()> {
-    let mut last_tick = Instant::now();
-    loop {
-        terminal.draw(|f| ui(f, &app))?;
+    /// Updates everything derived from `data`.
+    fn refresh(&mut self) {
+        let max = self
+            .data
+            .iter()
+            .map(|bucket| bucket.count)
+            .max()
+            .unwrap_or(0);
+        self.y_bound.update(max as f64);
+        let worst = self
+            .relative_errors()
+            .into_iter()
+            .flatten()
+            .fold(0.0, |worst: f64, error| worst.max(error.abs()));
+        self.error_bound.update(worst * 100.0);
+        if let Some(clip) = &mut self.clip {
+            clip.update(&self.data);
+        }
+
+        let stats = self.stats();
+        let counts = self
+            .data
+            .iter()
+            .map(|bucket| bucket.count)
+            .collect::<Vec<_>>();
+        let converged = stats::converged_percent(&counts, self.precision_target());
+        self.history.push(&stats, max, converged);
+        self.degenerate = stats::single_value(&self.buckets());
+        // The intro's histograms are only for show.
+        if self.intro.is_some() {
+            return;
+        }
 
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+        if let Some(alert) = &mut self.alert {
+            let metric = alert.condition.metric;
+            let value = self.history.latest(metric).unwrap_or(f64::NAN);
+            if alert.check(self.tick, value) {
+                // The desktop notification is sent by the tick hook.
+                let message = alert.condition.message(&self.strings, self.tick, value);
+                self.toasts.info(message);
+                self.bell = true;
+            }
+        }
 
-        if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if let KeyCode::Char('q') = key.code {
-                    return Ok(());
+        if let Some(until) = &mut self.until {
+            let value = self
+                .history
+                .latest(until.condition.metric)
+                .unwrap_or(f64::NAN);
+            if until.record(self.tick, value) {
+                let message = self.strings.fill(
+                    "toast.until",
+                    &[
+                        ("condition", &until.condition),
+                        ("ticks", &until.needed),
+                        ("tick", &self.tick),
+                    ],
+                );
+                self.toasts.info(message);
+                self.bell = true;
+                // Space carries on adding samples, if wanted.
+                if !self.paused {
+                    self.toggle_pause();
                 }
             }
         }
 
-        if last_tick.elapsed() >= tick_rate {
-            app.on_tick();
-            last_tick = Instant::now();
+        let samples = self.samples();
+        if let Some(target) = &mut self.target {
+            if target.record(samples, Instant::now()) {
+                let message = self.strings.fill("toast.target", &[("goal", &target.goal)]);
+                self.toasts.info(message);
+            }
+        }
+
+        // The announced summary only changes when it has changed meaningfully.
+        let summary = Summary::new(&self.shown_data(), self.shown_stats(stats));
+        if self
+            .summary
+            .as_ref()
+            .is_none_or(|s| s.differs_from(&summary))
+        {
+            self.summary = Some(summary);
+        }
+        // A replayed tick can bring different buckets, and a biased coin's
+        // tails move with its mean.
+        if self
+            .bins
+            .as_ref()
+            .is_some_and(|bins| bins.buckets() != self.data.len() || self.bias() != sampling::FAIR)
+        {
+            self.update_bins();
         }
     }
-}
 
-fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(2)
-        .constraints(
-            [
-                Constraint::Percentage(20),
-                Constraint::Percentage(40),
-                Constraint::Percentage(40),
-            ]
-            .as_ref(),
-        )
-        .split(f.size());
+    fn on_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('s') => self.metric = self.metric.next(),
+            KeyCode::Char('n') => self.show_normal = !self.show_normal,
+            KeyCode::Char('e') => self.model = Some(self.model().other()),
+            KeyCode::Char('l') => self.show_legend = !self.show_legend,
+            KeyCode::Char('v') => self.show_bands = !self.show_bands,
+            KeyCode::Char('g') => self.show_grid = !self.show_grid,
+            KeyCode::Char('w') => self.show_errors = !self.show_errors,
+            KeyCode::Char('d') => self.show_diff = !self.show_diff,
+            KeyCode::Char('m') => {
+                self.merge_tails = !self.merge_tails;
+                self.update_bins();
+            }
+            KeyCode::Char('t') => self.set_palette(self.palette.next()),
+            KeyCode::Char('o') => self.open_settings(),
+            KeyCode::Char('c') => self.set_mode(self.mode.next(self.lambda)),
+            KeyCode::Char('C') => self.set_mode(self.mode.previous(self.lambda)),
+            KeyCode::Char('p') => self.percent = !self.percent,
+            KeyCode::Char('k') => self.pin(),
+            KeyCode::Char('x') => self.toggle_blind(),
+            KeyCode::Char('f') => self.fast_forward(),
+            // `a` was the bias sweep's hold before there was sound, so in
+            // a sweep `A` turns the sound off and on.
+            KeyCode::Char('a') if self.bias_sweep.is_some() => self.toggle_bias_hold(),
+            KeyCode::Char('a' | 'A') => self.toggle_sound(),
+            KeyCode::Char(c @ ('-' | '+' | '=')) => self.adjust_volume(c != '-'),
+            KeyCode::Char('K') if !self.pins.is_empty() => {
+                self.pins.clear();
+                self.toasts
+                    .info(self.strings.get("toast.pins_cleared").to_string());
+            }
+            KeyCode::Char('b') if self.render.ascii => self
+                .toasts
+                .info(self.strings.get("toast.ascii_renderer").to_string()),
+            KeyCode::Char('b') => self.render.renderer = self.render.renderer.next(),
+            KeyCode::Char('r') => {
+                if let Some(seeds) = &mut self.seeds {
+                    seeds.cycle();
+                }
+            }
+            KeyCode::Char(c @ ('[' | ']')) => {
+                if let Some(decay) = self.mode.decay_mut() {
+                    decay.adjust(c == ']');
+                }
+            }
+            KeyCode::F(2) => self.show_hud = !self.show_hud,
+            KeyCode::Char('F') | KeyCode::F(11) => self.minimal = !self.minimal,
+            // Replay has its own pausing, and uses the arrow keys to step
+            // through the ticks.
+            KeyCode::Char(' ') if self.replay_status.is_none() => self.toggle_pause(),
+            KeyCode::Left | KeyCode::Right if self.paused => self.scrub(code == KeyCode::Left),
+            KeyCode::Left | KeyCode::Right if self.replay_status.is_none() => {
+                let last = self.data.len().saturating_sub(1);
+                let middle = self.data.len() / 2;
+                let i = match (self.selected_index(), code) {
+                    (Some(i), KeyCode::Left) => i.saturating_sub(1),
+                    (Some(i), _) => (i + 1).min(last),
+                    (None, _) => middle,
+                };
+                self.selected = self.data.get(i).map(|bucket| bucket.x);
+            }
+            KeyCode::Esc => self.selected = None,
+            KeyCode::Tab => self.view = self.view.next(),
+            KeyCode::BackTab => self.view = self.view.previous(),
+            KeyCode::Char(c @ '1'..='9') => {
+                if let Some(view) = View::ALL.get(c as usize - '1' as usize) {
+                    self.view = *view;
+                }
+            }
+            _ => {}
+        }
+    }
 
-    f.render_widget(
-        Paragraph::new(format!(
-            "A simulation of the Central Limit Theorem\n\nAfaan Bilal | https://afaan.dev\n\nIterations per render: {} | Tick rate: {}ms | Buckets: {}\nInspired by this excellent 3B1B video: https://youtu.be/zeJD6dqJ5lo\nPress q to quit",
-            &app.b_count, 500, &app.r_max
-        ))
-        .style(
-            Style::default()
-                .bg(Color::Black)
-                .fg(Color::Green)
-                .add_modifier(Modifier::BOLD),
-        )
-        .alignment(Alignment::Center),
-        chunks[0],
-    );
+    /// Selects the bucket nearest the mouse. Off the chart the selection
+    /// stays, so it can be read while the mouse is elsewhere.
+    fn on_mouse(&mut self, column: u16, row: u16) {
+        if let Some(x) = self.plot.get().and_then(|plot| plot.x_at(column, row)) {
+            self.selected = self.nearest_bucket(x).map(|i| self.data[i].x);
+        }
+    }
 
-    let bar_data = app
-        .data
-        .iter()
-        .map(|x| (x.0.as_str(), x.1))
-        .collect::<Vec<_>>();
-
-    let bar_chart = BarChart::default()
-        .block(Block::default().borders(Borders::ALL))
-        .data(&bar_data)
-        .bar_width(7)
-        .bar_gap(1)
-        .bar_style(Style::default().fg(Color::Green))
-        .label_style(
-            Style::default()
-                .fg(Color::DarkGray)
-                .add_modifier(Modifier::DIM),
-        )
-        .value_style(Style::default().fg(Color::Black).bg(Color::Green));
-    f.render_widget(bar_chart, chunks[1]);
-
-    let app_line_data = app
-        .data
-        .iter()
-        .map(|x| (x.0.parse::<f64>().unwrap(), x.1 as f64))
-        .collect::<Vec<_>>();
-
-    let line_data = vec![Dataset::default()
-        .marker(symbols::Marker::Dot)
-        .style(Style::default().fg(Color::Yellow))
-        .graph_type(GraphType::Line)
-        .data(&app_line_data)];
-
-    let y_max = (app.b_count as f64) / 4.5;
-
-    let chart = Chart::new(line_data)
-        .block(Block::default().borders(Borders::ALL))
-        .x_axis(
-            Axis::default()
-                .style(Style::default().fg(Color::Gray))
-                .labels(vec![
-                    Span::styled(
-                        format!("-{}", app.r_max),
-                        Style::default().add_modifier(Modifier::BOLD),
-                    ),
-                    Span::raw("0"),
-                    Span::styled(
-                        format!("{}", app.r_max),
-                        Style::default().add_modifier(Modifier::BOLD),
-                    ),
-                ])
-                .bounds([-app.r_max as f64, app.r_max as f64]),
+    /// Index of the bucket whose center is nearest `x`.
+    fn nearest_bucket(&self, x: f64) -> Option<usize> {
+        self.data
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (a.x - x).abs().total_cmp(&(b.x - x).abs()))
+            .map(|(i, _)| i)
+    }
+
+    /// Index of the selected bucket, or of the nearest one if the buckets
+    /// have changed since it was selected.
+    fn selected_index(&self) -> Option<usize> {
+        self.selected.and_then(|x| self.nearest_bucket(x))
+    }
+
+    fn buckets(&self) -> Vec<(f64, u64)> {
+        self.data
+            .iter()
+            .map(|bucket| (bucket.x, bucket.count))
+            .collect()
+    }
+
+    /// Bytes held by the per-tick buffers.
+    fn buffer_bytes(&self) -> usize {
+        use std::mem::size_of;
+        let data = self.data.capacity() * size_of::<Bucket>()
+            + self
+                .data
+                .iter()
+                .map(|bucket| bucket.label.capacity())
+                .sum::<usize>();
+        let previous = self.previous.capacity() * size_of::<u64>();
+        let walks = self.walks.capacity() * size_of::<Vec<i32>>()
+            + self
+                .walks
+                .iter()
+                .map(|walk| walk.capacity() * size_of::<i32>())
+                .sum::<usize>();
+        data + previous + walks + self.history.bytes() + self.timeline.bytes()
+    }
+
+    /// Keeps the buffers within `--max-memory` by dropping the oldest
+    /// timeline snapshots once they grow past it. The counts and the rest
+    /// are small and never pruned.
+    fn check_memory(&mut self) {
+        let Some(cap) = self.max_memory else {
+            return;
+        };
+        let used = self.buffer_bytes();
+        if used <= cap {
+            return;
+        }
+        let others = used - self.timeline.bytes();
+        let dropped = self.timeline.shrink(cap.saturating_sub(others));
+        if dropped == 0 {
+            return;
+        }
+        log::debug!(
+            "{} over the memory cap: dropped {} timeline snapshots, {} kept",
+            format::bytes(used - cap),
+            dropped,
+            self.timeline.ticks()
+        );
+        if !self.pruned {
+            self.pruned = true;
+            let message = self.strings.fill(
+                "toast.memory_pruned",
+                &[
+                    ("cap", &format::bytes(cap)),
+                    ("ticks", &self.timeline.ticks()),
+                ],
+            );
+            self.toasts.info(message);
+        }
+    }
+
+    /// Samples drawn over all ticks so far, with those carried over and
+    /// fast-forwarded.
+    fn samples(&self) -> u64 {
+        let carried = self.warm.as_ref().map_or(0, |warm| warm.samples);
+        self.drawn
+            .saturating_add(carried)
+            .saturating_add(self.fast_forwarded)
+    }
+
+    fn model(&self) -> Model {
+        self.model
+            .unwrap_or_else(|| Model::for_steps(self.r_max as u32))
+    }
+
+    /// Each bucket's count under the model shown, for as many samples as
+    /// `data` holds, unless there's no model of the aggregate.
+    fn expected(&self) -> Option<Vec<f64>> {
+        let total = stats::total(self.data.iter().map(|bucket| bucket.count)) as f64;
+        let probabilities = self.sim.layout().probabilities(self.model(), self.bias())?;
+        Some(
+            probabilities
+                .into_iter()
+                .map(|probability| total * probability)
+                .collect(),
         )
-        .y_axis(
-            Axis::default()
-                .style(Style::default().fg(Color::Gray))
-                .labels(vec![
-                    Span::styled("0", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::styled(
-                        format!("{:.0}", y_max),
-                        Style::default().add_modifier(Modifier::BOLD),
-                    ),
-                ])
-                .bounds([0.0, y_max]),
+    }
+
+    /// Each bucket's signed error relative to the exact binomial
+    /// probability, for the errors view.
+    fn relative_errors(&self) -> Vec<Option<f64>> {
+        let counts = self
+            .data
+            .iter()
+            .map(|bucket| bucket.count)
+            .collect::<Vec<_>>();
+        match self
+            .sim
+            .layout()
+            .probabilities(Model::Binomial, self.bias())
+        {
+            Some(probabilities) => {
+                stats::relative_errors(&counts, &probabilities, ERRORS_MIN_EXPECTED)
+            }
+            None => vec![None; counts.len()],
+        }
+    }
+
+    /// The coin's bias the counts on screen were drawn at, which sets the
+    /// model they're compared with.
+    fn bias(&self) -> f64 {
+        self.timeline
+            .viewing()
+            .map_or(self.bias, |snapshot| snapshot.bias)
+    }
+
+    /// The model's mean, or with an aggregate of positions, which has no
+    /// model, the histogram's own.
+    fn mean(&self) -> f64 {
+        match self.sim.aggregate().of_positions() {
+            true => self.fitted().mean,
+            false => stats::walk_mean(self.r_max as u32, self.bias()),
+        }
+    }
+
+    fn sd(&self) -> f64 {
+        match self.sim.aggregate().of_positions() {
+            true => self.fitted().sd,
+            false => stats::walk_sd(self.r_max as u32, self.bias()),
+        }
+    }
+
+    fn fitted(&self) -> Stats {
+        Stats::fitted(&self.buckets(), self.sim.layout().spacing())
+    }
+
+    /// The buckets as they're shown, labelled by their means rather than
+    /// their sums with `--aggregate mean`.
+    fn shown_data(&self) -> Cow<'_, [Bucket]> {
+        match self.sim.aggregate() {
+            aggregate @ Aggregate::Mean => Cow::Owned(
+                self.data
+                    .iter()
+                    .map(|bucket| Bucket {
+                        label: aggregate.label(bucket.x, self.r_max as u32),
+                        ..bucket.clone()
+                    })
+                    .collect(),
+            ),
+            _ => Cow::Borrowed(&self.data),
+        }
+    }
+
+    /// `stats` of the sums in the units they're shown in.
+    fn shown_stats(&self, stats: Stats) -> Stats {
+        let unit = self.sim.aggregate().unit(self.r_max as u32);
+        Stats {
+            mean: stats.mean / unit,
+            sd: stats.sd / unit,
+            ..stats
+        }
+    }
+
+    fn stats(&self) -> Stats {
+        let spacing = self.sim.layout().spacing();
+        match self.weights() {
+            Some(weights) => {
+                let buckets = self
+                    .data
+                    .iter()
+                    .zip(weights)
+                    .map(|(bucket, weight)| (bucket.x, *weight))
+                    .collect::<Vec<_>>();
+                Stats::weighted(&buckets, self.mean(), self.sd(), spacing)
+            }
+            None => Stats::compute(&self.buckets(), self.mean(), self.sd(), spacing),
+        }
+    }
+
+    /// With `--start-at`, the time left until the run starts, while it's
+    /// still to come.
+    fn countdown(&self) -> Option<Duration> {
+        self.start_at
+            .map(|at| at.duration_since(SystemTime::now()).unwrap_or_default())
+    }
+
+    /// Starts a run held by `--start-at`, on time or early.
+    fn start_now(&mut self) {
+        self.start_at = None;
+        self.provenance.start();
+        let now = DateTime::now();
+        log::info!("run started at {}", now.clock());
+        self.started = Some(now);
+    }
+
+    /// `f`: adds `--fast-forward` samples to the counts at once.
+    fn fast_forward(&mut self) {
+        // A replay's or the intro's counts aren't the run's to add to.
+        if self.replay_status.is_some() || self.intro.is_some() {
+            return;
+        }
+        if !self.mode.is_cumulative() {
+            self.toasts
+                .info(self.strings.get("toast.fast_forward_mode").to_string());
+            return;
+        }
+        if self.seeds.is_some() {
+            self.toasts
+                .info(self.strings.get("toast.fast_forward_seeds").to_string());
+            return;
+        }
+        // Without a model to draw from, every sample would have to be walked.
+        if !self.sim.layout().modelled() {
+            let message = self.strings.fill(
+                "toast.no_model",
+                &[("aggregate", &self.sim.aggregate().name())],
+            );
+            self.toasts.info(message);
+            return;
+        }
+        let n = self.fast_forward;
+        self.sim.fast_forward(n);
+        self.fast_forwarded = self.fast_forwarded.saturating_add(n);
+        // A tick being drawn in chunks counts its samples from the new total.
+        if let Some(before) = &mut self.pending {
+            *before += n;
+        }
+        self.remember_previous();
+        self.data = self.sim.histogram();
+        self.refresh();
+        let message = self
+            .strings
+            .fill("toast.fast_forwarded", &[("samples", &format::si(n))]);
+        self.toasts.info(message);
+    }
+
+    /// Pins the histogram as it's shown, replacing the oldest pin once
+    /// there are `pin::MAX`.
+    fn pin(&mut self) {
+        let label = self.strings.fill(
+            "legend.pin",
+            &[
+                ("number", &(self.pinned + 1)),
+                ("steps", &self.r_max),
+                (
+                    "samples",
+                    &format::si(stats::total(self.data.iter().map(|bucket| bucket.count))),
+                ),
+                ("tick", &self.tick),
+            ],
         );
-    f.render_widget(chart, chunks[2]);
+        let Some(pin) = Pin::new(&self.data, label) else {
+            self.toasts
+                .info(self.strings.get("toast.pin_empty").to_string());
+            return;
+        };
+        self.pinned += 1;
+        if self.pins.len() == pin::MAX {
+            self.pins.remove(0);
+        }
+        let message = self.strings.fill("toast.pinned", &[("label", &pin.label)]);
+        self.pins.push(pin);
+        self.toasts.info(message);
+    }
+
+    /// The warning that every sample is the same, while they are.
+    fn degenerate_text(&self) -> Option<String> {
+        let value = self.degenerate?;
+        Some(self.strings.fill(
+            "banner.degenerate",
+            &[("samples", &format::si(self.samples())), ("value", &value)],
+        ))
+    }
+
+    /// Starts a blind comparison of the histogram shown against a draw from
+    /// the exact model, or ends one, saying which was which.
+    fn toggle_blind(&mut self) {
+        if let Some(blind) = self.blind.take() {
+            let distance = blind.distance();
+            let (real, model) = match blind.real_is_a() {
+                true => ("A", "B"),
+                false => ("B", "A"),
+            };
+            let message = self.strings.fill(
+                "toast.blind_reveal",
+                &[
+                    ("real", &real),
+                    ("model", &model),
+                    ("statistic", &format!("{:.1}", distance.statistic)),
+                    ("dof", &distance.dof),
+                    ("p", &format!("{:.3}", distance.p_value)),
+                ],
+            );
+            self.toasts.info(message);
+            return;
+        }
+        let counts = self
+            .data
+            .iter()
+            .map(|bucket| bucket.count)
+            .collect::<Vec<_>>();
+        if counts.iter().all(|count| *count == 0) {
+            self.toasts
+                .info(self.strings.get("toast.blind_empty").to_string());
+            return;
+        }
+        let Some(probabilities) = self
+            .sim
+            .layout()
+            .probabilities(Model::Binomial, self.bias())
+        else {
+            let message = self.strings.fill(
+                "toast.no_model",
+                &[("aggregate", &self.sim.aggregate().name())],
+            );
+            self.toasts.info(message);
+            return;
+        };
+        self.blind = Some(Blind::new(counts, &probabilities, &mut self.presentation));
+        self.view = View::Histogram;
+        self.toasts
+            .info(self.strings.get("toast.blind").to_string());
+    }
+
+    /// Raises the drift warning the first time the steps drawn stray from
+    /// their expected mean by more than chance explains.
+    fn check_balance(&mut self) {
+        if self.drifted || !self.sim.balance().drifted() {
+            return;
+        }
+        self.drifted = true;
+        if let Some(text) = self.drift_text() {
+            self.toasts.error(text);
+        }
+    }
+
+    /// The drift warning, with the latest fraction of +1 steps, once there
+    /// has been drift.
+    fn drift_text(&self) -> Option<String> {
+        if !self.drifted {
+            return None;
+        }
+        let balance = self.sim.balance();
+        let percent = |fraction: f64| format!("{:.3}%", fraction * 100.0);
+        Some(self.strings.fill(
+            "banner.drift",
+            &[
+                ("fraction", &percent(balance.up_fraction())),
+                ("steps", &format::si(balance.steps())),
+                ("z", &format!("{:.1}", balance.z())),
+                ("expected", &percent(balance.expected_fraction())),
+            ],
+        ))
+    }
+
+    /// The parameters the run draws and shows its samples with, as the
+    /// exports and `--print-config` give them.
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let lambda = self.mode.decay().map_or(self.lambda, Decay::lambda);
+        vec![
+            ("samples", self.b_count.to_string()),
+            ("r_max", self.r_max.to_string()),
+            ("exact_below", self.sim.exact_below().to_string()),
+            ("tick_ms", self.tick_rate.as_millis().to_string()),
+            ("seed", self.seed.to_string()),
+            ("rng", self.sim.generator().name().to_string()),
+            ("mode", self.mode.name().to_string()),
+            ("lambda", format!("{:.2}", lambda)),
+            ("model", self.model().name().to_string()),
+            ("theme", self.palette.name().to_string()),
+            (
+                "bias",
+                self.bias_sweep
+                    .map_or(sampling::FAIR.to_string(), |sweep| sweep.describe()),
+            ),
+        ]
+    }
+
+    /// Credits whatever parameters changed since the last look to `source`.
+    fn note_changes(&mut self, source: provenance::Source) {
+        let params = self.params();
+        self.provenance.update(params, source, self.tick);
+    }
+
+    /// Whether the `--until` condition has been met.
+    fn until_met(&self) -> bool {
+        self.until
+            .as_ref()
+            .is_some_and(|until| until.met().is_some())
+    }
+
+    /// The relative standard error buckets are counted as converged within.
+    fn precision_target(&self) -> f64 {
+        self.precision
+            .map_or(DEFAULT_PRECISION, |percent| percent / 100.0)
+    }
+
+    /// `precision_target`, in percent, as given.
+    fn precision_percent(&self) -> f64 {
+        self.precision.unwrap_or(DEFAULT_PRECISION * 100.0)
+    }
+
+    /// Whether the buckets' precision is shown and exported: with
+    /// `--precision`, or a condition on it.
+    fn precision_shown(&self) -> bool {
+        let conditions = [
+            self.alert.as_ref().map(|alert| alert.condition),
+            self.until.as_ref().map(|until| until.condition),
+        ];
+        self.precision.is_some()
+            || conditions
+                .iter()
+                .flatten()
+                .any(|condition| condition.metric == Metric::Converged)
+    }
+
+    /// How many of the buckets with samples are within the precision target,
+    /// and how many have samples.
+    fn converged(&self) -> (usize, usize) {
+        let counts = self
+            .data
+            .iter()
+            .map(|bucket| bucket.count)
+            .collect::<Vec<_>>();
+        stats::converged(&counts, self.precision_target())
+    }
+
+    /// Whether the `--cycles` cycle running has had all its ticks.
+    fn cycle_over(&self) -> bool {
+        self.cycles
+            .as_ref()
+            .is_some_and(|cycles| cycles.due(self.tick))
+    }
+
+    /// The least, median and greatest KS distance across the `--seeds`
+    /// runs, this one included.
+    fn seeds_ks(&self) -> Option<[f64; 3]> {
+        let seeds = self.seeds.as_ref()?;
+        let mut ks = seeds.ks();
+        ks.push(self.stats().ks);
+        ks.sort_by(f64::total_cmp);
+        let middle = ks.len() / 2;
+        let median = match ks.len() % 2 {
+            0 => (ks[middle - 1] + ks[middle]) / 2.0,
+            _ => ks[middle],
+        };
+        Some([ks[0], median, ks[ks.len() - 1]])
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = match Args::parse() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, cli::USAGE);
+            process::exit(2);
+        }
+    };
+
+    if args.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+
+    // replay-events runs the logged session's command line in place of its
+    // own, keeping only where to log to.
+    let mut script = None;
+    if let Command::ReplayEvents(path) = &args.command {
+        let read = events::Script::read(path).and_then(|mut script| {
+            let logged = script.args()?;
+            match logged.command {
+                Command::Run => Ok((logged, script)),
+                _ => Err("the event log isn't of a live run".to_string()),
+            }
+        });
+        match read {
+            Ok((logged, read)) => {
+                args = Args {
+                    log_file: args.log_file.take(),
+                    verbose: args.verbose,
+                    ..logged
+                };
+                script = Some(read);
+            }
+            Err(err) => {
+                eprintln!("error: {}", err);
+                process::exit(2);
+            }
+        }
+    }
+
+    if let Some(path) = &args.log_file {
+        logger::init(path, args.verbose)?;
+    }
+
+    let seed = args.seed.unwrap_or_else(|| thread_rng().gen());
+    let generator = args.rng.unwrap_or(Generator::Std);
+
+    if let Command::Bench = args.command {
+        let samples = args.samples.unwrap_or(DEFAULT_SAMPLES);
+        let steps = args.steps.unwrap_or(DEFAULT_STEPS);
+        if let Err(err) = App::check_params(samples, steps) {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+        let config = bench::Config {
+            samples,
+            steps: steps as u32,
+            duration: Duration::from_secs(args.seconds.unwrap_or(bench::DEFAULT_SECONDS)),
+            seed,
+            format: args.format.unwrap_or(export::Format::Text),
+        };
+        bench::run(&mut io::stdout().lock(), &config)?;
+        return Ok(());
+    }
+
+    if let Command::Sweep = args.command {
+        let steps = match args.sweep_steps.is_empty() {
+            true => sweep::DEFAULT_STEPS.to_vec(),
+            false => args.sweep_steps.clone(),
+        };
+        let samples = args.samples.unwrap_or(sweep::DEFAULT_SAMPLES);
+        for n in &steps {
+            if let Err(err) = App::check_params(samples, *n) {
+                eprintln!("error: {}", err);
+                process::exit(2);
+            }
+        }
+        let config = sweep::Config {
+            steps,
+            samples,
+            seed,
+            generator,
+            threads: args
+                .threads
+                .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get())),
+            format: args.format.unwrap_or(export::Format::Text),
+        };
+        if !sweep::run(&mut io::stdout().lock(), &config)? {
+            // The conventional status for a process stopped by SIGINT.
+            process::exit(130);
+        }
+        return Ok(());
+    }
+
+    if let Command::Diff(a, b) = &args.command {
+        let alpha = args.alpha.unwrap_or(diff::DEFAULT_ALPHA);
+        if !(alpha > 0.0 && alpha < 1.0) {
+            eprintln!("error: --alpha must be between 0 and 1, not {}", alpha);
+            process::exit(2);
+        }
+        let config = diff::Config {
+            a: a.clone(),
+            b: b.clone(),
+            alpha,
+            format: args.format.unwrap_or(export::Format::Text),
+        };
+        match diff::run(&mut io::stdout().lock(), &config) {
+            Ok(true) => return Ok(()),
+            Ok(false) => process::exit(1),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                process::exit(2);
+            }
+        }
+    }
+
+    if args.smoke_test {
+        if !smoke::run(&mut io::stdout().lock())? {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Command::Selftest = args.command {
+        let steps = args.steps.unwrap_or(DEFAULT_STEPS);
+        let samples = args.samples.unwrap_or(selftest::DEFAULT_SAMPLES);
+        if let Err(err) = App::check_params(samples, steps) {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+        let config = selftest::Config {
+            samples,
+            steps: steps as u32,
+            seed,
+            generator,
+        };
+        if !selftest::run(&mut io::stdout().lock(), &config, &args.only)? {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if args.output_dir.is_none() {
+        if args.daemon {
+            return Err("--daemon needs --output-dir to export into".into());
+        }
+        if args.resume {
+            return Err("--resume needs --output-dir to resume from".into());
+        }
+        if args.cycles.is_some() {
+            return Err("--cycles needs --output-dir to export each cycle into".into());
+        }
+    }
+    match (args.cycles, args.ticks_per_cycle) {
+        (Some(_), None) => {
+            return Err("--cycles needs --ticks-per-cycle to say how long each cycle runs".into())
+        }
+        (None, Some(_)) => return Err("--ticks-per-cycle needs --cycles".into()),
+        (Some(0), _) => return Err("--cycles needs at least 1 cycle".into()),
+        (_, Some(0)) => return Err("--ticks-per-cycle needs at least 1 tick".into()),
+        _ => {}
+    }
+    if args.cycles.is_some() {
+        if args.command.is_playback() {
+            return Err("--cycles needs a live run".into());
+        }
+        if args.daemon || args.ticks.is_some() || args.until.is_some() {
+            return Err("--cycles says how long the run goes on; it can't be combined with --daemon, --ticks or --until".into());
+        }
+        // Each cycle is a run of its own, from empty counts.
+        if args.warm_start.is_some() || args.resume || args.fast_forward.is_some() {
+            return Err("--cycles starts each cycle from empty counts, so it can't be combined with --warm-start, --resume or --fast-forward".into());
+        }
+        if args.seeds.is_some() {
+            return Err("--seeds can't be combined with --cycles".into());
+        }
+    }
+    if args.bias_period.is_some() && args.bias_sweep.is_none() {
+        return Err("--bias-period needs --bias-sweep".into());
+    }
+    if args.bias_sweep.is_some() {
+        if args.command.is_playback() {
+            return Err("--bias-sweep needs a live run".into());
+        }
+        // Each tick is drawn at one bias, so each starts the histogram over.
+        if args.cumulative
+            || args.decay.is_some()
+            || args.warm_start.is_some()
+            || args.resume
+            || args.fast_forward.is_some()
+            || args.until.is_some()
+            || args.daemon
+        {
+            return Err("--bias-sweep starts the histogram over every tick, so it can't be combined with --cumulative, --decay, --warm-start, --resume, --fast-forward, --until or --daemon".into());
+        }
+        if args.seeds.is_some() {
+            return Err("--seeds can't be combined with --bias-sweep".into());
+        }
+    }
+    if args.resume && args.warm_start.is_some() {
+        return Err("--resume and --warm-start both say where to start from; pass one".into());
+    }
+    if args.auto_resume {
+        if args.autosave.is_none() {
+            return Err("--auto-resume needs --autosave to resume from".into());
+        }
+        if args.warm_start.is_some() || args.resume {
+            return Err(
+                "--auto-resume, --warm-start and --resume all say where to start from; pass one"
+                    .into(),
+            );
+        }
+    }
+    if args.autosave.is_some() {
+        if args.command.is_playback() {
+            return Err("--autosave needs a live run".into());
+        }
+        // Only counts that keep adding up are worth carrying on with.
+        if args.decay.is_some() || args.bias_sweep.is_some() {
+            return Err("--autosave keeps the cumulative counts, so it can't be combined with --decay or --bias-sweep".into());
+        }
+        if args.cycles.is_some() || args.seeds.is_some() {
+            return Err("--autosave carries on with one run's counts, so it can't be combined with --cycles or --seeds".into());
+        }
+    }
+    if args.event_log.is_some() {
+        if args.command.is_playback() {
+            return Err("--event-log needs a live run".into());
+        }
+        if args.headless || args.daemon {
+            return Err("--event-log records the keys pressed in the TUI, so it can't be combined with --headless or --daemon".into());
+        }
+    }
+    if args.sonify && args.command.is_playback() {
+        return Err("--sonify needs a live run".into());
+    }
+    if args.volume.is_some() && !args.sonify {
+        return Err("--volume needs --sonify".into());
+    }
+    if args.daemon && args.command.is_playback() {
+        return Err("--daemon needs a live run".into());
+    }
+    if args.until.is_some() && args.command.is_playback() {
+        return Err("--until needs a live run".into());
+    }
+    if args.verbose && args.log_file.is_none() && !args.daemon {
+        return Err("--verbose needs --log-file to log to".into());
+    }
+    if args.no_color && args.theme.is_some_and(|theme| theme != Palette::Mono) {
+        return Err("--no-color and --theme both say how to color; pass one".into());
+    }
+    if args.ascii && args.renderer == Some(Renderer::Braille) {
+        return Err("--ascii can't draw the bars in Braille; leave out --renderer braille".into());
+    }
+    if args.until_exit && args.until.is_none() {
+        return Err("--until-exit needs --until".into());
+    }
+    if args.decay.is_some() {
+        if args.command.is_playback() {
+            return Err("--decay needs a live run".into());
+        }
+        if args.cumulative || args.warm_start.is_some() || args.resume {
+            return Err("--decay fades the counts; it can't also keep them all with --cumulative, --warm-start or --resume".into());
+        }
+        if args.until.is_some() {
+            return Err("--until counts cumulatively, so it can't be combined with --decay".into());
+        }
+        if args.seeds.is_some() {
+            return Err("--seeds can't be combined with --decay".into());
+        }
+        if args.fast_forward.is_some() {
+            return Err("--fast-forward adds to the cumulative counts, so it can't be combined with --decay".into());
+        }
+    }
+    if args.fast_forward.is_some() {
+        if args.command.is_playback() {
+            return Err("--fast-forward needs a live run".into());
+        }
+        // The other runs would have nothing like it.
+        if args.seeds.is_some() {
+            return Err("--seeds can't be combined with --fast-forward".into());
+        }
+        if let Some(aggregate) = args.aggregate.filter(|a| a.of_positions()) {
+            return Err(format!(
+                "--aggregate {} has no model to fast-forward from",
+                aggregate.name()
+            )
+            .into());
+        }
+    }
+    if args.start_at.is_some() && args.command.is_playback() {
+        return Err("--start-at needs a live run".into());
+    }
+    if args.startup_check && args.command.is_playback() {
+        return Err("--startup-check needs a live run; a replay or attach draws no samples".into());
+    }
+    if let Some(count) = args.seeds {
+        if count < 1 {
+            return Err("--seeds needs at least 1 seed".into());
+        }
+        if args.command.is_playback() {
+            return Err("--seeds needs a live run".into());
+        }
+        // Carried-over counts would be in the one run and none of the others.
+        if args.warm_start.is_some() || args.resume {
+            return Err("--seeds can't be combined with --warm-start or --resume".into());
+        }
+    }
+    if args.overlay.is_some() && args.command.is_playback() {
+        return Err("--overlay needs a live run; a replay or attach draws no samples".into());
+    }
+    // The newest export is found before this run's directory is made.
+    let warm_start = match (&args.output_dir, args.resume) {
+        (Some(root), true) => match daemon::newest_export(root) {
+            Ok(Some(path)) => {
+                eprintln!("resuming from {}", path.display());
+                Some(path)
+            }
+            _ => {
+                eprintln!(
+                    "note: no export under {} to resume from, starting afresh",
+                    root.display()
+                );
+                None
+            }
+        },
+        _ => args.warm_start.clone(),
+    };
+    // An autosave next to the session directories, or here without them.
+    let autosave_dir = args
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("."));
+    let autosaved = match args.autosave {
+        Some(_) if warm_start.is_none() && !args.print_config => {
+            let path = autosave_dir.join(autosave::FILE);
+            autosave::offer(&path, args.auto_resume).map(|export| (path, export))
+        }
+        _ => None,
+    };
+
+    // --print-config only reads the parameters, so it leaves no files behind.
+    let session = match &args.output_dir {
+        Some(root) if !args.print_config => Some(Session::create(root, seed)?),
+        _ => None,
+    };
+    if let (true, None, Some(session)) = (args.daemon, &args.log_file, &session) {
+        logger::init(&session.resolve(Path::new("daemon.log")), args.verbose)?;
+    }
+    let stream_path = match (&args.stream, &session) {
+        (Some(path), Some(session)) => Some(session.resolve(path)),
+        (path, _) => path.clone(),
+    };
+
+    let streams = match &args.command {
+        Command::Run if !args.print_config => Some(Streams::open(
+            stream_path.as_deref(),
+            args.socket.as_deref(),
+        )?),
+        _ => None,
+    };
+    let source = match &args.command {
+        Command::Run => Source::Live,
+        Command::Replay(path) => Source::Replay(ReplaySource::open(path, DEFAULT_TICK_RATE)?),
+        Command::Attach(path) => Source::Attach(AttachSource::new(path)),
+        Command::ReplayEvents(_) => unreachable!("replay-events runs the logged command line"),
+        Command::Bench | Command::Sweep | Command::Selftest | Command::Diff(..) => {
+            unreachable!("subcommands return before the app starts")
+        }
+    };
+
+    let mut app = App::new(seed, generator);
+    if let Some(steps) = args.exact_below {
+        app.sim.set_exact_below(steps);
+    }
+    if let Some(lang) = &args.lang {
+        match Strings::load(lang) {
+            Ok(strings) => app.strings = strings,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                process::exit(2);
+            }
+        }
+    }
+    if let Err(err) = app.set_params(
+        args.samples.unwrap_or(app.b_count),
+        args.steps.unwrap_or(app.r_max),
+    ) {
+        eprintln!("error: {}", err);
+        process::exit(2);
+    }
+    // Before any counts are carried over, since it can lay the buckets out
+    // again.
+    if let Some(aggregate) = args.aggregate {
+        if let Err(err) = app.sim.set_aggregate(aggregate) {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    }
+    // A daemon's exports are only worth keeping if they add up, --until
+    // waits for the statistics to settle as the samples add up, and
+    // fast-forwarded samples are only worth adding to counts that keep them.
+    if args.cumulative
+        || warm_start.is_some()
+        || args.daemon
+        || args.until.is_some()
+        || args.fast_forward.is_some()
+        || args.autosave.is_some()
+    {
+        app.mode = Mode::Cumulative;
+    }
+    let resumed = autosaved.is_some();
+    let loaded = match (warm_start, autosaved) {
+        (Some(path), _) => {
+            let loaded = warm::load(&path, app.r_max, &app.data);
+            Some((path, loaded))
+        }
+        (None, Some((path, export))) => {
+            let loaded = warm::check(export, &path, app.r_max, &app.data);
+            Some((path, loaded))
+        }
+        (None, None) => None,
+    };
+    if let Some((path, loaded)) = loaded {
+        let loaded = loaded.and_then(|(warm, counts)| app.sim.add_counts(&counts).map(|()| warm));
+        match loaded {
+            Ok(warm) => {
+                app.data = app.sim.histogram();
+                app.warm = Some(warm);
+            }
+            Err(err) => {
+                eprintln!(
+                    "error: can't warm-start from {}: {}\n\
+                     (a warm start carries over the counts only, not the random sequence)",
+                    path.display(),
+                    err
+                );
+                process::exit(2);
+            }
+        }
+    }
+    if let Some(n) = args.fast_forward {
+        app.fast_forward = n;
+        app.fast_forward();
+    }
+    if let Some(path) = &args.reference {
+        match Reference::load(path, &app.data) {
+            Ok((reference, warning)) => {
+                if let Some(warning) = warning {
+                    eprintln!("warning: {}", warning);
+                    let message = app.strings.fill("toast.rebinned", &[("warning", &warning)]);
+                    app.toasts.info(message);
+                }
+                app.reference = Some(reference);
+            }
+            Err(err) => {
+                eprintln!(
+                    "error: can't load the reference {}: {}",
+                    path.display(),
+                    err
+                );
+                process::exit(2);
+            }
+        }
+    }
+    // Keys that would change the parameters are off from the start.
+    if let Source::Attach(attach) = &source {
+        app.attached = true;
+        attach.show_status(&mut app);
+    }
+    app.accessible = args.accessible;
+    app.minimal = args.minimal;
+    if let Some(split) = args.layout {
+        app.split = split;
+    }
+    app.show_hud = args.debug_hud;
+    app.animate = !args.no_animate;
+    if let Some(sigmas) = args.merge_tails {
+        app.merge_tails = true;
+        app.merge_sigmas = sigmas;
+        app.update_bins();
+    }
+    // Clipping at 0 leaves every bucket in.
+    app.clip = args
+        .clip_percentile
+        .filter(|percentile| *percentile > 0.0)
+        .map(Clip::new);
+    if let Some(percent) = args.max_cpu {
+        if percent < throttle::MIN_PERCENT {
+            eprintln!(
+                "warning: --max-cpu {} is too low to make progress, using {}",
+                percent,
+                throttle::MIN_PERCENT
+            );
+        }
+        app.throttle = Throttle::new(percent);
+    }
+    if let Some(ticks) = args.timeline {
+        app.timeline = Timeline::new(ticks);
+    }
+    app.max_memory = args.max_memory;
+    // NO_COLOR asks for no colors unless told otherwise, so an explicit
+    // --theme wins over it; --no-color doesn't go with one.
+    let no_color_env = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    if args.no_color || (no_color_env && args.theme.is_none()) {
+        app.palette = Palette::Mono;
+        app.no_color = true;
+    } else if let Some(palette) = &args.theme {
+        app.palette = *palette;
+    }
+    if let Some(count) = args.seeds {
+        app.seeds = Some(SeedRuns::new(
+            seed,
+            count,
+            app.r_max,
+            generator,
+            app.sim.exact_below(),
+            app.sim.aggregate(),
+        )?);
+    }
+    if let Some(sources) = &args.overlay {
+        app.overlay = Some(Overlay::new(sources, seed, generator));
+    }
+    if let Some(lambda) = args.decay {
+        let decay = Decay::new(lambda);
+        app.lambda = decay.lambda();
+        app.mode = Mode::Decay(decay);
+    }
+    app.target = args.target.map(Target::new);
+    app.alert = args.notify_when.map(Alert::new);
+    app.until = args
+        .until
+        .map(|condition| Until::new(condition, args.until_ticks.unwrap_or(until::DEFAULT_TICKS)));
+    app.until_exit = args.until_exit;
+    app.precision = args.precision;
+    if let Some((from, to)) = args.bias_sweep {
+        let period = args.bias_period.unwrap_or(bias::DEFAULT_PERIOD);
+        let sweep = Sweep::new(from, to, period)
+            .and_then(|sweep| app.check_bias_sweep(app.r_max, false).map(|()| sweep));
+        match sweep {
+            Ok(sweep) => {
+                app.sim.set_bias(sweep.bias())?;
+                app.bias = sweep.bias();
+                app.bias_sweep = Some(sweep);
+            }
+            Err(err) => {
+                eprintln!("error: --bias-sweep: {}", err);
+                process::exit(2);
+            }
+        }
+    }
+    app.cycles = args
+        .cycles
+        .zip(args.ticks_per_cycle)
+        .map(|(count, ticks)| Cycles::new(count, ticks, seed));
+    if args.startup_check {
+        let check = RngCheck::run(generator, seed);
+        log::info!(
+            "startup check of {}: frequency p = {:.3e}, runs p = {:.3e}, took {}",
+            generator.name(),
+            check.frequency,
+            check.runs,
+            format::duration(check.elapsed)
+        );
+        if !check.passed() {
+            let message = app.strings.fill(
+                "toast.rng_check",
+                &[
+                    ("rng", &generator.name()),
+                    ("frequency", &format::sig(check.frequency, 2)),
+                    ("runs", &format::sig(check.runs, 2)),
+                ],
+            );
+            app.toasts.error(message);
+        }
+        app.rng_check = Some(check);
+    }
+    if let Some(streams) = streams {
+        install_tick_hook(&mut app, streams);
+    }
+    if let Some(renderer) = args.renderer {
+        app.render.renderer = renderer;
+    }
+    app.render.ascii = args.ascii || ascii::locale_lacks_utf8();
+    let mut config = None;
+    if let Some(path) = &args.watch_config {
+        // The file applies from the start, and is then watched for changes.
+        let watch = Watch::new(path.clone());
+        match watch::read(path).and_then(|read| app.apply_config(&read).map(|()| read)) {
+            Ok(read) => config = Some(read),
+            Err(err) => {
+                eprintln!("error: can't read the config {}: {}", path.display(), err);
+                process::exit(2);
+            }
+        }
+        app.watch = Some(watch);
+    }
+    // Each parameter is credited to the last of these to set it: the config
+    // file applies over the command line, which applies over NO_COLOR.
+    app.note_changes(provenance::Source::Default);
+    if no_color_env && args.theme.is_none() && !args.no_color {
+        app.provenance.credit(&["theme"], provenance::Source::Env);
+    }
+    app.provenance
+        .credit(&args.params(), provenance::Source::Cli);
+    if let Some(config) = &config {
+        let mut names = config.names();
+        // A mono theme sticks, whatever the file asks for.
+        if app.no_color {
+            names.retain(|name| *name != "theme");
+        }
+        app.provenance.credit(&names, provenance::Source::Config);
+    }
+    if args.print_config {
+        for param in app.provenance.params() {
+            println!("{} = {} ({})", param.name, param.value, param.source.name());
+        }
+        return Ok(());
+    }
+    if let Some(path) = &args.status_file {
+        let every = args.status_every.unwrap_or(status::DEFAULT_EVERY);
+        app.status = Some(StatusFile::new(path.clone(), every));
+    }
+    if let Some(every) = args.autosave {
+        app.autosave = Some(Autosave::new(&autosave_dir, every));
+    }
+    if let Some(path) = &args.export_gif {
+        if !matches!(source, Source::Live) {
+            return Err("--export-gif needs a live run".into());
+        }
+        start_gif(&mut app, path, &args)?;
+    }
+    if args.sonify {
+        start_sound(&mut app, &args)?;
+    }
+    if let Some(session) = &session {
+        session.write_config(&app)?;
+        app.session = Some(session.path().display().to_string());
+    }
+    if let Some(path) = &args.event_log {
+        let path = match &session {
+            Some(session) => session.resolve(path),
+            None => path.clone(),
+        };
+        let argv = env::args().skip(1).collect::<Vec<_>>();
+        let config = args
+            .watch_config
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok());
+        let log = EventLog::create(&path, &app, &argv, config, resumed)
+            .map_err(|err| format!("can't create {}: {}", path.display(), err))?;
+        app.toasts.keep_raised();
+        app.events = Some(log);
+    }
+
+    // Why the run goes headless without being asked to, if it does.
+    let mut fallback = None;
+    if !args.headless && !args.daemon && script.is_none() {
+        let term = env::var("TERM").ok();
+        let checked = term::check(
+            io::stdin().is_terminal(),
+            io::stdout().is_terminal(),
+            term.as_deref(),
+            || {
+                let probe = term::Probe::run(term.as_deref());
+                log::debug!("terminal: {}", probe);
+                probe
+            },
+        );
+        match checked {
+            Ok(()) => {}
+            Err(term::Unsupported::TooSmall(width, height)) if !args.force_tui => {
+                return Err(format!(
+                    "the terminal is {}x{}, smaller than the {}x{} the TUI needs; \
+                     enlarge it or pass --headless",
+                    width,
+                    height,
+                    term::MIN_WIDTH,
+                    term::MIN_HEIGHT
+                )
+                .into());
+            }
+            Err(term::Unsupported::TooSmall(..)) => {}
+            Err(term::Unsupported::Unusable(reason)) => fallback = Some(reason),
+        }
+    }
+    if args.force_tui {
+        fallback = None;
+    }
+    let headless = args.headless || args.daemon || fallback.is_some();
+    if let Some(start_at) = args.start_at {
+        let at = start_at.resolve(SystemTime::now());
+        if at > SystemTime::now() {
+            app.start_at = Some(at);
+        } else {
+            let time = DateTime::at(at).clock();
+            match headless {
+                true => eprintln!(
+                    "note: the start time {} has already passed; starting now",
+                    time
+                ),
+                false => {
+                    let message = app.strings.fill("toast.start_past", &[("time", &time)]);
+                    app.toasts.info(message);
+                }
+            }
+            app.start_now();
+        }
+    }
+    if app.start_at.is_none() {
+        app.provenance.start();
+    }
+    if let Some(script) = script {
+        // The session had the intro the TUI would have.
+        if !args.no_intro && !app.accessible {
+            app.start_intro()?;
+        }
+        let matched = events::replay(&mut io::stdout().lock(), &mut app, script, session.as_ref())?;
+        finish_session(&mut app, session.as_ref())?;
+        if !matched {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+    let raw_to_stdout = args.raw_out.as_deref() == Some(Path::new("-"));
+    let raw = match &args.raw_out {
+        Some(_) if !matches!(source, Source::Live) => {
+            return Err("--raw-out needs a live run; a replay or attach draws no samples".into());
+        }
+        Some(_) if raw_to_stdout && !headless => {
+            return Err("--raw-out - needs headless mode, since the TUI draws on stdout".into());
+        }
+        Some(path) => {
+            let format = args.raw_format.unwrap_or(raw::Format::Binary);
+            let writer = raw::RawWriter::create(path, format, args.raw_limit)
+                .map_err(|err| format!("can't create {}: {}", path.display(), err))?;
+            Some((path, raw::install(&mut app.sim, writer)))
+        }
+        None => None,
+    };
+
+    if let (true, Some(session)) = (args.daemon, &session) {
+        eprintln!(
+            "running as a daemon in {}; stop with SIGTERM or Ctrl+C",
+            session.path().display()
+        );
+        wait_for_start(&mut app);
+        let config = daemon::Config {
+            every: args.export_every.unwrap_or(daemon::DEFAULT_EVERY),
+            keep: args.keep_exports.unwrap_or(daemon::DEFAULT_KEEP),
+            ticks: args.ticks,
+        };
+        daemon::run(&mut app, session, &config)?;
+        if let Some((path, raw)) = &raw {
+            finish_raw(path, raw);
+        }
+        finish_gif(&mut app);
+        finish_autosave(&mut app);
+        log_tick_times(&app);
+        session.write_exports(&app)?;
+        if let Some(status) = &app.status {
+            status.remove();
+        }
+        return Ok(());
+    }
+
+    if headless {
+        if !matches!(source, Source::Live) {
+            return Err("replay and attach need an interactive terminal".into());
+        }
+        // With --until, the run goes on until it's met unless --ticks says.
+        let ticks = match (args.ticks, &app.until) {
+            (Some(ticks), _) => Some(ticks),
+            (None, Some(_)) => None,
+            (None, None) => Some(headless::DEFAULT_TICKS),
+        };
+        if let Some(check) = app.rng_check.filter(|check| !check.passed()) {
+            eprintln!(
+                "warning: the {} generator failed the startup check (frequency p = {}, runs p = {})",
+                generator.name(),
+                format::sig(check.frequency, 2),
+                format::sig(check.runs, 2)
+            );
+        }
+        if let Some(reason) = &fallback {
+            let length = match (&app.cycles, ticks) {
+                (Some(cycles), _) => {
+                    format!("for {} cycles of {} ticks", cycles.count(), cycles.ticks())
+                }
+                (None, Some(ticks)) => format!("for {} ticks", ticks),
+                (None, None) => "until the --until condition is met".to_string(),
+            };
+            eprintln!(
+                "note: {}, running headless {} (use --force-tui to override)",
+                reason, length
+            );
+        }
+        wait_for_start(&mut app);
+        // The raw sums have stdout to themselves.
+        let cycles = session.as_ref().filter(|_| app.cycles.is_some());
+        let result = match (cycles, raw_to_stdout) {
+            (Some(session), true) => cycles::run(&mut io::stderr().lock(), &mut app, session),
+            (Some(session), false) => cycles::run(&mut io::stdout().lock(), &mut app, session),
+            (None, true) => headless::run(&mut io::stderr().lock(), &mut app, ticks),
+            (None, false) => headless::run(&mut io::stdout().lock(), &mut app, ticks),
+        };
+        let finished = match result {
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => return Err(err.into()),
+            Err(_) => true,
+            Ok(finished) => finished,
+        };
+        if let Some((path, raw)) = &raw {
+            finish_raw(path, raw);
+        }
+        finish_gif(&mut app);
+        finish_autosave(&mut app);
+        log_tick_times(&app);
+        if let Some(session) = &session {
+            // Each cycle has its exports already.
+            if app.cycles.is_none() {
+                session.write_exports(&app)?;
+            }
+            eprintln!("session saved to {}", session.path().display());
+        }
+        // A run stopped early leaves its status file behind.
+        if let (Some(status), true) = (&app.status, finished) {
+            status.remove();
+        }
+        if !finished {
+            // The conventional status for a process stopped by SIGINT.
+            process::exit(130);
+        }
+        return Ok(());
+    }
+
+    if !args.no_intro && !app.accessible && matches!(source, Source::Live) {
+        app.start_intro()?;
+    }
+
+    let res = {
+        let (_guard, inline) = term::Guard::enter(args.no_altscreen)?;
+        suspend::install();
+        let backend = CrosstermBackend::new(io::stdout());
+        match inline {
+            Some(area) => {
+                let mut terminal = Terminal::new(term::Inline::new(backend, area))?;
+                run_app(&mut terminal, &mut app, source, session.as_ref())
+            }
+            None => {
+                let mut terminal = Terminal::new(backend)?;
+                run_app(&mut terminal, &mut app, source, session.as_ref())
+            }
+        }
+    };
+
+    if let Err(err) = res {
+        println!("{:?}", err)
+    }
+    if let Some((path, raw)) = &raw {
+        finish_raw(path, raw);
+    }
+    finish_gif(&mut app);
+    finish_autosave(&mut app);
+    log_tick_times(&app);
+    if let Some(status) = &app.status {
+        status.remove();
+    }
+    finish_session(&mut app, session.as_ref())?;
+    Ok(())
+}
+
+/// Writes a TUI session's exports, and ends its `--event-log`.
+fn finish_session(app: &mut App, session: Option<&Session>) -> io::Result<()> {
+    if let Some(session) = session {
+        // A cycle stopped part-way is exported as it was.
+        match app.cycles {
+            Some(_) => {
+                cycles::finish(app, session)?;
+            }
+            None => {
+                session.write_exports(app)?;
+                events::export(app, session.path());
+            }
+        }
+        println!("session saved to {}", session.path().display());
+    }
+    events::finish(app);
+    Ok(())
+}
+
+/// Starts capturing `--export-gif` frames.
+#[cfg(feature = "image-export")]
+fn start_gif(app: &mut App, path: &Path, args: &Args) -> Result<(), String> {
+    let gif = gif::GifExport::create(
+        path,
+        args.gif_size.unwrap_or(gif::DEFAULT_SIZE),
+        args.gif_every.unwrap_or(gif::DEFAULT_EVERY),
+        args.gif_delay.unwrap_or(gif::DEFAULT_DELAY),
+        args.gif_max_frames.unwrap_or(gif::DEFAULT_MAX_FRAMES),
+    )
+    .map_err(|err| format!("can't create {}: {}", path.display(), err))?;
+    app.gif = Some(gif);
+    Ok(())
+}
+
+#[cfg(not(feature = "image-export"))]
+fn start_gif(_app: &mut App, _path: &Path, _args: &Args) -> Result<(), String> {
+    Err("--export-gif needs a build with --features image-export".to_string())
+}
+
+/// Ends the `--export-gif` file and says how many frames went into it.
+#[cfg(feature = "image-export")]
+fn finish_gif(app: &mut App) {
+    let Some(mut gif) = app.gif.take() else {
+        return;
+    };
+    let path = gif.path().display().to_string();
+    match gif.finish() {
+        Err(err) => eprintln!("warning: GIF export to {} failed: {}", path, err),
+        Ok(()) => eprintln!(
+            "{} frames written to {}{}",
+            gif.frames(),
+            path,
+            if gif.capped() {
+                " (--gif-max-frames reached)"
+            } else {
+                ""
+            }
+        ),
+    }
+}
+
+#[cfg(not(feature = "image-export"))]
+fn finish_gif(_app: &mut App) {}
+
+/// Starts playing the `--sonify` clicks.
+#[cfg(feature = "audio")]
+fn start_sound(app: &mut App, args: &Args) -> Result<(), String> {
+    let volume = args.volume.unwrap_or(sonify::DEFAULT_VOLUME);
+    app.sound = Some(sonify::Sonify::start(volume));
+    Ok(())
+}
+
+#[cfg(not(feature = "audio"))]
+fn start_sound(_app: &mut App, _args: &Args) -> Result<(), String> {
+    Err("--sonify needs a build with --features audio".to_string())
+}
+
+/// Flushes the `--raw-out` file and says how much went into it.
+/// Logs how long the ticks took to sample, for a run that ends.
+fn log_tick_times(app: &App) {
+    let times = app.perf.tick_times();
+    if times.ticks() == 0 {
+        return;
+    }
+    let time = |p| {
+        times
+            .percentile(p)
+            .map_or_else(String::new, format::duration)
+    };
+    log::info!(
+        "tick times over {} ticks: p50 {}, p95 {}, p99 {}; {}",
+        times.ticks(),
+        time(0.5),
+        time(0.95),
+        time(0.99),
+        times.to_json()
+    );
+}
+
+/// Waits for an autosave still being written, so the run doesn't end
+/// part-way through one.
+fn finish_autosave(app: &mut App) {
+    let Some(autosave) = &mut app.autosave else {
+        return;
+    };
+    if let Some(err) = autosave.join() {
+        eprintln!(
+            "warning: the last autosave to {} failed: {}",
+            autosave.path().display(),
+            err
+        );
+    }
+}
+
+fn finish_raw(path: &Path, raw: &Mutex<raw::RawWriter>) {
+    let Ok(mut raw) = raw.lock() else {
+        return;
+    };
+    let path = match path.to_str() {
+        Some("-") => "stdout".to_string(),
+        _ => path.display().to_string(),
+    };
+    match raw.finish() {
+        // The reader having had enough isn't a failure.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(err) => eprintln!("warning: raw output to {} failed: {}", path, err),
+        Ok(()) => eprintln!(
+            "{} raw sums written to {}{}",
+            raw.written(),
+            path,
+            if raw.limited() {
+                " (--raw-limit reached)"
+            } else {
+                ""
+            }
+        ),
+    }
+}
+
+/// Where each tick's histogram comes from.
+enum Source {
+    Live,
+    Replay(ReplaySource),
+    Attach(AttachSource),
+}
+
+/// The client count after a tick was published, or why publishing failed.
+type Feedback = Result<Option<usize>, String>;
+
+/// Publishes every tick to `streams` from the simulation's tick hook, and
+/// sends the desktop notification when the alert fires.
+fn install_tick_hook(app: &mut App, mut streams: Streams) {
+    let (sender, receiver) = mpsc::channel();
+    // The hook checks its own copy of the alert, against the same values.
+    let mut alert = app.alert.clone();
+    let precision = app.precision_target();
+    let strings = app.strings.clone();
+    let started = Instant::now();
+    app.sim.set_tick_hook(Box::new(move |report| {
+        let mut result = streams.publish(&stream::tick_event(
+            report.tick,
+            started.elapsed(),
+            report.new_samples as usize,
+            report.steps,
+            report.bias,
+            report.buckets(),
+        ));
+        if let Some(alert) = &mut alert {
+            let metric = alert.condition.metric;
+            let value = metric.of(&Point {
+                ks: report.stats.ks,
+                peak: report.peak,
+                sd: report.stats.sd,
+                converged: stats::converged_percent(report.counts, precision),
+            });
+            if alert.check(report.tick, value) {
+                result = result.and_then(|()| {
+                    streams.publish(&stream::alert_event(report.tick, &alert.condition))
+                });
+                alert::notify_desktop(&alert.condition.message(&strings, report.tick, value));
+            }
+        }
+        let feedback = result
+            .map(|()| streams.clients())
+            .map_err(|err| err.to_string());
+        // The app only goes away at exit, so there's no one left to tell.
+        let _ = sender.send(feedback);
+    }));
+    app.feedback = Some(receiver);
+}
+
+/// Redraw interval while animating between ticks.
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
+/// Samples drawn between checks for input, so a large tick can't freeze the UI.
+const CHUNK: usize = 10_000;
+
+/// Below this tick rate transitions are too short to be worth animating.
+const MIN_ANIMATED_TICK: Duration = Duration::from_millis(100);
+
+/// How often the countdown before `--start-at`'s time is redrawn.
+const COUNTDOWN_FRAME: Duration = Duration::from_millis(100);
+
+/// Upper bound on the events handled between two frames, so a flood of input
+/// can't keep the screen from updating.
+const MAX_EVENTS_PER_FRAME: usize = 256;
+
+fn set_counts(data: &mut [Bucket], counts: &[u64]) {
+    for (bucket, count) in data.iter_mut().zip(counts) {
+        bucket.count = *count;
+    }
+}
+
+/// Holds a headless run until `--start-at`'s time. Nothing has started yet,
+/// so Ctrl+C simply ends the wait.
+fn wait_for_start(app: &mut App) {
+    let (Some(at), Some(left)) = (app.start_at, app.countdown()) else {
+        return;
+    };
+    eprintln!(
+        "note: waiting {} to start at {}",
+        format::countdown(left),
+        DateTime::at(at).clock()
+    );
+    thread::sleep(left);
+    app.start_now();
+}
+
+/// Dispatches one input event and returns true if it asks to quit.
+fn handle_event(event: Event, app: &mut App, source: &mut Source) -> bool {
+    let at = events::position(app);
+    match event {
+        Event::Mouse(mouse) => {
+            if let MouseEventKind::Moved | MouseEventKind::Down(_) = mouse.kind {
+                // Rows above an inline viewport aren't the TUI's.
+                if let Some(row) = mouse.row.checked_sub(term::viewport_top()) {
+                    app.on_mouse(mouse.column, row);
+                    events::select(app, at);
+                }
+            }
+        }
+        // Windows and the kitty protocol also report releases and repeats;
+        // only presses act, plus repeats of the keys meant to be held.
+        Event::Key(key)
+            if key.kind == KeyEventKind::Press
+                || (key.kind == KeyEventKind::Repeat && repeats(key.code)) =>
+        {
+            // Raw mode turns Ctrl+C into a key too, and it quits as q does.
+            // Windows reports AltGr as Ctrl+Alt, so those are characters
+            // typed, not chords.
+            let held = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+            let chord = held == KeyModifiers::CONTROL || held == KeyModifiers::ALT;
+            let interrupt = held == KeyModifiers::CONTROL && key.code == KeyCode::Char('c');
+            if key.code == KeyCode::Char('q') || interrupt {
+                events::key(app, at, KeyCode::Char('q'), events::Action::Quit);
+                return true;
+            }
+            // Other chords aren't bound, and aren't taken for the bare key.
+            if chord {
+                return false;
+            }
+            // The countdown only waits for s, to start early.
+            if app.start_at.is_some() {
+                let action = match key.code {
+                    KeyCode::Char('s') => {
+                        app.start_now();
+                        events::Action::Start
+                    }
+                    _ => events::Action::Ignored,
+                };
+                events::key(app, at, key.code, action);
+                return false;
+            }
+            // Any other key skips the intro, and does nothing else.
+            if app.intro.is_some() {
+                app.end_intro();
+                events::key(app, at, key.code, events::Action::SkipIntro);
+                return false;
+            }
+            // The settings panel has the keys to itself while it's open, and
+            // a key that dismisses an error does nothing else.
+            let action = if app.settings.is_some() {
+                app.on_settings_key(key.code);
+                events::Action::Settings
+            } else if app.toasts.dismiss() {
+                events::Action::Dismiss
+            } else {
+                app.on_key(key.code);
+                if let Source::Replay(replay) = source {
+                    replay.handle_key(key.code, app);
+                }
+                events::Action::Key
+            };
+            app.note_changes(provenance::Source::Interactive);
+            events::key(app, at, key.code, action);
+        }
+        Event::Resize(width, height) => events::resize(app, width, height),
+        _ => {}
+    }
+    false
+}
+
+/// Keys that keep acting while held down.
+fn repeats(code: KeyCode) -> bool {
+    matches!(code, KeyCode::Left | KeyCode::Right)
+}
+
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    source: Source,
+    session: Option<&Session>,
+) -> io::Result<()> {
+    let clock = driver::SystemClock;
+    let mut frames = Frames::new(terminal, app, source, &clock)?;
+    while !frames.frame(terminal, app, session, &mut driver::Crossterm, &clock)? {}
+    Ok(())
+}
+
+/// `run_app`'s loop, a frame at a time, so the test harness can run it on
+/// its own events and clock.
+struct Frames {
+    source: Source,
+    pace: Pace,
+    tick_start: Instant,
+}
+
+impl Frames {
+    fn new<B: Backend>(
+        terminal: &mut Terminal<B>,
+        app: &mut App,
+        source: Source,
+        clock: &impl Clock,
+    ) -> io::Result<Frames> {
+        let size = terminal.size()?;
+        events::resize(app, size.width, size.height);
+        Ok(Frames {
+            source,
+            pace: Pace::new(clock.now()),
+            tick_start: clock.now(),
+        })
+    }
+
+    /// Draws if a frame is due, waits for input until the next tick or frame
+    /// and runs whatever ticks are due then. Returns whether the session is
+    /// over.
+    fn frame<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        app: &mut App,
+        session: Option<&Session>,
+        input: &mut impl Events,
+        clock: &impl Clock,
+    ) -> io::Result<bool> {
+        let Frames {
+            source,
+            pace,
+            tick_start,
+        } = self;
+        match source {
+            Source::Live => {}
+            Source::Replay(replay) => app.tick_rate = replay.interval(),
+            Source::Attach(_) => app.tick_rate = attach::POLL,
+        }
+        let tick_rate = match app.intro {
+            Some(_) => intro::TICK,
+            None => app.tick_rate,
+        };
+
+        let animating = app.animate && tick_rate >= MIN_ANIMATED_TICK;
+        if suspend::requested() {
+            suspend::run(terminal, app, pace, tick_rate)?;
+        } else if suspend::continued() {
+            suspend::resume(terminal, pace, tick_rate)?;
+        }
+
+        let now = clock.now();
+        app.progress = if animating {
+            pace.since_tick(now).as_secs_f64() / tick_rate.as_secs_f64()
+        } else {
+            1.0
+        };
+
+        app.toasts.update(now);
+        let drawn = pace.frame_due(now);
+        if drawn {
+            terminal.draw(|f| ui::draw(f, app))?;
+            app.perf.record_frame(now, clock.now());
+            pace.drew(now);
+        }
+
+        let now = clock.now();
+        let mut timeout = pace.until_tick(now, tick_rate);
+        if animating {
+            timeout = timeout.min(FRAME_INTERVAL);
+        }
+        if app.paused {
+            // No tick is due, but toasts still come and go.
+            timeout = FRAME_INTERVAL;
+        }
+        if app.pending.is_some() {
+            // Only check for input before drawing the next chunk.
+            timeout = Duration::ZERO;
+        }
+        if let Some(left) = app.countdown() {
+            timeout = left.min(COUNTDOWN_FRAME);
+        }
+        if !drawn {
+            // Whatever was skipped is drawn as soon as a frame is due.
+            timeout = timeout.min(pace.until_frame(now));
+        }
+
+        // Handle everything that queued up since the last frame, so a quit
+        // isn't stuck behind a burst of other keys.
+        let mut ready = input.poll(timeout)?;
+        for _ in 0..MAX_EVENTS_PER_FRAME {
+            if !ready {
+                break;
+            }
+            let event = input.read()?;
+            if suspend::is_key(&event) {
+                suspend::run(terminal, app, pace, tick_rate)?;
+                break;
+            }
+            if handle_event(event, app, source) {
+                return Ok(true);
+            }
+            ready = input.poll(Duration::ZERO)?;
+        }
+
+        if let Some(left) = app.countdown() {
+            if !left.is_zero() {
+                return Ok(false);
+            }
+            app.start_now();
+            pace.restart(clock.now());
+        }
+
+        // A tick drawn in chunks is finished on its own; otherwise every tick
+        // that's come due since the last frame is run, for as long as a frame
+        // lasts.
+        let due = match (&app.pending, app.paused) {
+            (Some(_), _) => 1,
+            (None, true) => {
+                pace.hold(clock.now(), tick_rate);
+                0
+            }
+            (None, false) => pace.due(clock.now(), tick_rate),
+        };
+        let batch_start = clock.now();
+        let mut ran = 0;
+        while ran < due {
+            let complete = match source {
+                Source::Live => {
+                    if app.pending.is_none() {
+                        *tick_start = clock.now();
+                        app.perf.record_late(pace.late(*tick_start, tick_rate, ran));
+                        app.begin_tick();
+                    }
+                    let complete = app.continue_tick(CHUNK);
+                    if let Some(err) = app.take_feedback() {
+                        app.toasts.error(err);
+                    }
+                    complete
+                }
+                Source::Replay(replay) => {
+                    *tick_start = clock.now();
+                    app.perf.record_late(pace.late(*tick_start, tick_rate, ran));
+                    replay.step(app);
+                    true
+                }
+                Source::Attach(attach) => {
+                    *tick_start = clock.now();
+                    attach.step(app);
+                    true
+                }
+            };
+            if !complete {
+                break;
+            }
+            ran += 1;
+            app.perf.record_tick(*tick_start, clock.now(), tick_rate);
+            events::ticked(app);
+            if let (Source::Live, None) = (&source, &app.intro) {
+                if app.tick.is_multiple_of(watch::CHECK_EVERY) {
+                    app.check_config();
+                }
+            }
+            if std::mem::take(&mut app.bell) {
+                print!("\x07");
+                io::stdout().flush()?;
+            }
+            if app.until_exit && app.until_met() {
+                return Ok(true);
+            }
+            if let (true, Some(session)) = (app.cycle_over(), session) {
+                if !cycles::end_cycle(app, session)? {
+                    return Ok(true);
+                }
+            }
+            if app.paused || clock.now().duration_since(batch_start) >= pace::MIN_FRAME {
+                break;
+            }
+        }
+        if ran > 0 {
+            app.perf.record_batch(clock.now(), ran);
+            let skipped = pace.ticked(clock.now(), tick_rate, ran);
+            app.perf.record_skipped(skipped);
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Taps `app`'s sums as they're drawn.
+    fn tap(app: &mut App) -> Arc<Mutex<Vec<i32>>> {
+        let sums = Arc::new(Mutex::new(vec![]));
+        let tap = Arc::clone(&sums);
+        app.sim
+            .set_sample_tap(Box::new(move |drawn| tap.lock().unwrap().extend(drawn)));
+        sums
+    }
+
+    #[test]
+    fn each_tick_counts_its_sums_into_the_buckets() {
+        let mut app = App::new(42, Generator::Std);
+        let sums = tap(&mut app);
+        for _ in 0..3 {
+            sums.lock().unwrap().clear();
+            app.on_tick();
+            // The buckets as they were built from scratch every tick.
+            let sums = sums.lock().unwrap();
+            let rebuilt = (-app.r_max..=app.r_max)
+                .step_by(2)
+                .map(|b| Bucket::new(b, sums.iter().filter(|s| **s == b).count() as u64))
+                .collect::<Vec<_>>();
+            assert_eq!(app.data, rebuilt);
+        }
+    }
+
+    #[test]
+    fn overlaid_sources_are_drawn_chunk_by_chunk() {
+        let mut app = App::new(171, Generator::Std);
+        let sources = overlay::Source::parse_list("coin,uniform01").unwrap();
+        app.overlay = Some(Overlay::new(&sources, 171, Generator::Std));
+        app.set_params(3 * CHUNK, 10).unwrap();
+        let samples = |app: &App| {
+            let overlay = app.overlay.as_ref().unwrap();
+            let series = overlay.series().iter().map(|series| series.samples());
+            series.collect::<Vec<_>>()
+        };
+        app.begin_tick();
+        let walks = app.sim.samples();
+        assert_eq!(samples(&app), [walks, walks]);
+        assert!(!app.continue_tick(CHUNK));
+        let drawn = app.sim.samples();
+        assert_eq!(samples(&app), [drawn, drawn]);
+        while !app.continue_tick(CHUNK) {}
+        assert_eq!(samples(&app), [3 * CHUNK as u64; 2]);
+    }
+
+    #[test]
+    fn a_reload_with_fewer_samples_keeps_the_total() {
+        let reload = |app: &mut App| {
+            let config = watch::parse(r#"{"samples": 10}"#).unwrap();
+            app.apply_config(&config).unwrap();
+        };
+        let mut app = App::new(42, Generator::Std);
+        app.set_params(1_000, 10).unwrap();
+        app.on_tick();
+        app.on_tick();
+        reload(&mut app);
+        app.on_tick();
+        assert_eq!(app.samples(), 2_010);
+
+        // Counting cumulatively, the tick part-drawn when the file changed
+        // is finished first.
+        let mut app = App::new(42, Generator::Std);
+        app.set_params(1_000, 10).unwrap();
+        app.set_mode(Mode::Cumulative);
+        app.on_tick();
+        app.begin_tick();
+        app.continue_tick(300);
+        reload(&mut app);
+        app.on_tick();
+        assert_eq!(app.samples(), 2_010);
+        assert_eq!(
+            stats::total(app.data.iter().map(|bucket| bucket.count)),
+            2_010
+        );
+    }
+
+    #[test]
+    fn what_is_shown_leaves_the_samples_alone() {
+        let toggles = "nlvgwdmtpbsF".chars().map(KeyCode::Char);
+        let mut plain = App::new(42, Generator::Std);
+        let mut shown = App::new(42, Generator::Std);
+        shown.start_intro().unwrap();
+        while shown.intro.is_some() {
+            shown.on_tick();
+        }
+        for tick in 0..12 {
+            plain.on_tick();
+            shown.on_tick();
+            assert_eq!(plain.data, shown.data, "tick {}", tick);
+            if tick % 2 == 0 {
+                toggles.clone().for_each(|key| shown.on_key(key));
+                shown.on_key(KeyCode::Tab);
+                shown.on_key(KeyCode::Right);
+            }
+        }
+    }
+
+    #[test]
+    fn the_error_bound_tightens_as_the_counts_add_up() {
+        let mut app = App::new(42, Generator::Std);
+        app.on_key(KeyCode::Char('c'));
+        app.on_tick();
+        let first = app.error_bound.value();
+        for _ in 0..40 {
+            app.on_tick();
+        }
+        let worst = app
+            .relative_errors()
+            .into_iter()
+            .flatten()
+            .fold(0.0, |worst: f64, error| worst.max(error.abs()));
+        assert!(app.error_bound.value() < first);
+        assert!(app.error_bound.value() >= worst * 100.0);
+    }
+
+    #[test]
+    fn the_buckets_are_laid_out_again_for_new_steps() {
+        let mut app = App::new(42, Generator::Std);
+        app.on_tick();
+        app.set_params(100, 4).unwrap();
+        let labels = app
+            .data
+            .iter()
+            .map(|b| b.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["-4", "-2", "0", "2", "4"]);
+        assert!(app.data.iter().all(|b| b.count == 0));
+        app.on_tick();
+        assert_eq!(app.data.len(), 5);
+        assert_eq!(app.data.iter().map(|b| b.count).sum::<u64>(), 100);
+    }
 }
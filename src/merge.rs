@@ -0,0 +1,211 @@
+//! `--merge-tails`: wider bars beyond a few standard deviations, where single
+//! buckets hold so few samples that the bars are mostly noise.
+//!
+//! Only the bar chart is merged. The counts, the statistics and the line chart
+//! stay per bucket, so merging changes how the tails look and nothing else.
+
+use crate::bucket::Bucket;
+use central_limit::histogram::Grouping;
+use std::ops::Range;
+
+/// Standard deviations beyond which buckets are merged, if `--merge-tails`
+/// is toggled on with `m` without a value.
+pub const DEFAULT_SIGMAS: f64 = 3.0;
+
+/// Buckets per merged bar.
+const GROUP: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct Bins {
+    /// The buckets each bar covers, in order, together covering all of them.
+    ranges: Vec<Range<usize>>,
+    labels: Vec<String>,
+    /// Sum edges between and around the bars: bar `i` covers the sums from
+    /// `edges[i]` to `edges[i + 1]`.
+    edges: Vec<i32>,
+    /// With `--clip-percentile`, the buckets shown: the end bars hold what
+    /// it leaves out, drawn a bar wide with all their samples, rather than
+    /// merged tails.
+    keep: Option<Range<usize>>,
+}
+
+impl Bins {
+    /// Merges the buckets more than `limit` from `center`, the mean, into
+    /// groups of up to `GROUP`, counted from the outside in, so the
+    /// outermost bars are full. A biased coin's tails differ in length.
+    pub fn new(data: &[Bucket], center: f64, limit: f64) -> Bins {
+        let left = data
+            .iter()
+            .take_while(|bucket| bucket.x < center - limit)
+            .count();
+        let right = data
+            .iter()
+            .rev()
+            .take_while(|bucket| bucket.x > center + limit)
+            .count()
+            .min(data.len() - left);
+        let mut ranges = vec![];
+        let mut start = 0;
+        while start < left {
+            let end = (start + GROUP).min(left);
+            ranges.push(start..end);
+            start = end;
+        }
+        ranges.extend((left..data.len() - right).map(|i| i..i + 1));
+        let mut tail = vec![];
+        let mut end = data.len();
+        while end > data.len() - right {
+            let start = end.saturating_sub(GROUP).max(data.len() - right);
+            tail.push(start..end);
+            end = start;
+        }
+        ranges.extend(tail.into_iter().rev());
+        Bins::from_ranges(data, ranges, None)
+    }
+
+    /// One bar per bucket in `keep`, and one at either end for the buckets
+    /// outside it, for `--clip-percentile`.
+    pub fn clipped(data: &[Bucket], keep: Range<usize>) -> Bins {
+        let mut ranges = vec![];
+        if keep.start > 0 {
+            ranges.push(0..keep.start);
+        }
+        ranges.extend(keep.clone().map(|i| i..i + 1));
+        if keep.end < data.len() {
+            ranges.push(keep.end..data.len());
+        }
+        let mut bins = Bins::from_ranges(data, ranges, Some(keep.clone()));
+        // The end bars are named for the buckets they're beyond.
+        let last = bins.labels.len() - 1;
+        if keep.start > 0 {
+            bins.labels[0] = format!("<{}", data[keep.start].label);
+        }
+        if keep.end < data.len() {
+            bins.labels[last] = format!(">{}", data[keep.end - 1].label);
+        }
+        bins
+    }
+
+    fn from_ranges(data: &[Bucket], ranges: Vec<Range<usize>>, keep: Option<Range<usize>>) -> Bins {
+        let labels = ranges
+            .iter()
+            .map(|range| match range.len() {
+                1 => data[range.start].label.clone(),
+                _ => format!("{}..{}", data[range.start].label, data[range.end - 1].label),
+            })
+            .collect();
+        // Each bucket reaches halfway to its neighbours: one either side of
+        // a sum, or further for a bin of several.
+        let reach = match data {
+            [first, second, ..] => (second.x - first.x) as i32 / 2,
+            _ => 1,
+        };
+        let edges = ranges
+            .iter()
+            .map(|range| data[range.start].x as i32 - reach)
+            .chain(data.last().map(|bucket| bucket.x as i32 + reach))
+            .collect();
+        Bins {
+            ranges,
+            labels,
+            edges,
+            keep,
+        }
+    }
+
+    /// Buckets covered, which is all of those the bins were made for.
+    pub fn buckets(&self) -> usize {
+        self.ranges.last().map_or(0, |range| range.end)
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    pub fn edges(&self) -> &[i32] {
+        &self.edges
+    }
+
+    /// Buckets covered by each bar, as widths on the bar chart. The end bars
+    /// of clipped bins are a bar wide, to leave the room to the middle.
+    pub fn spans(&self) -> Vec<u16> {
+        self.ranges
+            .iter()
+            .map(|range| match self.keep {
+                Some(_) => 1,
+                None => range.len() as u16,
+            })
+            .collect()
+    }
+
+    /// The bar covering bucket `index`.
+    pub fn bin_of(&self, index: usize) -> Option<usize> {
+        self.ranges.iter().position(|range| range.contains(&index))
+    }
+
+    /// Per-bucket values summed over each bar.
+    pub fn sums(&self, values: &[u64]) -> Vec<u64> {
+        self.ranges
+            .iter()
+            .map(|range| values[range.clone()].iter().sum())
+            .collect()
+    }
+
+    /// Like `sums`, for values with a fraction, such as expected counts.
+    pub fn sums_f64(&self, values: &[f64]) -> Vec<f64> {
+        self.ranges
+            .iter()
+            .map(|range| values[range.clone()].iter().sum())
+            .collect()
+    }
+
+    /// How tall each bar is drawn: as tall as its buckets' average, except
+    /// the end bars of clipped bins, with all of theirs.
+    pub fn heights(&self, values: &[f64]) -> Vec<f64> {
+        let Some(keep) = &self.keep else {
+            return self.averages(values);
+        };
+        self.ranges
+            .iter()
+            .zip(self.averages(values))
+            .zip(self.sums_f64(values))
+            .map(
+                |((range, average), sum)| match keep.contains(&range.start) {
+                    true => average,
+                    false => sum,
+                },
+            )
+            .collect()
+    }
+
+    /// Per-bucket values averaged over each bar, so a bar four buckets wide
+    /// is as tall as the buckets it replaces would be on average.
+    pub fn averages(&self, values: &[f64]) -> Vec<f64> {
+        self.ranges
+            .iter()
+            .map(|range| values[range.clone()].iter().sum::<f64>() / range.len() as f64)
+            .collect()
+    }
+}
+
+impl Grouping for Bins {
+    fn labels(&self) -> &[String] {
+        self.labels()
+    }
+
+    fn spans(&self) -> Vec<u16> {
+        self.spans()
+    }
+
+    fn bar_of(&self, bucket: usize) -> Option<usize> {
+        self.bin_of(bucket)
+    }
+
+    fn heights(&self, values: &[f64]) -> Vec<f64> {
+        self.heights(values)
+    }
+
+    fn sums(&self, counts: &[u64]) -> Vec<u64> {
+        self.sums(counts)
+    }
+}
@@ -0,0 +1,205 @@
+//! How each tick's samples go into the histogram: replacing the last tick's,
+//! adding to all of them so far, or fading the older ones with `--decay`.
+//! `c` and `C` switch between them while running, keeping as much of the
+//! counts as the new mode can use.
+
+use crate::decay::Decay;
+
+/// λ the first switch to decaying starts from, when `--decay` didn't give one.
+pub const DEFAULT_LAMBDA: f64 = 0.95;
+
+#[derive(Debug)]
+pub enum Mode {
+    /// Each tick's histogram replaces the last.
+    Regenerate,
+    /// Each tick's samples add to the counts so far.
+    Cumulative,
+    Decay(Decay),
+}
+
+/// What a switch of modes does with the counts so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// They're dropped, and the next tick starts afresh.
+    Discard,
+    /// The new mode counts on from the current tick's counts.
+    FromTick,
+    /// They're kept as they are.
+    Keep,
+    /// The decayed counts are kept, rounded to whole ones.
+    Round,
+}
+
+impl Mode {
+    pub fn is_cumulative(&self) -> bool {
+        matches!(self, Mode::Cumulative)
+    }
+
+    pub fn decay(&self) -> Option<&Decay> {
+        match self {
+            Mode::Decay(decay) => Some(decay),
+            _ => None,
+        }
+    }
+
+    pub fn decay_mut(&mut self) -> Option<&mut Decay> {
+        match self {
+            Mode::Decay(decay) => Some(decay),
+            _ => None,
+        }
+    }
+
+    /// The name in exports and the language files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Regenerate => "regenerate",
+            Mode::Cumulative => "cumulative",
+            Mode::Decay(_) => "decay",
+        }
+    }
+
+    /// The mode after this one, in the order `c` goes through them; a switch
+    /// to decaying starts from `lambda`.
+    pub fn next(&self, lambda: f64) -> Mode {
+        match self {
+            Mode::Regenerate => Mode::Cumulative,
+            Mode::Cumulative => Mode::Decay(Decay::new(lambda)),
+            Mode::Decay(_) => Mode::Regenerate,
+        }
+    }
+
+    /// The mode before this one, for `C`.
+    pub fn previous(&self, lambda: f64) -> Mode {
+        match self {
+            Mode::Regenerate => Mode::Decay(Decay::new(lambda)),
+            Mode::Cumulative => Mode::Regenerate,
+            Mode::Decay(_) => Mode::Cumulative,
+        }
+    }
+
+    /// What switching from this mode to `to` does with the counts, or `None`
+    /// if it's the same mode. Counting cumulatively or decaying counts on
+    /// from whatever the histogram shows, but regenerating never keeps any.
+    pub fn transition(&self, to: &Mode) -> Option<Transition> {
+        match (self, to) {
+            (Mode::Regenerate, Mode::Regenerate)
+            | (Mode::Cumulative, Mode::Cumulative)
+            | (Mode::Decay(_), Mode::Decay(_)) => None,
+            (_, Mode::Regenerate) => Some(Transition::Discard),
+            (Mode::Regenerate, _) => Some(Transition::FromTick),
+            (Mode::Cumulative, Mode::Decay(_)) => Some(Transition::Keep),
+            (Mode::Decay(_), Mode::Cumulative) => Some(Transition::Round),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::App;
+    use central_limit::{rng::Generator, stats};
+
+    /// `ticks` ticks of 1,000 samples each in `mode`.
+    fn run(mode: Mode, ticks: usize) -> App {
+        let mut app = App::new(42, Generator::Std);
+        app.set_params(1_000, 10).unwrap();
+        app.animate = false;
+        app.set_mode(mode);
+        for _ in 0..ticks {
+            app.on_tick();
+        }
+        app
+    }
+
+    fn switch(app: &mut App, to: Mode, transition: Transition) {
+        assert_eq!(app.mode.transition(&to), Some(transition));
+        let name = to.name();
+        app.set_mode(to);
+        assert_eq!(app.mode.name(), name);
+    }
+
+    /// The total frequencies are taken out of, and the sum of the shown
+    /// counts' shares of it.
+    fn normalized(app: &App) -> (u64, f64) {
+        let total = app.frequency_total();
+        let shares = app
+            .displayed_counts()
+            .iter()
+            .map(|count| count / total as f64)
+            .sum();
+        (total, shares)
+    }
+
+    fn assert_normalized(app: &App, total: u64) {
+        let (counted, shares) = normalized(app);
+        assert_eq!(counted, total);
+        assert!((shares - 1.0).abs() < 1e-3, "{}", shares);
+    }
+
+    #[test]
+    fn regenerating_discards_the_counts() {
+        let mut app = run(Mode::Cumulative, 3);
+        switch(&mut app, Mode::Regenerate, Transition::Discard);
+        assert_eq!(app.samples(), 3_000);
+        assert_eq!(normalized(&app).0, 0);
+        app.on_tick();
+        assert_eq!(app.samples(), 4_000);
+        assert_normalized(&app, 1_000);
+    }
+
+    #[test]
+    fn counting_on_from_the_tick() {
+        let mut app = run(Mode::Regenerate, 3);
+        switch(&mut app, Mode::Cumulative, Transition::FromTick);
+        assert_eq!(app.samples(), 3_000);
+        assert_normalized(&app, 1_000);
+        app.on_tick();
+        assert_eq!(app.samples(), 4_000);
+        assert_normalized(&app, 2_000);
+    }
+
+    #[test]
+    fn decaying_on_from_the_tick() {
+        let mut app = run(Mode::Regenerate, 3);
+        switch(&mut app, Mode::Decay(Decay::new(0.5)), Transition::FromTick);
+        assert_eq!(app.samples(), 3_000);
+        assert_normalized(&app, 1_000);
+        app.on_tick();
+        assert_eq!(app.samples(), 4_000);
+        // Half the tick's counts, and the next's.
+        assert_normalized(&app, 1_500);
+    }
+
+    #[test]
+    fn decaying_keeps_the_cumulative_counts() {
+        let mut app = run(Mode::Cumulative, 3);
+        let counts = app.data.clone();
+        switch(&mut app, Mode::Decay(Decay::new(0.5)), Transition::Keep);
+        assert_eq!(app.samples(), 3_000);
+        assert_eq!(app.data, counts);
+        assert_normalized(&app, 3_000);
+    }
+
+    #[test]
+    fn counting_on_rounds_the_decayed_counts() {
+        let mut app = run(Mode::Decay(Decay::new(0.5)), 3);
+        let weights = app.mode.decay().unwrap().weights().to_vec();
+        switch(&mut app, Mode::Cumulative, Transition::Round);
+        assert_eq!(app.samples(), 3_000);
+        let rounded = weights
+            .iter()
+            .map(|weight| weight.round() as u64)
+            .collect::<Vec<_>>();
+        let counts = app
+            .data
+            .iter()
+            .map(|bucket| bucket.count)
+            .collect::<Vec<_>>();
+        assert_eq!(counts, rounded);
+        let total = stats::total(rounded.iter().copied());
+        assert_normalized(&app, total);
+        app.on_tick();
+        assert_eq!(app.samples(), 4_000);
+        assert_normalized(&app, total + 1_000);
+    }
+}
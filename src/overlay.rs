@@ -0,0 +1,295 @@
+//! `--overlay coin,die6,uniform01`: up to three step sources walked
+//! alongside the run, with its samples and steps, and drawn in place of the
+//! histogram as the density of their sums' z-scores, each standardized by its
+//! own step's mean and variance. However different the steps, the curves fall
+//! on the same standard normal as the steps grow.
+//!
+//! The sources draw from streams of their own, so overlaying them doesn't
+//! move the run's samples.
+
+use central_limit::{
+    rng::{self, Generator},
+    sampling::{self, Coin, Die, StepSource, FAIR},
+};
+use rand::RngCore;
+use std::collections::BTreeMap;
+
+pub const MAX_SOURCES: usize = 3;
+
+/// Where the sources' seeds are derived from, past any `--seeds` run's.
+const STREAM: u64 = 1 << 32;
+
+/// How wide in z the buckets are that a continuous source's sums are
+/// counted into, about 80 of them across the chart's ±4σ.
+const Z_WIDTH: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Coin(Coin),
+    Die(Die),
+    /// A step drawn uniformly from [0, 1).
+    Uniform,
+}
+
+impl Source {
+    /// `coin`, a fair ±1 coin, `dieN`, a fair die of N faces, such as
+    /// `die6`, or `uniform01`, a step anywhere from 0 to 1.
+    pub fn parse(name: &str) -> Result<Source, String> {
+        if name == "coin" {
+            return Ok(Source::Coin(Coin(FAIR)));
+        }
+        if name == "uniform01" {
+            return Ok(Source::Uniform);
+        }
+        match name.strip_prefix("die").map(str::parse::<u32>) {
+            Some(Ok(faces)) if faces >= 2 => Ok(Source::Die(Die(faces))),
+            Some(_) => Err(format!("{:?} needs 2 faces or more, such as die6", name)),
+            None => Err(format!(
+                "unknown source {:?}: use coin, dieN, such as die6, or uniform01",
+                name
+            )),
+        }
+    }
+
+    /// Several of `parse`'s, comma-separated.
+    pub fn parse_list(list: &str) -> Result<Vec<Source>, String> {
+        let sources = list
+            .split(',')
+            .map(|name| Source::parse(name.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if sources.len() > MAX_SOURCES {
+            return Err(format!("at most {} sources can be overlaid", MAX_SOURCES));
+        }
+        Ok(sources)
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Source::Coin(_) => "coin".to_string(),
+            Source::Die(Die(faces)) => format!("die{}", faces),
+            Source::Uniform => "uniform01".to_string(),
+        }
+    }
+
+    /// The mean of a step.
+    pub fn mean(&self) -> f64 {
+        match self {
+            Source::Coin(coin) => coin.mean(),
+            Source::Die(die) => die.mean(),
+            Source::Uniform => 0.5,
+        }
+    }
+
+    pub fn variance(&self) -> f64 {
+        match self {
+            Source::Coin(coin) => coin.variance(),
+            Source::Die(die) => die.variance(),
+            Source::Uniform => 1.0 / 12.0,
+        }
+    }
+}
+
+/// One source's walks, and how often each sum came up. A whole-number
+/// source's buckets are its sums; a continuous source's are `Z_WIDTH` wide
+/// in z, numbered out from the one centered on the mean.
+pub struct Series {
+    pub source: Source,
+    rng: Box<dyn RngCore + Send>,
+    steps: u32,
+    counts: BTreeMap<i32, u64>,
+}
+
+impl Series {
+    pub fn samples(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// The standard deviation of the sums of `steps` of the source's steps.
+    fn sd(&self) -> f64 {
+        (f64::from(self.steps) * self.source.variance()).sqrt()
+    }
+
+    /// How far apart neighbouring buckets are in sums: a coin's sums share
+    /// the parity of the steps.
+    fn spacing(&self) -> f64 {
+        match self.source {
+            Source::Coin(_) => 2.0,
+            Source::Die(_) => 1.0,
+            Source::Uniform => Z_WIDTH * self.sd(),
+        }
+    }
+
+    /// Draws `samples` walks and counts them into their buckets.
+    fn draw(&mut self, samples: usize) {
+        let (mean, sd) = (f64::from(self.steps) * self.source.mean(), self.sd());
+        let rng = &mut self.rng;
+        let buckets = match &mut self.source {
+            Source::Coin(coin) => sampling::walk_sums(coin, rng, samples, self.steps),
+            Source::Die(die) => sampling::walk_sums(die, rng, samples, self.steps),
+            Source::Uniform => sampling::uniform_sums(rng, samples, self.steps)
+                .into_iter()
+                .map(|sum| ((sum - mean) / sd / Z_WIDTH).round() as i32)
+                .collect(),
+        };
+        for bucket in buckets {
+            *self.counts.entry(bucket).or_insert(0) += 1;
+        }
+    }
+
+    /// The sum each bucket that came up is centered on, with its count.
+    pub fn counts(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        let n = f64::from(self.steps);
+        self.counts.iter().map(move |(&bucket, &count)| {
+            let sum = match self.source {
+                Source::Uniform => n * self.source.mean() + f64::from(bucket) * self.spacing(),
+                _ => f64::from(bucket),
+            };
+            (sum, count)
+        })
+    }
+
+    /// The z-score of `sum`, by the mean and variance of `steps` of the
+    /// source's steps.
+    pub fn z(&self, sum: f64) -> f64 {
+        (sum - f64::from(self.steps) * self.source.mean()) / self.sd()
+    }
+
+    /// Each bucket's z-score and the density there: its share of the
+    /// samples over how far apart neighbouring buckets are in z, so a curve
+    /// is comparable with another's however finely its sums are spaced.
+    pub fn density(&self) -> Vec<(f64, f64)> {
+        let total = self.samples() as f64;
+        let spacing = self.spacing() / self.sd();
+        self.counts()
+            .map(|(sum, count)| (self.z(sum), count as f64 / total / spacing))
+            .collect()
+    }
+}
+
+/// The overlaid sources, ticked with the run.
+pub struct Overlay {
+    series: Vec<Series>,
+}
+
+impl Overlay {
+    /// The sources seeded from `seed`, each from a stream of its own.
+    pub fn new(sources: &[Source], seed: u64, generator: Generator) -> Overlay {
+        let series = sources
+            .iter()
+            .enumerate()
+            .map(|(i, &source)| Series {
+                source,
+                rng: generator.seeded(rng::derive_seed(seed, STREAM + i as u64)),
+                steps: 0,
+                counts: BTreeMap::new(),
+            })
+            .collect();
+        Overlay { series }
+    }
+
+    /// Starts a tick of walks of `steps` steps, drawn by `draw`. A change of
+    /// steps starts the counts over, as does every tick unless they're
+    /// `cumulative`.
+    pub fn begin_tick(&mut self, steps: u32, cumulative: bool) {
+        for series in &mut self.series {
+            if series.steps != steps || !cumulative {
+                series.counts.clear();
+                series.steps = steps;
+            }
+        }
+    }
+
+    /// Draws `samples` more walks of the tick from every source, so it can
+    /// be drawn in chunks along with the run's.
+    pub fn draw(&mut self, samples: usize) {
+        for series in &mut self.series {
+            series.draw(samples);
+        }
+    }
+
+    pub fn series(&self) -> &[Series] {
+        &self.series
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use central_limit::stats;
+
+    fn tick(overlay: &mut Overlay, samples: usize, steps: u32, cumulative: bool) {
+        overlay.begin_tick(steps, cumulative);
+        overlay.draw(samples);
+    }
+
+    #[test]
+    fn sources_by_name() {
+        assert_eq!(Source::parse("coin"), Ok(Source::Coin(Coin(FAIR))));
+        assert_eq!(Source::parse("die6"), Ok(Source::Die(Die(6))));
+        assert_eq!(Source::parse("uniform01"), Ok(Source::Uniform));
+        for name in ["die1", "die", "dice6", "uniform"] {
+            assert!(Source::parse(name).is_err(), "{}", name);
+        }
+        let sources = Source::parse_list("coin, die6,uniform01").unwrap();
+        let names = sources.iter().map(Source::name).collect::<Vec<_>>();
+        assert_eq!(names, ["coin", "die6", "uniform01"]);
+        assert!(Source::parse_list("coin,coin,coin,coin").is_err());
+    }
+
+    #[test]
+    fn every_source_falls_on_the_standard_normal() {
+        let sources = Source::parse_list("coin,die20,uniform01").unwrap();
+        let mut overlay = Overlay::new(&sources, 171, Generator::Std);
+        for _ in 0..10 {
+            tick(&mut overlay, 5_000, 30, true);
+        }
+        for series in overlay.series() {
+            assert_eq!(series.samples(), 50_000);
+            // Densities a bucket apart add up to 1...
+            let density = series.density();
+            let spacing = series.spacing() / series.sd();
+            let area = density.iter().map(|(_, d)| d * spacing).sum::<f64>();
+            assert!((area - 1.0).abs() < 1e-9, "{}", area);
+            // ...and are near the normal curve in the middle.
+            let middle = density
+                .iter()
+                .filter(|(z, _)| z.abs() < 1.0)
+                .map(|&(z, d)| (d - stats::normal_pdf(z)).abs())
+                .fold(0.0, f64::max);
+            assert!(middle < 0.05, "{}: {}", series.source.name(), middle);
+        }
+    }
+
+    #[test]
+    fn new_steps_start_the_counts_over() {
+        let mut overlay = Overlay::new(&[Source::Die(Die(6))], 1, Generator::Std);
+        tick(&mut overlay, 100, 5, true);
+        tick(&mut overlay, 100, 5, true);
+        assert_eq!(overlay.series()[0].samples(), 200);
+        tick(&mut overlay, 100, 6, true);
+        assert_eq!(overlay.series()[0].samples(), 100);
+        assert!(overlay.series()[0]
+            .counts()
+            .all(|(sum, _)| (6.0..=36.0).contains(&sum)));
+        tick(&mut overlay, 100, 6, false);
+        assert_eq!(overlay.series()[0].samples(), 100);
+    }
+
+    #[test]
+    fn a_tick_drawn_in_chunks_counts_the_same() {
+        let sources = Source::parse_list("coin,die6,uniform01").unwrap();
+        let mut whole = Overlay::new(&sources, 171, Generator::Std);
+        let mut chunks = Overlay::new(&sources, 171, Generator::Std);
+        tick(&mut whole, 1_000, 12, true);
+        chunks.begin_tick(12, true);
+        for n in [300, 300, 400] {
+            chunks.draw(n);
+        }
+        for (whole, chunks) in whole.series().iter().zip(chunks.series()) {
+            assert_eq!(
+                whole.counts().collect::<Vec<_>>(),
+                chunks.counts().collect::<Vec<_>>()
+            );
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! When the main loop draws and ticks. The screen is redrawn at most every
+//! `MIN_FRAME`, about 60 times a second, whatever the tick rate; ticks
+//! shorter than that are run several to a frame instead of each waiting for
+//! a redraw of its own.
+//!
+//! Ticks keep to a fixed schedule, one interval after another from the
+//! start, rather than each waiting an interval from when the last finished,
+//! so the time spent sampling and drawing doesn't add up into drift against
+//! the clock over a long run.
+//!
+//! Everything takes the time it's asked about, so the loop decides from one
+//! reading of the clock.
+
+use std::time::{Duration, Instant};
+
+/// The shortest time between two frames.
+pub const MIN_FRAME: Duration = Duration::from_millis(16);
+
+/// Ticks at most run between two frames, for tick rates far beyond what
+/// the machine can keep up with.
+const MAX_BATCH: u64 = 1_000;
+
+#[derive(Debug)]
+pub struct Pace {
+    /// When the last tick run was due, or would have been if it wasn't
+    /// skipped for running behind.
+    last_tick: Instant,
+    last_frame: Option<Instant>,
+}
+
+impl Pace {
+    pub fn new(now: Instant) -> Pace {
+        Pace {
+            last_tick: now,
+            last_frame: None,
+        }
+    }
+
+    /// Starts the interval to the next tick over, as when the run starts.
+    pub fn restart(&mut self, now: Instant) {
+        self.last_tick = now;
+    }
+
+    pub fn since_tick(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.last_tick)
+    }
+
+    /// How far behind its time a tick at `interval` starting at `now` is,
+    /// `ran` ticks into a batch.
+    pub fn late(&self, now: Instant, interval: Duration, ran: u64) -> Duration {
+        self.since_tick(now)
+            .saturating_sub(interval * (ran + 1) as u32)
+    }
+
+    /// Keeps the schedule from falling behind while no ticks are run at
+    /// `interval`, as when paused, so the next is due as soon as they are
+    /// again and nothing counts as skipped.
+    pub fn hold(&mut self, now: Instant, interval: Duration) {
+        self.last_tick = now.checked_sub(interval).unwrap_or(now);
+    }
+
+    /// Whether it's been long enough since the last frame to draw another.
+    pub fn frame_due(&self, now: Instant) -> bool {
+        self.until_frame(now).is_zero()
+    }
+
+    pub fn until_frame(&self, now: Instant) -> Duration {
+        self.last_frame.map_or(Duration::ZERO, |last| {
+            MIN_FRAME.saturating_sub(now.saturating_duration_since(last))
+        })
+    }
+
+    pub fn drew(&mut self, now: Instant) {
+        self.last_frame = Some(now);
+    }
+
+    /// How long until a tick is due at `interval`, and for ticks shorter
+    /// than a frame, until the frame they're batched into.
+    pub fn until_tick(&self, now: Instant, interval: Duration) -> Duration {
+        let wait = interval.saturating_sub(self.since_tick(now));
+        match interval < MIN_FRAME {
+            true => wait.max(self.until_frame(now)),
+            false => wait,
+        }
+    }
+
+    /// The ticks due at `interval`: at most one for ticks at least a frame
+    /// long, and all those that have come due since the last otherwise.
+    pub fn due(&self, now: Instant, interval: Duration) -> u64 {
+        let elapsed = self.since_tick(now);
+        if elapsed < interval {
+            return 0;
+        }
+        match interval < MIN_FRAME {
+            true => {
+                let due = elapsed.as_nanos() / interval.as_nanos().max(1);
+                due.min(u128::from(MAX_BATCH)) as u64
+            }
+            false => 1,
+        }
+    }
+
+    /// Records that `ran` ticks at `interval`, no more than were due,
+    /// finished at `now`, and returns how many were skipped for it. The
+    /// next tick is due an interval after the last was, however long that
+    /// took. Once the ticks fall a whole interval behind, or a frame for
+    /// those shorter than one, the ones missed are skipped rather than run
+    /// back to back: the latest that's due runs next, late but on the same
+    /// schedule.
+    pub fn ticked(&mut self, now: Instant, interval: Duration, ran: u64) -> u64 {
+        self.last_tick += interval * ran as u32;
+        let late = self.since_tick(now).saturating_sub(interval);
+        let limit = interval.max(MIN_FRAME);
+        if late < limit {
+            return 0;
+        }
+        let skipped = (late - limit).as_nanos() / interval.as_nanos().max(1) + 1;
+        self.last_tick += Duration::from_nanos((interval.as_nanos() * skipped) as u64);
+        skipped as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{driver::Clock, harness::Harness};
+    use crossterm::event::KeyCode;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    /// Runs the main loop's scheduling on a clock that only moves when told
+    /// to, for `ticks` ticks: it sleeps until a tick is due, waking `oversleep`
+    /// late, and each tick takes `work`. Gives when each tick started, from
+    /// the start, and how many were skipped.
+    fn run(
+        pace: &mut Pace,
+        start: Instant,
+        ticks: usize,
+        interval: impl Fn(usize) -> Duration,
+        work: impl Fn(usize) -> Duration,
+        oversleep: impl Fn(usize) -> Duration,
+    ) -> (Vec<Duration>, u64) {
+        let mut now = start;
+        let mut started = vec![];
+        let mut skipped = 0;
+        while started.len() < ticks {
+            let interval = interval(started.len());
+            let due = pace.due(now, interval);
+            if due == 0 {
+                now += pace.until_tick(now, interval) + oversleep(started.len());
+                continue;
+            }
+            for _ in 0..due {
+                started.push(now - start);
+                now += work(started.len() - 1);
+            }
+            skipped += pace.ticked(now, interval, due);
+        }
+        (started, skipped)
+    }
+
+    #[test]
+    fn ticks_keep_to_the_schedule_however_long_they_take() {
+        let start = Instant::now();
+        let mut pace = Pace::new(start);
+        let interval = 100 * MS;
+        let (started, skipped) = run(
+            &mut pace,
+            start,
+            10_000,
+            |_| interval,
+            |k| (10 + k as u32 % 60) * MS,
+            |k| (k as u32 % 7) * MS,
+        );
+        assert_eq!(skipped, 0);
+        for (k, at) in started.iter().enumerate() {
+            let due = interval * (k as u32 + 1);
+            assert!(*at >= due && *at - due < 7 * MS, "tick {} at {:?}", k, at);
+        }
+    }
+
+    #[test]
+    fn ticks_fallen_an_interval_behind_are_skipped() {
+        let start = Instant::now();
+        let mut pace = Pace::new(start);
+        let interval = 100 * MS;
+        // The first tick takes three and a half intervals, the rest none.
+        let (started, skipped) = run(
+            &mut pace,
+            start,
+            4,
+            |_| interval,
+            |k| if k == 0 { 350 * MS } else { Duration::ZERO },
+            |_| Duration::ZERO,
+        );
+        // It ends at 450ms: those due at 200ms and 300ms are skipped, the one
+        // due at 400ms runs late, and the rest are back on the schedule.
+        assert_eq!(skipped, 2);
+        assert_eq!(started, [100 * MS, 450 * MS, 500 * MS, 600 * MS]);
+    }
+
+    #[test]
+    fn short_ticks_are_batched_up_to_a_limit() {
+        let start = Instant::now();
+        let pace = Pace::new(start);
+        assert_eq!(pace.due(start + 50 * MS, MS), 50);
+        assert_eq!(pace.due(start + 5 * MIN_FRAME, MIN_FRAME), 1);
+        assert_eq!(pace.due(start + Duration::from_secs(60), MS), MAX_BATCH);
+    }
+
+    #[test]
+    fn a_hold_counts_the_time_away_as_paused() {
+        let start = Instant::now();
+        let interval = 100 * MS;
+        let back = start + Duration::from_secs(10);
+
+        let mut held = Pace::new(start);
+        held.hold(back, interval);
+        assert_eq!(held.due(back, interval), 1);
+        assert_eq!(held.late(back, interval, 0), Duration::ZERO);
+        assert_eq!(held.ticked(back, interval, 1), 0);
+        assert_eq!(held.until_tick(back, interval), interval);
+
+        // Without the hold, the ticks the suspend missed are skipped.
+        let mut unheld = Pace::new(start);
+        assert_eq!(unheld.ticked(back, interval, 1), 98);
+    }
+
+    #[test]
+    fn the_loop_reads_keys_between_ticks_that_overrun() {
+        // Each tick takes 800ms on the loop's clock, at 500ms a tick.
+        let mut harness = Harness::new(1);
+        let clock = harness.clock().clone();
+        harness.app.tick_rate = 500 * MS;
+        harness
+            .app
+            .sim
+            .set_tick_hook(Box::new(move |_| clock.advance(800 * MS)));
+        let (polls, start) = (harness.polls(), harness.clock().now());
+        harness.advance_ticks(10);
+        let app = harness.state();
+        assert_eq!(app.tick, 10);
+        assert_eq!(app.samples(), 10 * app.b_count as u64);
+        assert!(app.perf.overran());
+        assert_eq!(app.perf.overruns(), 10);
+        // The intervals missed are skipped rather than caught up on, and
+        // the loop checks for input at least once a tick.
+        assert!(app.perf.skipped() >= 4, "{} skipped", app.perf.skipped());
+        assert!(harness.polls() - polls >= 10);
+        let took = harness.clock().now() - start;
+        assert!(took >= 8_000 * MS && took < 9_000 * MS, "{:?}", took);
+
+        // A key pressed during a tick is read before the next one.
+        harness.press(KeyCode::Char(' ')).advance_ticks(3);
+        assert_eq!(harness.state().tick, 10);
+    }
+
+    #[test]
+    fn millisecond_ticks_are_batched_into_frames() {
+        let mut harness = Harness::new(1);
+        harness.app.b_count = 100;
+        harness.app.tick_rate = MS;
+        let polls = harness.polls();
+        harness.run_for(Duration::from_secs(1));
+        let app = harness.state();
+        // Every tick is run and counted, a frame's worth at a time.
+        assert!((980..=1_020).contains(&app.tick), "{} ticks", app.tick);
+        assert_eq!(app.samples(), app.tick * 100);
+        assert_eq!(app.perf.skipped(), 0);
+        let ticks_per_sec = app.perf.ticks_per_sec().unwrap();
+        assert!((ticks_per_sec - 1_000.0).abs() < 50.0, "{}", ticks_per_sec);
+        // The loop waits out each frame rather than spinning: it checks for
+        // input and draws about 60 times a second.
+        let polls = harness.polls() - polls;
+        assert!((55..=65).contains(&polls), "{} polls", polls);
+        let frames_per_sec = app.perf.frames_per_sec().unwrap();
+        assert!(frames_per_sec <= 63.0, "{}", frames_per_sec);
+        assert!(app.perf.last_batch().unwrap() >= 15);
+    }
+
+    #[test]
+    fn replayed_frames_are_shown_as_far_apart_as_recorded() {
+        let start = Instant::now();
+        let mut pace = Pace::new(start);
+        // The time to each frame from the last, as `ReplaySource::interval`
+        // gives it, which changes from one tick to the next.
+        let apart = [250, 40, 1000, 30, 500, 30, 30, 8000].map(|ms| ms * MS);
+        let (started, skipped) = run(
+            &mut pace,
+            start,
+            apart.len(),
+            |k| apart[k],
+            |_| 20 * MS,
+            |_| 2 * MS,
+        );
+        assert_eq!(skipped, 0);
+        let mut recorded = Duration::ZERO;
+        for (k, at) in started.iter().enumerate() {
+            recorded += apart[k];
+            assert!(
+                *at >= recorded && *at - recorded <= 2 * MS,
+                "frame {} at {:?}",
+                k,
+                at
+            );
+        }
+    }
+}
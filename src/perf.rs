@@ -0,0 +1,336 @@
+//! Timings for the debug readout.
+//!
+//! Every tick's sampling time also goes into `TickTimes`, a histogram of
+//! them from 0.1ms to 10s, for telling the odd slow tick that makes the
+//! animation hiccup from a steady load. It's kept whether or not the readout
+//! is open, and only summed up when it's drawn or exported.
+//!
+//! Times are the main loop's, read from its clock, so a test running the
+//! loop on a mock clock sees the same overruns and rates the terminal would.
+
+use crate::json::Value;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Samples kept for the rolling averages.
+const WINDOW: usize = 20;
+
+/// The shortest tick time with a bucket of its own, in seconds; those below
+/// it share one.
+const LOWEST: f64 = 1e-4;
+
+/// Decades of tick times from `LOWEST`, up to 10s; those above share one.
+const DECADES: usize = 5;
+
+const PER_DECADE: usize = 10;
+
+/// The buckets from `LOWEST` up, plus one either side for those beyond.
+const BUCKETS: usize = DECADES * PER_DECADE + 2;
+
+#[derive(Debug, Default)]
+pub struct Perf {
+    ticks: VecDeque<Duration>,
+    frames: VecDeque<Duration>,
+    frame_times: VecDeque<Instant>,
+    /// When each batch of ticks run between two frames finished, and how
+    /// many ticks it had.
+    batches: VecDeque<(Instant, u64)>,
+    /// How far behind the schedule each tick started.
+    late: VecDeque<Duration>,
+    /// Ticks left out of the schedule for running behind it.
+    skipped: u64,
+    /// Ticks that took longer than the tick interval.
+    overruns: u64,
+    overran: bool,
+    tick_times: TickTimes,
+}
+
+/// Tick times in buckets a tenth of a decade wide, each about a quarter
+/// longer than the one before.
+#[derive(Debug, Clone)]
+pub struct TickTimes {
+    counts: [u64; BUCKETS],
+    ticks: u64,
+}
+
+impl Default for TickTimes {
+    fn default() -> TickTimes {
+        TickTimes {
+            counts: [0; BUCKETS],
+            ticks: 0,
+        }
+    }
+}
+
+impl TickTimes {
+    pub fn record(&mut self, took: Duration) {
+        let secs = took.as_secs_f64();
+        let i = match secs < LOWEST {
+            true => 0,
+            false => ((secs / LOWEST).log10() * PER_DECADE as f64).floor() as usize + 1,
+        };
+        self.counts[i.min(BUCKETS - 1)] += 1;
+        self.ticks += 1;
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Where bucket `i` starts and ends, in seconds.
+    fn bounds(i: usize) -> (f64, f64) {
+        let edge = |i: usize| LOWEST * 10f64.powf(i as f64 / PER_DECADE as f64);
+        match i {
+            0 => (0.0, LOWEST),
+            i if i == BUCKETS - 1 => (edge(i - 1), f64::INFINITY),
+            i => (edge(i - 1), edge(i)),
+        }
+    }
+
+    /// The tick time below which `p` of them fall, from 0 to 1, as the
+    /// middle of its bucket, or the edge the histogram reaches for the
+    /// buckets beyond it.
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        if self.ticks == 0 {
+            return None;
+        }
+        let rank = ((p * self.ticks as f64).ceil() as u64).clamp(1, self.ticks);
+        let mut seen = 0;
+        let i = self.counts.iter().position(|&count| {
+            seen += count;
+            seen >= rank
+        })?;
+        let secs = match TickTimes::bounds(i) {
+            (0.0, high) => high,
+            (low, high) if high.is_infinite() => low,
+            (low, high) => (low * high).sqrt(),
+        };
+        Some(Duration::from_secs_f64(secs))
+    }
+
+    /// The counts of the buckets from the shortest tick time to the longest,
+    /// with where the first starts and the last ends.
+    pub fn spread(&self) -> Option<(Duration, Duration, &[u64])> {
+        let first = self.counts.iter().position(|&count| count > 0)?;
+        let last = self.counts.iter().rposition(|&count| count > 0)?;
+        let (low, _) = TickTimes::bounds(first);
+        let high = match TickTimes::bounds(last) {
+            (low, high) if high.is_infinite() => low,
+            (_, high) => high,
+        };
+        Some((
+            Duration::from_secs_f64(low),
+            Duration::from_secs_f64(high),
+            &self.counts[first..=last],
+        ))
+    }
+
+    /// The buckets that have any ticks, for the exports.
+    pub fn to_json(&self) -> Value {
+        let ms = |d: Option<Duration>| d.map_or(Value::Null, |d| (d.as_secs_f64() * 1e3).into());
+        let buckets = (0..BUCKETS)
+            .filter(|&i| self.counts[i] > 0)
+            .map(|i| {
+                let (low, high) = TickTimes::bounds(i);
+                Value::object(vec![
+                    ("from_ms", (low * 1e3).into()),
+                    // The last bucket has no end; infinity is written as null.
+                    ("to_ms", (high * 1e3).into()),
+                    ("ticks", self.counts[i].into()),
+                ])
+            })
+            .collect::<Vec<_>>();
+        Value::object(vec![
+            ("ticks", self.ticks.into()),
+            ("p50_ms", ms(self.percentile(0.5))),
+            ("p95_ms", ms(self.percentile(0.95))),
+            ("p99_ms", ms(self.percentile(0.99))),
+            ("buckets", buckets.into()),
+        ])
+    }
+}
+
+fn push<T>(window: &mut VecDeque<T>, value: T) {
+    if window.len() == WINDOW {
+        window.pop_front();
+    }
+    window.push_back(value);
+}
+
+fn average(window: &VecDeque<Duration>) -> Option<Duration> {
+    let total = window.iter().sum::<Duration>();
+    (!window.is_empty()).then(|| total / window.len() as u32)
+}
+
+impl Perf {
+    /// Records a tick that started and finished at those times.
+    pub fn record_tick(&mut self, started: Instant, finished: Instant, interval: Duration) {
+        let took = finished.saturating_duration_since(started);
+        self.overran = took > interval;
+        if self.overran {
+            self.overruns += 1;
+        }
+        push(&mut self.ticks, took);
+    }
+
+    /// Records how far behind its time on the schedule a tick started.
+    pub fn record_late(&mut self, late: Duration) {
+        push(&mut self.late, late);
+    }
+
+    pub fn record_skipped(&mut self, ticks: u64) {
+        self.skipped += ticks;
+    }
+
+    /// Records how long a tick took to sample, not counting the frames
+    /// drawn while it was drawn in chunks.
+    pub fn record_work(&mut self, took: Duration) {
+        self.tick_times.record(took);
+    }
+
+    pub fn tick_times(&self) -> &TickTimes {
+        &self.tick_times
+    }
+
+    /// Records that `ticks` ticks were run between two frames, the last
+    /// finishing at `now`.
+    pub fn record_batch(&mut self, now: Instant, ticks: u64) {
+        push(&mut self.batches, (now, ticks));
+    }
+
+    pub fn record_frame(&mut self, started: Instant, finished: Instant) {
+        push(
+            &mut self.frames,
+            finished.saturating_duration_since(started),
+        );
+        push(&mut self.frame_times, finished);
+    }
+
+    pub fn last_tick(&self) -> Option<Duration> {
+        self.ticks.back().copied()
+    }
+
+    pub fn average_tick(&self) -> Option<Duration> {
+        average(&self.ticks)
+    }
+
+    pub fn last_frame(&self) -> Option<Duration> {
+        self.frames.back().copied()
+    }
+
+    pub fn average_frame(&self) -> Option<Duration> {
+        average(&self.frames)
+    }
+
+    pub fn last_late(&self) -> Option<Duration> {
+        self.late.back().copied()
+    }
+
+    pub fn average_late(&self) -> Option<Duration> {
+        average(&self.late)
+    }
+
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    pub fn overruns(&self) -> u64 {
+        self.overruns
+    }
+
+    /// Whether the most recent tick took longer than the tick interval.
+    pub fn overran(&self) -> bool {
+        self.overran
+    }
+
+    /// Ticks per second over the window, counting every tick of a batch.
+    pub fn ticks_per_sec(&self) -> Option<f64> {
+        let (first, last) = (self.batches.front()?, self.batches.back()?);
+        let span = last.0.duration_since(first.0).as_secs_f64();
+        let ticks = self
+            .batches
+            .iter()
+            .skip(1)
+            .map(|batch| batch.1)
+            .sum::<u64>();
+        (span > 0.0).then(|| ticks as f64 / span)
+    }
+
+    /// Frames drawn per second over the window.
+    pub fn frames_per_sec(&self) -> Option<f64> {
+        let (first, last) = (self.frame_times.front()?, self.frame_times.back()?);
+        let span = last.duration_since(*first).as_secs_f64();
+        (span > 0.0).then(|| (self.frame_times.len() - 1) as f64 / span)
+    }
+
+    /// Ticks run between the last two frames that ran any.
+    pub fn last_batch(&self) -> Option<u64> {
+        self.batches.back().map(|batch| batch.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: f64) -> Duration {
+        Duration::from_secs_f64(ms / 1e3)
+    }
+
+    /// Whether `percentile` gives `ms`, to within its bucket.
+    fn near(percentile: Option<Duration>, ms: f64) -> bool {
+        let within = 10f64.powf(0.5 / PER_DECADE as f64);
+        let got = percentile.unwrap().as_secs_f64() * 1e3;
+        got >= ms / within && got <= ms * within
+    }
+
+    #[test]
+    fn percentiles_of_a_steady_load_and_its_odd_slow_ticks() {
+        let mut times = TickTimes::default();
+        for took in [(90, 1.3), (9, 12.0), (1, 1500.0)] {
+            for _ in 0..took.0 {
+                times.record(ms(took.1));
+            }
+        }
+        assert_eq!(times.ticks(), 100);
+        assert!(near(times.percentile(0.5), 1.3));
+        assert!(near(times.percentile(0.9), 1.3));
+        assert!(near(times.percentile(0.95), 12.0));
+        assert!(near(times.percentile(0.99), 12.0));
+        assert!(near(times.percentile(1.0), 1500.0));
+        // The first tick is the lowest percentile there is.
+        assert_eq!(times.percentile(0.0), times.percentile(0.01));
+        let (low, high, counts) = times.spread().unwrap();
+        assert!(low <= ms(1.3) && high >= ms(1500.0));
+        assert_eq!(counts.iter().sum::<u64>(), 100);
+        assert_eq!((counts[0], counts[counts.len() - 1]), (90, 1));
+    }
+
+    #[test]
+    fn tick_times_beyond_the_buckets_are_clamped_to_their_edges() {
+        let mut times = TickTimes::default();
+        assert_eq!(times.percentile(0.5), None);
+        assert!(times.spread().is_none());
+        times.record(Duration::from_micros(20));
+        assert_eq!(times.percentile(0.5), Some(Duration::from_secs_f64(LOWEST)));
+        times.record(Duration::from_secs(60));
+        let top = times.percentile(1.0).unwrap().as_secs_f64();
+        assert!((top - 10.0).abs() < 1e-9, "{}", top);
+        let (_, high, counts) = times.spread().unwrap();
+        assert!((high.as_secs_f64() - 10.0).abs() < 1e-9);
+        assert_eq!(counts.len(), BUCKETS);
+    }
+
+    #[test]
+    fn only_the_sampling_time_goes_in_the_histogram() {
+        let mut perf = Perf::default();
+        perf.record_work(ms(2.0));
+        let now = Instant::now();
+        perf.record_tick(now, now + ms(3.0), Duration::from_millis(500));
+        perf.record_frame(now, now + ms(4.0));
+        assert_eq!(perf.tick_times().ticks(), 1);
+        assert!(near(perf.tick_times().percentile(0.5), 2.0));
+    }
+}
@@ -0,0 +1,59 @@
+//! `k`: pinning the histogram as it stands, to compare against after a
+//! change of parameters. A pin keeps each bucket's share of the samples, not
+//! its count, so it stays comparable as the live histogram grows, and is
+//! re-binned the way a `--reference` is when the steps change.
+
+use crate::{bucket::Bucket, json::Value, reference, stats};
+
+/// Pins kept at once; another replaces the oldest.
+pub const MAX: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct Pin {
+    /// Bucket centers and each one's share of the samples.
+    shares: Vec<(f64, f64)>,
+    /// The parameters it was pinned under, for the legend.
+    pub label: String,
+}
+
+impl Pin {
+    /// Pins `data`, unless it's empty.
+    pub fn new(data: &[Bucket], label: String) -> Option<Pin> {
+        let total = stats::total(data.iter().map(|bucket| bucket.count));
+        if total == 0 {
+            return None;
+        }
+        let shares = data
+            .iter()
+            .map(|bucket| (bucket.x, bucket.count as f64 / total as f64))
+            .collect();
+        Some(Pin { shares, label })
+    }
+
+    /// The pin's share of the samples in each of `data`'s buckets.
+    pub fn frequencies(&self, data: &[Bucket]) -> Vec<f64> {
+        reference::rebin(self.shares.iter().copied(), data)
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("label", self.label.as_str().into()),
+            (
+                "buckets",
+                self.shares
+                    .iter()
+                    .map(|(x, _)| *x)
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            (
+                "frequencies",
+                self.shares
+                    .iter()
+                    .map(|(_, share)| *share)
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+        ])
+    }
+}
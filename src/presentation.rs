@@ -0,0 +1,39 @@
+//! Randomness for how the run looks rather than what it draws, such as the
+//! intro's samples. It's a stream of its own, derived from the seed apart
+//! from the simulation's, so a visual feature can draw from it as much as it
+//! likes, or be turned off, without moving the run's samples; the same seed
+//! still shows the same intro.
+//!
+//! The simulation's stream lives inside `Simulation`, out of the UI's reach,
+//! and `PresentationRng` doesn't implement `RngCore`, so it can't be handed
+//! to a `Simulation` or the samplers in its place.
+
+use central_limit::{rng, sampling};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// The presentation stream's index for `derive_seed`, clear of the
+/// `--seeds` runs, the sweep's step counts and the startup check's.
+const STREAM: u64 = u64::MAX - 1;
+
+pub struct PresentationRng(StdRng);
+
+impl PresentationRng {
+    pub fn new(seed: u64) -> PresentationRng {
+        PresentationRng(StdRng::seed_from_u64(rng::derive_seed(seed, STREAM)))
+    }
+
+    /// A seed for a simulation that's only there to be shown, such as the
+    /// intro's.
+    pub fn seed(&mut self) -> u64 {
+        self.0.gen()
+    }
+
+    pub fn coin(&mut self) -> bool {
+        self.0.gen()
+    }
+
+    /// `n` samples shared among buckets with `probabilities`.
+    pub fn multinomial(&mut self, n: u64, probabilities: &[f64]) -> Vec<u64> {
+        sampling::multinomial(&mut self.0, n, probabilities)
+    }
+}
@@ -0,0 +1,143 @@
+//! Where each of the run's parameters came from, for the exports and
+//! `--print-config`: a default, the `--watch-config` file, the environment,
+//! the command line, or a change made in the TUI. Changes made once the run
+//! has started are also kept in order, so an export says what it was drawn
+//! under from tick to tick.
+
+use crate::{datetime::DateTime, json::Value};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    Config,
+    Env,
+    Cli,
+    Interactive,
+}
+
+impl Source {
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Default => "default",
+            Source::Config => "config",
+            Source::Env => "env",
+            Source::Cli => "cli",
+            Source::Interactive => "interactive",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: &'static str,
+    pub value: String,
+    pub source: Source,
+}
+
+/// A parameter changed while the run was going.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub tick: u64,
+    pub time: SystemTime,
+    pub name: &'static str,
+    pub value: String,
+    pub source: Source,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Provenance {
+    params: Vec<Param>,
+    changes: Vec<Change>,
+    /// When the run started; changes before then aren't kept in `changes`.
+    started: Option<SystemTime>,
+}
+
+impl Provenance {
+    /// Takes in the parameters' current values, crediting those that changed
+    /// since the last look, or are new, to `source`.
+    pub fn update(&mut self, values: Vec<(&'static str, String)>, source: Source, tick: u64) {
+        for (name, value) in values {
+            match self.params.iter_mut().find(|param| param.name == name) {
+                Some(param) if param.value == value => continue,
+                Some(param) => {
+                    param.value = value.clone();
+                    param.source = source;
+                }
+                None => self.params.push(Param {
+                    name,
+                    value: value.clone(),
+                    source,
+                }),
+            }
+            if self.started.is_some() {
+                self.changes.push(Change {
+                    tick,
+                    time: SystemTime::now(),
+                    name,
+                    value,
+                    source,
+                });
+            }
+        }
+    }
+
+    /// Credits `names` to `source` whatever their values, for settings given
+    /// explicitly but the same as the defaults.
+    pub fn credit(&mut self, names: &[&str], source: Source) {
+        for param in &mut self.params {
+            if names.contains(&param.name) {
+                param.source = source;
+            }
+        }
+    }
+
+    /// Marks the start of the run: from here on, changes are kept in order.
+    pub fn start(&mut self) {
+        self.started.get_or_insert_with(SystemTime::now);
+    }
+
+    pub fn started(&self) -> Option<SystemTime> {
+        self.started
+    }
+
+    pub fn params(&self) -> &[Param] {
+        &self.params
+    }
+
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Each parameter's value and source, and the changes in order, for the
+    /// JSON export.
+    pub fn to_json(&self) -> (Value, Value) {
+        let params = self
+            .params
+            .iter()
+            .map(|param| {
+                (
+                    param.name,
+                    Value::object(vec![
+                        ("value", param.value.clone().into()),
+                        ("source", param.source.name().into()),
+                    ]),
+                )
+            })
+            .collect();
+        let changes = self
+            .changes
+            .iter()
+            .map(|change| {
+                Value::object(vec![
+                    ("tick", change.tick.into()),
+                    ("time", DateTime::rfc3339(change.time).into()),
+                    ("name", change.name.into()),
+                    ("value", change.value.clone().into()),
+                    ("source", change.source.name().into()),
+                ])
+            })
+            .collect::<Vec<_>>();
+        (Value::object(params), Value::Array(changes))
+    }
+}
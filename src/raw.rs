@@ -0,0 +1,129 @@
+//! `--raw-out`: every sum as it's drawn, before it's counted, for analysis
+//! in other tools.
+
+use central_limit::simulation::Simulation;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// Large enough that writing costs little next to sampling.
+const BUFFER: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Little-endian `i32`s, back to back.
+    Binary,
+    /// One decimal sum per line.
+    Text,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "binary" => Some(Format::Binary),
+            "text" => Some(Format::Text),
+            _ => None,
+        }
+    }
+}
+
+pub struct RawWriter {
+    out: Option<BufWriter<Box<dyn Write + Send>>>,
+    format: Format,
+    /// Sums still to write under `--raw-limit`.
+    remaining: Option<u64>,
+    written: u64,
+    error: Option<io::Error>,
+}
+
+impl RawWriter {
+    /// Writes to the file at `path`, or to stdout for `-`, stopping after
+    /// `limit` sums if given.
+    pub fn create(path: &Path, format: Format, limit: Option<u64>) -> io::Result<RawWriter> {
+        let out: Box<dyn Write + Send> = match path.to_str() {
+            Some("-") => Box::new(io::stdout()),
+            _ => Box::new(File::create(path)?),
+        };
+        Ok(RawWriter {
+            out: Some(BufWriter::with_capacity(BUFFER, out)),
+            format,
+            remaining: limit,
+            written: 0,
+            error: None,
+        })
+    }
+
+    /// Writes as many of `sums` as the limit allows. The first error stops
+    /// the writer, and is kept for `finish`.
+    pub fn write(&mut self, sums: &[i32]) {
+        let Some(out) = &mut self.out else {
+            return;
+        };
+        let n = match self.remaining {
+            Some(remaining) => sums.len().min(remaining as usize),
+            None => sums.len(),
+        };
+        let result = sums[..n].iter().try_for_each(|sum| match self.format {
+            Format::Binary => out.write_all(&sum.to_le_bytes()),
+            Format::Text => writeln!(out, "{}", sum),
+        });
+        self.written += n as u64;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= n as u64;
+        }
+        match result {
+            Err(err) => {
+                self.error = Some(err);
+                self.out = None;
+            }
+            Ok(()) if self.remaining == Some(0) => {
+                if let Err(err) = self.close() {
+                    self.error = Some(err);
+                }
+            }
+            Ok(()) => {}
+        }
+    }
+
+    /// Sums written so far.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Whether `--raw-limit` stopped the writer.
+    pub fn limited(&self) -> bool {
+        self.remaining == Some(0)
+    }
+
+    /// Flushes and closes the output, returning the first error there was.
+    pub fn finish(&mut self) -> io::Result<()> {
+        let closed = self.close();
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => closed,
+        }
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        match self.out.take() {
+            Some(mut out) => out.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Taps `sim`'s sums into `writer`. The writer is shared, so it can be
+/// finished once the run is over.
+pub fn install(sim: &mut Simulation, writer: RawWriter) -> Arc<Mutex<RawWriter>> {
+    let writer = Arc::new(Mutex::new(writer));
+    let tap = Arc::clone(&writer);
+    sim.set_sample_tap(Box::new(move |sums| {
+        if let Ok(mut writer) = tap.lock() {
+            writer.write(sums);
+        }
+    }));
+    writer
+}
@@ -0,0 +1,77 @@
+//! `--reference`: a previous export drawn alongside the live histogram.
+
+use crate::{bucket::Bucket, export, warm};
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct Reference {
+    /// Bucket centers and counts, as exported.
+    buckets: Vec<(f64, u64)>,
+    pub samples: u64,
+}
+
+impl Reference {
+    /// Reads an export written by `--output-dir`. If its buckets aren't the
+    /// ones `data` lays out, it's still used, re-binned, and the warning
+    /// says so.
+    pub fn load(path: &Path, data: &[Bucket]) -> Result<(Reference, Option<String>), String> {
+        let export = export::read(path)?;
+        let buckets = export
+            .labels
+            .iter()
+            .zip(&export.counts)
+            .map(|(label, count)| match label.parse::<f64>() {
+                Ok(x) => Ok((x, *count)),
+                Err(_) => Err(format!("bucket label '{}' is not a number", label)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let warning = match export.labels.iter().eq(data.iter().map(|b| &b.label)) {
+            true => None,
+            false => Some(format!(
+                "the reference buckets are {} but this run's are {}; re-binned onto this run's",
+                warm::describe(&export.labels.iter().map(String::as_str).collect::<Vec<_>>()),
+                warm::describe(&data.iter().map(|b| b.label.as_str()).collect::<Vec<_>>())
+            )),
+        };
+        let samples = export.counts.iter().copied().fold(0, u64::saturating_add);
+        Ok((Reference { buckets, samples }, warning))
+    }
+
+    /// The reference's share of samples in each of `data`'s buckets.
+    pub fn frequencies(&self, data: &[Bucket]) -> Vec<f64> {
+        let samples = self.samples.max(1) as f64;
+        rebin(
+            self.buckets
+                .iter()
+                .map(|(x, count)| (*x, *count as f64 / samples)),
+            data,
+        )
+    }
+}
+
+/// Shares of the samples at bucket centers, summed onto `data`'s buckets.
+/// Each goes to the nearest of them, and ones more than half a bucket beyond
+/// either end are left out.
+pub fn rebin(shares: impl IntoIterator<Item = (f64, f64)>, data: &[Bucket]) -> Vec<f64> {
+    let mut frequencies = vec![0.0; data.len()];
+    let (Some(first), Some(last)) = (data.first(), data.last()) else {
+        return frequencies;
+    };
+    // Adjacent sums differ by 2, so half a bucket is 1.
+    let range = first.x - 1.0..=last.x + 1.0;
+    for (x, share) in shares {
+        if !range.contains(&x) {
+            continue;
+        }
+        let nearest = data
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (a.x - x).abs().total_cmp(&(b.x - x).abs()))
+            .map(|(i, _)| i);
+        if let Some(i) = nearest {
+            frequencies[i] += share;
+        }
+    }
+    frequencies
+}
@@ -0,0 +1,226 @@
+//! Playback of a session recorded with `--stream`.
+
+use crate::{bucket::Bucket, json, App};
+use central_limit::sampling;
+use crossterm::event::KeyCode;
+use std::{fs, io, path::Path, time::Duration};
+
+const MIN_INTERVAL: Duration = Duration::from_millis(30);
+const MAX_INTERVAL: Duration = Duration::from_secs(8);
+
+/// How much slower or faster than recorded a recording with timestamps can
+/// be played.
+const MIN_SPEED: f64 = 1.0 / 16.0;
+const MAX_SPEED: f64 = 16.0;
+
+/// One tick event's histogram, and the parameters it was drawn with.
+pub struct Frame {
+    tick: u64,
+    /// Seconds into the recording, for those made since ticks had a time.
+    at: Option<f64>,
+    samples: usize,
+    r_max: i32,
+    /// The chance of a +1 step, which the model follows.
+    bias: f64,
+    data: Vec<Bucket>,
+}
+
+impl Frame {
+    /// Parses a tick event; other events, such as alerts, give `None`.
+    pub fn parse(line: &str) -> Result<Option<Frame>, String> {
+        let event = json::parse(line)?;
+        if event.get("event").is_some() {
+            return Ok(None);
+        }
+        let field = |name: &str| event.get(name).ok_or(format!("missing field '{}'", name));
+
+        let buckets = field("buckets")?
+            .as_array()
+            .ok_or("'buckets' is not an array")?;
+        let counts = field("counts")?
+            .as_array()
+            .ok_or("'counts' is not an array")?;
+        if buckets.len() != counts.len() {
+            return Err("'buckets' and 'counts' differ in length".to_string());
+        }
+
+        let data = buckets
+            .iter()
+            .zip(counts)
+            .map(|(label, count)| {
+                let label = label.as_str().ok_or("bucket label is not a string")?;
+                Ok(Bucket {
+                    x: label
+                        .parse()
+                        .map_err(|_| format!("bucket label '{}' is not a number", label))?,
+                    label: label.to_string(),
+                    count: count.as_u64().ok_or("bucket count is not a count")?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let samples = field("samples")?
+            .as_u64()
+            .ok_or("'samples' is not a count")? as usize;
+        let r_max = field("r_max")?.as_f64().ok_or("'r_max' is not a number")? as i32;
+        App::check_params(samples, r_max)?;
+        let bias = match event.get("bias") {
+            Some(bias) => bias.as_f64().ok_or("'bias' is not a number")?,
+            None => sampling::FAIR,
+        };
+        if !(bias > 0.0 && bias < 1.0) {
+            return Err(format!("'bias' must be between 0 and 1, not {}", bias));
+        }
+
+        let at = match event.get("at") {
+            Some(at) => Some(at.as_f64().ok_or("'at' is not a number")?),
+            None => None,
+        };
+
+        Ok(Some(Frame {
+            tick: field("tick")?.as_u64().ok_or("'tick' is not a count")?,
+            at,
+            samples,
+            r_max,
+            bias,
+            data,
+        }))
+    }
+
+    /// Puts the tick's histogram and parameters in place of the app's.
+    pub fn show(&self, app: &mut App) {
+        if app.tick != self.tick {
+            app.remember_previous();
+        }
+        app.tick = self.tick;
+        // A recording doesn't say what earlier ticks drew, so they're taken
+        // to have drawn as many as this one.
+        app.drawn = self.tick.saturating_mul(self.samples as u64);
+        app.b_count = self.samples;
+        app.r_max = self.r_max;
+        app.bias = self.bias;
+        app.data.clone_from(&self.data);
+        app.refresh();
+    }
+}
+
+/// Feeds recorded frames to the app in place of `App::on_tick`.
+pub struct ReplaySource {
+    frames: Vec<Frame>,
+    /// Why the frames stop short of the end of the file, if they do.
+    error: Option<String>,
+    position: usize,
+    paused: bool,
+    /// Time between frames, for a recording without timestamps.
+    interval: Duration,
+    /// Whether every frame has a timestamp, so they're shown as far apart
+    /// as they were recorded.
+    timed: bool,
+    /// How many times faster than recorded a timed recording is played.
+    speed: f64,
+}
+
+impl ReplaySource {
+    /// Reads the whole recording up front. A corrupt or truncated line ends the
+    /// playable frames; the problem is reported once playback reaches it.
+    pub fn open(path: &Path, interval: Duration) -> io::Result<ReplaySource> {
+        let contents = fs::read_to_string(path)?;
+
+        let mut frames = vec![];
+        let mut error = None;
+        for (n, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match Frame::parse(line) {
+                Ok(frame) => frames.extend(frame),
+                Err(err) => {
+                    error = Some(format!("line {}: {}", n + 1, err));
+                    break;
+                }
+            }
+        }
+
+        if frames.is_empty() && error.is_none() {
+            error = Some("the file contains no ticks".to_string());
+        }
+
+        let timed = frames.iter().all(|frame| frame.at.is_some());
+        Ok(ReplaySource {
+            frames,
+            error,
+            position: 0,
+            paused: false,
+            interval,
+            timed,
+            speed: 1.0,
+        })
+    }
+
+    /// How long until the next frame: as long after the one shown as it was
+    /// recorded, at the playback speed, within the bounds of `<` and `>`, or
+    /// the interval for a recording without timestamps.
+    pub fn interval(&self) -> Duration {
+        let shown = self
+            .position
+            .checked_sub(1)
+            .and_then(|i| self.frames.get(i));
+        let next = self.frames.get(self.position);
+        match (shown.and_then(|f| f.at), next.and_then(|f| f.at)) {
+            (Some(from), Some(to)) if self.timed => {
+                let apart = ((to - from) / self.speed).clamp(0.0, MAX_INTERVAL.as_secs_f64());
+                Duration::from_secs_f64(apart).max(MIN_INTERVAL)
+            }
+            _ => self.interval,
+        }
+    }
+
+    /// Advances to the next frame unless paused or at the end.
+    pub fn step(&mut self, app: &mut App) {
+        if !self.paused && self.position < self.frames.len() {
+            self.position += 1;
+        }
+        self.show(app);
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode, app: &mut App) {
+        match code {
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Char('<') if self.timed => self.speed = (self.speed / 2.0).max(MIN_SPEED),
+            KeyCode::Char('>') if self.timed => self.speed = (self.speed * 2.0).min(MAX_SPEED),
+            KeyCode::Char('<') => self.interval = (self.interval * 2).min(MAX_INTERVAL),
+            KeyCode::Char('>') => self.interval = (self.interval / 2).max(MIN_INTERVAL),
+            KeyCode::Left => self.position = self.position.saturating_sub(1).max(1),
+            KeyCode::Right => self.position = (self.position + 1).min(self.frames.len()),
+            _ => return,
+        }
+        self.show(app);
+    }
+
+    fn show(&self, app: &mut App) {
+        if let Some(frame) = self
+            .position
+            .checked_sub(1)
+            .and_then(|i| self.frames.get(i))
+        {
+            frame.show(app);
+        }
+
+        let total = self.frames.last().map_or(0, |frame| frame.tick);
+        let strings = &app.strings;
+        let state = match &self.error {
+            Some(err) if self.position == self.frames.len() => {
+                strings.fill("replay.stopped", &[("error", err)])
+            }
+            _ if self.position == self.frames.len() => strings.get("replay.finished").to_string(),
+            _ if self.paused => strings.get("replay.paused").to_string(),
+            _ if self.timed => strings.fill("replay.recorded_speed", &[("speed", &self.speed)]),
+            _ => strings.fill("replay.speed", &[("ms", &self.interval.as_millis())]),
+        };
+        let status = strings.fill(
+            "replay.status",
+            &[("tick", &app.tick), ("total", &total), ("state", &state)],
+        );
+        app.replay_status = Some(status);
+    }
+}
@@ -0,0 +1,215 @@
+//! The random number generators a simulation can draw from, for comparing
+//! how much generator quality matters.
+
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Generator {
+    /// rand's `StdRng`, the default.
+    Std,
+    Pcg32,
+    Xoshiro256PlusPlus,
+    /// A linear congruential generator with a period of 256, which is
+    /// deliberately far too short.
+    BadLcg,
+}
+
+impl Generator {
+    pub const ALL: [Generator; 4] = [
+        Generator::Std,
+        Generator::Pcg32,
+        Generator::Xoshiro256PlusPlus,
+        Generator::BadLcg,
+    ];
+
+    pub fn parse(name: &str) -> Option<Generator> {
+        Generator::ALL
+            .into_iter()
+            .find(|generator| generator.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Generator::Std => "std",
+            Generator::Pcg32 => "pcg32",
+            Generator::Xoshiro256PlusPlus => "xoshiro256++",
+            Generator::BadLcg => "bad-lcg",
+        }
+    }
+
+    /// Whether the generator is broken on purpose, to show what that does.
+    pub fn flawed(self) -> bool {
+        self == Generator::BadLcg
+    }
+
+    /// The generator seeded from `seed`. Every generator expands the seed
+    /// the same way, through `SeedableRng::seed_from_u64`.
+    pub fn seeded(self, seed: u64) -> Box<dyn RngCore + Send> {
+        match self {
+            Generator::Std => Box::new(StdRng::seed_from_u64(seed)),
+            Generator::Pcg32 => Box::new(Pcg32::seed_from_u64(seed)),
+            Generator::Xoshiro256PlusPlus => Box::new(Xoshiro256PlusPlus::seed_from_u64(seed)),
+            Generator::BadLcg => Box::new(BadLcg::seed_from_u64(seed)),
+        }
+    }
+}
+
+/// A seed for the `index`th of several independent runs from one `seed`.
+pub fn derive_seed(seed: u64, index: u64) -> u64 {
+    // SplitMix64's finalizer spreads nearby inputs across the whole range.
+    let mut z = seed ^ index.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Fills `dest` from `next_u32`, least significant byte first.
+fn fill_from_u32<R: RngCore>(rng: &mut R, dest: &mut [u8]) {
+    for chunk in dest.chunks_mut(4) {
+        let bytes = rng.next_u32().to_le_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
+
+/// PCG XSH RR 64/32, O'Neill's recommended 32-bit PCG.
+pub struct Pcg32 {
+    state: u64,
+    increment: u64,
+}
+
+impl Pcg32 {
+    const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(Pcg32::MULTIPLIER)
+            .wrapping_add(self.increment);
+    }
+}
+
+impl SeedableRng for Pcg32 {
+    type Seed = [u8; 16];
+
+    fn from_seed(seed: [u8; 16]) -> Pcg32 {
+        let [state, stream] = [&seed[..8], &seed[8..]]
+            .map(|half| u64::from_le_bytes(half.try_into().expect("8 bytes")));
+        // The increment must be odd.
+        let mut pcg = Pcg32 {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        pcg.state = state.wrapping_add(pcg.increment);
+        pcg.step();
+        pcg
+    }
+}
+
+impl RngCore for Pcg32 {
+    fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.step();
+        let rotate = (state >> 59) as u32;
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        xorshifted.rotate_right(rotate)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        u64::from(self.next_u32()) | (u64::from(self.next_u32()) << 32)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill_from_u32(self, dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Blackman and Vigna's xoshiro256++.
+pub struct Xoshiro256PlusPlus {
+    state: [u64; 4],
+}
+
+impl SeedableRng for Xoshiro256PlusPlus {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: [u8; 32]) -> Xoshiro256PlusPlus {
+        let mut state = [0; 4];
+        for (word, bytes) in state.iter_mut().zip(seed.chunks_exact(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().expect("8 bytes"));
+        }
+        // An all-zero state would only ever produce zeros.
+        if state == [0; 4] {
+            state[0] = 1;
+        }
+        Xoshiro256PlusPlus { state }
+    }
+}
+
+impl RngCore for Xoshiro256PlusPlus {
+    fn next_u32(&mut self) -> u32 {
+        // The upper bits are the stronger ones.
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill_from_u32(self, dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// `x ← 5x + 1 mod 256`: every output repeats after 256 draws, so a few
+/// thousand steps are already mostly repeats. Don't use it for anything.
+pub struct BadLcg {
+    state: u8,
+}
+
+impl SeedableRng for BadLcg {
+    type Seed = [u8; 1];
+
+    fn from_seed(seed: [u8; 1]) -> BadLcg {
+        BadLcg { state: seed[0] }
+    }
+}
+
+impl RngCore for BadLcg {
+    fn next_u32(&mut self) -> u32 {
+        self.state = self.state.wrapping_mul(5).wrapping_add(1);
+        // Spread the byte over the word, so the flaw is the period rather
+        // than a bias in the high bits.
+        u32::from_le_bytes([self.state; 4])
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        u64::from(self.next_u32()) | (u64::from(self.next_u32()) << 32)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill_from_u32(self, dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+//! `--startup-check`: a quick test of the generator before the run starts,
+//! so a broken one is caught before anyone reads much into its histogram.
+
+use central_limit::{
+    rng::{self, Generator},
+    sampling, stats,
+};
+use std::time::{Duration, Instant};
+
+/// Steps drawn, enough to catch a bad generator in a few milliseconds.
+const FLIPS: usize = 100_000;
+
+/// Either test failing below this is a broken generator, not bad luck.
+const THRESHOLD: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RngCheck {
+    pub frequency: f64,
+    pub runs: f64,
+    pub elapsed: Duration,
+}
+
+impl RngCheck {
+    /// Draws steps the way the simulation does, from a generator of its own
+    /// so the run's random sequence is left as it was.
+    pub fn run(generator: Generator, seed: u64) -> RngCheck {
+        let started = Instant::now();
+        let mut rng = generator.seeded(rng::derive_seed(seed, u64::MAX));
+        let flips = (0..FLIPS)
+            .map(|_| sampling::step(&mut rng) > 0)
+            .collect::<Vec<_>>();
+        RngCheck {
+            frequency: stats::frequency_test(&flips),
+            runs: stats::runs_test(&flips),
+            elapsed: started.elapsed(),
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.frequency >= THRESHOLD && self.runs >= THRESHOLD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_flawed_generator_fails() {
+        for generator in Generator::ALL {
+            for seed in [0, 42, u64::MAX] {
+                let check = RngCheck::run(generator, seed);
+                assert_eq!(check.passed(), !generator.flawed(), "{:?}", check);
+            }
+        }
+    }
+}
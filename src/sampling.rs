@@ -0,0 +1,538 @@
+//! Drawing samples: each one is the sum of a number of ±1 steps, fair unless
+//! they're given a bias, drawn step by step or, for long walks, from the
+//! sums' normal approximation.
+
+use rand::Rng;
+
+/// The mean and variance of `step`, which `balance::Balance` checks the
+/// steps drawn against.
+pub const STEP_MEAN: f64 = 0.0;
+pub const STEP_VARIANCE: f64 = 1.0;
+
+/// The chance of a +1 step from a fair coin, which `step` draws with.
+pub const FAIR: f64 = 0.5;
+
+/// Walks of this many steps or more are drawn from the normal approximation
+/// by default: step by step, a tick of them takes too long to draw.
+pub const EXACT_BELOW: u32 = 1_000;
+
+/// How the sums are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Regime {
+    /// Step by step, the exact distribution of the sums.
+    Exact,
+    /// From the normal approximation, in constant time per sum.
+    Normal,
+}
+
+impl Regime {
+    /// The regime for `n_steps`-step walks, drawn exactly below `exact_below`
+    /// steps.
+    pub fn for_steps(n_steps: u32, exact_below: u32) -> Regime {
+        match n_steps < exact_below {
+            true => Regime::Exact,
+            false => Regime::Normal,
+        }
+    }
+
+    /// The name in exports.
+    pub fn name(self) -> &'static str {
+        match self {
+            Regime::Exact => "exact",
+            Regime::Normal => "normal",
+        }
+    }
+}
+
+/// One fair ±1 step.
+pub fn step<R: Rng + ?Sized>(rng: &mut R) -> i32 {
+    if rng.gen_range(0..10) < 5 {
+        -1
+    } else {
+        1
+    }
+}
+
+/// A +1 step with probability `p`, and -1 otherwise. A fair one is drawn as
+/// `step` draws it, so a bias of `FAIR` draws the same as none.
+pub fn biased_step<R: Rng + ?Sized>(rng: &mut R, p: f64) -> i32 {
+    if p == FAIR {
+        return step(rng);
+    }
+    if rng.gen::<f64>() < p {
+        1
+    } else {
+        -1
+    }
+}
+
+/// Where the steps of a walk come from, for walks of steps other than ±1,
+/// such as a `Die`'s rolls or a three-point step that can also stay put.
+/// `walk_sums` draws the sums of any source's walks; `Simulation` counts a
+/// `Coin`'s only, since its buckets are the sums two apart that ±1 steps
+/// reach.
+pub trait StepSource {
+    fn step<R: Rng + ?Sized>(&mut self, rng: &mut R) -> i32;
+
+    /// The mean of a step, for the normal curve the sums tend to.
+    fn mean(&self) -> f64;
+
+    fn variance(&self) -> f64;
+}
+
+/// A ±1 step that's +1 with probability `p`, as `biased_step` draws it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coin(pub f64);
+
+impl StepSource for Coin {
+    fn step<R: Rng + ?Sized>(&mut self, rng: &mut R) -> i32 {
+        biased_step(rng, self.0)
+    }
+
+    fn mean(&self) -> f64 {
+        2.0 * self.0 - 1.0
+    }
+
+    fn variance(&self) -> f64 {
+        4.0 * self.0 * (1.0 - self.0)
+    }
+}
+
+/// A fair die's roll, of its `n` faces numbered 1 to `n`; `n` is at least 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Die(pub u32);
+
+impl StepSource for Die {
+    fn step<R: Rng + ?Sized>(&mut self, rng: &mut R) -> i32 {
+        rng.gen_range(1..=self.0 as i32)
+    }
+
+    fn mean(&self) -> f64 {
+        (f64::from(self.0) + 1.0) / 2.0
+    }
+
+    fn variance(&self) -> f64 {
+        (f64::from(self.0).powi(2) - 1.0) / 12.0
+    }
+}
+
+/// `n_samples` sums of `n_steps` steps drawn uniformly from [0, 1) each,
+/// steps that aren't whole numbers, so aren't a `StepSource`'s.
+pub fn uniform_sums<R: Rng + ?Sized>(rng: &mut R, n_samples: usize, n_steps: u32) -> Vec<f64> {
+    (0..n_samples)
+        .map(|_| (0..n_steps).map(|_| rng.gen::<f64>()).sum())
+        .collect()
+}
+
+/// `n_samples` sums of `n_steps` steps from `source` each. With a `Coin`,
+/// the same sums as `generate_biased_sums` draws.
+pub fn walk_sums<S: StepSource, R: Rng + ?Sized>(
+    source: &mut S,
+    rng: &mut R,
+    n_samples: usize,
+    n_steps: u32,
+) -> Vec<i32> {
+    (0..n_samples)
+        .map(|_| (0..n_steps).map(|_| source.step(rng)).sum())
+        .collect()
+}
+
+/// The running sum after each of `n_steps` steps, starting from 0.
+pub fn walk<R: Rng + ?Sized>(rng: &mut R, n_steps: u32) -> Vec<i32> {
+    biased_walk(rng, n_steps, FAIR)
+}
+
+/// Like `walk`, with steps that are +1 with probability `p`.
+pub fn biased_walk<R: Rng + ?Sized>(rng: &mut R, n_steps: u32, p: f64) -> Vec<i32> {
+    let mut sum = 0;
+    let mut path = Vec::with_capacity(n_steps as usize + 1);
+    path.push(sum);
+    for _ in 0..n_steps {
+        sum += biased_step(rng, p);
+        path.push(sum);
+    }
+    path
+}
+
+/// `n_samples` sums of `n_steps` steps each. Every sum lies in
+/// `-n_steps..=n_steps` and has the parity of `n_steps`.
+pub fn generate_sums<R: Rng + ?Sized>(rng: &mut R, n_samples: usize, n_steps: u32) -> Vec<i32> {
+    generate_biased_sums(rng, n_samples, n_steps, FAIR)
+}
+
+/// Like `generate_sums`, with steps that are +1 with probability `p`.
+pub fn generate_biased_sums<R: Rng + ?Sized>(
+    rng: &mut R,
+    n_samples: usize,
+    n_steps: u32,
+    p: f64,
+) -> Vec<i32> {
+    (0..n_samples)
+        .map(|_| (0..n_steps).map(|_| biased_step(rng, p)).sum())
+        .collect()
+}
+
+/// Like `generate_sums`, drawing each sum from the normal approximation: a
+/// normal draw with the sums' standard deviation, rounded to the nearest sum
+/// of the right parity, so each sum takes the width-2 slice around it that
+/// the normal model gives it.
+pub fn approximate_sums<R: Rng + ?Sized>(rng: &mut R, n_samples: usize, n_steps: u32) -> Vec<i32> {
+    approximate_biased_sums(rng, n_samples, n_steps, FAIR)
+}
+
+/// Like `approximate_sums`, with steps that are +1 with probability `p`.
+pub fn approximate_biased_sums<R: Rng + ?Sized>(
+    rng: &mut R,
+    n_samples: usize,
+    n_steps: u32,
+    p: f64,
+) -> Vec<i32> {
+    let n = n_steps as f64;
+    (0..n_samples)
+        .map(|_| {
+            let heads = match p == FAIR {
+                true => (standard_normal(rng) * n.sqrt() + n) / 2.0,
+                false => standard_normal(rng) * (n * p * (1.0 - p)).sqrt() + n * p,
+            };
+            2 * heads.round().clamp(0.0, n) as i32 - n_steps as i32
+        })
+        .collect()
+}
+
+/// A standard normal draw, by the Box-Muller transform.
+pub fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    // 1 - u is in (0, 1], so its log is finite.
+    let u = 1.0 - rng.gen::<f64>();
+    let v = rng.gen::<f64>();
+    (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+}
+
+/// Above this many expected successes, a binomial draw is made by `btpe`
+/// rather than counted out, which would start from a probability too small
+/// for an f64 and take ever longer.
+const INVERT_UP_TO: f64 = 30.0;
+
+/// `n` samples shared among buckets with `probabilities`, as a run of
+/// binomial draws: each bucket takes its share of what the buckets before it
+/// left, its probability over theirs still to come.
+pub fn multinomial<R: Rng + ?Sized>(rng: &mut R, n: u64, probabilities: &[f64]) -> Vec<u64> {
+    // What's still to come is summed from the far end, rather than taken
+    // away from 1, so the last buckets' shares don't drown in rounding.
+    let mut rest = probabilities
+        .iter()
+        .rev()
+        .scan(0.0, |sum, probability| {
+            *sum += probability;
+            Some(*sum)
+        })
+        .collect::<Vec<_>>();
+    rest.reverse();
+    let mut left = n;
+    probabilities
+        .iter()
+        .zip(rest)
+        .map(|(probability, rest)| {
+            let count = match rest > 0.0 {
+                true => binomial(rng, left, (probability / rest).clamp(0.0, 1.0)),
+                false => 0,
+            };
+            left -= count;
+            count
+        })
+        .collect()
+}
+
+/// One Binomial(n, p) draw: counted out by inverting its CDF when few
+/// successes are expected, and by `btpe` otherwise. Both are exact, so the
+/// tails come out as thin as they should however many samples there are.
+pub fn binomial<R: Rng + ?Sized>(rng: &mut R, n: u64, p: f64) -> u64 {
+    // Counting the rarer outcome keeps the inversion short, and is the case
+    // `btpe` is set up for.
+    if p > 0.5 {
+        return n - binomial(rng, n, 1.0 - p);
+    }
+    let trials = n as f64;
+    if trials * p > INVERT_UP_TO {
+        return btpe(rng, n, p);
+    }
+    if p <= 0.0 {
+        return 0;
+    }
+    let u = rng.gen::<f64>();
+    let ratio = p / (1.0 - p);
+    let mut k = 0;
+    let mut pmf = (1.0 - p).powf(trials);
+    let mut cdf = pmf;
+    while cdf < u && k < n {
+        pmf *= ratio * (n - k) as f64 / (k + 1) as f64;
+        k += 1;
+        cdf += pmf;
+    }
+    k
+}
+
+/// A Binomial(n, p) draw for p at most a half and more than `INVERT_UP_TO`
+/// successes expected, in constant time, by Kachitvichyanukul and
+/// Schmeiser's BTPE (1988). A point is drawn under a hat over the pmf, made
+/// of a triangle at the mode, a parallelogram either side of it and
+/// exponential tails. It's then accepted against the pmf's ratio to the
+/// mode's: multiplied out near the mode, and further out bounded, or failing
+/// that computed, from Stirling's series.
+fn btpe<R: Rng + ?Sized>(rng: &mut R, n: u64, p: f64) -> u64 {
+    let (trials, q) = (n as f64, 1.0 - p);
+    let npq = trials * p * q;
+    let fm = trials * p + p;
+    let m = fm.floor();
+    let p1 = (2.195 * npq.sqrt() - 4.6 * q).floor() + 0.5;
+    let xm = m + 0.5;
+    let (xl, xr) = (xm - p1, xm + p1);
+    let c = 0.134 + 20.5 / (15.3 + m);
+    let a = (fm - xl) / (fm - xl * p);
+    let lambda_l = a * (1.0 + a / 2.0);
+    let a = (xr - fm) / (xr * q);
+    let lambda_r = a * (1.0 + a / 2.0);
+    let p2 = p1 * (1.0 + 2.0 * c);
+    let p3 = p2 + c / lambda_l;
+    let p4 = p3 + c / lambda_r;
+
+    loop {
+        let u = rng.gen::<f64>() * p4;
+        let mut v = rng.gen::<f64>();
+        // The triangle lies under the pmf, so its points need no check.
+        if u <= p1 {
+            return (xm - p1 * v + u).floor() as u64;
+        }
+        let y = if u <= p2 {
+            let x = xl + (u - p1) / c;
+            v = v * c + 1.0 - (m - x + 0.5).abs() / p1;
+            if v > 1.0 {
+                continue;
+            }
+            x.floor()
+        } else if u <= p3 {
+            let y = (xl + v.ln() / lambda_l).floor();
+            if y < 0.0 || v == 0.0 {
+                continue;
+            }
+            v *= (u - p2) * lambda_l;
+            y
+        } else {
+            let y = (xr - v.ln() / lambda_r).floor();
+            if y > trials || v == 0.0 {
+                continue;
+            }
+            v *= (u - p3) * lambda_r;
+            y
+        };
+
+        let k = (y - m).abs();
+        if k <= 20.0 || k >= npq / 2.0 - 1.0 {
+            // f(y) / f(m), a factor for each count between them.
+            let s = p / q;
+            let a = s * (trials + 1.0);
+            let mut ratio = 1.0;
+            let mut i = m.min(y) + 1.0;
+            while i <= m.max(y) {
+                match m < y {
+                    true => ratio *= a / i - s,
+                    false => ratio /= a / i - s,
+                }
+                i += 1.0;
+            }
+            if v <= ratio {
+                return y as u64;
+            }
+            continue;
+        }
+
+        // Squeezes on the log of the ratio, which settle most points.
+        let rho = (k / npq) * ((k * (k / 3.0 + 0.625) + 1.0 / 6.0) / npq + 0.5);
+        let t = -k * k / (2.0 * npq);
+        let log_v = v.ln();
+        if log_v < t - rho {
+            return y as u64;
+        }
+        if log_v > t + rho {
+            continue;
+        }
+        let x1 = y + 1.0;
+        let f1 = m + 1.0;
+        let z = trials + 1.0 - m;
+        let w = trials - y + 1.0;
+        let log_ratio = xm * (f1 / x1).ln()
+            + (trials - m + 0.5) * (z / w).ln()
+            + (y - m) * (w * p / (x1 * q)).ln()
+            + stirling(f1)
+            + stirling(z)
+            + stirling(x1)
+            + stirling(w);
+        if log_v <= log_ratio {
+            return y as u64;
+        }
+    }
+}
+
+/// The first terms of Stirling's series for ln Γ(x) past its leading ones,
+/// 1/12x - 1/360x³ + 1/1260x⁵ - 1/1680x⁷ + 1/1188x⁹.
+fn stirling(x: f64) -> f64 {
+    let x2 = x * x;
+    (13860.0 - (462.0 - (132.0 - (99.0 - 140.0 / x2) / x2) / x2) / x2) / x / 166320.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Below `ALPHA`, a test's counts don't look like the model's.
+    const ALPHA: f64 = 1e-4;
+
+    fn draws(n: u64, p: f64, count: usize) -> Vec<u64> {
+        let mut rng = StdRng::seed_from_u64(n ^ p.to_bits());
+        (0..count).map(|_| binomial(&mut rng, n, p)).collect()
+    }
+
+    #[test]
+    fn binomial_draws_follow_the_pmf() {
+        // Either side of INVERT_UP_TO, the flip for p over a half, and
+        // enough spread for BTPE's squeezes and Stirling bound to be used.
+        for (n, p) in [
+            (100, 0.25),
+            (100, 0.35),
+            (1_000, 0.5),
+            (1_000, 0.8),
+            (50_000, 0.1),
+        ] {
+            let mut counts = vec![0; n as usize + 1];
+            for draw in draws(n, p, 200_000) {
+                counts[draw as usize] += 1;
+            }
+            let probabilities = (0..=n as u32)
+                .map(|k| stats::biased_binomial_pmf(n as u32, k, p))
+                .collect::<Vec<_>>();
+            let test = stats::chi_square(&counts, &probabilities);
+            assert!(
+                test.p_value > ALPHA,
+                "Binomial({}, {}): chi-square {:.1} on {} dof",
+                n,
+                p,
+                test.statistic,
+                test.dof
+            );
+        }
+    }
+
+    #[test]
+    fn binomial_draws_have_its_moments_for_huge_n() {
+        let (n, p) = (1_000_000_000_000, 0.3);
+        let draws = draws(n, p, 20_000);
+        let count = draws.len() as f64;
+        let mean = draws.iter().map(|&k| k as f64).sum::<f64>() / count;
+        let variance = draws
+            .iter()
+            .map(|&k| (k as f64 - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        let expected = n as f64 * p * (1.0 - p);
+        let z = (mean - n as f64 * p) / (expected / count).sqrt();
+        assert!(
+            z.abs() < 4.0,
+            "mean {} is {:.1} standard errors out",
+            mean,
+            z
+        );
+        assert!(
+            (variance / expected - 1.0).abs() < 0.05,
+            "variance {}",
+            variance
+        );
+    }
+
+    #[test]
+    fn binomial_draws_stay_in_range() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(binomial(&mut rng, 1_000, 0.0), 0);
+        assert_eq!(binomial(&mut rng, 1_000, 1.0), 1_000);
+        assert!((0..1_000).all(|_| binomial(&mut rng, 61, 0.5) <= 61));
+    }
+
+    /// Whether `sum` is a sum that `n_steps` ±1 steps can reach.
+    fn reachable(sum: i32, n_steps: u32) -> bool {
+        sum.unsigned_abs() <= n_steps && (sum + n_steps as i32) % 2 == 0
+    }
+
+    #[test]
+    fn every_sum_drawn_is_reachable() {
+        let mut params = StdRng::seed_from_u64(129);
+        for _ in 0..200 {
+            let n_steps = params.gen_range(0..200);
+            let p = params.gen_range(0.0..=1.0);
+            let mut rng = StdRng::seed_from_u64(params.gen());
+            let sums = generate_sums(&mut rng, 50, n_steps)
+                .into_iter()
+                .chain(generate_biased_sums(&mut rng, 50, n_steps, p))
+                .chain(approximate_sums(&mut rng, 50, n_steps))
+                .chain(approximate_biased_sums(&mut rng, 50, n_steps, p));
+            for sum in sums {
+                assert!(
+                    reachable(sum, n_steps),
+                    "{} of {} steps, p {}",
+                    sum,
+                    n_steps,
+                    p
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sums_are_drawn_as_walks_are() {
+        // Step by step from the same seed, the sums are where the walks end,
+        // and those the loop that filled the sums in by index drew.
+        for n_steps in [1, 2, 19, 100] {
+            let seeded = || StdRng::seed_from_u64(u64::from(n_steps));
+            let mut rng = seeded();
+            let by_step = (0..500)
+                .map(|_| (0..n_steps).map(|_| step(&mut rng)).sum::<i32>())
+                .collect::<Vec<_>>();
+            assert_eq!(generate_sums(&mut seeded(), 500, n_steps), by_step);
+            let mut rng = seeded();
+            let ends = (0..500)
+                .map(|_| *walk(&mut rng, n_steps).last().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(ends, by_step);
+            let coin = walk_sums(&mut Coin(FAIR), &mut seeded(), 500, n_steps);
+            assert_eq!(coin, by_step);
+        }
+    }
+
+    #[test]
+    fn a_dies_rolls_have_its_moments() {
+        let mut rng = StdRng::seed_from_u64(171);
+        for n in [1, 2, 6, 20] {
+            let mut die = Die(n);
+            let rolls = (0..100_000).map(|_| die.step(&mut rng)).collect::<Vec<_>>();
+            assert!(rolls.iter().all(|&roll| (1..=n as i32).contains(&roll)));
+            let mean = rolls.iter().map(|&roll| f64::from(roll)).sum::<f64>() / 1e5;
+            let variance = rolls
+                .iter()
+                .map(|&roll| (f64::from(roll) - mean).powi(2))
+                .sum::<f64>()
+                / 1e5;
+            // Well within 5 standard errors of each.
+            let sd = die.variance().sqrt();
+            assert!((mean - die.mean()).abs() <= 5.0 * sd / 1e5f64.sqrt() + 1e-12);
+            assert!((variance - die.variance()).abs() <= 0.05 * die.variance() + 1e-12);
+        }
+    }
+
+    #[test]
+    fn multinomial_shares_out_every_sample() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let probabilities = stats::Model::Binomial.probabilities(40);
+        let counts = multinomial(&mut rng, 100_000_000, &probabilities);
+        assert_eq!(counts.len(), probabilities.len());
+        assert_eq!(stats::total(counts.iter().copied()), 100_000_000);
+    }
+}
@@ -0,0 +1,118 @@
+//! `--start-at`: opening the TUI straight away but holding the first tick
+//! until a wall-clock time, with a countdown until then, so a demo starts
+//! when the class is ready for it.
+
+use crate::datetime::DateTime;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartAt {
+    /// `14:05`, local time today.
+    Clock { hour: u32, minute: u32 },
+    /// An RFC 3339 time, as seconds since the Unix epoch.
+    Unix(i64),
+}
+
+impl StartAt {
+    pub fn parse(text: &str) -> Result<StartAt, String> {
+        let clock = text
+            .split_once(':')
+            .filter(|(_, minute)| minute.len() == 2)
+            .and_then(|(hour, minute)| Some((digits(hour)?, digits(minute)?)))
+            .filter(|(hour, minute)| *hour < 24 && *minute < 60);
+        if let Some((hour, minute)) = clock {
+            return Ok(StartAt::Clock { hour, minute });
+        }
+        rfc3339(text).map(StartAt::Unix).ok_or_else(|| {
+            format!(
+                "'{}' is not a time: expected HH:MM or RFC 3339, such as 2024-06-01T14:05:00+02:00",
+                text
+            )
+        })
+    }
+
+    /// The moment it names, taking a clock time as today's, even if it has
+    /// already gone by.
+    pub fn resolve(&self, now: SystemTime) -> SystemTime {
+        let secs = match *self {
+            StartAt::Clock { hour, minute } => {
+                let now = now
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs() as i64);
+                let local = DateTime::local(now);
+                let today = (local.hour * 3600 + local.minute * 60 + local.second) as i64;
+                now - today + (hour * 3600 + minute * 60) as i64
+            }
+            StartAt::Unix(secs) => secs,
+        };
+        UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+    }
+}
+
+/// A number written only in digits, with no sign.
+fn digits(text: &str) -> Option<u32> {
+    match !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) {
+        true => text.parse().ok(),
+        false => None,
+    }
+}
+
+/// `2024-06-01T14:05:00Z`, with an optional fraction of a second, which is
+/// dropped, and `Z` or an offset such as `+02:00`.
+fn rfc3339(text: &str) -> Option<i64> {
+    let (date, rest) = text.split_at_checked(10)?;
+    let mut fields = date.split('-');
+    let (year, month, day) = (fields.next()?, fields.next()?, fields.next()?);
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let rest = rest.strip_prefix(['T', 't', ' '])?;
+    let (time, mut zone) = rest.split_at_checked(8)?;
+    let mut fields = time.split(':');
+    let (hour, minute, second) = (fields.next()?, fields.next()?, fields.next()?);
+    if hour.len() != 2 || minute.len() != 2 || second.len() != 2 {
+        return None;
+    }
+    if let Some(fraction) = zone.strip_prefix('.') {
+        let end = fraction
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(fraction.len());
+        if end == 0 {
+            return None;
+        }
+        zone = &fraction[end..];
+    }
+    let offset = match zone {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match zone.get(..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let (hours, minutes) = zone[1..].split_once(':')?;
+            if hours.len() != 2 || minutes.len() != 2 {
+                return None;
+            }
+            sign * (digits(hours)? * 3600 + digits(minutes)? * 60) as i64
+        }
+    };
+    let time = DateTime {
+        year: digits(year)? as i32,
+        month: digits(month)?,
+        day: digits(day)?,
+        hour: digits(hour)?,
+        minute: digits(minute)?,
+        second: digits(second)?,
+    };
+    if time.hour > 23 || time.minute > 59 || time.second > 59 {
+        return None;
+    }
+    let secs = time.unix_utc();
+    // A day past the end of the month, such as 02-30, comes back different.
+    let same = DateTime::utc(secs);
+    if (same.year, same.month, same.day) != (time.year, time.month, time.day) {
+        return None;
+    }
+    Some(secs - offset)
+}
@@ -0,0 +1,148 @@
+//! `--seeds`: the same run drawn from several seeds at once, to show how much
+//! of the histogram's shape is Monte Carlo noise. Each bucket's spread across
+//! the seeds is drawn as a band around its median, or one seed's run can be
+//! shown on its own.
+
+use crate::CHUNK;
+use central_limit::{
+    bucket::{Aggregate, Bucket},
+    rng::{self, Generator},
+    simulation::Simulation,
+};
+use std::thread;
+
+/// Where the runs are across the seeds in one bucket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spread {
+    pub min: u64,
+    pub median: f64,
+    pub max: u64,
+}
+
+#[derive(Debug)]
+pub struct SeedRuns {
+    /// The seeds past the first and their runs; the first seed is the app's
+    /// own run.
+    runs: Vec<(u64, Simulation)>,
+    /// The extra run drawn instead of the band, if one is picked with `r`.
+    shown: Option<usize>,
+}
+
+impl SeedRuns {
+    /// `count` seeds in all, counting `seed` itself. The others are derived
+    /// from it, so the same `--seed` gives the same spread.
+    pub fn new(
+        seed: u64,
+        count: usize,
+        steps: i32,
+        generator: Generator,
+        exact_below: u32,
+        aggregate: Aggregate,
+    ) -> Result<SeedRuns, String> {
+        let runs = (1..count as u64)
+            .map(|i| {
+                let seed = rng::derive_seed(seed, i);
+                let mut sim = Simulation::with_generator(steps, seed, generator)?;
+                sim.set_exact_below(exact_below);
+                sim.set_aggregate(aggregate)?;
+                Ok((seed, sim))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(SeedRuns { runs, shown: None })
+    }
+
+    /// Draws a tick of `samples` in every run, in parallel. The runs follow
+    /// the app's steps and whether it counts cumulatively.
+    pub fn tick(&mut self, samples: usize, steps: i32, cumulative: bool) {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk = self.runs.len().div_ceil(threads).max(1);
+        thread::scope(|scope| {
+            for runs in self.runs.chunks_mut(chunk) {
+                scope.spawn(move || {
+                    for (_, sim) in runs {
+                        if sim.steps() != steps {
+                            // Steps the app accepted are valid.
+                            let _ = sim.set_steps(steps);
+                        } else if !cumulative {
+                            sim.reset();
+                        }
+                        // In pieces, like the app's own ticks.
+                        let mut left = samples;
+                        while left > 0 {
+                            let n = left.min(CHUNK);
+                            sim.draw(n);
+                            left -= n;
+                        }
+                        sim.end_tick();
+                    }
+                });
+            }
+        });
+    }
+
+    /// Moves from the band to each extra run in turn, and back to the band.
+    pub fn cycle(&mut self) {
+        self.shown = match self.shown {
+            None if !self.runs.is_empty() => Some(0),
+            Some(i) if i + 1 < self.runs.len() => Some(i + 1),
+            _ => None,
+        };
+    }
+
+    /// The seed and simulation of the run shown instead of the band.
+    pub fn shown(&self) -> Option<(u64, &Simulation)> {
+        self.shown
+            .and_then(|i| self.runs.get(i))
+            .map(|(seed, sim)| (*seed, sim))
+    }
+
+    /// Every extra seed with its simulation.
+    pub fn runs(&self) -> impl Iterator<Item = (u64, &Simulation)> {
+        self.runs.iter().map(|(seed, sim)| (*seed, sim))
+    }
+
+    /// The KS distance of each extra run.
+    pub fn ks(&self) -> Vec<f64> {
+        self.runs.iter().map(|(_, sim)| sim.stats().ks).collect()
+    }
+
+    /// Seeds in all, counting the app's.
+    pub fn count(&self) -> usize {
+        self.runs.len() + 1
+    }
+
+    /// Each bucket's spread across `data`, the app's run, and the others, or
+    /// `None` while the runs' buckets differ from the app's, as they do
+    /// between a change of steps and the next tick.
+    pub fn spread(&self, data: &[Bucket]) -> Option<Vec<Spread>> {
+        if self
+            .runs
+            .iter()
+            .any(|(_, sim)| sim.counts_slice().len() != data.len())
+        {
+            return None;
+        }
+        let mut counts = Vec::with_capacity(self.count());
+        let spread = data
+            .iter()
+            .enumerate()
+            .map(|(i, bucket)| {
+                counts.clear();
+                counts.push(bucket.count);
+                counts.extend(self.runs.iter().map(|(_, sim)| sim.counts_slice()[i]));
+                counts.sort_unstable();
+                let middle = counts.len() / 2;
+                let median = match counts.len() % 2 {
+                    0 => (counts[middle - 1] + counts[middle]) as f64 / 2.0,
+                    _ => counts[middle] as f64,
+                };
+                Spread {
+                    min: counts[0],
+                    median,
+                    max: counts[counts.len() - 1],
+                }
+            })
+            .collect();
+        Some(spread)
+    }
+}
@@ -0,0 +1,200 @@
+//! `central-limit selftest`: statistical checks of the sampler and the
+//! statistics, for when a histogram looks wrong on someone's machine.
+
+use crate::stats::{self, Stats};
+use central_limit::{balance::Balance, rng::Generator, sampling};
+use std::io::{self, Write};
+
+pub const DEFAULT_SAMPLES: usize = 100_000;
+
+/// Chance of any false alarm across a full run; each check gets an equal
+/// share, so running more checks doesn't make spurious failures likelier.
+const ALPHA: f64 = 0.001;
+
+pub struct Config {
+    pub samples: usize,
+    pub steps: u32,
+    pub seed: u64,
+    pub generator: Generator,
+}
+
+struct Outcome {
+    /// Chance of a result at least this extreme if the sampler is fair, for
+    /// checks that are statistical rather than exact.
+    p_value: Option<f64>,
+    passed: bool,
+    detail: String,
+}
+
+type Check = fn(&Config) -> Outcome;
+
+const CHECKS: &[(&str, Check)] = &[
+    ("binomial", binomial),
+    ("symmetry", symmetry),
+    ("balance", balance),
+    ("walks", walks),
+    ("stats", batch_stats),
+];
+
+pub fn is_check(name: &str) -> bool {
+    CHECKS.iter().any(|(check, _)| *check == name)
+}
+
+/// Runs the checks named in `only`, or all of them, and returns whether every
+/// one passed.
+pub fn run<W: Write>(out: &mut W, config: &Config, only: &[String]) -> io::Result<bool> {
+    let checks = CHECKS
+        .iter()
+        .filter(|(name, _)| only.is_empty() || only.iter().any(|o| o == name))
+        .collect::<Vec<_>>();
+    let threshold = ALPHA / checks.len() as f64;
+    writeln!(
+        out,
+        "{} samples of {} steps from {} (seed {}), failing below p = {}",
+        config.samples,
+        config.steps,
+        config.generator.name(),
+        config.seed,
+        threshold
+    )?;
+
+    let mut failed = vec![];
+    for (name, check) in checks {
+        let mut outcome = check(config);
+        if let Some(p) = outcome.p_value {
+            outcome.passed = p >= threshold;
+            outcome.detail += &format!(", p = {:.4}", p);
+        }
+        let verdict = if outcome.passed { "PASS" } else { "FAIL" };
+        writeln!(out, "{} {:<9} {}", verdict, name, outcome.detail)?;
+        if !outcome.passed {
+            failed.push(*name);
+        }
+    }
+
+    if !failed.is_empty() {
+        writeln!(
+            out,
+            "\n{} of the checks failed: {}. A fair sampler fails this rarely;\n\
+             run again with another --seed, and report it if the failure repeats.",
+            failed.len(),
+            failed.join(", ")
+        )?;
+    }
+    out.flush()?;
+    Ok(failed.is_empty())
+}
+
+/// Sums bucketed by number of +1 steps, from 0 to `steps`.
+fn heads(sums: impl IntoIterator<Item = i32>, steps: u32) -> Vec<u64> {
+    let mut counts = vec![0; steps as usize + 1];
+    for sum in sums {
+        counts[((sum + steps as i32) / 2) as usize] += 1;
+    }
+    counts
+}
+
+fn against_binomial(counts: &[u64], steps: u32) -> Outcome {
+    let test = stats::binomial_chi_square(counts, steps);
+    Outcome {
+        p_value: Some(test.p_value),
+        passed: true,
+        detail: format!("chi-square {:.1} with {} dof", test.statistic, test.dof),
+    }
+}
+
+/// The sums follow the binomial distribution the steps imply.
+fn binomial(config: &Config) -> Outcome {
+    let mut rng = config.generator.seeded(config.seed);
+    let sums = sampling::generate_sums(&mut rng, config.samples, config.steps);
+    against_binomial(&heads(sums, config.steps), config.steps)
+}
+
+/// Positive and negative sums are equally likely.
+fn symmetry(config: &Config) -> Outcome {
+    let mut rng = config.generator.seeded(config.seed);
+    let sums = sampling::generate_sums(&mut rng, config.samples, config.steps);
+    let above = sums.iter().filter(|sum| **sum > 0).count() as f64;
+    let below = sums.iter().filter(|sum| **sum < 0).count() as f64;
+    let z = (above - below) / (above + below).max(1.0).sqrt();
+    Outcome {
+        p_value: Some(2.0 * stats::normal_cdf(-z.abs())),
+        passed: true,
+        detail: format!("{} above zero, {} below, z = {:.2}", above, below, z),
+    }
+}
+
+/// The steps are +1 as often as they're meant to be, as the TUI's drift
+/// warning checks while running.
+fn balance(config: &Config) -> Outcome {
+    let mut rng = config.generator.seeded(config.seed);
+    let sums = sampling::generate_sums(&mut rng, config.samples, config.steps);
+    let mut balance = Balance::default();
+    for sum in sums {
+        balance.add(sum, config.steps as i32);
+    }
+    let z = balance.z();
+    Outcome {
+        p_value: Some(2.0 * stats::normal_cdf(-z.abs())),
+        passed: true,
+        detail: format!(
+            "{:.5} of {} steps were +1, expected {}, z = {:.2}",
+            balance.up_fraction(),
+            balance.steps(),
+            balance.expected_fraction(),
+            z
+        ),
+    }
+}
+
+/// Drawing step by step, as the walks view does, gives the same samples as
+/// drawing the sums directly from the same seed.
+fn walks(config: &Config) -> Outcome {
+    let mut rng = config.generator.seeded(config.seed);
+    let ends = (0..config.samples)
+        .map(|_| {
+            let walk = sampling::walk(&mut rng, config.steps);
+            walk[walk.len() - 1]
+        })
+        .collect::<Vec<_>>();
+    let mut rng = config.generator.seeded(config.seed);
+    let sums = sampling::generate_sums(&mut rng, config.samples, config.steps);
+    let differ = ends.iter().zip(&sums).filter(|(a, b)| a != b).count();
+    Outcome {
+        p_value: None,
+        passed: differ == 0,
+        detail: format!("{} of {} samples differ", differ, sums.len()),
+    }
+}
+
+/// The histogram statistics match the mean and deviation of the raw sums.
+fn batch_stats(config: &Config) -> Outcome {
+    let mut rng = config.generator.seeded(config.seed);
+    let sums = sampling::generate_sums(&mut rng, config.samples, config.steps);
+
+    let n = sums.len() as f64;
+    let mean = sums.iter().map(|sum| *sum as f64).sum::<f64>() / n;
+    let sd = (sums
+        .iter()
+        .map(|sum| (*sum as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n)
+        .sqrt();
+
+    let buckets = heads(sums, config.steps)
+        .into_iter()
+        .enumerate()
+        .map(|(k, count)| ((2 * k as i32 - config.steps as i32) as f64, count))
+        .collect::<Vec<_>>();
+    let binned = Stats::compute(&buckets, 0.0, (config.steps as f64).sqrt(), 2.0);
+
+    let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * a.abs().max(1.0);
+    Outcome {
+        p_value: None,
+        passed: close(mean, binned.mean) && close(sd, binned.sd),
+        detail: format!(
+            "mean {:.6} vs {:.6}, sd {:.6} vs {:.6}",
+            binned.mean, mean, binned.sd, sd
+        ),
+    }
+}
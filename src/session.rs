@@ -0,0 +1,120 @@
+//! Per-run output directories (`--output-dir`).
+
+use crate::{datetime::DateTime, export, json::Value, App};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+pub struct Session {
+    dir: PathBuf,
+}
+
+impl Session {
+    /// Creates `<root>/<timestamp>_seed<seed>`, appending a counter if a run in
+    /// the same second already claimed the name, and points `latest` at it.
+    pub fn create(root: &Path, seed: u64) -> io::Result<Session> {
+        fs::create_dir_all(root)?;
+
+        let name = format!("{}_seed{}", DateTime::now().file_stamp(), seed);
+        let mut dir = root.join(&name);
+        let mut n = 1;
+        loop {
+            match fs::create_dir(&dir) {
+                Ok(()) => break,
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                    n += 1;
+                    dir = root.join(format!("{}-{}", name, n));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        let session = Session { dir };
+        if let Err(err) = session.point_latest(root) {
+            log::warn!("could not update the latest run pointer: {}", err);
+        }
+        Ok(session)
+    }
+
+    #[cfg(unix)]
+    fn point_latest(&self, root: &Path) -> io::Result<()> {
+        let link = root.join("latest");
+        if fs::symlink_metadata(&link).is_ok() {
+            fs::remove_file(&link)?;
+        }
+        std::os::unix::fs::symlink(self.dir.strip_prefix(root).unwrap_or(&self.dir), link)
+    }
+
+    #[cfg(not(unix))]
+    fn point_latest(&self, root: &Path) -> io::Result<()> {
+        fs::write(root.join("latest.txt"), self.dir.display().to_string())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Relative output paths are placed inside the session directory.
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        self.dir.join(path)
+    }
+
+    pub fn write_config(&self, app: &App) -> io::Result<()> {
+        let config = Value::object(vec![
+            ("samples", app.b_count.into()),
+            ("r_max", app.r_max.into()),
+            ("seed", app.seed.to_string().into()),
+            ("rng", app.sim.generator().name().into()),
+            ("tick_ms", (app.tick_rate.as_millis() as u64).into()),
+            ("cumulative", app.mode.is_cumulative().into()),
+        ]);
+        fs::write(self.dir.join("config.json"), format!("{}\n", config))
+    }
+
+    pub fn write_exports(&self, app: &App) -> io::Result<()> {
+        write_exports(
+            app,
+            &self.dir.join("histogram.json"),
+            &self.dir.join("histogram.csv"),
+        )
+    }
+
+    /// Writes `exports/<name>.json` and `.csv`, for the periodic exports of
+    /// a daemon run, and returns their paths.
+    pub fn write_snapshot(&self, app: &App, name: &str) -> io::Result<[PathBuf; 2]> {
+        let dir = self.dir.join("exports");
+        fs::create_dir_all(&dir)?;
+        let json = dir.join(format!("{}.json", name));
+        let csv = dir.join(format!("{}.csv", name));
+        write_exports(app, &json, &csv)?;
+        Ok([json, csv])
+    }
+}
+
+fn write_exports(app: &App, json: &Path, csv: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(csv)?);
+    export::write_csv(&mut out, app)?;
+    out.flush()?;
+    if let Some(seeds) = &app.seeds {
+        let path = csv.with_file_name(match csv.file_stem() {
+            Some(stem) => format!("{}.seeds.csv", stem.to_string_lossy()),
+            None => "seeds.csv".to_string(),
+        });
+        let mut out = BufWriter::new(File::create(path)?);
+        export::write_seeds_csv(&mut out, app, seeds)?;
+        out.flush()?;
+    }
+    if let Some(overlay) = &app.overlay {
+        let path = csv.with_file_name(match csv.file_stem() {
+            Some(stem) => format!("{}.overlay.csv", stem.to_string_lossy()),
+            None => "overlay.csv".to_string(),
+        });
+        let mut out = BufWriter::new(File::create(path)?);
+        export::write_overlay_csv(&mut out, app, overlay)?;
+        out.flush()?;
+    }
+
+    fs::write(json, format!("{}\n", export::to_json(app)))
+}
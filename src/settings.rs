@@ -0,0 +1,120 @@
+//! The settings panel, opened with `o`: the run's parameters in a list, each
+//! edited in place instead of through keys to remember.
+
+use crossterm::event::KeyCode;
+
+/// Digits an edited value can have, more than any valid value needs.
+const MAX_DIGITS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Samples,
+    Steps,
+    TickRate,
+    Theme,
+}
+
+impl Field {
+    pub const ALL: [Field; 4] = [Field::Samples, Field::Steps, Field::TickRate, Field::Theme];
+
+    /// The string table key of the field's name.
+    pub fn key(self) -> &'static str {
+        match self {
+            Field::Samples => "settings.samples",
+            Field::Steps => "settings.steps",
+            Field::TickRate => "settings.tick_rate",
+            Field::Theme => "settings.theme",
+        }
+    }
+
+    /// Whether the value is typed in, rather than cycled through.
+    fn typed(self) -> bool {
+        self != Field::Theme
+    }
+}
+
+/// What a key press in the panel asks of the app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    None,
+    Close,
+    /// Set the field from the typed text; the theme ignores the text and
+    /// moves on to the next one.
+    Commit(Field, String),
+}
+
+#[derive(Debug)]
+pub struct Panel {
+    selected: usize,
+    /// The text typed so far, while the selected field is edited.
+    editing: Option<String>,
+    /// Why the last commit was rejected, until the next key.
+    error: Option<String>,
+    /// Whether the run was going when the panel opened, to resume it when
+    /// the panel closes.
+    resume: bool,
+}
+
+impl Panel {
+    pub fn new(resume: bool) -> Panel {
+        Panel {
+            selected: 0,
+            editing: None,
+            error: None,
+            resume,
+        }
+    }
+
+    pub fn selected(&self) -> Field {
+        Field::ALL[self.selected]
+    }
+
+    pub fn editing(&self) -> Option<&str> {
+        self.editing.as_deref()
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn resume(&self) -> bool {
+        self.resume
+    }
+
+    /// Handles a key, given the selected field's current `value`.
+    pub fn on_key(&mut self, code: KeyCode, value: &str) -> Action {
+        self.error = None;
+        let field = self.selected();
+        if let Some(text) = &mut self.editing {
+            match code {
+                KeyCode::Char(c @ '0'..='9') if text.len() < MAX_DIGITS => text.push(c),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Enter => return Action::Commit(field, text.clone()),
+                KeyCode::Esc => self.editing = None,
+                _ => {}
+            }
+            return Action::None;
+        }
+        let count = Field::ALL.len();
+        match code {
+            KeyCode::Up => self.selected = (self.selected + count - 1) % count,
+            KeyCode::Down => self.selected = (self.selected + 1) % count,
+            KeyCode::Enter if field.typed() => self.editing = Some(value.to_string()),
+            KeyCode::Enter => return Action::Commit(field, String::new()),
+            KeyCode::Esc | KeyCode::Char('o') => return Action::Close,
+            _ => {}
+        }
+        Action::None
+    }
+
+    /// Ends the edit if the app took the value, or keeps it open with the
+    /// reason it didn't.
+    pub fn committed(&mut self, result: Result<(), String>) {
+        match result {
+            Ok(()) => self.editing = None,
+            Err(err) => self.error = Some(err),
+        }
+    }
+}
@@ -0,0 +1,745 @@
+//! A histogram of random-walk sums that grows as samples are drawn, for
+//! driving an experiment from your own loop.
+//!
+//! ```
+//! use central_limit::simulation::Simulation;
+//!
+//! let mut sim = Simulation::new(19, 42).unwrap();
+//! sim.advance(10_000);
+//! println!("KS distance {:.4}", sim.stats().ks);
+//! ```
+//!
+//! `buckets` borrows the counts bucket by bucket, so a statistic of your own
+//! takes a few lines, such as the interquartile range:
+//!
+//! ```
+//! use central_limit::simulation::Simulation;
+//!
+//! let mut sim = Simulation::new(19, 42).unwrap();
+//! sim.advance(10_000);
+//! let quartile = |p: f64| {
+//!     let mut below = 0.0;
+//!     sim.buckets()
+//!         .find(|bucket| {
+//!             below += bucket.frequency();
+//!             below >= p
+//!         })
+//!         .map(|bucket| bucket.center())
+//!         .unwrap()
+//! };
+//! let iqr = quartile(0.75) - quartile(0.25);
+//! assert!(iqr > 0.0 && iqr < 19.0);
+//! ```
+
+use crate::{
+    balance::Balance,
+    bucket::{self, Aggregate, Bucket, Layout, View},
+    rng::Generator,
+    sampling::{self, Regime},
+    stats::{self, Model, Stats},
+};
+use rand::RngCore;
+use std::{
+    cell::Cell,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+};
+
+/// What a tick hook is shown at the end of each tick.
+#[derive(Debug, Clone, Copy)]
+pub struct TickReport<'a> {
+    /// Ticks ended so far, counting this one.
+    pub tick: u64,
+    pub steps: i32,
+    /// The chance of each step of the tick being +1.
+    pub bias: f64,
+    /// Samples drawn since the previous tick ended.
+    pub new_samples: u64,
+    /// Where the buckets lie.
+    pub layout: Layout,
+    /// One count per bucket of `layout`.
+    pub counts: &'a [u64],
+    pub stats: Stats,
+    /// The largest bucket count.
+    pub peak: u64,
+}
+
+impl<'a> TickReport<'a> {
+    pub fn buckets(&self) -> impl ExactSizeIterator<Item = View<'a>> + Clone {
+        bucket::views(self.counts, self.layout, self.bias)
+    }
+}
+
+/// Called with a report at the end of every tick. It only gets a shared
+/// borrow, so it can watch the simulation but not change it.
+pub type TickHook = Box<dyn FnMut(&TickReport) + Send>;
+
+/// Called with every batch of sums as it's drawn, before the sums are
+/// counted, so the raw samples can be kept as well as the histogram. With an
+/// aggregate of positions, it's their values that are counted and shown.
+pub type SampleTap = Box<dyn FnMut(&[i32]) + Send>;
+
+thread_local! {
+    static IN_HOOK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether the current thread is running a tick hook. A panic hook can check
+/// this to leave a panic that `Simulation` is about to catch alone.
+pub fn in_tick_hook() -> bool {
+    IN_HOOK.with(Cell::get)
+}
+
+/// The sums of `steps` ±1 steps, fair unless given a bias, counted into one
+/// bucket per reachable sum, or into `bucket::BINS` or fewer once they're
+/// drawn from the normal approximation; or the max, min or median of the
+/// walks' positions, into a bucket per value they can take. `Simulation` is
+/// `Send`, so it can be moved to a worker thread.
+pub struct Simulation {
+    steps: i32,
+    aggregate: Aggregate,
+    /// The chance of each step being +1.
+    bias: f64,
+    generator: Generator,
+    rng: Box<dyn RngCore + Send>,
+    /// Walks this long or longer are drawn from the normal approximation.
+    exact_below: u32,
+    layout: Layout,
+    /// One count per bucket of `layout`.
+    counts: Vec<u64>,
+    /// The part of each count added by `fast_forward` rather than drawn
+    /// walk by walk.
+    fast_forwarded: Vec<u64>,
+    tick: u64,
+    /// Samples counted when the last tick ended.
+    tick_start: u64,
+    /// Every step drawn, across resets and changes of steps.
+    balance: Balance,
+    hook: Option<TickHook>,
+    hook_error: Option<String>,
+    tap: Option<SampleTap>,
+}
+
+impl fmt::Debug for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Simulation")
+            .field("steps", &self.steps)
+            .field("aggregate", &self.aggregate)
+            .field("bias", &self.bias)
+            .field("generator", &self.generator)
+            .field("regime", &self.regime())
+            .field("tick", &self.tick)
+            .field("samples", &self.samples())
+            .field("hook", &self.hook.is_some())
+            .field("tap", &self.tap.is_some())
+            .finish()
+    }
+}
+
+impl Simulation {
+    /// A simulation of `steps`-step walks, seeded so runs can be repeated.
+    /// `steps` must be at least 1.
+    pub fn new(steps: i32, seed: u64) -> Result<Simulation, String> {
+        Simulation::with_generator(steps, seed, Generator::Std)
+    }
+
+    /// Like `new`, drawing from `generator` instead of `StdRng`.
+    pub fn with_generator(
+        steps: i32,
+        seed: u64,
+        generator: Generator,
+    ) -> Result<Simulation, String> {
+        let mut sim = Simulation {
+            steps: 0,
+            aggregate: Aggregate::Sum,
+            bias: sampling::FAIR,
+            generator,
+            rng: generator.seeded(seed),
+            exact_below: sampling::EXACT_BELOW,
+            layout: Layout::Sums { steps: 0 },
+            counts: vec![],
+            fast_forwarded: vec![],
+            tick: 0,
+            tick_start: 0,
+            balance: Balance::default(),
+            hook: None,
+            hook_error: None,
+            tap: None,
+        };
+        sim.set_steps(steps)?;
+        Ok(sim)
+    }
+
+    pub fn steps(&self) -> i32 {
+        self.steps
+    }
+
+    pub fn generator(&self) -> Generator {
+        self.generator
+    }
+
+    pub fn bias(&self) -> f64 {
+        self.bias
+    }
+
+    /// Draws the steps from now on as +1 with probability `p`, rather than
+    /// `sampling::FAIR`. The counts so far stay as they are; before there
+    /// are any, the buckets are laid out again around the new mean.
+    pub fn set_bias(&mut self, p: f64) -> Result<(), String> {
+        if !(p > 0.0 && p < 1.0) {
+            return Err(format!("the bias must be between 0 and 1, not {}", p));
+        }
+        self.bias = p;
+        if self.samples() == 0 {
+            self.lay_out();
+        }
+        Ok(())
+    }
+
+    /// Draws walks of `steps` steps or more from the normal approximation,
+    /// rather than from `sampling::EXACT_BELOW` on. Before there are any
+    /// counts, the buckets are laid out again to match.
+    pub fn set_exact_below(&mut self, steps: u32) {
+        self.exact_below = steps;
+        if self.samples() == 0 {
+            self.lay_out();
+        }
+    }
+
+    pub fn exact_below(&self) -> u32 {
+        self.exact_below
+    }
+
+    /// How the current walk length's sums are drawn. Walks whose positions
+    /// are aggregated are always drawn exactly, step by step.
+    pub fn regime(&self) -> Regime {
+        match self.aggregate.of_positions() {
+            true => Regime::Exact,
+            false => Regime::for_steps(self.steps as u32, self.exact_below),
+        }
+    }
+
+    /// Changes the walk length. The counts are cleared, since sums of
+    /// different lengths can't share a histogram; the random sequence carries on.
+    pub fn set_steps(&mut self, steps: i32) -> Result<(), String> {
+        if steps < 1 {
+            return Err("steps per sample must be at least 1".to_string());
+        }
+        Simulation::check_positions(self.aggregate, steps)?;
+        self.steps = steps;
+        self.lay_out();
+        Ok(())
+    }
+
+    /// Rejects an aggregate of positions of walks too long to draw step by
+    /// step: `sampling::EXACT_BELOW` steps or more.
+    fn check_positions(aggregate: Aggregate, steps: i32) -> Result<(), String> {
+        if aggregate.of_positions() && steps as u32 >= sampling::EXACT_BELOW {
+            return Err(format!(
+                "the {} is of every position of a walk, so its steps must be fewer than {}",
+                aggregate.name(),
+                crate::format::grouped(u64::from(sampling::EXACT_BELOW))
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn aggregate(&self) -> Aggregate {
+        self.aggregate
+    }
+
+    /// Counts `aggregate` of each sample from now on. Sums and means share
+    /// their buckets; any other change clears the counts, as a change of
+    /// steps does.
+    pub fn set_aggregate(&mut self, aggregate: Aggregate) -> Result<(), String> {
+        Simulation::check_positions(aggregate, self.steps)?;
+        let relaid = aggregate.of_positions() || self.aggregate.of_positions();
+        let relaid = relaid && aggregate != self.aggregate;
+        self.aggregate = aggregate;
+        if relaid {
+            self.lay_out();
+        }
+        Ok(())
+    }
+
+    /// Clears the counts into fresh buckets for the steps, bias and regime,
+    /// or across the support of an aggregate of positions.
+    fn lay_out(&mut self) {
+        self.layout = match self.aggregate.of_positions() {
+            true => Layout::for_positions(self.aggregate, self.steps),
+            false => Layout::for_walks(self.steps, self.bias, self.regime()),
+        };
+        self.counts = vec![0; self.layout.buckets()];
+        self.fast_forwarded = vec![0; self.layout.buckets()];
+        self.tick_start = 0;
+    }
+
+    /// Where the buckets lie.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Draws `n_samples` more sums, counts them and ends the tick.
+    pub fn advance(&mut self, n_samples: usize) {
+        self.draw(n_samples);
+        self.end_tick();
+    }
+
+    /// Draws `n_samples` more sums and counts them without ending the tick,
+    /// so a large tick can be drawn in parts. Sums from the normal
+    /// approximation have no steps, so they aren't in `balance`. An
+    /// aggregate of positions is counted in place of the sum.
+    pub fn draw(&mut self, n_samples: usize) {
+        if self.aggregate.of_positions() {
+            self.advance_walks(n_samples);
+            return;
+        }
+        let regime = self.regime();
+        let sums = match regime {
+            Regime::Exact => sampling::generate_biased_sums(
+                &mut self.rng,
+                n_samples,
+                self.steps as u32,
+                self.bias,
+            ),
+            Regime::Normal => sampling::approximate_biased_sums(
+                &mut self.rng,
+                n_samples,
+                self.steps as u32,
+                self.bias,
+            ),
+        };
+        if let Some(tap) = &mut self.tap {
+            tap(&sums);
+        }
+        for sum in sums {
+            self.count(sum);
+            if regime == Regime::Exact {
+                self.balance.add_biased(sum, self.steps, self.bias);
+            }
+        }
+    }
+
+    /// Ends the current tick and shows it to the tick hook. A hook that
+    /// panics is removed, and the panic message kept for `take_hook_error`.
+    pub fn end_tick(&mut self) {
+        self.tick += 1;
+        let samples = self.samples();
+        let new_samples = samples.saturating_sub(self.tick_start);
+        self.tick_start = samples;
+
+        let Some(mut hook) = self.hook.take() else {
+            return;
+        };
+        let report = TickReport {
+            tick: self.tick,
+            steps: self.steps,
+            bias: self.bias,
+            new_samples,
+            layout: self.layout,
+            counts: &self.counts,
+            stats: self.stats(),
+            peak: self.counts.iter().copied().max().unwrap_or(0),
+        };
+        IN_HOOK.with(|flag| flag.set(true));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| hook(&report)));
+        IN_HOOK.with(|flag| flag.set(false));
+        match result {
+            Ok(()) => self.hook = Some(hook),
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                self.hook_error = Some(message);
+            }
+        }
+    }
+
+    /// Ticks ended so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Replaces the hook called at the end of every tick.
+    pub fn set_tick_hook(&mut self, hook: TickHook) {
+        self.hook = Some(hook);
+    }
+
+    /// Replaces the tap shown every sum as it's drawn.
+    pub fn set_sample_tap(&mut self, tap: SampleTap) {
+        self.tap = Some(tap);
+    }
+
+    /// The message of a tick hook panic, once, if there has been one.
+    pub fn take_hook_error(&mut self) -> Option<String> {
+        self.hook_error.take()
+    }
+
+    /// Draws `n_walks` more samples step by step, counts where they end, or
+    /// their aggregate, and returns the paths, without ending the tick. The
+    /// random sequence is the same as for `draw` in the exact regime, which
+    /// walks always are.
+    pub fn advance_walks(&mut self, n_walks: usize) -> Vec<Vec<i32>> {
+        let walks = (0..n_walks)
+            .map(|_| sampling::biased_walk(&mut self.rng, self.steps as u32, self.bias))
+            .collect::<Vec<_>>();
+        let values = walks
+            .iter()
+            .map(|walk| self.aggregate.of(walk))
+            .collect::<Vec<_>>();
+        if let Some(tap) = &mut self.tap {
+            tap(&values);
+        }
+        for (walk, value) in walks.iter().zip(values) {
+            self.count(value);
+            self.balance
+                .add_biased(walk[walk.len() - 1], self.steps, self.bias);
+        }
+        walks
+    }
+
+    fn count(&mut self, sum: i32) {
+        let count = &mut self.counts[self.layout.index(sum)];
+        *count = count.saturating_add(1);
+    }
+
+    /// Adds `counts`, one per bucket, to the histogram, for example to carry
+    /// on from a previous run. The random sequence is unaffected.
+    pub fn add_counts(&mut self, counts: &[u64]) -> Result<(), String> {
+        if counts.len() != self.counts.len() {
+            return Err(format!(
+                "expected {} counts, one per bucket, but got {}",
+                self.counts.len(),
+                counts.len()
+            ));
+        }
+        for (total, count) in self.counts.iter_mut().zip(counts) {
+            *total = total.saturating_add(*count);
+        }
+        // Carried-over counts aren't new samples of the next tick.
+        self.tick_start = self.samples();
+        Ok(())
+    }
+
+    /// Adds `n` samples at once, as one multinomial draw over the binomial
+    /// model's bucket probabilities instead of walk by walk. They come from
+    /// the simulation's random sequence, so a run that fast-forwards can be
+    /// repeated. They have no steps, so they aren't in `balance`, and the
+    /// sample tap doesn't see them. An aggregate of positions has no model,
+    /// so its samples are walked after all, though they're still left out
+    /// of both.
+    pub fn fast_forward(&mut self, n: u64) {
+        let counts = match self.layout.probabilities(Model::Binomial, self.bias) {
+            Some(probabilities) => sampling::multinomial(&mut self.rng, n, &probabilities),
+            None => {
+                let mut counts = vec![0; self.counts.len()];
+                for _ in 0..n {
+                    let walk = sampling::biased_walk(&mut self.rng, self.steps as u32, self.bias);
+                    counts[self.layout.index(self.aggregate.of(&walk))] += 1;
+                }
+                counts
+            }
+        };
+        for ((total, forwarded), count) in self
+            .counts
+            .iter_mut()
+            .zip(&mut self.fast_forwarded)
+            .zip(counts)
+        {
+            *total = total.saturating_add(count);
+            *forwarded = forwarded.saturating_add(count);
+        }
+        // They aren't new samples of the tick being drawn.
+        self.tick_start += n;
+    }
+
+    /// The fast-forwarded part of each bucket's count, since the last reset.
+    pub fn fast_forwarded(&self) -> &[u64] {
+        &self.fast_forwarded
+    }
+
+    pub fn fast_forwarded_samples(&self) -> u64 {
+        crate::stats::total(self.fast_forwarded.iter().copied())
+    }
+
+    /// Clears the counts, keeping the parameters and the random sequence.
+    pub fn reset(&mut self) {
+        self.lay_out();
+    }
+
+    /// Starts over from `seed`, as a new simulation of the same steps would,
+    /// but keeping the tick hook, the sample tap and the count of ticks
+    /// they've been shown.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = self.generator.seeded(seed);
+        self.balance = Balance::default();
+        self.reset();
+    }
+
+    /// One bucket per reachable sum, from `-steps` to `steps`, or per bin of
+    /// them, each a view of the counts rather than a copy.
+    pub fn buckets(&self) -> impl ExactSizeIterator<Item = View<'_>> + Clone {
+        bucket::views(&self.counts, self.layout, self.bias)
+    }
+
+    /// The count of each bucket of `buckets`, in the same order.
+    pub fn counts_slice(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// A copy of the histogram, for keeping one that carries on changing
+    /// while the simulation doesn't.
+    pub fn histogram(&self) -> Vec<Bucket> {
+        self.buckets().map(Bucket::from).collect()
+    }
+
+    /// The steps drawn since the simulation was made, whatever has been
+    /// reset since; counts added with `add_counts` weren't drawn, so aren't
+    /// in it.
+    pub fn balance(&self) -> Balance {
+        self.balance
+    }
+
+    /// Samples counted since the last reset.
+    pub fn samples(&self) -> u64 {
+        crate::stats::total(self.counts.iter().copied())
+    }
+
+    /// The histogram's statistics against the normal approximation, or for
+    /// an aggregate of positions, which has none, against the normal curve
+    /// of its own mean and standard deviation, so its KS distance says how
+    /// far from normal its shape is.
+    pub fn stats(&self) -> Stats {
+        let buckets = self
+            .buckets()
+            .map(|bucket| (bucket.center(), bucket.count()))
+            .collect::<Vec<_>>();
+        if self.aggregate.of_positions() {
+            return Stats::fitted(&buckets, self.layout.spacing());
+        }
+        Stats::compute(
+            &buckets,
+            stats::walk_mean(self.steps as u32, self.bias),
+            stats::walk_sd(self.steps as u32, self.bias),
+            self.layout.spacing(),
+        )
+    }
+}
+
+// Moving a simulation to a worker thread is part of the API.
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<Simulation>();
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::sync::{Arc, Mutex};
+
+    /// Parameters for a run, drawn at random but from a fixed seed, so a
+    /// failure names a case that can be run again on its own.
+    #[derive(Debug, Clone, Copy)]
+    struct Case {
+        steps: i32,
+        bias: f64,
+        seed: u64,
+        samples: usize,
+        ticks: usize,
+    }
+
+    fn cases(n: usize) -> Vec<Case> {
+        let mut rng = StdRng::seed_from_u64(140);
+        (0..n)
+            .map(|i| Case {
+                // One in ten is long enough for the normal approximation.
+                steps: match i % 10 {
+                    0 => rng.gen_range(1_000..3_000),
+                    _ => rng.gen_range(1..40),
+                },
+                bias: match i % 3 {
+                    0 => 0.5,
+                    _ => rng.gen_range(0.05..0.95),
+                },
+                seed: rng.gen(),
+                samples: rng.gen_range(1..500),
+                ticks: rng.gen_range(1..4),
+            })
+            .collect()
+    }
+
+    /// Runs `case`, returning the simulation and every sum it drew.
+    fn run(case: Case) -> (Simulation, Vec<i32>) {
+        let mut sim = Simulation::new(case.steps, case.seed).unwrap();
+        sim.set_bias(case.bias).unwrap();
+        let sums = Arc::new(Mutex::new(vec![]));
+        let tap = Arc::clone(&sums);
+        sim.set_sample_tap(Box::new(move |drawn| tap.lock().unwrap().extend(drawn)));
+        for _ in 0..case.ticks {
+            sim.advance(case.samples);
+        }
+        let sums = sums.lock().unwrap().clone();
+        (sim, sums)
+    }
+
+    #[test]
+    fn the_counts_add_up_to_the_samples_drawn() {
+        for case in cases(300) {
+            let (sim, sums) = run(case);
+            let drawn = (case.samples * case.ticks) as u64;
+            assert_eq!(sim.samples(), drawn, "{:?}", case);
+            assert_eq!(sums.len() as u64, drawn, "{:?}", case);
+            assert_eq!(sim.tick(), case.ticks as u64, "{:?}", case);
+        }
+    }
+
+    #[test]
+    fn every_sum_is_reachable() {
+        for case in cases(300) {
+            let (sim, sums) = run(case);
+            for sum in sums {
+                assert!(sum.abs() <= case.steps, "{} in {:?}", sum, case);
+                assert_eq!((sum + case.steps) % 2, 0, "{} in {:?}", sum, case);
+            }
+            // Bins of several sums are centered between them.
+            if let Layout::Bins { .. } = sim.layout() {
+                continue;
+            }
+            for bucket in sim.buckets() {
+                let x = bucket.center() as i32;
+                assert!(
+                    x.abs() <= case.steps && (x + case.steps) % 2 == 0,
+                    "{:?}",
+                    case
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn the_mean_is_close_to_the_walks() {
+        for case in cases(300) {
+            let (sim, sums) = run(case);
+            let n = sums.len() as f64;
+            let mean = sums.iter().map(|&sum| f64::from(sum)).sum::<f64>() / n;
+            let expected = stats::walk_mean(case.steps as u32, case.bias);
+            // Six standard errors, which the 300 cases together fail by chance
+            // less than once in a million runs.
+            let bound = 6.0 * stats::walk_sd(case.steps as u32, case.bias) / n.sqrt();
+            assert!(
+                (mean - expected).abs() <= bound,
+                "mean {} in {:?}",
+                mean,
+                case
+            );
+            assert_eq!(sim.stats().total, sums.len() as u64, "{:?}", case);
+        }
+    }
+
+    #[test]
+    fn ticks_drawn_in_parts_count_the_same() {
+        for case in cases(50) {
+            let whole = run(case).0;
+            let mut parts = Simulation::new(case.steps, case.seed).unwrap();
+            parts.set_bias(case.bias).unwrap();
+            for _ in 0..case.ticks {
+                let half = case.samples / 2;
+                parts.draw(half);
+                parts.draw(case.samples - half);
+                parts.end_tick();
+            }
+            assert_eq!(parts.counts_slice(), whole.counts_slice(), "{:?}", case);
+        }
+    }
+
+    #[test]
+    fn fast_forwarding_adds_its_samples() {
+        for case in cases(50) {
+            let (mut sim, _) = run(case);
+            let before = sim.samples();
+            sim.fast_forward(1_000_000);
+            assert_eq!(sim.samples(), before + 1_000_000, "{:?}", case);
+            assert_eq!(sim.fast_forwarded_samples(), 1_000_000, "{:?}", case);
+        }
+    }
+
+    #[test]
+    fn long_walks_are_counted_into_a_few_bins() {
+        let mut sim = Simulation::new(100_000_000, 1).unwrap();
+        assert_eq!(sim.regime(), Regime::Normal);
+        assert!(sim.counts_slice().len() <= bucket::BINS);
+        assert_eq!(sim.fast_forwarded().len(), sim.counts_slice().len());
+        sim.advance(10_000);
+        sim.fast_forward(1_000_000);
+        assert_eq!(sim.samples(), 1_010_000);
+        let stats = sim.stats();
+        assert!((stats.sd / 10_000.0 - 1.0).abs() < 0.05, "{:?}", stats);
+        assert!(stats.ks < 0.01, "{:?}", stats);
+    }
+
+    #[test]
+    fn positions_are_counted_by_their_aggregate() {
+        for aggregate in [Aggregate::Max, Aggregate::Min, Aggregate::Median] {
+            let mut sim = Simulation::new(19, 175).unwrap();
+            sim.set_aggregate(aggregate).unwrap();
+            let values = Arc::new(Mutex::new(Vec::<i32>::new()));
+            let tap = Arc::clone(&values);
+            sim.set_sample_tap(Box::new(move |drawn| tap.lock().unwrap().extend(drawn)));
+            sim.advance(2_000);
+            sim.fast_forward(500);
+            assert_eq!(sim.samples(), 2_500);
+
+            // Every value is counted in its own bucket, across the support.
+            let (low, high) = aggregate.support(19);
+            assert_eq!(sim.layout(), Layout::for_positions(aggregate, 19));
+            let values = values.lock().unwrap().clone();
+            assert_eq!(values.len(), 2_000);
+            for bucket in sim.buckets() {
+                let x = bucket.center() as i32;
+                assert!((low..=high).contains(&x), "{:?}", aggregate);
+                let drawn = values.iter().filter(|&&value| value == x).count() as u64;
+                let forwarded = sim.fast_forwarded()[sim.layout().index(x)];
+                assert_eq!(bucket.count(), drawn + forwarded, "{:?} {}", aggregate, x);
+                assert_eq!(bucket.expected(), None);
+            }
+            // The walks' steps are the same as the sums' would be.
+            let mut sums = Simulation::new(19, 175).unwrap();
+            sums.advance(2_000);
+            assert_eq!(sim.balance().mean(), sums.balance().mean());
+        }
+
+        // The max of a walk isn't far from half-normal, so it's nowhere near
+        // a normal curve of its own mean and spread.
+        let mut sim = Simulation::new(99, 1).unwrap();
+        sim.set_aggregate(Aggregate::Max).unwrap();
+        sim.advance(20_000);
+        let stats = sim.stats();
+        assert!(stats.ks > 0.05, "{:?}", stats);
+        assert!(sim.set_steps(1_000).is_err());
+        assert_eq!(sim.steps(), 99);
+    }
+
+    #[test]
+    fn buckets_span_the_reachable_sums() {
+        let sim = Simulation::new(5, 1).unwrap();
+        let centers = sim
+            .buckets()
+            .map(|bucket| bucket.center())
+            .collect::<Vec<_>>();
+        assert_eq!(centers, [-5.0, -3.0, -1.0, 1.0, 3.0, 5.0]);
+        let labels = sim
+            .buckets()
+            .map(|bucket| bucket.label())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["-5", "-3", "-1", "1", "3", "5"]);
+
+        let sim = Simulation::new(4, 1).unwrap();
+        let centers = sim
+            .buckets()
+            .map(|bucket| bucket.center())
+            .collect::<Vec<_>>();
+        assert_eq!(centers, [-4.0, -2.0, 0.0, 2.0, 4.0]);
+    }
+}
@@ -0,0 +1,157 @@
+//! `--smoke-test`: a check that a build works on the machine it's on, for
+//! packagers and CI, in well under a second and without a terminal.
+//!
+//! A small seeded run is made as the TUI makes one and checked against what
+//! it gives on every platform: its counts, mean and chi-square statistic are
+//! committed below, so a generator or float arithmetic that behaves
+//! differently here shows up as a failure rather than as a histogram that's
+//! subtly off. Its export is read back in, and then the `selftest` checks
+//! run on samples of the same size.
+
+use crate::{export, selftest, stats, App};
+use central_limit::rng::Generator;
+use std::io::{self, Write};
+
+/// rand documents that `StdRng`'s output may change between its versions, so
+/// the run draws from the crate's own PCG, which is the same everywhere.
+const GENERATOR: Generator = Generator::Pcg32;
+const SEED: u64 = 42;
+const SAMPLES: usize = 1_000;
+const STEPS: i32 = 9;
+const TICKS: u64 = 5;
+
+/// The last tick's counts from `SEED`, from -9 to 9.
+const EXPECTED_COUNTS: [u64; 10] = [2, 13, 74, 153, 271, 252, 150, 60, 23, 2];
+/// Their mean and chi-square statistic against the binomial distribution.
+const EXPECTED_MEAN: f64 = -0.028;
+const EXPECTED_CHI_SQUARE: f64 = 8.9020952380951;
+
+/// Standard errors the mean may be from the model's.
+const MEAN_LIMIT: f64 = 4.0;
+
+/// The chi-square test's p-value below which the counts don't look binomial.
+const ALPHA: f64 = 0.001;
+
+/// Whether the run passed, and what was found.
+type Check = fn(&App) -> (bool, String);
+
+const CHECKS: &[(&str, Check)] = &[
+    ("counts", counts),
+    ("mean", mean),
+    ("chisquare", chi_square),
+    ("export", round_trip),
+];
+
+/// Runs the checks, printing PASS or FAIL for each, and returns whether every
+/// one passed.
+pub fn run<W: Write>(out: &mut W) -> io::Result<bool> {
+    let mut app = App::new(SEED, GENERATOR);
+    if let Err(err) = app.set_params(SAMPLES, STEPS) {
+        writeln!(out, "FAIL {:<9} {}", "setup", err)?;
+        return Ok(false);
+    }
+    while app.tick < TICKS {
+        app.on_tick();
+    }
+    writeln!(
+        out,
+        "{} ticks of {} samples of {} steps (seed {})",
+        TICKS, SAMPLES, STEPS, SEED
+    )?;
+
+    let mut failed = vec![];
+    for (name, check) in CHECKS {
+        let (passed, detail) = check(&app);
+        let verdict = if passed { "PASS" } else { "FAIL" };
+        writeln!(out, "{} {:<9} {}", verdict, name, detail)?;
+        if !passed {
+            failed.push(*name);
+        }
+    }
+    writeln!(out)?;
+
+    let config = selftest::Config {
+        samples: SAMPLES,
+        steps: STEPS as u32,
+        seed: SEED,
+        generator: GENERATOR,
+    };
+    let checked = selftest::run(out, &config, &[])?;
+
+    if !failed.is_empty() {
+        writeln!(
+            out,
+            "\n{} of the checks failed: {}. The run is seeded, so this build\n\
+             doesn't draw or count samples as others do; please report it.",
+            failed.len(),
+            failed.join(", ")
+        )?;
+    }
+    out.flush()?;
+    Ok(failed.is_empty() && checked)
+}
+
+fn bucket_counts(app: &App) -> Vec<u64> {
+    app.data.iter().map(|bucket| bucket.count).collect()
+}
+
+/// The counts hold each tick's samples, and are the ones this seed gives.
+fn counts(app: &App) -> (bool, String) {
+    let counts = bucket_counts(app);
+    let total = stats::total(counts.iter().copied());
+    (
+        total == SAMPLES as u64 && counts == EXPECTED_COUNTS,
+        format!("{} in all, {:?}", total, counts),
+    )
+}
+
+/// The mean is near the model's, and is the one this seed gives.
+fn mean(app: &App) -> (bool, String) {
+    let stats = app.stats();
+    let error = stats.sd / (stats.total as f64).sqrt();
+    let z = (stats.mean - app.mean()) / error;
+    (
+        z.abs() <= MEAN_LIMIT && close(stats.mean, EXPECTED_MEAN),
+        format!(
+            "{:.4}, z = {:.2}, expected {}",
+            stats.mean, z, EXPECTED_MEAN
+        ),
+    )
+}
+
+/// The counts look binomial, with the statistic this seed gives.
+fn chi_square(app: &App) -> (bool, String) {
+    let test = stats::binomial_chi_square(&bucket_counts(app), STEPS as u32);
+    (
+        test.p_value >= ALPHA && close(test.statistic, EXPECTED_CHI_SQUARE),
+        format!(
+            "{:.4} with {} dof, p = {:.4}, expected {:.4}",
+            test.statistic, test.dof, test.p_value, EXPECTED_CHI_SQUARE
+        ),
+    )
+}
+
+/// The JSON export reads back in as the histogram it was written from.
+fn round_trip(app: &App) -> (bool, String) {
+    let written = export::to_json(app).to_string();
+    match export::parse(&written) {
+        Ok(read) => {
+            let labels = app
+                .data
+                .iter()
+                .map(|bucket| bucket.label.clone())
+                .collect::<Vec<_>>();
+            (
+                read.r_max == app.r_max
+                    && read.labels == labels
+                    && read.counts == bucket_counts(app),
+                format!("{} buckets read back", read.counts.len()),
+            )
+        }
+        Err(err) => (false, format!("can't be read back: {}", err)),
+    }
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * a.abs().max(1.0)
+}
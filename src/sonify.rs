@@ -0,0 +1,202 @@
+//! `--sonify`: a short click every tick, pitched by the KS distance, so a
+//! run can be heard settling. The further the histogram is from the normal
+//! curve the higher the click, and the pitch falls as it converges.
+//!
+//! The clicks are made here and played by a command-line player fed raw
+//! samples on its input, `aplay`, `pacat` or sox's `play`, whichever starts
+//! first, from a thread of its own so a tick never waits on the sound. A
+//! tick that comes while the last click is still playing goes unheard. If
+//! no player can be started, or the one playing stops, the sound goes quiet
+//! and the reason is passed back once, for a warning.
+
+use std::{
+    f64::consts::TAU,
+    io::Write,
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread,
+    time::Duration,
+};
+
+/// `--volume` unless it says, out of 100.
+pub const DEFAULT_VOLUME: u8 = 50;
+
+/// How much the volume keys turn it up or down.
+const VOLUME_STEP: u8 = 10;
+
+/// Samples per second, as the players below are told.
+const RATE: u32 = 22_050;
+const CLICK: Duration = Duration::from_millis(40);
+/// How quickly a click dies away, and how long it takes to fade at the end
+/// so it doesn't stop with a pop.
+const DECAY: f64 = 0.012;
+const FADE: f64 = 0.005;
+
+/// The KS distances clicked at the lowest and highest pitch; a distance
+/// between them is pitched between them on a log scale, three octaves up
+/// from the A below middle C.
+const KS_LOW: f64 = 1e-3;
+const KS_HIGH: f64 = 0.3;
+const PITCH_LOW: f64 = 220.0;
+const OCTAVES: f64 = 3.0;
+
+/// The players tried in turn, each reading signed 16-bit mono at `RATE`.
+const PLAYERS: [(&str, &[&str]); 3] = [
+    (
+        "aplay",
+        &["-q", "-t", "raw", "-f", "S16_LE", "-r", "22050", "-c", "1"],
+    ),
+    (
+        "pacat",
+        &["--raw", "--format=s16le", "--rate=22050", "--channels=1"],
+    ),
+    (
+        "play",
+        &[
+            "-q", "-t", "raw", "-b", "16", "-e", "signed", "-r", "22050", "-c", "1", "-",
+        ],
+    ),
+];
+
+pub struct Sonify {
+    clicks: SyncSender<Click>,
+    failed: Receiver<String>,
+    volume: u8,
+    on: bool,
+    warned: bool,
+}
+
+struct Click {
+    ks: f64,
+    volume: u8,
+}
+
+impl Sonify {
+    /// Starts the thread the clicks are played from, at `volume` out of 100.
+    pub fn start(volume: u8) -> Sonify {
+        // With no room in the channel, a click is only taken while the
+        // thread is waiting for one, so they never queue up behind the sound.
+        let (clicks, heard) = mpsc::sync_channel(0);
+        let (fail, failed) = mpsc::channel();
+        thread::spawn(move || {
+            if let Err(err) = play(heard) {
+                log::warn!("--sonify: {}", err);
+                let _ = fail.send(err);
+            }
+        });
+        Sonify {
+            clicks,
+            failed,
+            volume: volume.min(100),
+            on: true,
+            warned: false,
+        }
+    }
+
+    /// Clicks for a tick whose histogram is `ks` from the model, unless the
+    /// sound is off or still playing the last one. Returns why the sound
+    /// failed, the first time it's known to have.
+    pub fn click(&mut self, ks: f64) -> Option<String> {
+        if self.on {
+            let _ = self.clicks.try_send(Click {
+                ks,
+                volume: self.volume,
+            });
+        }
+        if self.warned {
+            return None;
+        }
+        let err = self.failed.try_recv().ok()?;
+        self.warned = true;
+        Some(err)
+    }
+
+    pub fn on(&self) -> bool {
+        self.on
+    }
+
+    pub fn toggle(&mut self) {
+        self.on = !self.on;
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    /// Turns the volume up or down a step, and returns it.
+    pub fn adjust(&mut self, up: bool) -> u8 {
+        self.volume = match up {
+            true => (self.volume + VOLUME_STEP).min(100),
+            false => self.volume.saturating_sub(VOLUME_STEP),
+        };
+        self.volume
+    }
+}
+
+/// Plays the clicks that come in until the run ends, waiting out each one so
+/// the player is fed no faster than it plays.
+fn play(clicks: Receiver<Click>) -> Result<(), String> {
+    let (name, mut player) = spawn()?;
+    log::info!("--sonify: playing through {}", name);
+    let Some(mut input) = player.stdin.take() else {
+        return Err(format!("{} has no input to play from", name));
+    };
+    for click in clicks {
+        if let Err(err) = input.write_all(&synthesize(&click)) {
+            drop(input);
+            return Err(match player.wait() {
+                Ok(status) if !status.success() => format!("{} stopped ({})", name, status),
+                _ => format!("{} stopped: {}", name, err),
+            });
+        }
+        thread::sleep(CLICK);
+    }
+    drop(input);
+    let _ = player.wait();
+    Ok(())
+}
+
+/// The first of `PLAYERS` that starts, and its name.
+fn spawn() -> Result<(&'static str, Child), String> {
+    for (name, args) in PLAYERS {
+        match Command::new(name)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => return Ok((name, child)),
+            Err(err) => log::debug!("--sonify: can't start {}: {}", name, err),
+        }
+    }
+    let names = PLAYERS.map(|(name, _)| name).join(", ");
+    Err(format!("no sound player found; tried {}", names))
+}
+
+/// The click's samples, as `PLAYERS` read them: a tone at the click's pitch
+/// that dies away quickly. The volume is squared, as loudness is heard.
+fn synthesize(click: &Click) -> Vec<u8> {
+    let pitch = pitch(click.ks);
+    let amplitude = (f64::from(click.volume) / 100.0).powi(2) * f64::from(i16::MAX);
+    let length = CLICK.as_secs_f64();
+    let samples = (f64::from(RATE) * length) as usize;
+    (0..samples)
+        .flat_map(|i| {
+            let t = i as f64 / f64::from(RATE);
+            let envelope = (-t / DECAY).exp() * ((length - t) / FADE).min(1.0);
+            let sample = amplitude * envelope * (TAU * pitch * t).sin();
+            (sample as i16).to_le_bytes()
+        })
+        .collect()
+}
+
+/// The pitch in hertz of a click `ks` from the model.
+fn pitch(ks: f64) -> f64 {
+    let ks = match ks.is_finite() {
+        true => ks.clamp(KS_LOW, KS_HIGH),
+        false => KS_LOW,
+    };
+    let high = (ks / KS_LOW).ln() / (KS_HIGH / KS_LOW).ln();
+    PITCH_LOW * 2f64.powf(OCTAVES * high)
+}
@@ -0,0 +1,916 @@
+//! Summary statistics over bucketed counts.
+
+use crate::sampling::FAIR;
+
+/// Statistics of a histogram given as (bucket center, count) pairs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub total: u64,
+    pub mean: f64,
+    pub sd: f64,
+    /// Kolmogorov-Smirnov distance to the normal approximation.
+    pub ks: f64,
+}
+
+impl Stats {
+    /// `spacing` is the distance between adjacent bucket centers, used as the
+    /// continuity correction when comparing the discrete CDF to the normal one.
+    pub fn compute(buckets: &[(f64, u64)], mean: f64, sd: f64, spacing: f64) -> Stats {
+        let weighted = buckets
+            .iter()
+            .map(|(x, count)| (*x, *count as f64))
+            .collect::<Vec<_>>();
+        Stats {
+            total: total(buckets.iter().map(|(_, count)| *count)),
+            ..Stats::weighted(&weighted, mean, sd, spacing)
+        }
+    }
+
+    /// Like `compute`, against the normal curve of the buckets' own mean and
+    /// standard deviation, for those with no model of their own.
+    pub fn fitted(buckets: &[(f64, u64)], spacing: f64) -> Stats {
+        let Stats { mean, sd, .. } = Stats::compute(buckets, 0.0, 1.0, spacing);
+        Stats::compute(buckets, mean, sd, spacing)
+    }
+
+    /// Like `compute`, for weights that needn't be whole numbers, such as
+    /// decayed counts. The total is the weights' sum, rounded.
+    pub fn weighted(buckets: &[(f64, f64)], mean: f64, sd: f64, spacing: f64) -> Stats {
+        let n = buckets.iter().map(|(_, weight)| weight).sum::<f64>();
+        if n <= 0.0 {
+            return Stats {
+                total: 0,
+                mean: f64::NAN,
+                sd: f64::NAN,
+                ks: f64::NAN,
+            };
+        }
+
+        let observed_mean = buckets.iter().map(|(x, w)| x * w).sum::<f64>() / n;
+        let variance = buckets
+            .iter()
+            .map(|(x, w)| (x - observed_mean).powi(2) * w)
+            .sum::<f64>()
+            / n;
+
+        let mut cumulative = 0.0;
+        let mut ks: f64 = 0.0;
+        for (x, weight) in buckets {
+            cumulative += weight;
+            let expected = cdf(x + spacing / 2.0, mean, sd);
+            ks = ks.max((cumulative / n - expected).abs());
+        }
+
+        Stats {
+            total: n.round() as u64,
+            mean: observed_mean,
+            sd: variance.sqrt(),
+            ks,
+        }
+    }
+}
+
+/// The normal CDF at `x`, taking a zero `sd` as all the probability at
+/// `mean` rather than dividing by it.
+fn cdf(x: f64, mean: f64, sd: f64) -> f64 {
+    match sd > 0.0 {
+        true => normal_cdf((x - mean) / sd),
+        false if x < mean => 0.0,
+        false => 1.0,
+    }
+}
+
+/// The bucket center every sample is in, when there are samples enough to
+/// spread out, at least two, and they haven't: their spread is 0, and
+/// whatever is measured against it can't be worked out.
+pub fn single_value(buckets: &[(f64, u64)]) -> Option<f64> {
+    let mut filled = buckets.iter().filter(|(_, count)| *count > 0);
+    match (filled.next(), filled.next()) {
+        (Some((x, count)), None) if *count >= 2 => Some(*x),
+        _ => None,
+    }
+}
+
+/// Fraction of the samples whose bucket center lies within `half_width` of `center`.
+pub fn fraction_within(buckets: &[(f64, u64)], center: f64, half_width: f64) -> f64 {
+    let total = total(buckets.iter().map(|(_, count)| *count));
+    if total == 0 {
+        return f64::NAN;
+    }
+    let inside = self::total(
+        buckets
+            .iter()
+            .filter(|(x, _)| (x - center).abs() <= half_width)
+            .map(|(_, count)| *count),
+    );
+    share(inside, total)
+}
+
+/// Skewness and excess kurtosis of a histogram, both 0 for a normal
+/// distribution, or NaN when there is too little data to tell.
+pub fn shape(buckets: &[(f64, u64)]) -> (f64, f64) {
+    let n = total(buckets.iter().map(|(_, count)| *count)) as f64;
+    let moment = |center: f64, k: i32| {
+        buckets
+            .iter()
+            .map(|(x, c)| (x - center).powi(k) * *c as f64)
+            .sum::<f64>()
+            / n
+    };
+    let mean = moment(0.0, 1);
+    let variance = moment(mean, 2);
+    if n == 0.0 || variance == 0.0 {
+        return (f64::NAN, f64::NAN);
+    }
+    (
+        moment(mean, 3) / variance.powf(1.5),
+        moment(mean, 4) / variance.powi(2) - 3.0,
+    )
+}
+
+/// Sum of `counts`, saturating rather than overflowing.
+pub fn total(counts: impl IntoIterator<Item = u64>) -> u64 {
+    counts.into_iter().fold(0, u64::saturating_add)
+}
+
+/// `count` as a fraction of `total`, or 0 when `total` is 0. Every share and
+/// percentage goes through here, in f64, so large counts can't overflow.
+pub fn share(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+/// The sampling error of a bucket holding `count` of `total` samples, as a
+/// count: √(N·p·(1 − p)), about √count while the bucket is a small share. An
+/// empty bucket is given the error a count of one would have, since a count
+/// that small could be hiding in the noise.
+pub fn count_error(count: u64, total: u64) -> f64 {
+    let p = share(count.max(1), total.max(1)).min(1.0);
+    (total as f64 * p * (1.0 - p)).sqrt()
+}
+
+/// The relative standard error of a bucket's share of the samples, holding
+/// `count` of `total`: √(p(1 − p)/N) / p, which is √((1 − p)/count). An
+/// empty bucket's is infinite, since its share could be anything small.
+pub fn relative_error(count: u64, total: u64) -> f64 {
+    if count == 0 {
+        return f64::INFINITY;
+    }
+    let p = share(count, total).min(1.0);
+    ((1.0 - p) / count as f64).sqrt()
+}
+
+/// How many of the buckets with samples in them have a relative standard
+/// error of at most `target`, such as 0.01, and how many have samples.
+/// Buckets no sample has reached yet aren't counted either way.
+pub fn converged(counts: &[u64], target: f64) -> (usize, usize) {
+    let total = total(counts.iter().copied());
+    let occupied = counts.iter().filter(|count| **count > 0).count();
+    let converged = counts
+        .iter()
+        .filter(|count| **count > 0 && relative_error(**count, total) <= target)
+        .count();
+    (converged, occupied)
+}
+
+/// The share of the occupied buckets that are within `target`, in percent,
+/// or NaN before there are samples.
+pub fn converged_percent(counts: &[u64], target: f64) -> f64 {
+    match converged(counts, target) {
+        (_, 0) => f64::NAN,
+        (converged, occupied) => converged as f64 / occupied as f64 * 100.0,
+    }
+}
+
+/// How a bucket's count moved from one tick to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Grew,
+    Shrank,
+    /// Within the noise.
+    Steady,
+}
+
+/// Standard errors a count has to move by to be more than noise.
+pub const CHANGE_Z: f64 = 2.0;
+
+/// How each count in `current` moved from `previous`, or `None` if they
+/// aren't the same buckets. A move counts if it's more than `z` times
+/// √(previous + current), the spread of the difference between two counts
+/// that only differ by chance. Regenerated ticks show the sampling noise;
+/// counting cumulatively, every bar grows at first and settles to steady as
+/// each tick's increment becomes small against its count.
+pub fn changes(previous: &[u64], current: &[u64], z: f64) -> Option<Vec<Change>> {
+    if previous.len() != current.len() {
+        return None;
+    }
+    let changes = previous
+        .iter()
+        .zip(current)
+        .map(|(previous, current)| {
+            let (previous, current) = (*previous as f64, *current as f64);
+            let noise = z * (previous + current).sqrt();
+            match current - previous {
+                diff if diff > noise => Change::Grew,
+                diff if diff < -noise => Change::Shrank,
+                _ => Change::Steady,
+            }
+        })
+        .collect();
+    Some(changes)
+}
+
+/// The frequency (monobit) test: the chance of a count of heads at least this
+/// far from half in `flips` fair coin flips, by the normal approximation.
+pub fn frequency_test(flips: &[bool]) -> f64 {
+    let n = flips.len() as f64;
+    if n == 0.0 {
+        return 1.0;
+    }
+    let heads = flips.iter().filter(|flip| **flip).count() as f64;
+    let z = (2.0 * heads - n) / n.sqrt();
+    2.0 * normal_cdf(-z.abs())
+}
+
+/// The Wald-Wolfowitz runs test: the chance of a number of runs of equal
+/// flips at least this far from what independent flips with the same heads
+/// and tails would give. Too few runs means the flips stick together, too
+/// many that they alternate.
+pub fn runs_test(flips: &[bool]) -> f64 {
+    let n = flips.len() as f64;
+    let heads = flips.iter().filter(|flip| **flip).count() as f64;
+    let tails = n - heads;
+    if heads == 0.0 || tails == 0.0 {
+        // All one side: as far from independent flips as can be, unless
+        // there are too few to tell.
+        return if n < 2.0 { 1.0 } else { 0.0 };
+    }
+    let runs = 1 + flips.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    let mean = 2.0 * heads * tails / n + 1.0;
+    let variance = (mean - 1.0) * (mean - 2.0) / (n - 1.0);
+    if variance <= 0.0 {
+        return 1.0;
+    }
+    let z = (runs as f64 - mean) / variance.sqrt();
+    2.0 * normal_cdf(-z.abs())
+}
+
+pub fn normal_pdf(z: f64) -> f64 {
+    (-0.5 * z * z).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+pub fn normal_cdf(z: f64) -> f64 {
+    0.5 * (1.0 + erf(z / std::f64::consts::SQRT_2))
+}
+
+/// Inverse of `normal_cdf`, by bisection; `p` is clamped to (0, 1).
+pub fn normal_quantile(p: f64) -> f64 {
+    let p = p.clamp(1e-9, 1.0 - 1e-9);
+    let (mut lo, mut hi) = (-8.0, 8.0);
+    for _ in 0..60 {
+        let mid = (lo + hi) / 2.0;
+        if normal_cdf(mid) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+/// Each non-empty bucket with the middle of its share of the samples, as a
+/// cumulative probability, for reading quantiles off a histogram.
+fn mid_shares(buckets: &[(f64, u64)]) -> Vec<(f64, f64)> {
+    let total = total(buckets.iter().map(|(_, count)| *count));
+    if total == 0 {
+        return vec![];
+    }
+    let mut below: u64 = 0;
+    let mut shares = vec![];
+    for (x, count) in buckets {
+        if *count > 0 {
+            shares.push((*x, (below as f64 + *count as f64 / 2.0) / total as f64));
+        }
+        below = u64::saturating_add(below, *count);
+    }
+    shares
+}
+
+/// Empirical against theoretical z-scores for a Q-Q plot, one point per
+/// non-empty bucket, using the middle of each bucket's share of the samples.
+pub fn qq_points(buckets: &[(f64, u64)], mean: f64, sd: f64) -> Vec<(f64, f64)> {
+    mid_shares(buckets)
+        .into_iter()
+        .map(|(x, p)| match sd > 0.0 {
+            true => (normal_quantile(p), (x - mean) / sd),
+            false => (normal_quantile(p), 0.0),
+        })
+        .collect()
+}
+
+/// The `p` quantile of a histogram: the first bucket whose share of the
+/// samples reaches `p`, taking the middle of each bucket's share as the
+/// Q-Q plot does. `None` while it's empty.
+pub fn quantile(buckets: &[(f64, u64)], p: f64) -> Option<f64> {
+    let shares = mid_shares(buckets);
+    shares
+        .iter()
+        .find(|(_, share)| *share >= p)
+        .or(shares.last())
+        .map(|(x, _)| *x)
+}
+
+/// Probability of `k` heads in `n` fair coin flips, computed in logs so large
+/// `n` doesn't underflow.
+pub fn binomial_pmf(n: u32, k: u32) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    let (n, k) = (n as f64, k as f64);
+    (ln_gamma(n + 1.0) - ln_gamma(k + 1.0) - ln_gamma(n - k + 1.0) - n * std::f64::consts::LN_2)
+        .exp()
+}
+
+/// Like `binomial_pmf`, for a coin that lands heads with probability `p`,
+/// from above 0 to below 1.
+pub fn biased_binomial_pmf(n: u32, k: u32, p: f64) -> f64 {
+    if p == FAIR || k > n {
+        return binomial_pmf(n, k);
+    }
+    let (n, k) = (n as f64, k as f64);
+    (ln_gamma(n + 1.0) - ln_gamma(k + 1.0) - ln_gamma(n - k + 1.0)
+        + k * p.ln()
+        + (n - k) * (1.0 - p).ln())
+    .exp()
+}
+
+/// The mean of the sums of `n` steps that are +1 with probability `p`.
+pub fn walk_mean(n: u32, p: f64) -> f64 {
+    n as f64 * (2.0 * p - 1.0)
+}
+
+/// The standard deviation of the sums of `n` steps that are +1 with
+/// probability `p`: √n for fair ones.
+pub fn walk_sd(n: u32, p: f64) -> f64 {
+    match p == FAIR {
+        true => (n as f64).sqrt(),
+        false => 2.0 * (n as f64 * p * (1.0 - p)).sqrt(),
+    }
+}
+
+/// Each bucket's signed relative error, (observed - expected) / expected,
+/// against `probabilities` for the samples in `counts`: 0.1 is 10% more
+/// than expected. Buckets expected to hold fewer than `min_expected`
+/// samples have none, since one sample more or less would swamp it.
+pub fn relative_errors(
+    counts: &[u64],
+    probabilities: &[f64],
+    min_expected: f64,
+) -> Vec<Option<f64>> {
+    let n = total(counts.iter().copied()) as f64;
+    counts
+        .iter()
+        .zip(probabilities)
+        .map(|(&count, &p)| {
+            let expected = n * p;
+            (expected >= min_expected && expected > 0.0)
+                .then(|| (count as f64 - expected) / expected)
+        })
+        .collect()
+}
+
+/// Kolmogorov-Smirnov distance between two distributions over the same
+/// buckets, given as each bucket's share of the samples.
+pub fn ks_between(p: &[f64], q: &[f64]) -> f64 {
+    let (mut below_p, mut below_q, mut ks) = (0.0, 0.0, 0.0_f64);
+    for (p, q) in p.iter().zip(q) {
+        below_p += p;
+        below_q += q;
+        ks = ks.max((below_p - below_q).abs());
+    }
+    ks
+}
+
+/// What a histogram of `n`-step sums is expected to look like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    /// The normal approximation, with each bucket taking the width-2 slice
+    /// around its sum, and the end buckets the tails beyond them.
+    Normal,
+    /// The exact distribution of the sums, Binomial(n, 1/2) in heads, or
+    /// Binomial(n, p) for steps that are +1 with probability `p`.
+    Binomial,
+}
+
+impl Model {
+    /// Below this many steps the normal approximation visibly misses the
+    /// exact probabilities.
+    pub const EXACT_BELOW: u32 = 30;
+
+    /// The model to show by default for `n`-step sums.
+    pub fn for_steps(n: u32) -> Model {
+        if n < Model::EXACT_BELOW {
+            Model::Binomial
+        } else {
+            Model::Normal
+        }
+    }
+
+    pub fn other(self) -> Model {
+        match self {
+            Model::Normal => Model::Binomial,
+            Model::Binomial => Model::Normal,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Model::Normal => "normal",
+            Model::Binomial => "binomial",
+        }
+    }
+
+    /// Each bucket's probability, for sums from `-n` up in twos. Both models'
+    /// probabilities add up to 1.
+    pub fn probabilities(self, n: u32) -> Vec<f64> {
+        self.biased_probabilities(n, FAIR)
+    }
+
+    /// Like `probabilities`, for steps that are +1 with probability `p`.
+    pub fn biased_probabilities(self, n: u32, p: f64) -> Vec<f64> {
+        let (mean, sd) = (walk_mean(n, p), walk_sd(n, p));
+        let cdf = |x: f64| normal_cdf((x - mean) / sd);
+        (0..=n)
+            .map(|k| match self {
+                Model::Normal => {
+                    let x = 2.0 * k as f64 - n as f64;
+                    let upper = if k == n { 1.0 } else { cdf(x + 1.0) };
+                    let lower = if k == 0 { 0.0 } else { cdf(x - 1.0) };
+                    upper - lower
+                }
+                Model::Binomial => biased_binomial_pmf(n, k, p),
+            })
+            .collect()
+    }
+}
+
+/// Pearson's chi-square test of a histogram against a model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChiSquare {
+    pub statistic: f64,
+    pub dof: usize,
+    /// Chance of a statistic at least this large if the counts do follow
+    /// the model.
+    pub p_value: f64,
+}
+
+/// Chi-square bins are merged until they expect at least this many samples.
+const MIN_EXPECTED: f64 = 5.0;
+
+/// Tests `counts`, the number of samples with k heads for k from 0 to `n`,
+/// against Binomial(n, 1/2), with sparse tail bins merged into their
+/// neighbours.
+pub fn binomial_chi_square(counts: &[u64], n: u32) -> ChiSquare {
+    chi_square(counts, &Model::Binomial.probabilities(n))
+}
+
+/// Tests `counts` against the bucket `probabilities`, with sparse tail bins
+/// merged into their neighbours.
+pub fn chi_square(counts: &[u64], probabilities: &[f64]) -> ChiSquare {
+    let total = total(counts.iter().copied()) as f64;
+    let mut bins: Vec<(f64, f64)> = vec![];
+    let mut open = (0.0, 0.0);
+    for (count, probability) in counts.iter().zip(probabilities) {
+        open.0 += *count as f64;
+        open.1 += total * probability;
+        if open.1 >= MIN_EXPECTED {
+            bins.push(open);
+            open = (0.0, 0.0);
+        }
+    }
+    match bins.last_mut() {
+        Some(last) => {
+            last.0 += open.0;
+            last.1 += open.1;
+        }
+        None => bins.push(open),
+    }
+
+    let statistic = bins
+        .iter()
+        .map(|(observed, expected)| (observed - expected).powi(2) / expected)
+        .sum::<f64>();
+    let dof = bins.len().saturating_sub(1);
+    ChiSquare {
+        statistic,
+        dof,
+        p_value: chi_square_sf(statistic, dof),
+    }
+}
+
+/// Pearson's chi-square test of whether two histograms over the same buckets
+/// are draws from one distribution. Buckets are pooled like `chi_square`'s,
+/// until the two would expect enough in each. Histograms of different sizes
+/// are weighed against each other by their totals.
+///
+/// With totals A and B, the statistic, the sum over bins of
+/// (√(B/A) aᵢ - √(A/B) bᵢ)² / (aᵢ + bᵢ), is the same as Pearson's for the
+/// two-by-k table of the counts. That has (2 - 1)(k - 1) degrees of freedom
+/// with the totals taken as given, whether or not they're equal, so k bins
+/// have k - 1. An empty histogram gives no evidence either way: the test
+/// has no degrees of freedom and a p-value of 1.
+pub fn chi_square_between(a: &[u64], b: &[u64]) -> ChiSquare {
+    let (n_a, n_b) = (
+        total(a.iter().copied()) as f64,
+        total(b.iter().copied()) as f64,
+    );
+    if n_a == 0.0 || n_b == 0.0 {
+        return ChiSquare {
+            statistic: 0.0,
+            dof: 0,
+            p_value: 1.0,
+        };
+    }
+    // 1 for histograms of one size, when each bucket is expected to hold
+    // half of what the two hold between them.
+    let (scale_a, scale_b) = ((n_b / n_a).sqrt(), (n_a / n_b).sqrt());
+    let mut bins: Vec<(f64, f64)> = vec![];
+    let mut open = (0.0, 0.0);
+    for (a, b) in a.iter().zip(b) {
+        open.0 += *a as f64;
+        open.1 += *b as f64;
+        if open.0 + open.1 >= 2.0 * MIN_EXPECTED {
+            bins.push(open);
+            open = (0.0, 0.0);
+        }
+    }
+    match bins.last_mut() {
+        Some(last) => {
+            last.0 += open.0;
+            last.1 += open.1;
+        }
+        None => bins.push(open),
+    }
+
+    let statistic = bins
+        .iter()
+        .filter(|(a, b)| a + b > 0.0)
+        .map(|(a, b)| (scale_a * a - scale_b * b).powi(2) / (a + b))
+        .sum::<f64>();
+    let dof = bins.len().saturating_sub(1);
+    ChiSquare {
+        statistic,
+        dof,
+        p_value: chi_square_sf(statistic, dof),
+    }
+}
+
+/// How far apart two histograms over the same buckets are in each bucket:
+/// the difference of the bucket's shares of their samples, in standard
+/// errors of that difference. Buckets neither has samples in have none, nor
+/// does any bucket if either histogram is empty.
+pub fn two_sample_residuals(a: &[u64], b: &[u64]) -> Vec<Option<f64>> {
+    let (n_a, n_b) = (
+        total(a.iter().copied()) as f64,
+        total(b.iter().copied()) as f64,
+    );
+    if n_a == 0.0 || n_b == 0.0 {
+        return vec![None; a.len().min(b.len())];
+    }
+    a.iter()
+        .zip(b)
+        .map(|(&a, &b)| {
+            let pooled = (a + b) as f64 / (n_a + n_b);
+            let se = (pooled * (1.0 - pooled) * (1.0 / n_a + 1.0 / n_b)).sqrt();
+            (se > 0.0).then(|| (a as f64 / n_a - b as f64 / n_b) / se)
+        })
+        .collect()
+}
+
+/// The chance of a two-sample KS distance of at least `distance` between
+/// histograms of `n_a` and `n_b` samples from one distribution, from the
+/// Kolmogorov distribution with Stephens' small-sample correction. Over
+/// buckets rather than single values, it's an overestimate.
+pub fn ks_two_sample_p(distance: f64, n_a: u64, n_b: u64) -> f64 {
+    if n_a == 0 || n_b == 0 {
+        return 1.0;
+    }
+    let n = (n_a as f64 * n_b as f64 / (n_a + n_b) as f64).sqrt();
+    kolmogorov_sf((n + 0.12 + 0.11 / n) * distance)
+}
+
+/// The chance that the Kolmogorov distribution is above `x`.
+fn kolmogorov_sf(x: f64) -> f64 {
+    // The series converges slowly below this, where the chance is all but 1.
+    if x < 0.2 {
+        return 1.0;
+    }
+    let mut sum = 0.0;
+    for k in 1..=100 {
+        let k = k as f64;
+        let term = (-2.0 * k * k * x * x).exp();
+        sum += if k % 2.0 == 1.0 { term } else { -term };
+        if term < 1e-16 {
+            break;
+        }
+    }
+    (2.0 * sum).clamp(0.0, 1.0)
+}
+
+/// Probability that a chi-square variable with `dof` degrees of freedom is at
+/// least `x`: the regularized upper incomplete gamma function Q(dof/2, x/2).
+pub fn chi_square_sf(x: f64, dof: usize) -> f64 {
+    if x <= 0.0 || dof == 0 {
+        return 1.0;
+    }
+    let (a, x) = (dof as f64 / 2.0, x / 2.0);
+    let front = (a * x.ln() - x - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // Series for the lower function P, converging quickly for small x.
+        let (mut term, mut sum) = (1.0 / a, 1.0 / a);
+        for n in 1..500 {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term < sum * 1e-15 {
+                break;
+            }
+        }
+        (1.0 - front * sum).max(0.0)
+    } else {
+        // Lentz's continued fraction for Q.
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for n in 1..500 {
+            let an = -(n as f64) * (n as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            d = if d.abs() < tiny { tiny } else { d };
+            c = b + an / c;
+            c = if c.abs() < tiny { tiny } else { c };
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        front * h
+    }
+}
+
+/// Lanczos approximation (g = 7), accurate to about 15 digits for x > 0.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection keeps the approximation in its accurate range.
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| {
+            sum + c / (x + i as f64 + 1.0)
+        });
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+/// Abramowitz & Stegun 7.1.26, accurate to about 1.5e-7.
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    y.copysign(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Generator;
+    use rand::Rng;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * a.abs().max(1.0)
+    }
+
+    /// Pearson's statistic for the two-by-k table, worked out from the
+    /// expected counts.
+    fn contingency(a: &[u64], b: &[u64]) -> f64 {
+        let (n_a, n_b) = (
+            total(a.iter().copied()) as f64,
+            total(b.iter().copied()) as f64,
+        );
+        a.iter()
+            .zip(b)
+            .map(|(&a, &b)| {
+                let column = (a + b) as f64;
+                let expected_a = n_a * column / (n_a + n_b);
+                let expected_b = n_b * column / (n_a + n_b);
+                (a as f64 - expected_a).powi(2) / expected_a
+                    + (b as f64 - expected_b).powi(2) / expected_b
+            })
+            .sum()
+    }
+
+    #[test]
+    fn chi_square_between_a_histogram_and_itself_is_zero() {
+        let a = [10, 40, 80, 40, 10];
+        let test = chi_square_between(&a, &a);
+        assert_eq!(test.statistic, 0.0);
+        assert_eq!(test.dof, 4);
+        assert_eq!(test.p_value, 1.0);
+    }
+
+    #[test]
+    fn chi_square_between_is_pearsons_for_unequal_totals() {
+        let (a, b) = ([12, 45, 70, 38, 15], [30, 95, 160, 70, 25]);
+        let test = chi_square_between(&a, &b);
+        assert!(close(test.statistic, contingency(&a, &b)));
+        assert_eq!(test.dof, a.len() - 1);
+        // A histogram scaled up is the same distribution.
+        let doubled = a.map(|count| 2 * count);
+        assert!(chi_square_between(&a, &doubled).statistic.abs() < 1e-9);
+    }
+
+    #[test]
+    fn chi_square_between_tells_disjoint_histograms_apart() {
+        let test = chi_square_between(&[100, 100, 0, 0], &[0, 0, 100, 100]);
+        assert!(test.p_value < 1e-10, "p = {}", test.p_value);
+    }
+
+    #[test]
+    fn chi_square_between_pools_sparse_buckets() {
+        // The tails hold too few to stand alone, so the left one joins the
+        // bucket after it and the right one the bucket before: two bins.
+        let test = chi_square_between(&[1, 2, 50, 50, 2, 1], &[2, 1, 50, 50, 1, 2]);
+        assert_eq!(test.dof, 1);
+    }
+
+    #[test]
+    fn an_empty_histogram_gives_no_evidence() {
+        let test = chi_square_between(&[0, 0, 0], &[5, 10, 5]);
+        assert_eq!((test.statistic, test.dof, test.p_value), (0.0, 0, 1.0));
+        assert_eq!(two_sample_residuals(&[0, 0, 0], &[5, 10, 5]), vec![None; 3]);
+    }
+
+    #[test]
+    fn two_sample_residuals_are_signed_standard_errors() {
+        let residuals = two_sample_residuals(&[60, 40, 0], &[40, 60, 0]);
+        let (up, down) = (residuals[0].unwrap(), residuals[1].unwrap());
+        // a has 0.6 against b's 0.4, with a pooled share of a half.
+        let se = (0.5 * 0.5 * (1.0 / 100.0 + 1.0 / 100.0_f64)).sqrt();
+        assert!(close(up, 0.2 / se));
+        assert!(close(down, -up));
+        assert_eq!(residuals[2], None);
+    }
+
+    #[test]
+    fn ks_between_is_the_largest_gap_in_the_cdfs() {
+        assert_eq!(ks_between(&[0.5, 0.5], &[0.5, 0.5]), 0.0);
+        assert!(close(ks_between(&[1.0, 0.0, 0.0], &[0.0, 0.0, 1.0]), 1.0));
+        assert!(close(ks_between(&[0.2, 0.3, 0.5], &[0.3, 0.4, 0.3]), 0.2));
+    }
+
+    fn flips(generator: Generator, n: usize) -> Vec<bool> {
+        let mut rng = generator.seeded(174);
+        (0..n).map(|_| rng.gen_bool(0.5)).collect()
+    }
+
+    #[test]
+    fn good_generators_pass_the_coin_tests() {
+        for generator in Generator::ALL.into_iter().filter(|g| !g.flawed()) {
+            let flips = flips(generator, 100_000);
+            assert!(frequency_test(&flips) > 1e-6, "{}", generator.name());
+            assert!(runs_test(&flips) > 1e-6, "{}", generator.name());
+        }
+    }
+
+    #[test]
+    fn a_short_period_fails_the_coin_tests() {
+        let flips = flips(Generator::BadLcg, 100_000);
+        assert!(frequency_test(&flips).min(runs_test(&flips)) < 1e-6);
+    }
+
+    #[test]
+    fn the_coin_tests_catch_what_they_look_for() {
+        // Heads too often, with runs as independent flips of it would give.
+        let biased = (0..10_000).map(|i| i % 5 != 0).collect::<Vec<_>>();
+        assert!(frequency_test(&biased) < 1e-6);
+        // Half heads, but alternating, or in long runs.
+        let alternating = (0..10_000).map(|i| i % 2 == 0).collect::<Vec<_>>();
+        assert!(frequency_test(&alternating) > 0.999);
+        assert!(runs_test(&alternating) < 1e-6);
+        let blocks = (0..10_000).map(|i| i / 10 % 2 == 0).collect::<Vec<_>>();
+        assert!(runs_test(&blocks) < 1e-6);
+        // Nothing to go on.
+        assert_eq!(frequency_test(&[]), 1.0);
+        assert_eq!(runs_test(&[true]), 1.0);
+        assert_eq!(runs_test(&[true; 100]), 0.0);
+    }
+
+    #[test]
+    fn a_shares_relative_error_is_the_binomials() {
+        for (count, total) in [(1, 2), (50, 1000), (9_999, 10_000), (3, 1_000_000)] {
+            let (p, n) = (count as f64 / total as f64, total as f64);
+            let closed_form = (p * (1.0 - p) / n).sqrt() / p;
+            assert!(close(relative_error(count, total), closed_form));
+        }
+        assert_eq!(relative_error(7, 7), 0.0);
+        assert_eq!(relative_error(0, 7), f64::INFINITY);
+    }
+
+    #[test]
+    fn buckets_converge_once_their_error_is_within_the_target() {
+        // √(0.99/10⁴) ≈ 0.00995 and √(0.01/10⁶) = 0.0001 are within 1%, but
+        // √(1/100) is 10%, and the empty bucket isn't counted.
+        let counts = [0, 10_000, 100, 1_000_000];
+        assert_eq!(converged(&counts, 0.01), (2, 3));
+        assert!(close(converged_percent(&counts, 0.01), 200.0 / 3.0));
+        assert_eq!(converged(&counts, 0.1), (3, 3));
+        assert!(converged_percent(&[0, 0], 0.01).is_nan());
+    }
+
+    #[test]
+    fn relative_errors_are_signed_against_the_expected_count() {
+        let p = [0.25, 0.5, 0.25];
+        let errors = relative_errors(&[25, 50, 25], &p, 5.0);
+        assert_eq!(errors, [Some(0.0), Some(0.0), Some(0.0)]);
+        let errors = relative_errors(&[20, 50, 30], &p, 5.0);
+        assert!(close(errors[0].unwrap(), -0.2) && close(errors[2].unwrap(), 0.2));
+        // 2.5 expected is too few to go on, and nothing is expected of none.
+        assert_eq!(
+            relative_errors(&[2, 4, 4], &p, 5.0),
+            [None, Some(-0.2), None]
+        );
+        assert_eq!(relative_errors(&[0, 0, 0], &p, 0.0), [None, None, None]);
+        assert_eq!(
+            relative_errors(&[3, 0], &[1.0, 0.0], 0.0),
+            [Some(0.0), None]
+        );
+    }
+
+    #[test]
+    fn counts_near_the_limit_saturate() {
+        let max = u64::MAX;
+        assert_eq!(total([max - 1, 1, 1]), max);
+        assert_eq!(share(max, max), 1.0);
+        assert_eq!(share(max, 0), 0.0);
+
+        let buckets = [(-1.0, max), (1.0, max - 1)];
+        let stats = Stats::compute(&buckets, 0.0, 1.0, 2.0);
+        assert_eq!(stats.total, max);
+        assert!(stats.mean.abs() < 1e-9 && close(stats.sd, 1.0));
+        assert!(stats.ks.is_finite() && stats.ks <= 1.0, "{:?}", stats);
+        assert!(close(fraction_within(&buckets, -1.0, 0.5), 1.0));
+        let points = qq_points(&buckets, 0.0, 1.0);
+        assert!(points.iter().all(|(z, x)| z.is_finite() && x.is_finite()));
+
+        let test = chi_square_between(&[max, max], &[max, 1]);
+        assert!(test.statistic.is_finite() && (0.0..=1.0).contains(&test.p_value));
+    }
+
+    #[test]
+    fn every_sample_alike_is_one_value() {
+        assert_eq!(single_value(&[(-1.0, 0), (1.0, 5), (3.0, 0)]), Some(1.0));
+        // One sample can't have spread out, and none isn't a value.
+        assert_eq!(single_value(&[(-1.0, 0), (1.0, 1)]), None);
+        assert_eq!(single_value(&[(-1.0, 0), (1.0, 0)]), None);
+        assert_eq!(single_value(&[(-1.0, 1), (1.0, 5)]), None);
+    }
+
+    #[test]
+    fn a_zero_spread_is_measured_without_dividing_by_it() {
+        let buckets = [(-1.0, 0), (1.0, 5), (3.0, 0)];
+        let stats = Stats::compute(&buckets, 1.0, 0.0, 2.0);
+        assert_eq!((stats.total, stats.mean, stats.sd), (5, 1.0, 0.0));
+        assert_eq!(stats.ks, 0.0);
+        // Off the point mass, it's all of the distance.
+        assert_eq!(Stats::compute(&buckets, 3.0, 0.0, 2.0).ks, 1.0);
+        let qq = qq_points(&buckets, 1.0, 0.0);
+        assert!(
+            qq.iter().all(|(x, y)| x.is_finite() && *y == 0.0),
+            "{:?}",
+            qq
+        );
+    }
+}
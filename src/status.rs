@@ -0,0 +1,104 @@
+//! `--status-file`: a small JSON heartbeat, rewritten every few ticks, for
+//! keeping an eye on unattended runs from scripts.
+
+use crate::{history::Metric, json::Value, App};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub const DEFAULT_EVERY: u64 = 10;
+
+#[derive(Debug)]
+pub struct StatusFile {
+    path: PathBuf,
+    every: u64,
+    /// Samples drawn over the whole run, and when and how many there had
+    /// been at the last write, for the recent rate.
+    drawn: u64,
+    last_write: (Instant, u64),
+    failing: bool,
+}
+
+impl StatusFile {
+    /// Writes to `path` every `every` ticks; 0 is taken as 1.
+    pub fn new(path: PathBuf, every: u64) -> StatusFile {
+        StatusFile {
+            path,
+            every: every.max(1),
+            drawn: 0,
+            last_write: (Instant::now(), 0),
+            failing: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Counts a finished tick of `app`, rewriting the file if it's due. An
+    /// error is only returned the first time in a row writing fails, so a
+    /// full disk is reported once rather than every few ticks.
+    pub fn record(&mut self, app: &App) -> Option<io::Error> {
+        self.drawn += app.b_count as u64;
+        if !app.tick.is_multiple_of(self.every) {
+            return None;
+        }
+        match self.write(app) {
+            Ok(()) => {
+                self.failing = false;
+                None
+            }
+            Err(err) => (!std::mem::replace(&mut self.failing, true)).then_some(err),
+        }
+    }
+
+    /// Writes a temporary file beside the status file and renames it over
+    /// it, so readers see the old contents or the new, never half of them.
+    fn write(&mut self, app: &App) -> io::Result<()> {
+        let (since, drawn_then) = self.last_write;
+        let secs = since.elapsed().as_secs_f64();
+        // Written as null if no time has passed.
+        let rate = (self.drawn - drawn_then) as f64 / secs;
+        self.last_write = (Instant::now(), self.drawn);
+
+        let stats = app.stats();
+        let peak = app.data.iter().map(|bucket| bucket.count).max();
+        let updated = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let mut fields = vec![
+            ("tick", app.tick.into()),
+            ("samples", stats.total.into()),
+            ("drawn", self.drawn.into()),
+            ("samples_per_sec", rate.into()),
+            ("r_max", app.r_max.into()),
+            ("mean", stats.mean.into()),
+            ("sd", stats.sd.into()),
+            ("ks", stats.ks.into()),
+            ("peak", peak.unwrap_or(0).into()),
+            ("updated", updated.into()),
+        ];
+        if app.precision_shown() {
+            let converged = app.history.latest(Metric::Converged);
+            fields.push(("buckets_converged", converged.unwrap_or(f64::NAN).into()));
+        }
+        let status = Value::object(fields);
+
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        fs::write(&temporary, format!("{}\n", status))?;
+        fs::rename(&temporary, &self.path)
+    }
+
+    /// Removes the file at the end of a run, so a file that's left means the
+    /// run didn't end cleanly.
+    pub fn remove(&self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                log::warn!("could not remove {}: {}", self.path.display(), err);
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! JSON-lines tick events, written to a file and/or served over a socket.
+
+use crate::{alert::Condition, json::Value};
+use central_limit::{bucket::View, sampling};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+/// A tick's event, `at` seconds after the stream started.
+pub fn tick_event<'a>(
+    tick: u64,
+    at: Duration,
+    b_count: usize,
+    r_max: i32,
+    bias: f64,
+    buckets: impl Iterator<Item = View<'a>> + Clone,
+) -> String {
+    let mut fields = vec![
+        ("tick", tick.into()),
+        // To the millisecond, which is as finely as ticks can be set apart.
+        ("at", (at.as_millis() as f64 / 1000.0).into()),
+        ("samples", b_count.into()),
+        ("r_max", r_max.into()),
+    ];
+    // Left out for a fair coin, as recorded before there was a bias.
+    if bias != sampling::FAIR {
+        fields.push(("bias", bias.into()));
+    }
+    fields.extend([
+        (
+            "buckets",
+            buckets
+                .clone()
+                .map(|bucket| bucket.label())
+                .collect::<Vec<_>>()
+                .into(),
+        ),
+        (
+            "counts",
+            buckets
+                .map(|bucket| bucket.count())
+                .collect::<Vec<_>>()
+                .into(),
+        ),
+    ]);
+    Value::object(fields).to_string()
+}
+
+/// Marks the tick a `--notify-when` condition first held. Replay skips it.
+pub fn alert_event(tick: u64, condition: &Condition) -> String {
+    Value::object(vec![
+        ("event", "alert".into()),
+        ("tick", tick.into()),
+        ("condition", condition.to_string().into()),
+    ])
+    .to_string()
+}
+
+pub struct Streams {
+    file: Option<BufWriter<File>>,
+    socket: Option<SocketServer>,
+}
+
+impl Streams {
+    pub fn open(stream: Option<&Path>, socket: Option<&Path>) -> io::Result<Streams> {
+        let file = match stream {
+            Some(path) => Some(BufWriter::new(File::create(path)?)),
+            None => None,
+        };
+        let socket = match socket {
+            Some(path) => Some(SocketServer::bind(path)?),
+            None => None,
+        };
+
+        Ok(Streams { file, socket })
+    }
+
+    pub fn publish(&mut self, event: &str) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            writeln!(file, "{}", event)?;
+            file.flush()?;
+        }
+        if let Some(socket) = &mut self.socket {
+            socket.broadcast(event);
+        }
+        Ok(())
+    }
+
+    /// Number of connected socket clients, if a socket is being served.
+    pub fn clients(&self) -> Option<usize> {
+        self.socket.as_ref().map(SocketServer::clients)
+    }
+}
+
+#[cfg(unix)]
+pub use unix::SocketServer;
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        fs,
+        io::{self, Write},
+        os::unix::{
+            fs::FileTypeExt,
+            net::{UnixListener, UnixStream},
+        },
+        path::{Path, PathBuf},
+    };
+
+    pub struct SocketServer {
+        path: PathBuf,
+        listener: UnixListener,
+        clients: Vec<UnixStream>,
+    }
+
+    impl SocketServer {
+        pub fn bind(path: &Path) -> io::Result<SocketServer> {
+            // A socket left behind by a previous run would make bind fail.
+            if let Ok(meta) = fs::symlink_metadata(path) {
+                if meta.file_type().is_socket() {
+                    fs::remove_file(path)?;
+                }
+            }
+
+            let listener = UnixListener::bind(path)?;
+            listener.set_nonblocking(true)?;
+            log::info!("serving tick events on {}", path.display());
+
+            Ok(SocketServer {
+                path: path.to_path_buf(),
+                listener,
+                clients: vec![],
+            })
+        }
+
+        fn accept(&mut self) {
+            loop {
+                match self.listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(err) = stream.set_nonblocking(true) {
+                            log::warn!("could not configure socket client: {}", err);
+                            continue;
+                        }
+                        self.clients.push(stream);
+                        log::info!("socket client connected ({} total)", self.clients.len());
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(err) => {
+                        log::warn!("socket accept failed: {}", err);
+                        break;
+                    }
+                }
+            }
+        }
+
+        /// Sends one event line to every client. Clients that can't take the
+        /// whole line without blocking are dropped so the simulation never waits.
+        pub fn broadcast(&mut self, event: &str) {
+            self.accept();
+
+            let line = format!("{}\n", event);
+            self.clients
+                .retain_mut(|client| match client.write_all(line.as_bytes()) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        log::info!("socket client disconnected: {}", err);
+                        false
+                    }
+                });
+        }
+
+        pub fn clients(&self) -> usize {
+            self.clients.len()
+        }
+    }
+
+    impl Drop for SocketServer {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub struct SocketServer;
+
+#[cfg(not(unix))]
+impl SocketServer {
+    pub fn bind(_path: &std::path::Path) -> io::Result<SocketServer> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "--socket is not supported on this platform",
+        ))
+    }
+
+    pub fn broadcast(&mut self, _event: &str) {}
+
+    pub fn clients(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::{json, App};
+    use central_limit::rng::Generator;
+    use std::{
+        env, fs,
+        io::{BufRead, BufReader},
+        os::unix::net::UnixStream,
+        process,
+    };
+
+    #[test]
+    fn socket_clients_get_each_tick_as_a_line() {
+        let dir = env::temp_dir().join(format!("central-limit-socket-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ticks.sock");
+        let mut streams = Streams::open(None, Some(&path)).unwrap();
+        let client = UnixStream::connect(&path).unwrap();
+
+        let mut app = App::new(42, Generator::Std);
+        app.set_params(100, 10).unwrap();
+        let mut sent = vec![];
+        for tick in 1..=2 {
+            app.on_tick();
+            let event = tick_event(
+                tick,
+                Duration::from_millis(250 * tick),
+                app.b_count,
+                app.r_max,
+                app.bias(),
+                app.sim.buckets(),
+            );
+            streams.publish(&event).unwrap();
+            sent.push(app.data.clone());
+        }
+        assert_eq!(streams.clients(), Some(1));
+
+        let mut lines = BufReader::new(client).lines();
+        for (tick, data) in (1..=2).zip(sent) {
+            let event = json::parse(&lines.next().unwrap().unwrap()).unwrap();
+            let field = |name| event.get(name).and_then(json::Value::as_f64);
+            assert_eq!(field("tick"), Some(tick as f64));
+            assert_eq!(field("at"), Some(0.25 * tick as f64));
+            assert_eq!(field("samples"), Some(100.0));
+            assert_eq!(field("r_max"), Some(10.0));
+            assert!(event.get("bias").is_none());
+            let counts = event.get("counts").and_then(json::Value::as_array).unwrap();
+            let counts = counts.iter().map(|count| count.as_u64().unwrap());
+            assert!(counts.eq(data.iter().map(|bucket| bucket.count)));
+        }
+
+        // The socket goes with the server.
+        drop(streams);
+        assert!(!path.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+//! Textual description of the histogram, for screen readers and plain-text output.
+
+use crate::{
+    bucket::Bucket,
+    format,
+    i18n::Strings,
+    stats::{share, Stats},
+};
+
+const TOP_BUCKETS: usize = 5;
+
+// Changes smaller than these are not worth announcing again.
+const PERCENT_STEP: f64 = 0.5;
+const MEAN_STEP: f64 = 0.05;
+const SD_STEP: f64 = 0.01;
+const KS_STEP: f64 = 0.005;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    top: Vec<(String, u64, f64)>,
+    stats: Stats,
+}
+
+impl Summary {
+    pub fn new(data: &[Bucket], stats: Stats) -> Summary {
+        let mut top = data
+            .iter()
+            .map(|bucket| {
+                let percent = share(bucket.count, stats.total) * 100.0;
+                (bucket.label.clone(), bucket.count, percent)
+            })
+            .collect::<Vec<_>>();
+        top.sort_by_key(|b| std::cmp::Reverse(b.1));
+        top.truncate(TOP_BUCKETS);
+
+        Summary { top, stats }
+    }
+
+    /// Whether `other` differs enough from this summary to be worth re-reading.
+    pub fn differs_from(&self, other: &Summary) -> bool {
+        let (a, b) = (&self.stats, &other.stats);
+        self.top.len() != other.top.len()
+            || self
+                .top
+                .iter()
+                .zip(&other.top)
+                .any(|(x, y)| x.0 != y.0 || (x.2 - y.2).abs() >= PERCENT_STEP)
+            || (a.mean - b.mean).abs() >= MEAN_STEP
+            || (a.sd - b.sd).abs() >= SD_STEP * a.sd
+            || (a.ks - b.ks).abs() >= KS_STEP
+            || a.total == 0 && b.total != 0
+    }
+
+    pub fn lines(&self, strings: &Strings) -> Vec<String> {
+        if self.stats.total == 0 {
+            return vec![strings.get("summary.no_samples").to_string()];
+        }
+
+        let mut lines = vec![strings.get("summary.largest").to_string()];
+        for (label, count, percent) in &self.top {
+            lines.push(strings.fill(
+                "summary.bucket",
+                &[
+                    ("label", label),
+                    ("count", &format::grouped(*count)),
+                    ("percent", &format!("{:.1}", percent)),
+                ],
+            ));
+        }
+        // One sample has no spread to speak of.
+        let sd = match self.stats.total {
+            1 => format::UNDEFINED.to_string(),
+            _ => format!("{:.2}", self.stats.sd),
+        };
+        lines.push(strings.fill(
+            "summary.samples",
+            &[
+                ("samples", &format::grouped(self.stats.total)),
+                ("mean", &format!("{:.2}", self.stats.mean)),
+                ("sd", &sd),
+            ],
+        ));
+
+        let shape = if self.stats.ks < 0.02 {
+            "summary.approximately_normal"
+        } else if self.stats.ks < 0.1 {
+            "summary.roughly_normal"
+        } else {
+            "summary.far_from_normal"
+        };
+        lines.push(strings.fill(
+            "summary.shape",
+            &[
+                ("shape", &strings.get(shape)),
+                ("ks", &format!("{:.3}", self.stats.ks)),
+            ],
+        ));
+
+        lines
+    }
+}
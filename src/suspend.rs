@@ -0,0 +1,131 @@
+//! Ctrl+Z in the TUI. Raw mode delivers it as a key rather than as SIGTSTP,
+//! so it's taken here: the terminal is put back as the shell wants it, the
+//! process stops itself, and once it's continued the terminal is set up
+//! again and redrawn in full. The time away counts as paused: the ticks are
+//! held back to their interval, and the toast on screen, the next autosave
+//! and the `--target` rate are put off by it. The debug readout's timings
+//! and a `--start-at` time keep to the clock. A SIGTSTP from elsewhere is
+//! taken the same way between frames. A SIGSTOP can't be caught, so it's
+//! only noticed by the SIGCONT that follows it. That has the terminal set
+//! up again and the ticks held back, but how long the process was away
+//! isn't known, so nothing else is put off.
+//!
+//! There's no job control off Unix, so there Ctrl+Z is an unbound key.
+
+use crate::{pace::Pace, term, App};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use std::{
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+use tui::{backend::Backend, Terminal};
+
+static STOP: AtomicBool = AtomicBool::new(false);
+static CONTINUED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a SIGTSTP has asked the process to stop since this was last asked.
+pub fn requested() -> bool {
+    STOP.swap(false, Ordering::Relaxed)
+}
+
+/// Whether the process has been stopped from outside, by a SIGSTOP, and
+/// continued since this was last asked.
+pub fn continued() -> bool {
+    CONTINUED.swap(false, Ordering::Relaxed)
+}
+
+pub fn is_key(event: &Event) -> bool {
+    cfg!(unix)
+        && matches!(event, Event::Key(key) if key.kind == KeyEventKind::Press
+            && key.code == KeyCode::Char('z')
+            && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+/// Stops the process with the terminal as it was before the TUI started,
+/// and once it's continued sets it up again, redraws it in full and puts
+/// off what's timed by the clock, as if the time away had been paused.
+pub fn run<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    pace: &mut Pace,
+    tick_rate: Duration,
+) -> io::Result<()> {
+    let stopped = Instant::now();
+    term::suspend();
+    stop();
+    let away = stopped.elapsed();
+    resume(terminal, pace, tick_rate)?;
+    app.toasts.delay(away);
+    if let Some(autosave) = &mut app.autosave {
+        autosave.delay(away);
+    }
+    if let Some(target) = &mut app.target {
+        target.delay(away);
+    }
+    Ok(())
+}
+
+/// Sets the terminal up again after the process was continued, however it
+/// was stopped.
+pub fn resume<B: Backend>(
+    terminal: &mut Terminal<B>,
+    pace: &mut Pace,
+    tick_rate: Duration,
+) -> io::Result<()> {
+    term::resume()?;
+    // Forgets what was drawn, so the next frame is drawn cell by cell over
+    // whatever the shell left on the screen.
+    terminal.clear()?;
+    pace.hold(Instant::now(), tick_rate);
+    Ok(())
+}
+
+#[cfg(unix)]
+extern "C" fn handle_stop(_signal: libc::c_int) {
+    STOP.store(true, Ordering::Relaxed);
+}
+
+#[cfg(unix)]
+extern "C" fn handle_continue(_signal: libc::c_int) {
+    CONTINUED.store(true, Ordering::Relaxed);
+}
+
+#[cfg(unix)]
+pub fn install() {
+    // SAFETY: the handlers only store to atomics, which is async-signal-safe.
+    unsafe {
+        libc::signal(
+            libc::SIGTSTP,
+            handle_stop as *const () as libc::sighandler_t,
+        );
+        libc::signal(
+            libc::SIGCONT,
+            handle_continue as *const () as libc::sighandler_t,
+        );
+    }
+}
+
+/// Stops the process as the shell's own Ctrl+Z would, returning once it's
+/// continued.
+#[cfg(unix)]
+fn stop() {
+    // SAFETY: as for `install`; with the default action back, raising
+    // SIGTSTP stops the process until a SIGCONT.
+    unsafe {
+        libc::signal(libc::SIGTSTP, libc::SIG_DFL);
+        libc::raise(libc::SIGTSTP);
+        libc::signal(
+            libc::SIGTSTP,
+            handle_stop as *const () as libc::sighandler_t,
+        );
+    }
+    // The continue is this stop's, and has been dealt with.
+    CONTINUED.store(false, Ordering::Relaxed);
+}
+
+#[cfg(not(unix))]
+pub fn install() {}
+
+#[cfg(not(unix))]
+fn stop() {}
@@ -0,0 +1,176 @@
+//! `central-limit sweep`: how the distribution's shape converges as the walk
+//! length grows, one row per step count.
+
+use crate::{export::Format, interrupt, json::Value};
+use central_limit::{
+    rng::{self, Generator},
+    simulation::Simulation,
+    stats,
+};
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+pub const DEFAULT_STEPS: [i32; 7] = [1, 2, 5, 10, 20, 50, 100];
+pub const DEFAULT_SAMPLES: usize = 100_000;
+
+/// Samples drawn between checks for Ctrl+C.
+const CHUNK: usize = 10_000;
+
+pub struct Config {
+    pub steps: Vec<i32>,
+    pub samples: usize,
+    pub seed: u64,
+    pub generator: Generator,
+    pub threads: usize,
+    pub format: Format,
+}
+
+struct Row {
+    steps: i32,
+    seed: u64,
+    ks: f64,
+    skewness: f64,
+    kurtosis: f64,
+}
+
+/// Runs the sweep and prints a row per step count, or the rows finished so
+/// far if interrupted. Returns false if it was interrupted.
+pub fn run<W: Write>(out: &mut W, config: &Config) -> io::Result<bool> {
+    interrupt::install();
+
+    let next = AtomicUsize::new(0);
+    let rows = Mutex::new(vec![]);
+    thread::scope(|scope| {
+        for _ in 0..config.threads.clamp(1, config.steps.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&steps) = config.steps.get(i) else {
+                    break;
+                };
+                if let Some(row) = measure(steps, config) {
+                    rows.lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push((i, row));
+                }
+            });
+        }
+    });
+
+    let mut rows = rows.into_inner().unwrap_or_else(|e| e.into_inner());
+    rows.sort_by_key(|(i, _)| *i);
+    let rows = rows.into_iter().map(|(_, row)| row).collect::<Vec<_>>();
+    match config.format {
+        Format::Text => write_table(out, config, &rows)?,
+        Format::Csv => write_csv(out, &rows)?,
+        Format::Json => writeln!(out, "{}", to_json(config, &rows))?,
+    }
+    out.flush()?;
+
+    let complete = rows.len() == config.steps.len();
+    if !complete {
+        eprintln!(
+            "interrupted: {} of {} step counts finished",
+            rows.len(),
+            config.steps.len()
+        );
+    }
+    Ok(complete)
+}
+
+fn measure(steps: i32, config: &Config) -> Option<Row> {
+    // Each step count gets its own seed, so a row doesn't depend on which
+    // others ran or in what order.
+    let seed = rng::derive_seed(config.seed, steps as u64);
+    let mut sim = Simulation::with_generator(steps, seed, config.generator).ok()?;
+    let mut drawn = 0;
+    while drawn < config.samples {
+        if interrupt::requested() {
+            return None;
+        }
+        let n = CHUNK.min(config.samples - drawn);
+        sim.advance(n);
+        drawn += n;
+    }
+
+    let buckets = sim
+        .buckets()
+        .map(|bucket| (bucket.center(), bucket.count()))
+        .collect::<Vec<_>>();
+    let (skewness, kurtosis) = stats::shape(&buckets);
+    Some(Row {
+        steps,
+        seed,
+        ks: sim.stats().ks,
+        skewness,
+        kurtosis,
+    })
+}
+
+fn write_table<W: Write>(out: &mut W, config: &Config, rows: &[Row]) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} samples per step count from {} (seed {})",
+        config.samples,
+        config.generator.name(),
+        config.seed
+    )?;
+    writeln!(
+        out,
+        "{:>6} {:>10} {:>10} {:>10} {:>10}",
+        "steps", "KS", "1/√n", "skewness", "kurtosis"
+    )?;
+    for row in rows {
+        writeln!(
+            out,
+            "{:>6} {:>10.5} {:>10.5} {:>10.5} {:>10.5}",
+            row.steps,
+            row.ks,
+            1.0 / (row.steps as f64).sqrt(),
+            row.skewness,
+            row.kurtosis
+        )?;
+    }
+    Ok(())
+}
+
+fn write_csv<W: Write>(out: &mut W, rows: &[Row]) -> io::Result<()> {
+    writeln!(out, "steps,seed,ks,skewness,kurtosis")?;
+    for row in rows {
+        writeln!(
+            out,
+            "{},{},{},{},{}",
+            row.steps, row.seed, row.ks, row.skewness, row.kurtosis
+        )?;
+    }
+    Ok(())
+}
+
+fn to_json(config: &Config, rows: &[Row]) -> Value {
+    Value::object(vec![
+        ("samples", config.samples.into()),
+        ("seed", config.seed.to_string().into()),
+        ("rng", config.generator.name().into()),
+        (
+            "rows",
+            Value::Array(
+                rows.iter()
+                    .map(|row| {
+                        Value::object(vec![
+                            ("steps", row.steps.into()),
+                            ("seed", row.seed.to_string().into()),
+                            ("ks", row.ks.into()),
+                            ("skewness", row.skewness.into()),
+                            ("kurtosis", row.kurtosis.into()),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+    ])
+}
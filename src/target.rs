@@ -0,0 +1,83 @@
+//! Progress toward a `--target` number of samples.
+
+use std::time::{Duration, Instant};
+
+/// Weight of the newest rate in the moving average.
+const SMOOTHING: f64 = 0.2;
+
+/// The rate counts as zero once no samples have arrived for this many
+/// tick intervals, e.g. while a replay is paused.
+const STALE_INTERVALS: u32 = 3;
+
+pub struct Target {
+    pub goal: u64,
+    /// Samples per second, exponentially smoothed.
+    rate: Option<f64>,
+    last: Option<(Instant, u64)>,
+    interval: Duration,
+}
+
+impl Target {
+    pub fn new(goal: u64) -> Target {
+        Target {
+            goal,
+            rate: None,
+            last: None,
+            interval: Duration::ZERO,
+        }
+    }
+
+    /// Records the running total of samples at `now`, returning whether this
+    /// is when it reached the goal.
+    pub fn record(&mut self, samples: u64, now: Instant) -> bool {
+        let reached = !self.done(self.last.map_or(0, |(_, before)| before)) && self.done(samples);
+        if let Some((then, before)) = self.last {
+            let elapsed = now.duration_since(then);
+            if !elapsed.is_zero() {
+                // Scrubbing a replay backwards counts as no progress.
+                let rate = samples.saturating_sub(before) as f64 / elapsed.as_secs_f64();
+                self.rate = Some(match self.rate {
+                    Some(average) => average + SMOOTHING * (rate - average),
+                    None => rate,
+                });
+                self.interval = elapsed;
+            }
+        }
+        self.last = Some((now, samples));
+        reached
+    }
+
+    /// Moves the last record on by `by`, time no samples could have come in,
+    /// so the rate isn't taken over it.
+    pub fn delay(&mut self, by: Duration) {
+        if let Some((then, _)) = &mut self.last {
+            *then += by;
+        }
+    }
+
+    pub fn done(&self, samples: u64) -> bool {
+        samples >= self.goal
+    }
+
+    /// Samples per second at `now`; zero once the samples stop coming.
+    pub fn rate(&self, now: Instant) -> f64 {
+        match (self.rate, self.last) {
+            (Some(rate), Some((then, _)))
+                if now.duration_since(then) <= self.interval * STALE_INTERVALS =>
+            {
+                rate
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Time left at the current rate, or `None` when there is no rate to go by.
+    pub fn eta(&self, samples: u64, now: Instant) -> Option<Duration> {
+        let rate = self.rate(now);
+        if rate <= 0.0 {
+            return None;
+        }
+        let left = self.goal.saturating_sub(samples) as f64;
+        Some(Duration::from_secs_f64(left / rate))
+    }
+}
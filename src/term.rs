@@ -0,0 +1,380 @@
+//! Putting the terminal into TUI mode and reliably getting it back out.
+
+use crossterm::{
+    cursor::{self, MoveTo, Show},
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+use std::{
+    fmt,
+    io::{self, Write},
+    panic,
+    sync::atomic::{AtomicU16, AtomicU8, Ordering},
+};
+use tui::{backend::Backend, buffer::Cell, layout::Rect};
+
+/// The smallest terminal the TUI is drawn in.
+pub const MIN_WIDTH: u16 = 40;
+pub const MIN_HEIGHT: u16 = 10;
+
+/// Rows the TUI takes when drawn inline, below the shell's output: few
+/// enough for the compact layout and to leave the history above in view.
+const INLINE_HEIGHT: u16 = 18;
+
+/// What the terminal turned out to support, found before anything is
+/// switched, so a terminal that can't run the TUI isn't left half set up.
+#[derive(Debug)]
+pub struct Probe {
+    pub term: Option<String>,
+    pub size: Result<(u16, u16), String>,
+    /// Whether raw mode could be turned on and straight back off.
+    pub raw_mode: Result<(), String>,
+}
+
+/// Why the TUI can't be started.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Unsupported {
+    /// The terminal works but is too small; worth telling the user rather
+    /// than running headless behind their back.
+    TooSmall(u16, u16),
+    /// The terminal can't draw the TUI at all.
+    Unusable(String),
+}
+
+/// Whether the TUI can be started, from whether stdin and stdout are
+/// terminals and from `TERM`. Only once neither rules it out is `probe` run,
+/// to try the terminal itself.
+pub fn check(
+    stdin: bool,
+    stdout: bool,
+    term: Option<&str>,
+    probe: impl FnOnce() -> Probe,
+) -> Result<(), Unsupported> {
+    if !stdin || !stdout {
+        return Err(Unsupported::Unusable(
+            "stdout or stdin is not a terminal".to_string(),
+        ));
+    }
+    if term == Some("dumb") {
+        return Err(Unsupported::Unusable("TERM is 'dumb'".to_string()));
+    }
+    probe().check()
+}
+
+impl Probe {
+    pub fn run(term: Option<&str>) -> Probe {
+        Probe {
+            term: term.map(str::to_string),
+            size: terminal::size().map_err(|err| err.to_string()),
+            raw_mode: enable_raw_mode()
+                .and_then(|()| disable_raw_mode())
+                .map_err(|err| err.to_string()),
+        }
+    }
+
+    /// Checks the results in order of what's most useful to hear first.
+    pub fn check(&self) -> Result<(), Unsupported> {
+        if let Err(err) = &self.raw_mode {
+            return Err(Unsupported::Unusable(format!(
+                "the terminal can't enter raw mode ({})",
+                err
+            )));
+        }
+        match self.size {
+            Err(ref err) => Err(Unsupported::Unusable(format!(
+                "the terminal's size can't be read ({})",
+                err
+            ))),
+            Ok((width, height)) if width < MIN_WIDTH || height < MIN_HEIGHT => {
+                Err(Unsupported::TooSmall(width, height))
+            }
+            Ok(_) => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.term {
+            Some(term) => write!(f, "TERM={}", term)?,
+            None => write!(f, "TERM unset")?,
+        }
+        match &self.size {
+            Ok((width, height)) => write!(f, ", size {}x{}", width, height)?,
+            Err(err) => write!(f, ", size unknown: {}", err)?,
+        }
+        match &self.raw_mode {
+            Ok(()) => write!(f, ", raw mode ok"),
+            Err(err) => write!(f, ", raw mode failed: {}", err),
+        }
+    }
+}
+
+const RAW: u8 = 1;
+const ALTERNATE_SCREEN: u8 = 2;
+const MOUSE: u8 = 4;
+const INLINE: u8 = 8;
+
+/// The setup stages that have succeeded and not been undone yet. Global so
+/// the panic hook can see them.
+static STAGES: AtomicU8 = AtomicU8::new(0);
+
+/// The first row of the inline viewport, for clearing it on the way out.
+static INLINE_TOP: AtomicU16 = AtomicU16::new(0);
+
+/// The stages undone by `suspend`, for `resume` to set up again.
+static SUSPENDED: AtomicU8 = AtomicU8::new(0);
+
+/// Holds the terminal in raw mode with mouse capture, on the alternate screen
+/// or in a viewport at the bottom of the main one. Dropping it, or panicking
+/// while it's alive, undoes whichever of those succeeded, in reverse order.
+pub struct Guard(());
+
+impl Guard {
+    /// Switches to the alternate screen, or with `inline`, or if the terminal
+    /// won't switch, reserves rows at the bottom of the main screen and
+    /// returns them.
+    pub fn enter(inline: bool) -> io::Result<(Guard, Option<Rect>)> {
+        install_panic_hook();
+        // From here on an early return drops the guard, undoing what's done.
+        let guard = Guard(());
+
+        enable_raw_mode()?;
+        STAGES.fetch_or(RAW, Ordering::SeqCst);
+        let viewport = match inline {
+            true => Some(reserve_inline()?),
+            false => match execute!(io::stdout(), EnterAlternateScreen) {
+                Ok(()) => {
+                    STAGES.fetch_or(ALTERNATE_SCREEN, Ordering::SeqCst);
+                    None
+                }
+                Err(err) => {
+                    log::warn!("no alternate screen ({}), drawing inline", err);
+                    Some(reserve_inline()?)
+                }
+            },
+        };
+        execute!(io::stdout(), EnableMouseCapture)?;
+        STAGES.fetch_or(MOUSE, Ordering::SeqCst);
+
+        Ok((guard, viewport))
+    }
+}
+
+/// A backend that draws into `area`, a band of rows of the main screen, as
+/// if it were the whole terminal. (tui's own fixed viewports lose their
+/// offset when a frame is drawn, so the offset is added here instead.) The
+/// band moves to wherever it's reserved again after a suspend.
+pub struct Inline<B> {
+    backend: B,
+    area: Rect,
+}
+
+impl<B: Backend> Inline<B> {
+    pub fn new(backend: B, area: Rect) -> Inline<B> {
+        Inline { backend, area }
+    }
+}
+
+impl<B: Backend> Backend for Inline<B> {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        let top = INLINE_TOP.load(Ordering::SeqCst);
+        self.backend
+            .draw(content.map(|(x, y, cell)| (x, y + top, cell)))
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.backend.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.backend.show_cursor()
+    }
+
+    fn get_cursor(&mut self) -> io::Result<(u16, u16)> {
+        let (x, y) = self.backend.get_cursor()?;
+        Ok((x, y.saturating_sub(INLINE_TOP.load(Ordering::SeqCst))))
+    }
+
+    fn set_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.backend
+            .set_cursor(x, y + INLINE_TOP.load(Ordering::SeqCst))
+    }
+
+    /// Blanks the band only, not the shell's output above it.
+    fn clear(&mut self) -> io::Result<()> {
+        let blank = Cell::default();
+        let Rect { width, height, .. } = self.area;
+        let cells = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+        self.draw(
+            cells
+                .map(|(x, y)| (x, y, &blank))
+                .collect::<Vec<_>>()
+                .into_iter(),
+        )
+    }
+
+    fn size(&self) -> io::Result<Rect> {
+        Ok(Rect::new(0, 0, self.area.width, self.area.height))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.backend.flush()
+    }
+}
+
+/// The screen row the TUI's first row is on: 0 on the alternate screen.
+pub fn viewport_top() -> u16 {
+    match STAGES.load(Ordering::SeqCst) & INLINE {
+        0 => 0,
+        _ => INLINE_TOP.load(Ordering::SeqCst),
+    }
+}
+
+/// Makes room for the inline viewport below the cursor, scrolling the
+/// screen up into the history if there isn't enough.
+fn reserve_inline() -> io::Result<Rect> {
+    let (width, rows) = terminal::size()?;
+    let (_, row) = cursor::position()?;
+    let height = INLINE_HEIGHT.min(rows);
+    let short = (row + height).saturating_sub(rows);
+    let mut stdout = io::stdout();
+    if short > 0 {
+        execute!(stdout, MoveTo(0, rows - 1))?;
+        for _ in 0..short {
+            stdout.write_all(b"\n")?;
+        }
+        stdout.flush()?;
+    }
+    let top = row - short;
+    INLINE_TOP.store(top, Ordering::SeqCst);
+    STAGES.fetch_or(INLINE, Ordering::SeqCst);
+    // Whatever was below the cursor would show through the first frame.
+    execute!(stdout, MoveTo(0, top), Clear(ClearType::FromCursorDown))?;
+    Ok(Rect::new(0, top, width, height))
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Undoes the recorded stages. Errors are ignored: this runs on the way out
+/// and there is nothing better to do than carry on with the next stage.
+fn restore() {
+    let stages = STAGES.swap(0, Ordering::SeqCst);
+    let mut stdout = io::stdout();
+    if stages & MOUSE != 0 {
+        let _ = execute!(stdout, DisableMouseCapture);
+    }
+    if stages & ALTERNATE_SCREEN != 0 {
+        let _ = execute!(stdout, LeaveAlternateScreen);
+    }
+    // Only the viewport is cleared; the shell's output above it stays.
+    if stages & INLINE != 0 {
+        let top = INLINE_TOP.load(Ordering::SeqCst);
+        let _ = execute!(stdout, MoveTo(0, top), Clear(ClearType::FromCursorDown));
+    }
+    if stages & RAW != 0 {
+        let _ = disable_raw_mode();
+    }
+    if stages != 0 {
+        let _ = execute!(stdout, Show);
+    }
+}
+
+/// Puts the terminal back as the shell wants it while the process is
+/// stopped, remembering what `resume` has to set up again.
+pub fn suspend() {
+    SUSPENDED.store(STAGES.load(Ordering::SeqCst), Ordering::SeqCst);
+    restore();
+}
+
+/// Sets up again what `suspend` undid, or after a stop that didn't go
+/// through it, what the shell may have undone. An inline viewport is
+/// reserved afresh below wherever the shell left the cursor.
+pub fn resume() -> io::Result<()> {
+    let stages = SUSPENDED.swap(0, Ordering::SeqCst) | STAGES.swap(0, Ordering::SeqCst);
+    let mut stdout = io::stdout();
+    if stages & RAW != 0 {
+        enable_raw_mode()?;
+        STAGES.fetch_or(RAW, Ordering::SeqCst);
+    }
+    if stages & ALTERNATE_SCREEN != 0 {
+        execute!(stdout, EnterAlternateScreen)?;
+        STAGES.fetch_or(ALTERNATE_SCREEN, Ordering::SeqCst);
+    }
+    if stages & INLINE != 0 {
+        reserve_inline()?;
+    }
+    if stages & MOUSE != 0 {
+        execute!(stdout, EnableMouseCapture)?;
+        STAGES.fetch_or(MOUSE, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Restores the terminal before the panic message is printed, so it's
+/// readable and the shell isn't left in raw mode.
+fn install_panic_hook() {
+    static INSTALLED: std::sync::Once = std::sync::Once::new();
+    INSTALLED.call_once(|| {
+        let default = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            // A tick hook's panic is caught and shown as a toast instead.
+            if central_limit::simulation::in_tick_hook() {
+                return;
+            }
+            restore();
+            default(info);
+        }));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A terminal that would run the TUI, for the checks that come first.
+    fn usable() -> Probe {
+        Probe {
+            term: Some("xterm-256color".to_string()),
+            size: Ok((80, 24)),
+            raw_mode: Ok(()),
+        }
+    }
+
+    fn not_probed() -> Probe {
+        panic!("the terminal was probed")
+    }
+
+    #[test]
+    fn piped_runs_go_headless_without_probing() {
+        let piped = Err(Unsupported::Unusable(
+            "stdout or stdin is not a terminal".to_string(),
+        ));
+        for (stdin, stdout) in [(false, true), (true, false), (false, false)] {
+            assert_eq!(check(stdin, stdout, Some("xterm"), not_probed), piped);
+        }
+        assert_eq!(check(true, true, Some("xterm"), usable), Ok(()));
+    }
+
+    #[test]
+    fn a_dumb_terminal_goes_headless_without_probing() {
+        let dumb = Err(Unsupported::Unusable("TERM is 'dumb'".to_string()));
+        assert_eq!(check(true, true, Some("dumb"), not_probed), dumb);
+        // Piped output is what's said, dumb or not.
+        assert_ne!(check(true, false, Some("dumb"), not_probed), dumb);
+        // Only 'dumb' itself is: an unset or other TERM gets probed.
+        assert_eq!(check(true, true, None, usable), Ok(()));
+        assert_eq!(check(true, true, Some("dumb-ish"), usable), Ok(()));
+    }
+}
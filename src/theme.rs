@@ -0,0 +1,276 @@
+//! Colors, modifiers and markers for every styled element of the UI.
+
+use central_limit::histogram::HistogramStyle;
+use tui::{
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    Classic,
+    /// Okabe-Ito blue/orange/vermilion, distinguishable with most color vision deficiencies.
+    Colorblind,
+    /// No colors at all; datasets differ by marker and modifier.
+    Mono,
+}
+
+impl Palette {
+    pub fn parse(name: &str) -> Option<Palette> {
+        match name {
+            "classic" => Some(Palette::Classic),
+            "colorblind" => Some(Palette::Colorblind),
+            "mono" => Some(Palette::Mono),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Classic => "classic",
+            Palette::Colorblind => "colorblind",
+            Palette::Mono => "mono",
+        }
+    }
+
+    pub fn next(self) -> Palette {
+        match self {
+            Palette::Classic => Palette::Colorblind,
+            Palette::Colorblind => Palette::Mono,
+            Palette::Mono => Palette::Classic,
+        }
+    }
+
+    pub fn theme(self) -> Theme {
+        match self {
+            Palette::Classic => Theme {
+                header: Style::default()
+                    .bg(Color::Black)
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+                bar: Style::default().fg(Color::Green),
+                bar_value: Style::default().fg(Color::Black).bg(Color::Green),
+                bar_label: Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::DIM),
+                axis: Style::default().fg(Color::Gray),
+                empirical: Style::default().fg(Color::Yellow),
+                empirical_marker: Marker::Dot,
+                normal: Style::default().fg(Color::Cyan),
+                normal_marker: Marker::Dot,
+                reference: Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
+                reference_marker: Marker::Braille,
+                pins: [Color::Magenta, Color::Blue, Color::Red]
+                    .map(|color| Style::default().fg(color).add_modifier(Modifier::DIM)),
+                blind: [Color::Cyan, Color::Yellow].map(|color| Style::default().fg(color)),
+                overlay: [Color::Yellow, Color::Magenta, Color::LightGreen]
+                    .map(|color| Style::default().fg(color)),
+                band: Style::default().fg(Color::DarkGray),
+                band_marker: Marker::Dot,
+                grid: Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::DIM),
+                grid_marker: Marker::Braille,
+                error_marker: Marker::Braille,
+                point_marker: Marker::Block,
+                blind_markers: [Marker::Braille, Marker::Dot],
+                overlay_markers: [Marker::Dot, Marker::Braille, Marker::Dot],
+                sparkline: Style::default().fg(Color::Yellow),
+                accent: Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+                toast: Style::default().fg(Color::Black).bg(Color::Gray),
+                warning: Style::default().fg(Color::White).bg(Color::Red),
+                gauge: Style::default().fg(Color::Yellow).bg(Color::Black),
+                gauge_done: Style::default().fg(Color::Green).bg(Color::Black),
+                grew: Style::default().fg(Color::LightGreen),
+                shrank: Style::default().fg(Color::LightRed),
+                steady: Style::default().fg(Color::DarkGray),
+            },
+            Palette::Colorblind => {
+                let blue = Color::Indexed(25);
+                let orange = Color::Indexed(214);
+                let vermilion = Color::Indexed(166);
+                Theme {
+                    header: Style::default()
+                        .bg(Color::Black)
+                        .fg(Color::Indexed(75))
+                        .add_modifier(Modifier::BOLD),
+                    bar: Style::default().fg(blue),
+                    bar_value: Style::default().fg(Color::White).bg(blue),
+                    bar_label: Style::default()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::DIM),
+                    axis: Style::default().fg(Color::Gray),
+                    empirical: Style::default().fg(orange),
+                    empirical_marker: Marker::Dot,
+                    normal: Style::default().fg(vermilion),
+                    normal_marker: Marker::Braille,
+                    reference: Style::default().fg(Color::Gray).add_modifier(Modifier::DIM),
+                    reference_marker: Marker::Dot,
+                    // Sky blue, bluish green and reddish purple, from the
+                    // same colorblind-safe set.
+                    pins: [117, 36, 175].map(|color| {
+                        Style::default()
+                            .fg(Color::Indexed(color))
+                            .add_modifier(Modifier::DIM)
+                    }),
+                    // Orange and blue.
+                    blind: [214, 33].map(|color| Style::default().fg(Color::Indexed(color))),
+                    // Orange, sky blue and bluish green.
+                    overlay: [214, 117, 36].map(|color| Style::default().fg(Color::Indexed(color))),
+                    band: Style::default().fg(Color::DarkGray),
+                    band_marker: Marker::Dot,
+                    grid: Style::default()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::DIM),
+                    grid_marker: Marker::Braille,
+                    error_marker: Marker::Braille,
+                    point_marker: Marker::Block,
+                    blind_markers: [Marker::Braille, Marker::Dot],
+                    overlay_markers: [Marker::Dot, Marker::Braille, Marker::Dot],
+                    sparkline: Style::default().fg(orange),
+                    accent: Style::default().fg(orange).add_modifier(Modifier::BOLD),
+                    toast: Style::default().fg(Color::Black).bg(Color::Gray),
+                    warning: Style::default().fg(Color::Black).bg(vermilion),
+                    gauge: Style::default().fg(orange).bg(Color::Black),
+                    gauge_done: Style::default().fg(Color::Indexed(75)).bg(Color::Black),
+                    grew: Style::default().fg(Color::Indexed(75)),
+                    shrank: Style::default().fg(vermilion),
+                    steady: Style::default().fg(Color::DarkGray),
+                }
+            }
+            Palette::Mono => Theme {
+                header: Style::default().add_modifier(Modifier::BOLD),
+                bar: Style::default(),
+                bar_value: Style::default().add_modifier(Modifier::REVERSED),
+                bar_label: Style::default().add_modifier(Modifier::DIM),
+                axis: Style::default(),
+                empirical: Style::default().add_modifier(Modifier::BOLD),
+                empirical_marker: Marker::Dot,
+                normal: Style::default(),
+                normal_marker: Marker::Braille,
+                reference: Style::default().add_modifier(Modifier::DIM),
+                reference_marker: Marker::Dot,
+                pins: [Style::default().add_modifier(Modifier::DIM); 3],
+                blind: [
+                    Style::default(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ],
+                overlay: [
+                    Style::default().add_modifier(Modifier::BOLD),
+                    Style::default(),
+                    Style::default().add_modifier(Modifier::DIM),
+                ],
+                band: Style::default().add_modifier(Modifier::DIM),
+                band_marker: Marker::Block,
+                grid: Style::default().add_modifier(Modifier::DIM),
+                grid_marker: Marker::Braille,
+                error_marker: Marker::Braille,
+                point_marker: Marker::Block,
+                blind_markers: [Marker::Braille, Marker::Dot],
+                overlay_markers: [Marker::Dot, Marker::Braille, Marker::Block],
+                sparkline: Style::default(),
+                accent: Style::default().add_modifier(Modifier::REVERSED),
+                toast: Style::default().add_modifier(Modifier::REVERSED),
+                warning: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+                gauge: Style::default().add_modifier(Modifier::DIM),
+                gauge_done: Style::default().add_modifier(Modifier::BOLD),
+                grew: Style::default().add_modifier(Modifier::BOLD),
+                shrank: Style::default().add_modifier(Modifier::DIM),
+                steady: Style::default(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub header: Style,
+    pub bar: Style,
+    pub bar_value: Style,
+    pub bar_label: Style,
+    pub axis: Style,
+    pub empirical: Style,
+    pub empirical_marker: Marker,
+    pub normal: Style,
+    pub normal_marker: Marker,
+    /// The `--reference` histogram, dimmer than the live one.
+    pub reference: Style,
+    pub reference_marker: Marker,
+    /// The histograms pinned with `k`, dim, one style to a pin.
+    pub pins: [Style; 3],
+    /// A and B of the blind comparison, alike in weight so neither stands
+    /// out as the real one.
+    pub blind: [Style; 2],
+    /// The `--overlay` sources, one style and marker to a source.
+    pub overlay: [Style; 3],
+    pub band: Style,
+    pub band_marker: Marker,
+    pub grid: Style,
+    pub grid_marker: Marker,
+    /// The error bars and the lines either side of the histogram's error.
+    pub error_marker: Marker,
+    /// Single points: the Q-Q plot's and the highlighted bucket's.
+    pub point_marker: Marker,
+    pub blind_markers: [Marker; 2],
+    pub overlay_markers: [Marker; 3],
+    pub sparkline: Style,
+    /// The selected tab.
+    pub accent: Style,
+    pub toast: Style,
+    pub warning: Style,
+    pub gauge: Style,
+    pub gauge_done: Style,
+    /// Bars in the diff view, by how their count changed since the last tick.
+    pub grew: Style,
+    pub shrank: Style,
+    pub steady: Style,
+}
+
+impl Theme {
+    /// The theme as `--ascii` draws it, with the Braille markers swapped for
+    /// dots, which are drawn as `*`, as blocks are as `#`.
+    pub fn ascii(self) -> Theme {
+        let dot = |marker| match marker {
+            Marker::Braille => Marker::Dot,
+            marker => marker,
+        };
+        Theme {
+            empirical_marker: dot(self.empirical_marker),
+            normal_marker: dot(self.normal_marker),
+            reference_marker: dot(self.reference_marker),
+            band_marker: dot(self.band_marker),
+            grid_marker: dot(self.grid_marker),
+            error_marker: dot(self.error_marker),
+            point_marker: dot(self.point_marker),
+            blind_markers: self.blind_markers.map(dot),
+            overlay_markers: self.overlay_markers.map(dot),
+            ..self
+        }
+    }
+
+    /// The histogram view's part of the theme.
+    pub fn histogram(&self) -> HistogramStyle {
+        HistogramStyle {
+            bar: self.bar,
+            bar_value: self.bar_value,
+            bar_label: self.bar_label,
+            axis: self.axis,
+            empirical: self.empirical,
+            empirical_marker: self.empirical_marker,
+            model: self.normal,
+            model_marker: self.normal_marker,
+            reference: self.reference,
+            grid: self.grid,
+            grid_marker: self.grid_marker,
+            band: self.band,
+            band_marker: self.band_marker,
+            accent: self.accent,
+            point_marker: self.point_marker,
+            grew: self.grew,
+            shrank: self.shrank,
+            steady: self.steady,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! `--max-cpu`: sleeping between sampling chunks, so a run doesn't keep a
+//! core busy when it doesn't need to.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Limits below this would make ticks crawl, so they're raised to it.
+pub const MIN_PERCENT: u32 = 5;
+
+/// How long the CPU share estimate is averaged over.
+const WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct Throttle {
+    /// Most of the time to spend sampling, from 0 to 1; `None` doesn't limit.
+    limit: Option<f64>,
+    window_start: Instant,
+    window_work: Duration,
+    share: Option<f64>,
+}
+
+impl Default for Throttle {
+    fn default() -> Throttle {
+        Throttle::new(100)
+    }
+}
+
+impl Throttle {
+    /// Keeps sampling to about `percent` of the time; 100 or more doesn't limit it.
+    pub fn new(percent: u32) -> Throttle {
+        Throttle {
+            limit: (percent < 100).then(|| f64::from(percent.max(MIN_PERCENT)) / 100.0),
+            window_start: Instant::now(),
+            window_work: Duration::ZERO,
+            share: None,
+        }
+    }
+
+    /// Records a chunk of sampling that took `work`, then sleeps long enough
+    /// for it to be the limited share of the time since it started.
+    pub fn after_work(&mut self, work: Duration) {
+        if let Some(limit) = self.limit {
+            thread::sleep(work.mul_f64((1.0 - limit) / limit));
+        }
+        self.window_work += work;
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= WINDOW {
+            self.share = Some(self.window_work.as_secs_f64() / elapsed.as_secs_f64());
+            self.window_start = Instant::now();
+            self.window_work = Duration::ZERO;
+        }
+    }
+
+    /// The share of the last second or so spent sampling, from 0 to 1.
+    pub fn cpu_share(&self) -> Option<f64> {
+        self.share
+    }
+}
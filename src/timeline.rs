@@ -0,0 +1,129 @@
+//! The last few ticks' histograms, so a paused run can be stepped back through.
+
+use crate::bucket::Bucket;
+use std::{collections::VecDeque, mem::size_of};
+
+pub const DEFAULT_TICKS: usize = 240;
+
+/// The most the snapshots may take, however many ticks were asked for.
+const MAX_BYTES: usize = 64 << 20;
+
+/// A tick's counts, and the coin's bias they were drawn at. The statistics
+/// are recomputed from them when shown, so they aren't kept.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub bias: f64,
+    pub counts: Vec<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    snapshots: VecDeque<Snapshot>,
+    ticks: usize,
+    /// Index of the snapshot on screen, while stepping through them.
+    viewing: Option<usize>,
+}
+
+impl Timeline {
+    /// Keeps up to `ticks` snapshots; 0 keeps none.
+    pub fn new(ticks: usize) -> Timeline {
+        Timeline {
+            snapshots: VecDeque::new(),
+            ticks,
+            viewing: None,
+        }
+    }
+
+    /// Snapshots of `buckets` buckets to keep: as many as asked for, as far
+    /// as `MAX_BYTES` allows.
+    fn capacity(&self, buckets: usize) -> usize {
+        self.ticks
+            .min(MAX_BYTES / (buckets.max(1) * size_of::<u64>()))
+    }
+
+    pub fn push(&mut self, tick: u64, bias: f64, data: &[Bucket]) {
+        let capacity = self.capacity(data.len());
+        if capacity == 0 {
+            return;
+        }
+        while self.snapshots.len() >= capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(Snapshot {
+            tick,
+            bias,
+            counts: data.iter().map(|bucket| bucket.count).collect(),
+        });
+    }
+
+    /// Forgets every snapshot, for when the buckets change.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.viewing = None;
+    }
+
+    /// Shows the snapshot before or after the one on screen, starting from
+    /// the latest, and returns it.
+    pub fn step(&mut self, back: bool) -> Option<&Snapshot> {
+        let last = self.snapshots.len().checked_sub(1)?;
+        let current = self.viewing.unwrap_or(last);
+        let next = if back {
+            current.saturating_sub(1)
+        } else {
+            (current + 1).min(last)
+        };
+        self.viewing = Some(next);
+        self.snapshots.get(next)
+    }
+
+    /// Goes back to the latest snapshot and returns it.
+    pub fn resume(&mut self) -> Option<&Snapshot> {
+        self.viewing = None;
+        self.snapshots.back()
+    }
+
+    /// The snapshot on screen, while stepping through them.
+    pub fn viewing(&self) -> Option<&Snapshot> {
+        self.viewing.and_then(|i| self.snapshots.get(i))
+    }
+
+    /// Drops the oldest snapshots until the rest take at most `bytes`, and
+    /// keeps no more than that many from then on. Returns how many went.
+    pub fn shrink(&mut self, bytes: usize) -> usize {
+        let mut total = self.bytes();
+        let mut dropped = 0;
+        for snapshot in &self.snapshots {
+            if total <= bytes {
+                break;
+            }
+            total = total.saturating_sub(
+                size_of::<Snapshot>() + snapshot.counts.capacity() * size_of::<u64>(),
+            );
+            dropped += 1;
+        }
+        if dropped > 0 {
+            self.snapshots.drain(..dropped);
+            self.snapshots.shrink_to_fit();
+            self.ticks = self.snapshots.len();
+            // The snapshot on screen moves down with the rest, or is the
+            // oldest kept if it was dropped.
+            self.viewing = self.viewing.map(|i| i.saturating_sub(dropped));
+        }
+        dropped
+    }
+
+    /// Snapshots kept, at most.
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.snapshots.capacity() * size_of::<Snapshot>()
+            + self
+                .snapshots
+                .iter()
+                .map(|snapshot| snapshot.counts.capacity() * size_of::<u64>())
+                .sum::<usize>()
+    }
+}
@@ -0,0 +1,120 @@
+//! Short messages about runtime events, shown one at a time in a corner.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// How long an info toast stays up.
+const DURATION: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    /// Stays up until a key is pressed.
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    pub level: Level,
+    shown: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+pub struct Toasts {
+    queue: VecDeque<Toast>,
+    /// The errors raised since the last `take_raised`, once they're kept.
+    raised: Option<Vec<String>>,
+}
+
+impl Toasts {
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(message.into(), Level::Info);
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(message.into(), Level::Error);
+    }
+
+    fn push(&mut self, message: String, level: Level) {
+        // A failure that repeats every tick should show once, not queue up.
+        if self.queue.iter().any(|t| t.message == message) {
+            return;
+        }
+        log::info!("{}", message);
+        if let (Level::Error, Some(raised)) = (level, &mut self.raised) {
+            raised.push(message.clone());
+        }
+        self.queue.push_back(Toast {
+            message,
+            level,
+            shown: None,
+        });
+    }
+
+    /// Retires an expired info toast and starts the clock on the next one.
+    pub fn update(&mut self, now: Instant) {
+        if let Some(Toast {
+            level: Level::Info,
+            shown: Some(shown),
+            ..
+        }) = self.queue.front()
+        {
+            if now.duration_since(*shown) >= DURATION {
+                self.queue.pop_front();
+            }
+        }
+        if let Some(toast) = self.queue.front_mut() {
+            toast.shown.get_or_insert(now);
+        }
+    }
+
+    /// Puts off the expiry of the toast on screen by `by`, time it wasn't
+    /// on screen for, as while the process was stopped.
+    pub fn delay(&mut self, by: Duration) {
+        if let Some(shown) = self.queue.front_mut().and_then(|t| t.shown.as_mut()) {
+            *shown += by;
+        }
+    }
+
+    pub fn current(&self) -> Option<&Toast> {
+        self.queue.front().filter(|t| t.shown.is_some())
+    }
+
+    /// Keeps the errors raised from here on, for `take_raised`.
+    pub fn keep_raised(&mut self) {
+        self.raised.get_or_insert_with(Vec::new);
+    }
+
+    /// The errors raised since the last look, as for `--event-log`.
+    pub fn take_raised(&mut self) -> Vec<String> {
+        self.raised.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Dismisses the first error toast waiting, and the info toasts before
+    /// it, as a key did once they'd come and gone; for `replay-events`,
+    /// where toasts don't keep time.
+    pub fn dismiss_next_error(&mut self) -> bool {
+        let Some(i) = self.queue.iter().position(|t| t.level == Level::Error) else {
+            return false;
+        };
+        self.queue.drain(..=i);
+        true
+    }
+
+    /// Dismisses the error toast on screen, if there is one.
+    pub fn dismiss(&mut self) -> bool {
+        match self.current() {
+            Some(Toast {
+                level: Level::Error,
+                ..
+            }) => {
+                self.queue.pop_front();
+                true
+            }
+            _ => false,
+        }
+    }
+}
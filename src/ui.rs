@@ -0,0 +1,1860 @@
+//! Drawing the interactive views.
+
+use crate::{
+    ascii::Ascii,
+    bars::RenderSettings,
+    blind::Blind,
+    clip::Clip,
+    datetime::DateTime,
+    format,
+    mode::Mode,
+    overlay::Overlay,
+    seeds::Spread,
+    settings::{Field, Panel},
+    stats::{self, Model, Stats},
+    target::Target,
+    theme::Theme,
+    toast::Level,
+    until::Until,
+    watch, App, ERRORS_MIN_EXPECTED,
+};
+use central_limit::{
+    axis::{self, Ticks},
+    bucket::Aggregate,
+    histogram::{
+        axis_labels, guides, HistogramSnapshot, HistogramState, HistogramView, Legend, Series,
+    },
+    sampling::{self, Regime},
+};
+use std::time::{Duration, Instant, SystemTime};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    symbols,
+    text::{Span, Spans},
+    widgets::{
+        Axis, Block, Borders, Chart, Clear, Dataset, Gauge, GraphType, Paragraph, Sparkline, Tabs,
+        Wrap,
+    },
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// The panels that can fill the area below the header, one at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Histogram,
+    Convergence,
+    QQ,
+    Walks,
+    Stats,
+    Errors,
+}
+
+impl View {
+    pub const ALL: [View; 6] = [
+        View::Histogram,
+        View::Convergence,
+        View::QQ,
+        View::Walks,
+        View::Stats,
+        View::Errors,
+    ];
+
+    /// The strings table key for the view's tab title.
+    pub fn key(self) -> &'static str {
+        match self {
+            View::Histogram => "view.histogram",
+            View::Convergence => "view.convergence",
+            View::QQ => "view.qq",
+            View::Walks => "view.walks",
+            View::Stats => "view.stats",
+            View::Errors => "view.errors",
+        }
+    }
+
+    pub fn index(self) -> usize {
+        View::ALL.iter().position(|view| *view == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> View {
+        View::ALL[(self.index() + 1) % View::ALL.len()]
+    }
+
+    pub fn previous(self) -> View {
+        View::ALL[(self.index() + View::ALL.len() - 1) % View::ALL.len()]
+    }
+}
+
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &App) {
+    app.plot.set(None);
+    if let (Some(at), Some(left)) = (app.start_at, app.countdown()) {
+        draw_countdown(f, app, at, left);
+        draw_toast(f, app);
+    } else {
+        draw_screen(f, app);
+    }
+    // Last, so it catches what tui draws that can't be asked for in ASCII:
+    // borders, the tabs' divider and the dot marker.
+    if app.render.ascii {
+        f.render_widget(Ascii, f.size());
+    }
+}
+
+fn draw_screen<B: Backend>(f: &mut Frame<B>, app: &App) {
+    if app.minimal {
+        draw_view(f, f.size(), app);
+    } else {
+        draw_full(f, app);
+    }
+    if let Some(panel) = &app.settings {
+        draw_settings(f, app, panel);
+    }
+    if app.show_hud {
+        draw_hud(f, app);
+    }
+    draw_toast(f, app);
+}
+
+fn draw_full<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let theme = app.theme();
+    let compact = f.size().height < COMPACT_HEIGHT;
+    let header_text = header_text(app, compact);
+    let margin = if compact { 0 } else { 2 };
+    let warnings = [
+        app.connection_lost.clone(),
+        app.drift_text(),
+        app.degenerate_text(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    let mut constraints = vec![
+        Constraint::Length(header_text.lines().count() as u16),
+        Constraint::Length(1),
+    ];
+    if app.target.is_some() {
+        constraints.push(Constraint::Length(1));
+    }
+    // The warnings wrap rather than being cut short.
+    let width = f.size().width.saturating_sub(2 * margin).max(1) as usize;
+    for text in &warnings {
+        constraints.push(Constraint::Length(text.width().div_ceil(width) as u16));
+    }
+    constraints.extend([Constraint::Min(6), Constraint::Length(1)]);
+    let mut chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(margin)
+        .constraints(constraints)
+        .split(f.size());
+
+    if let Some(target) = &app.target {
+        render_target(f, chunks.remove(2), app, target);
+    }
+    for text in warnings {
+        f.render_widget(
+            Paragraph::new(text)
+                .style(theme.warning)
+                .wrap(Wrap { trim: true }),
+            chunks.remove(2),
+        );
+    }
+
+    let header = if compact {
+        vec![chunks[0]]
+    } else {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+            .split(chunks[0])
+    };
+
+    f.render_widget(
+        Paragraph::new(header_text)
+            .style(theme.header)
+            .alignment(Alignment::Center),
+        header[0],
+    );
+
+    if let Some(area) = header.get(1) {
+        draw_sparkline(f, *area, app);
+    }
+    let titles = View::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, view)| Spans::from(format!("{} {}", i + 1, app.strings.get(view.key()))))
+        .collect();
+    f.render_widget(
+        Tabs::new(titles)
+            .select(app.view.index())
+            .highlight_style(theme.accent),
+        chunks[1],
+    );
+
+    f.render_widget(Paragraph::new(stats_line(app, &theme)), chunks[3]);
+    draw_view(f, chunks[2], app);
+}
+
+/// The header's history of the selected statistic.
+fn draw_sparkline<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = app.theme();
+    let name = app.strings.get(app.metric.name_key());
+    let caption = match app.history.latest(app.metric) {
+        Some(value) if value.is_finite() => format!(" {}: {} ", name, app.metric.format(value)),
+        _ => format!(" {} ", name),
+    };
+    // Sparkline draws from the start of its data, so keep only what fits.
+    let sparkline_data = app.history.sparkline(app.metric);
+    let visible = sparkline_data
+        .len()
+        .saturating_sub(area.width.saturating_sub(2) as usize);
+    f.render_widget(
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(caption))
+            .data(&sparkline_data[visible..])
+            .bar_set(app.render.bar_set())
+            .style(theme.sparkline),
+        area,
+    );
+}
+
+fn draw_view<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    // Accessible mode describes the data in words instead of drawing it.
+    if app.accessible && app.view != View::Stats {
+        let lines = app
+            .summary
+            .as_ref()
+            .map(|summary| summary.lines(&app.strings))
+            .unwrap_or_default();
+        f.render_widget(Paragraph::new(lines.join("\n")).block(panel(app)), area);
+        return;
+    }
+
+    // Empty charts would only show axes scaled to nothing.
+    if app.view != View::Stats && app.data.iter().all(|bucket| bucket.count == 0) {
+        let text = match app.computing() {
+            Some(_) => app.strings.get("empty.drawing"),
+            None => app.strings.get("empty.no_data"),
+        };
+        f.render_widget(
+            Paragraph::new(text)
+                .alignment(Alignment::Center)
+                .block(panel(app)),
+            area,
+        );
+        return;
+    }
+
+    match app.view {
+        View::Histogram => match (&app.blind, &app.overlay) {
+            (Some(blind), _) => draw_blind(f, area, app, blind),
+            (None, Some(overlay)) => draw_overlay(f, area, app, overlay),
+            (None, None) => draw_histogram(f, area, app),
+        },
+        View::Convergence => draw_convergence(f, area, app),
+        View::QQ => draw_qq(f, area, app),
+        View::Walks => draw_walks(f, area, app),
+        View::Stats => draw_stats(f, area, app),
+        View::Errors => draw_errors(f, area, app),
+    }
+}
+
+/// Below this many rows the header shrinks to a single line.
+const COMPACT_HEIGHT: u16 = 20;
+
+fn header_text(app: &App, compact: bool) -> String {
+    let strings = &app.strings;
+    let badge = match app.clients {
+        Some(1) => strings.get("header.one_client").to_string(),
+        Some(n) => strings.fill("header.clients", &[("count", &n)]),
+        None => String::new(),
+    };
+    // The histogram's own total, which is short of the samples drawn once
+    // cumulative counting started after the first tick.
+    let counted = format::si(stats::total(app.data.iter().map(|bucket| bucket.count)));
+    let mode = match (&app.mode, &app.warm) {
+        (Mode::Regenerate, _) => strings.get("header.regenerate").to_string(),
+        (Mode::Cumulative, Some(warm)) => strings.fill(
+            "header.total_carried",
+            &[("total", &counted), ("carried", &format::si(warm.samples))],
+        ),
+        (Mode::Cumulative, None) => strings.fill("header.total", &[("total", &counted)]),
+        (Mode::Decay(decay), _) => strings.fill(
+            "header.decay",
+            &[("lambda", &format!("{:.2}", decay.lambda()))],
+        ),
+    };
+    let generator = app.sim.generator();
+    let rng = match generator.flawed() {
+        true => strings.fill("header.flawed_rng", &[("name", &generator.name())]),
+        false => generator.name().to_string(),
+    };
+    let rates = strings.fill(
+        "header.rates",
+        &[
+            ("samples", &format::si(app.b_count as u64)),
+            ("mode", &mode),
+            ("rate", &format::tick_rate(app.tick_rate)),
+            ("buckets", &app.r_max),
+            ("rng", &rng),
+            ("clients", &badge),
+        ],
+    );
+
+    let mut rates = rates;
+    // Not an exact simulation any more, which should never go unnoticed.
+    if app.sim.regime() == Regime::Normal {
+        rates += strings.get("header.approximate");
+    }
+    let aggregate = app.sim.aggregate();
+    if aggregate != Aggregate::Sum {
+        rates += &strings.fill("header.aggregate", &[("aggregate", &aggregate.name())]);
+    }
+    let forwarded = app.sim.fast_forwarded_samples();
+    if forwarded > 0 && app.intro.is_none() {
+        rates += &strings.fill(
+            "header.fast_forwarded",
+            &[("samples", &format::si(forwarded))],
+        );
+    }
+    let p = format!("{:.3}", app.bias());
+    match &app.bias_sweep {
+        Some(sweep) => {
+            let (low, high) = sweep.range();
+            let key = match sweep.held() {
+                true => "header.bias_held",
+                false => "header.bias_sweep",
+            };
+            rates += &strings.fill(key, &[("p", &p), ("low", &low), ("high", &high)]);
+        }
+        None if app.bias() != sampling::FAIR => {
+            rates += &strings.fill("header.bias", &[("p", &p)]);
+        }
+        None => {}
+    }
+    if let Some(cycles) = &app.cycles {
+        rates += &strings.fill(
+            "header.cycle",
+            &[
+                ("cycle", &cycles.current()),
+                ("cycles", &cycles.count()),
+                ("tick", &app.tick),
+                ("ticks", &cycles.ticks()),
+            ],
+        );
+    }
+    if app
+        .reloaded
+        .is_some_and(|reloaded| reloaded.elapsed() < watch::RELOADED_FOR)
+    {
+        rates += strings.get("header.reloaded");
+    }
+
+    let paused = match app.timeline.viewing() {
+        _ if !app.paused => None,
+        Some(snapshot) => Some(strings.fill(
+            "header.viewing",
+            &[("tick", &snapshot.tick), ("latest", &app.tick)],
+        )),
+        None => Some(strings.fill("header.paused", &[("tick", &app.tick)])),
+    };
+
+    if compact {
+        let status = match app.replay_status.as_ref().or(paused.as_ref()) {
+            Some(status) => format!(" | {}", status),
+            None => String::new(),
+        };
+        return strings.fill("header.compact", &[("rates", &rates), ("status", &status)]);
+    }
+
+    let help = match (&app.replay_status, paused, &app.intro) {
+        (_, _, Some(intro)) => strings.fill(
+            "help.intro",
+            &[("steps", &app.r_max), ("target", &intro.target())],
+        ),
+        (Some(status), _, _) if app.attached => {
+            format!("{}\n{}", status, strings.get("help.attach"))
+        }
+        (Some(status), _, _) => format!("{}\n{}", status, strings.get("help.replay")),
+        (None, Some(status), _) => format!("{}\n{}", status, strings.get("help.paused")),
+        (None, None, None) => {
+            let mut keys = strings.fill("help.keys", &[("theme", &app.palette.name())]);
+            if app.seeds.is_some() {
+                keys += strings.get("help.seeds");
+            }
+            if app.mode.decay().is_some() {
+                keys += strings.get("help.decay");
+            }
+            if app.bias_sweep.is_some() {
+                keys += strings.get("help.bias");
+            }
+            keys
+        }
+    };
+
+    let session = match &app.session {
+        Some(dir) => format!("\n{}", strings.fill("header.session", &[("dir", dir)])),
+        None => String::new(),
+    };
+
+    format!(
+        "{}\n\nAfaan Bilal | https://afaan.dev\n\n{}\n{}{}\n{}",
+        strings.get("header.title"),
+        rates,
+        strings.get("header.inspired"),
+        session,
+        help
+    )
+}
+
+/// Timings in the bottom right corner, over the margin below the status line.
+fn draw_hud<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let perf = &app.perf;
+    let undefined = || format::UNDEFINED.to_string();
+    let time = |d: Option<Duration>| d.map_or_else(undefined, format::duration);
+    let rate = perf.ticks_per_sec().map_or_else(undefined, format::si_f64);
+    let fps = perf.frames_per_sec().map_or_else(undefined, format::si_f64);
+    let batch = perf
+        .last_batch()
+        .map_or_else(undefined, |ticks| ticks.to_string());
+    let target = match app.tick_rate.as_secs_f64() {
+        secs if secs > 0.0 => format::si_f64(1.0 / secs),
+        _ => undefined(),
+    };
+    let cpu = app
+        .throttle
+        .cpu_share()
+        .map_or_else(undefined, |share| format!("{:.0}%", share * 100.0));
+    let buffers = format::bytes(app.buffer_bytes());
+    let buffers = match app.max_memory {
+        Some(cap) => app.strings.fill(
+            "hud.buffers_capped",
+            &[("used", &buffers), ("cap", &format::bytes(cap))],
+        ),
+        None => buffers,
+    };
+    let text = app.strings.fill(
+        "hud.line",
+        &[
+            ("tick", &time(perf.last_tick())),
+            ("tick_avg", &time(perf.average_tick())),
+            ("frame", &time(perf.last_frame())),
+            ("frame_avg", &time(perf.average_frame())),
+            ("rate", &rate),
+            ("target", &target),
+            ("fps", &fps),
+            ("batch", &batch),
+            ("overruns", &perf.overruns()),
+            ("late", &time(perf.last_late())),
+            ("late_avg", &time(perf.average_late())),
+            ("skipped", &perf.skipped()),
+            ("cpu", &cpu),
+            ("buffers", &buffers),
+        ],
+    );
+    let size = f.size();
+    let width = (text.width() as u16).min(size.width);
+    let area = Rect::new(
+        size.right() - width,
+        size.bottom().saturating_sub(1),
+        width,
+        1.min(size.height),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(text).style(app.theme().toast), area);
+
+    // The spread of the tick times, on the line above.
+    let times = perf.tick_times();
+    let Some((low, high, counts)) = times.spread() else {
+        return;
+    };
+    let percentile = |p| time(times.percentile(p));
+    let text = app.strings.fill(
+        "hud.tick_times",
+        &[
+            ("p50", &percentile(0.5)),
+            ("p95", &percentile(0.95)),
+            ("p99", &percentile(0.99)),
+            ("low", &format::duration(low)),
+            ("spread", &tick_spread(counts, &app.render.bar_set())),
+            ("high", &format::duration(high)),
+        ],
+    );
+    let width = (text.width() as u16).min(size.width);
+    let area = Rect::new(
+        size.right() - width,
+        size.bottom().saturating_sub(2),
+        width,
+        1.min(size.height.saturating_sub(1)),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(text).style(app.theme().toast), area);
+}
+
+/// A bar a character wide for each bucket of tick times, on a log scale so
+/// the odd slow tick shows beside thousands of quick ones.
+fn tick_spread(counts: &[u64], set: &symbols::bar::Set) -> String {
+    let levels = [
+        set.one_eighth,
+        set.one_quarter,
+        set.three_eighths,
+        set.half,
+        set.five_eighths,
+        set.three_quarters,
+        set.seven_eighths,
+        set.full,
+    ];
+    let max = counts.iter().copied().max().unwrap_or(0) as f64;
+    counts
+        .iter()
+        .map(|&count| match count {
+            0 => " ",
+            count => {
+                let level = (count as f64).ln_1p() / max.ln_1p() * (levels.len() - 1) as f64;
+                levels[level.round() as usize]
+            }
+        })
+        .collect()
+}
+
+/// The settings panel, over the middle of the screen.
+fn draw_settings<B: Backend>(f: &mut Frame<B>, app: &App, panel: &Panel) {
+    let theme = app.theme();
+    let strings = &app.strings;
+    let mut lines = Field::ALL
+        .iter()
+        .map(|field| {
+            let selected = *field == panel.selected();
+            let value = match panel.editing() {
+                Some(text) if selected => format!("{}_", text),
+                _ => app.setting(*field),
+            };
+            let text = format!(
+                "{} {:<18}{:>12}",
+                if selected { '>' } else { ' ' },
+                strings.get(field.key()),
+                value
+            );
+            match selected {
+                true => Spans::from(Span::styled(text, theme.accent)),
+                false => Spans::from(text),
+            }
+        })
+        .collect::<Vec<_>>();
+    lines.push(Spans::from(""));
+    lines.push(match panel.error() {
+        Some(err) => Spans::from(Span::styled(err.to_string(), theme.warning)),
+        None if panel.editing().is_some() => Spans::from(strings.get("settings.editing")),
+        None => Spans::from(strings.get("settings.hint")),
+    });
+
+    let size = f.size();
+    let width = lines
+        .iter()
+        .map(Spans::width)
+        .max()
+        .unwrap_or(0)
+        .max(strings.get("settings.title").width()) as u16
+        + 4;
+    let height = lines.len() as u16 + 2;
+    let area = Rect::new(
+        size.x + size.width.saturating_sub(width) / 2,
+        size.y + size.height.saturating_sub(height) / 2,
+        width.min(size.width),
+        height.min(size.height),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled(strings.get("settings.title"), theme.accent)),
+        ),
+        area,
+    );
+}
+
+/// The screen shown until `--start-at`'s time, in the middle of the terminal.
+fn draw_countdown<B: Backend>(f: &mut Frame<B>, app: &App, at: SystemTime, left: Duration) {
+    let theme = app.theme();
+    let strings = &app.strings;
+    let lines = vec![
+        Spans::from(Span::styled(strings.get("header.title"), theme.header)),
+        Spans::from(""),
+        Spans::from(Span::styled(
+            strings.fill(
+                "countdown.starting",
+                &[
+                    ("left", &format::countdown(left)),
+                    ("time", &DateTime::at(at).clock()),
+                ],
+            ),
+            theme.accent,
+        )),
+        Spans::from(""),
+        Spans::from(strings.get("countdown.keys")),
+    ];
+    let size = f.size();
+    let height = lines.len() as u16;
+    let area = Rect::new(
+        size.x,
+        size.y + size.height.saturating_sub(height) / 2,
+        size.width,
+        height.min(size.height),
+    );
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), area);
+}
+
+/// The current toast, in the top right corner above the header, where it
+/// can't cover the data or the axis labels.
+fn draw_toast<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let toast = match app.toasts.current() {
+        Some(toast) => toast,
+        None => return,
+    };
+    let theme = app.theme();
+    let (style, hint) = match toast.level {
+        Level::Info => (theme.toast, ""),
+        Level::Error => (theme.warning, app.strings.get("toast.press_any_key")),
+    };
+    let text = format!(" {}{} ", toast.message, hint);
+    let size = f.size();
+    let width = (text.width() as u16).min(size.width);
+    let area = Rect::new(size.right() - width, size.y, width, 1.min(size.height));
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(text).style(style), area);
+}
+
+/// The frame around a view, left out in minimal mode.
+fn panel(app: &App) -> Block<'static> {
+    if app.minimal {
+        Block::default()
+    } else {
+        Block::default().borders(Borders::ALL)
+    }
+}
+
+fn draw_histogram<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = app.theme();
+    let total = app.frequency_total();
+    let snapshot = HistogramSnapshot {
+        xs: app.data.iter().map(|bucket| bucket.x).collect(),
+        labels: app
+            .shown_data()
+            .iter()
+            .map(|bucket| bucket.label.clone())
+            .collect(),
+        counts: app.displayed_counts(),
+        total,
+        steps: app.r_max as u32,
+        bias: app.bias(),
+        model: app.model(),
+        regime: app.sim.regime(),
+        layout: app.sim.layout(),
+    };
+    let mut state = HistogramState {
+        percent: app.percent,
+        zoom: app.clip.as_ref().and_then(Clip::keep),
+        selected: app.selected_index(),
+        y_max: Some(app.y_bound.value()),
+        aggregate: app.sim.aggregate(),
+        ..HistogramState::default()
+    };
+
+    // The reference and pins are compared by frequency, so they're drawn as
+    // if they had as many samples as the live histogram.
+    let as_counts = |frequencies: Vec<f64>| {
+        frequencies
+            .into_iter()
+            .map(|frequency| frequency * total as f64)
+            .collect::<Vec<_>>()
+    };
+    let on_buckets = |values: Vec<f64>| {
+        app.data
+            .iter()
+            .map(|bucket| bucket.x)
+            .zip(values)
+            .collect::<Vec<_>>()
+    };
+    let reference = app
+        .reference
+        .as_ref()
+        .map(|reference| as_counts(reference.frequencies(&app.data)));
+    let mut series = vec![];
+    if let (Some(values), Some(loaded)) = (&reference, &app.reference) {
+        series.push(Series {
+            name: app.strings.fill(
+                "legend.reference",
+                &[("samples", &format::si(loaded.samples))],
+            ),
+            style: theme.reference,
+            marker: theme.reference_marker,
+            points: on_buckets(values.clone()),
+        });
+    }
+    for (pin, style) in app.pins.iter().zip(theme.pins) {
+        series.push(Series {
+            name: pin.label.clone(),
+            style,
+            marker: theme.reference_marker,
+            points: on_buckets(as_counts(pin.frequencies(&app.data))),
+        });
+    }
+
+    // The spread across the seeds is drawn as its median between lines at
+    // the least and most, unless one seed's run is picked to show instead.
+    let guide = |points| Series {
+        name: String::new(),
+        style: theme.band,
+        marker: theme.band_marker,
+        points,
+    };
+    let mut guides = vec![];
+    if let Some(seeds) = &app.seeds {
+        if let Some((seed, sim)) = seeds.shown() {
+            series.push(Series {
+                name: app.strings.fill("legend.seed", &[("seed", &seed)]),
+                style: theme.reference,
+                marker: theme.reference_marker,
+                points: sim
+                    .buckets()
+                    .map(|bucket| (bucket.center(), bucket.count() as f64))
+                    .collect(),
+            });
+        } else if let Some(spread) = seeds.spread(&app.data) {
+            let line = |value: fn(&Spread) -> f64| on_buckets(spread.iter().map(value).collect());
+            guides = vec![guide(line(|s| s.min as f64)), guide(line(|s| s.max as f64))];
+            series.push(Series {
+                name: app
+                    .strings
+                    .fill("legend.seeds", &[("count", &seeds.count())]),
+                style: theme.reference,
+                marker: theme.reference_marker,
+                points: line(|s| s.median),
+            });
+        }
+    }
+
+    // Lines a standard error above and below each bucket. An empty bucket's
+    // lower line stays on the axis rather than going below it.
+    if app.show_errors {
+        let envelope = |side: f64| Series {
+            name: String::new(),
+            style: theme.empirical.add_modifier(Modifier::DIM),
+            marker: theme.error_marker,
+            points: app
+                .data
+                .iter()
+                .zip(&snapshot.counts)
+                .map(|(bucket, count)| {
+                    (
+                        bucket.x,
+                        count + side * stats::count_error(bucket.count, total),
+                    )
+                })
+                .collect(),
+        };
+        guides.extend([envelope(-1.0), envelope(1.0)]);
+    }
+
+    let current = app
+        .data
+        .iter()
+        .map(|bucket| bucket.count)
+        .collect::<Vec<_>>();
+    // Minimal mode shows the bars alone, without a frame, and lets them be
+    // twice as wide.
+    let settings = if app.minimal {
+        RenderSettings {
+            max_bar_width: app.render.max_bar_width * 2,
+            ..app.render
+        }
+    } else {
+        app.render
+    };
+    let model = match app.model() {
+        Model::Normal => "legend.normal",
+        Model::Binomial => "legend.binomial",
+    };
+    let view = HistogramView::new(&snapshot)
+        .settings(settings)
+        .style(theme.histogram())
+        .block(panel(app))
+        .bars_only(app.minimal)
+        .split(app.split.0, app.split.1)
+        .empirical(app.strings.get("legend.empirical").to_string())
+        .model(app.show_normal.then(|| app.strings.get(model).to_string()))
+        .series(series)
+        .guides(guides)
+        .grid(app.show_grid)
+        .bands(app.show_bands)
+        .legend(app.show_legend);
+    let view = match &reference {
+        Some(reference) => view.reference(reference),
+        None => view,
+    };
+    let view = match app.show_diff {
+        true => view.changes(&app.previous, &current),
+        false => view,
+    };
+    let view = match &app.bins {
+        Some(bins) => view.merged(bins),
+        None => view,
+    };
+    // Clipped bars are laid out bar by bar rather than bucket by bucket.
+    let clipped = app
+        .clip
+        .as_ref()
+        .and_then(|clip| Some(clip).zip(clip.bins()))
+        .filter(|(_, bins)| bins.buckets() == app.data.len());
+    let view = match clipped {
+        Some((clip, bins)) if !app.minimal => {
+            view.grouped(bins).grouped_title(clipped_title(app, clip))
+        }
+        Some((_, bins)) => view.grouped(bins),
+        None => view,
+    };
+    let view = match app.minimal {
+        true => view,
+        false => {
+            view.binned_title(|factor| app.strings.fill("histogram.binned", &[("factor", &factor)]))
+        }
+    };
+    f.render_stateful_widget(view, area, &mut state);
+    app.plot.set(state.plot);
+    if let (Some(i), Some(column)) = (
+        state.selected.filter(|_| !app.minimal),
+        state.selected_column,
+    ) {
+        draw_bucket_details(f, state.bars_area, app, i, column);
+    }
+}
+
+/// The bars' title when they're clipped, with the share of samples they
+/// leave out.
+fn clipped_title(app: &App, clip: &Clip) -> String {
+    let share = format!("{:.1}%", clip.clipped() * 100.0);
+    let percentile = clip.percentile();
+    app.strings.fill(
+        "histogram.clipped",
+        &[
+            ("share", &share),
+            ("low", &percentile),
+            ("high", &(100.0 - percentile)),
+        ],
+    )
+}
+
+/// The blind comparison: the two histograms as lines named only A and B,
+/// without the bars, the model or the numbers that would tell them apart.
+fn draw_blind<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App, blind: &Blind) {
+    // The legend writes cells itself, so it mustn't be handed rows a short
+    // terminal doesn't have.
+    let area = area.intersection(f.size());
+    if area.area() == 0 {
+        return;
+    }
+    let theme = app.theme();
+    // Sums from -steps up in twos, one per count.
+    let sum = |i: usize| 2.0 * i as f64 - (blind.a().len() - 1) as f64;
+    let line = |counts: &[u64]| {
+        counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| (sum(i), *count as f64))
+            .collect::<Vec<_>>()
+    };
+    let series = [("blind.a", blind.a()), ("blind.b", blind.b())]
+        .into_iter()
+        .zip(theme.blind_markers.into_iter().zip(theme.blind))
+        .map(|((key, counts), (marker, style))| Series {
+            name: app.strings.get(key).to_string(),
+            style,
+            marker,
+            points: line(counts),
+        })
+        .collect::<Vec<_>>();
+
+    // Narrowed to the buckets either holds anything in, and a bucket more.
+    let filled = |i: &usize| blind.a()[*i] > 0 || blind.b()[*i] > 0;
+    let mut indices = 0..blind.a().len();
+    let first = indices.clone().find(filled).unwrap_or(0);
+    let last = indices.rfind(filled).unwrap_or(0);
+    let x_ticks = Ticks::new(sum(first) - 2.0, sum(last) + 2.0, area.width as usize / 8);
+    let peak = blind
+        .a()
+        .iter()
+        .chain(blind.b())
+        .max()
+        .copied()
+        .unwrap_or(0);
+    let y_ticks = Ticks::new(
+        0.0,
+        axis::nice_ceil(peak as f64 * 1.1),
+        area.height as usize / 3,
+    );
+
+    let datasets = series
+        .iter()
+        .map(|s| {
+            Dataset::default()
+                .name(s.name.as_str())
+                .marker(s.marker)
+                .style(s.style)
+                .graph_type(GraphType::Line)
+                .data(&s.points)
+        })
+        .collect();
+    let title = app.strings.get("blind.title").to_string();
+    let chart = line_chart(app, datasets, title, &x_ticks, &y_ticks);
+    render_chart(f, chart, area, &x_ticks);
+    f.render_widget(Legend::new(&series, &x_ticks, &y_ticks), area);
+}
+
+/// The `--overlay` sources' densities by z-score, over the standard normal
+/// curve they tend to.
+fn draw_overlay<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App, overlay: &Overlay) {
+    let area = area.intersection(f.size());
+    if area.area() == 0 {
+        return;
+    }
+    let theme = app.theme();
+    let mut series = overlay
+        .series()
+        .iter()
+        .zip(theme.overlay_markers.into_iter().zip(theme.overlay))
+        .map(|(series, (marker, style))| Series {
+            name: app.strings.fill(
+                "overlay.series",
+                &[
+                    ("source", &series.source.name()),
+                    ("samples", &format::si(series.samples())),
+                ],
+            ),
+            style,
+            marker,
+            points: series.density(),
+        })
+        .collect::<Vec<_>>();
+    // The curve is drawn to ±4σ, a point every twentieth of one.
+    let (lo, hi) = (-4.0, 4.0);
+    series.push(Series {
+        name: app.strings.get("overlay.normal").to_string(),
+        style: theme.normal,
+        marker: theme.normal_marker,
+        points: (0..=160)
+            .map(|i| lo + f64::from(i) / 20.0)
+            .map(|z| (z, stats::normal_pdf(z)))
+            .collect(),
+    });
+
+    let x_ticks = Ticks::sigmas(lo, hi, 1.0, area.width as usize / 8);
+    let peak = series
+        .iter()
+        .flat_map(|s| &s.points)
+        .map(|&(_, density)| density)
+        .fold(0.0, f64::max);
+    let y_ticks = Ticks::new(0.0, axis::nice_ceil(peak * 1.1), area.height as usize / 3);
+
+    let datasets = series
+        .iter()
+        .map(|s| {
+            Dataset::default()
+                .name(s.name.as_str())
+                .marker(s.marker)
+                .style(s.style)
+                .graph_type(GraphType::Line)
+                .data(&s.points)
+        })
+        .collect();
+    let title = app.strings.fill("overlay.title", &[("steps", &app.r_max)]);
+    let chart = line_chart(app, datasets, title, &x_ticks, &y_ticks);
+    render_chart(f, chart, area, &x_ticks);
+    f.render_widget(Legend::new(&series, &x_ticks, &y_ticks), area);
+}
+
+/// Exact numbers for the selected bucket, in a box over the top of the bar
+/// chart on the side away from its bar, which starts `column` cells in.
+/// It's cleared behind like the legend.
+fn draw_bucket_details<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    app: &App,
+    index: usize,
+    column: u16,
+) {
+    let Some(bucket) = app.data.get(index) else {
+        return;
+    };
+    let total = stats::total(app.data.iter().map(|bucket| bucket.count));
+    let below = stats::total(app.data[..=index].iter().map(|bucket| bucket.count));
+    // Whichever model is drawn, so the residual matches the picture.
+    let expected = app
+        .expected()
+        .and_then(|expected| expected.get(index).copied());
+    let residual = match expected.filter(|&expected| expected > 0.0) {
+        Some(expected) => format!("{:+.2}", (bucket.count as f64 - expected) / expected.sqrt()),
+        None => format::UNDEFINED.to_string(),
+    };
+    let rows = [
+        ("details.count", format::grouped(bucket.count)),
+        (
+            "details.frequency",
+            format!("{:.2}%", stats::share(bucket.count, total) * 100.0),
+        ),
+        (
+            "details.expected",
+            expected.map_or(format::UNDEFINED.to_string(), |expected| {
+                format!("{:.1}", expected)
+            }),
+        ),
+        ("details.residual", residual),
+        (
+            "details.cumulative",
+            format!("{:.4}", stats::share(below, total)),
+        ),
+    ];
+    let lines = rows
+        .iter()
+        .map(|(key, value)| Spans::from(format!("{:<10}{:>10}", app.strings.get(key), value)))
+        .collect::<Vec<_>>();
+
+    let width = 20 + 2;
+    let height = lines.len() as u16 + 2;
+    let inner = panel(app).inner(area);
+    if inner.width < width * 2 || inner.height < height {
+        return;
+    }
+    let x = if column < inner.width / 2 {
+        inner.right() - width
+    } else {
+        inner.x
+    };
+    let details_area = Rect::new(x, inner.y, width, height);
+    f.render_widget(Clear, details_area);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                app.strings
+                    .fill("details.title", &[("x", &app.shown_data()[index].label)]),
+                app.theme().accent,
+            )),
+        ),
+        details_area,
+    );
+}
+
+/// The sparkline statistic over the whole history, full size.
+fn draw_convergence<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = app.theme();
+    let values = app.history.values(app.metric);
+    let first = app
+        .tick
+        .saturating_sub(values.len() as u64)
+        .saturating_add(1);
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| (first.saturating_add(i as u64) as f64, *value))
+        .collect::<Vec<_>>();
+    let max = values.iter().cloned().fold(0.0, f64::max);
+
+    let x_ticks = Ticks::new(
+        first as f64,
+        first.saturating_add(values.len().max(2) as u64 - 1) as f64,
+        area.width as usize / 8,
+    );
+    let y_ticks = Ticks::new(0.0, max.max(1e-3), area.height as usize / 3);
+    let title = app.strings.fill(
+        "convergence.title",
+        &[
+            ("metric", &app.strings.get(app.metric.name_key())),
+            ("ticks", &values.len()),
+        ],
+    );
+    // A vertical line where the --notify-when condition first held.
+    let marker = app
+        .alert
+        .as_ref()
+        .and_then(|alert| alert.fired)
+        .map(|tick| tick as f64)
+        .filter(|tick| (x_ticks.bounds[0]..=x_ticks.bounds[1]).contains(tick))
+        .map(|tick| guides(&[tick], &[], &x_ticks, &y_ticks))
+        .unwrap_or_default();
+    let datasets = marker
+        .iter()
+        .map(|points| {
+            Dataset::default()
+                .marker(theme.band_marker)
+                .style(theme.accent)
+                .graph_type(GraphType::Line)
+                .data(points)
+        })
+        .chain([Dataset::default()
+            .marker(theme.empirical_marker)
+            .style(theme.empirical)
+            .graph_type(GraphType::Line)
+            .data(&points)])
+        .collect();
+    let chart = line_chart(app, datasets, title, &x_ticks, &y_ticks);
+    render_chart(f, chart, area, &x_ticks);
+}
+
+/// Empirical quantiles against normal ones; a normal histogram lies on the diagonal.
+fn draw_qq<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = app.theme();
+    let points = stats::qq_points(&app.buckets(), app.mean(), app.sd());
+    let reach = points
+        .iter()
+        .flat_map(|(x, y)| [x.abs(), y.abs()])
+        .fold(3.0, f64::max)
+        .ceil();
+    let diagonal = vec![(-reach, -reach), (reach, reach)];
+
+    let x_ticks = Ticks::new(-reach, reach, area.width as usize / 8);
+    let y_ticks = Ticks::new(-reach, reach, area.height as usize / 3);
+    let datasets = vec![
+        Dataset::default()
+            .marker(theme.normal_marker)
+            .style(theme.normal)
+            .graph_type(GraphType::Line)
+            .data(&diagonal),
+        Dataset::default()
+            .marker(theme.point_marker)
+            .style(theme.empirical)
+            .graph_type(GraphType::Scatter)
+            .data(&points),
+    ];
+    let title = app.strings.get("qq.title").to_string();
+    let chart = line_chart(app, datasets, title, &x_ticks, &y_ticks);
+    render_chart(f, chart, area, &x_ticks);
+}
+
+/// Each bucket's signed error relative to the binomial probability, as a
+/// bar up or down from 0, so the tails can be seen to take far longer to
+/// settle than the middle. The bound is the same either side of 0 and
+/// shrinks as the errors do.
+fn draw_errors<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = app.theme();
+    let errors = app.relative_errors();
+    let keep = app
+        .clip
+        .as_ref()
+        .and_then(Clip::keep)
+        .unwrap_or(0..app.data.len());
+    let bars = app.data[keep.clone()]
+        .iter()
+        .zip(&errors[keep])
+        .filter_map(|(bucket, error)| Some((bucket.x, (*error)? * 100.0)))
+        .map(|(x, percent)| [(x, 0.0), (x, percent)])
+        .collect::<Vec<_>>();
+    let low = bars.first().map_or(-1.0, |bar| bar[0].0 - 1.0);
+    let high = bars.last().map_or(1.0, |bar| bar[0].0 + 1.0);
+    let zero = [(low, 0.0), (high, 0.0)];
+
+    let reach = app.error_bound.value();
+    let x_ticks = Ticks::new(low, high, area.width as usize / 8);
+    let y_ticks = Ticks::new(-reach, reach, area.height as usize / 3);
+    let mut datasets = vec![Dataset::default()
+        .marker(theme.grid_marker)
+        .style(theme.axis)
+        .graph_type(GraphType::Line)
+        .data(&zero)];
+    datasets.extend(bars.iter().map(|bar| {
+        Dataset::default()
+            .marker(theme.error_marker)
+            .style(match bar[1].1 >= 0.0 {
+                true => theme.grew,
+                false => theme.shrank,
+            })
+            .graph_type(GraphType::Line)
+            .data(bar)
+    }));
+    let title = app
+        .strings
+        .fill("errors.title", &[("expected", &ERRORS_MIN_EXPECTED)]);
+    let chart = line_chart(app, datasets, title, &x_ticks, &y_ticks);
+    render_chart(f, chart, area, &x_ticks);
+}
+
+/// The step-by-step running sums behind the first few samples of the tick.
+fn draw_walks<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = app.theme();
+    let walks = app
+        .walks
+        .iter()
+        .map(|walk| {
+            walk.iter()
+                .enumerate()
+                .map(|(step, sum)| (step as f64, *sum as f64))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let styles = [theme.empirical, theme.normal, theme.bar];
+
+    let x_ticks = Ticks::new(0.0, app.r_max as f64, area.width as usize / 8);
+    let y_ticks = Ticks::new(
+        -app.r_max as f64,
+        app.r_max as f64,
+        area.height as usize / 3,
+    );
+    let datasets = walks
+        .iter()
+        .enumerate()
+        .map(|(i, points)| {
+            Dataset::default()
+                .marker(theme.empirical_marker)
+                .style(styles[i % styles.len()])
+                .graph_type(GraphType::Line)
+                .data(points)
+        })
+        .collect();
+    let title = app.strings.fill("walks.title", &[("count", &walks.len())]);
+    let chart = line_chart(app, datasets, title, &x_ticks, &y_ticks);
+    render_chart(f, chart, area, &x_ticks);
+}
+
+fn draw_stats<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let stats = app.shown_stats(app.stats());
+    let unit = app.sim.aggregate().unit(app.r_max as u32);
+    let strings = &app.strings;
+    let mut lines = vec![
+        strings.fill("stats.ticks", &[("ticks", &app.tick)]),
+        strings.fill(
+            "stats.samples_per_tick",
+            &[("samples", &format::grouped(app.b_count as u64))],
+        ),
+        strings.fill(
+            "stats.samples_total",
+            &[("samples", &format::grouped(app.samples()))],
+        ),
+        strings.fill("stats.sample_rate", &[("rate", &sample_rate(app))]),
+        strings.fill("stats.steps", &[("steps", &app.r_max)]),
+        String::new(),
+        strings.fill("stats.mean", &[("mean", &format!("{:.3}", stats.mean))]),
+        strings.fill(
+            "stats.sd",
+            &[
+                ("sd", &sd_text(&stats, 3)),
+                ("expected", &format!("{:.3}", app.sd() / unit)),
+            ],
+        ),
+        strings.fill("stats.ks", &[("ks", &format!("{:.4}", stats.ks))]),
+    ];
+    if let Some(value) = app.degenerate {
+        let value = app.sim.aggregate().label(value, app.r_max as u32);
+        lines.push(strings.fill("stats.degenerate", &[("value", &value)]));
+    }
+    let buckets = app.buckets();
+    for (k, expected) in [(1, 68.3), (2, 95.4), (3, 99.7)] {
+        let within = stats::fraction_within(&buckets, app.mean(), k as f64 * app.sd());
+        lines.push(strings.fill(
+            "stats.within",
+            &[
+                ("k", &k),
+                ("share", &format!("{:.1}", within * 100.0)),
+                ("expected", &expected),
+            ],
+        ));
+    }
+    if let Some(summary) = &app.summary {
+        lines.push(String::new());
+        lines.extend(summary.lines(strings));
+    }
+    f.render_widget(Paragraph::new(lines.join("\n")).block(panel(app)), area);
+}
+
+/// How far the `--until` condition is from being met.
+fn until_text(app: &App, until: &Until) -> String {
+    let strings = &app.strings;
+    let condition = &until.condition;
+    if let Some(tick) = until.met() {
+        return strings.fill(
+            "status.until_met",
+            &[("condition", condition), ("tick", &tick)],
+        );
+    }
+    let value = until.latest().map_or_else(
+        || format::UNDEFINED.to_string(),
+        |v| condition.metric.format(v),
+    );
+    let eta = until
+        .ticks_left()
+        .filter(|_| !app.paused)
+        .and_then(|ticks| {
+            Duration::try_from_secs_f64(app.tick_rate.as_secs_f64() * ticks as f64).ok()
+        })
+        .map_or_else(|| format::UNDEFINED.to_string(), format::eta);
+    strings.fill(
+        "status.until",
+        &[
+            ("metric", &strings.get(condition.metric.name_key())),
+            ("value", &value),
+            ("condition", condition),
+            ("streak", &until.streak()),
+            ("needed", &until.needed),
+            ("eta", &eta),
+        ],
+    )
+}
+
+/// Samples drawn a second over the last few ticks, or none while paused.
+fn sample_rate(app: &App) -> String {
+    match app.perf.ticks_per_sec() {
+        Some(rate) if !app.paused => format::si_f64(rate * app.b_count as f64),
+        _ => format::UNDEFINED.to_string(),
+    }
+}
+
+/// The standard deviation to `decimals` places, if there are the two
+/// samples it takes.
+fn sd_text(stats: &Stats, decimals: usize) -> String {
+    match stats.total {
+        0 | 1 => format::UNDEFINED.to_string(),
+        _ => format!("{:.*}", decimals, stats.sd),
+    }
+}
+
+/// Draws `chart` into `area` as far as it's inside the frame. tui's `Chart`
+/// puts the x axis's inner labels past the right of a graph with fewer
+/// columns than labels, so a chart too narrow for that isn't drawn: the y
+/// labels take at most a third of the width, and the borders and the axis
+/// three columns more.
+fn render_chart<B: Backend>(f: &mut Frame<B>, chart: Chart, area: Rect, x_ticks: &Ticks) {
+    let area = area.intersection(f.size());
+    if area.width >= 2 * x_ticks.values.len() as u16 + 3 {
+        f.render_widget(chart, area);
+    }
+}
+
+fn line_chart<'a>(
+    app: &App,
+    datasets: Vec<Dataset<'a>>,
+    title: String,
+    x_ticks: &Ticks,
+    y_ticks: &Ticks,
+) -> Chart<'a> {
+    let theme = app.theme();
+    let block = if app.minimal {
+        panel(app)
+    } else {
+        panel(app).title(title)
+    };
+    Chart::new(datasets)
+        .block(block)
+        .hidden_legend_constraints((Constraint::Length(0), Constraint::Length(0)))
+        .x_axis(
+            Axis::default()
+                .style(theme.axis)
+                .labels(axis_labels(x_ticks, ""))
+                .bounds(x_ticks.bounds),
+        )
+        .y_axis(
+            Axis::default()
+                .style(theme.axis)
+                .labels(axis_labels(y_ticks, ""))
+                .bounds(y_ticks.bounds),
+        )
+}
+
+fn render_target<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App, target: &Target) {
+    let theme = app.theme();
+    let samples = app.samples();
+    let ratio = if target.goal == 0 {
+        1.0
+    } else {
+        (samples as f64 / target.goal as f64).min(1.0)
+    };
+    let (style, status) = if target.done(samples) {
+        (
+            theme.gauge_done,
+            app.strings.get("target.complete").to_string(),
+        )
+    } else {
+        let eta = target
+            .eta(samples, Instant::now())
+            .filter(|_| !app.paused)
+            .map_or_else(|| format::UNDEFINED.to_string(), format::eta);
+        (
+            theme.gauge,
+            app.strings.fill("target.eta", &[("eta", &eta)]),
+        )
+    };
+    let label = app.strings.fill(
+        "target.progress",
+        &[
+            ("percent", &format!("{:.1}", ratio * 100.0)),
+            ("samples", &format::si(samples)),
+            ("goal", &format::si(target.goal)),
+            ("status", &status),
+        ],
+    );
+    f.render_widget(
+        Gauge::default()
+            .gauge_style(style)
+            .ratio(ratio)
+            .label(label),
+        area,
+    );
+}
+
+/// A chi-square p-value this small isn't bad luck: the sampler is broken.
+const IMPLAUSIBLE: f64 = 1e-6;
+
+fn stats_line(app: &App, theme: &Theme) -> Spans<'static> {
+    let strings = &app.strings;
+    let mut status = app
+        .computing()
+        .map(|done| {
+            strings.fill(
+                "status.computing",
+                &[("percent", &format!("{:.0}", done * 100.0))],
+            )
+        })
+        .unwrap_or_default();
+    if app.perf.overran() {
+        status += strings.get("status.overrun");
+    }
+    if let Some(started) = &app.started {
+        status += &strings.fill(
+            "status.clock",
+            &[
+                ("started", &started.clock()),
+                ("now", &DateTime::now().clock()),
+            ],
+        );
+    }
+    let stats = app.shown_stats(app.stats());
+    if stats.total == 0 {
+        return Spans::from(format!("{}{}", strings.get("status.no_samples"), status));
+    }
+
+    let mut line = strings.fill(
+        "status.stats",
+        &[
+            ("mean", &format!("{:.2}", stats.mean)),
+            ("sd", &sd_text(&stats, 2)),
+            ("ks", &format!("{:.3}", stats.ks)),
+        ],
+    );
+    if app.show_bands {
+        let buckets = app.buckets();
+        for (k, expected) in [(1, 68.3), (2, 95.4), (3, 99.7)] {
+            let within = stats::fraction_within(&buckets, app.mean(), k as f64 * app.sd());
+            line += &strings.fill(
+                "status.band",
+                &[
+                    ("k", &k),
+                    ("share", &format!("{:.1}", within * 100.0)),
+                    ("expected", &expected),
+                ],
+            );
+        }
+    }
+    if let Some(reference) = &app.reference {
+        let total = stats::total(app.data.iter().map(|bucket| bucket.count));
+        let live = app
+            .data
+            .iter()
+            .map(|bucket| stats::share(bucket.count, total))
+            .collect::<Vec<_>>();
+        let ks = stats::ks_between(&live, &reference.frequencies(&app.data));
+        line += &strings.fill("status.reference", &[("ks", &format!("{:.3}", ks))]);
+    }
+    if app.precision_shown() {
+        let (converged, occupied) = app.converged();
+        line += &strings.fill(
+            "status.converged",
+            &[
+                ("converged", &converged),
+                ("occupied", &occupied),
+                ("target", &app.precision_percent()),
+            ],
+        );
+    }
+    if let Some(until) = &app.until {
+        line += &until_text(app, until);
+    }
+    if let (Some(seeds), Some([min, _, max])) = (&app.seeds, app.seeds_ks()) {
+        line += &strings.fill(
+            "status.seeds",
+            &[
+                ("count", &seeds.count()),
+                ("min", &format!("{:.3}", min)),
+                ("max", &format!("{:.3}", max)),
+            ],
+        );
+    }
+    line += " | ";
+
+    let counts = app
+        .data
+        .iter()
+        .map(|bucket| bucket.count)
+        .collect::<Vec<_>>();
+    // There's nothing to test an aggregate with no model against.
+    let Some(probabilities) = app.sim.layout().probabilities(app.model(), app.bias()) else {
+        return Spans::from(format!("{}{}", line, status));
+    };
+    let test = stats::chi_square(&counts, &probabilities);
+    let chi_square = strings.fill("status.chi_square", &[("p", &format::sig(test.p_value, 3))]);
+    let chi_square = if test.p_value < IMPLAUSIBLE {
+        Span::styled(chi_square, theme.warning)
+    } else {
+        Span::raw(chi_square)
+    };
+    Spans::from(vec![Span::raw(line), chi_square, Span::raw(status)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden;
+    use central_limit::rng::Generator;
+    use crossterm::event::KeyCode;
+    use std::{collections::HashMap, fmt::Write};
+    use tui::{backend::TestBackend, buffer::Buffer, Terminal};
+
+    /// A seeded run `ticks` ticks in, after `keys`.
+    fn app(ticks: usize, keys: &[KeyCode]) -> App {
+        let mut app = App::new(42, Generator::Std);
+        for _ in 0..ticks {
+            app.on_tick();
+        }
+        for &key in keys {
+            app.on_key(key);
+        }
+        app
+    }
+
+    fn draw_frame(app: &App, width: u16, height: u16) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| draw(f, app)).unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    /// The frame as text: its characters, then a letter per cell for the
+    /// cell's style, then what each letter stands for.
+    fn describe(buf: &Buffer) -> String {
+        let area = buf.area;
+        let mut styles: Vec<String> = vec![];
+        let mut letters = HashMap::new();
+        let mut text = String::new();
+        let mut map = String::new();
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get(x, y);
+                text.push_str(&cell.symbol);
+                let style = format!("{:?} on {:?}, {:?}", cell.fg, cell.bg, cell.modifier);
+                let letter = *letters.entry(style.clone()).or_insert_with(|| {
+                    styles.push(style);
+                    char::from_digit(styles.len() as u32 - 1, 36).unwrap_or('?')
+                });
+                map.push(letter);
+            }
+            text.push('\n');
+            map.push('\n');
+        }
+        let mut out = text + "\n" + &map + "\n";
+        for (i, style) in styles.iter().enumerate() {
+            let letter = char::from_digit(i as u32, 36).unwrap_or('?');
+            writeln!(out, "{} {}", letter, style).unwrap();
+        }
+        out
+    }
+
+    /// Checks the frame, characters and styles, against the golden file
+    /// `name`.
+    fn assert_golden(name: &str, buf: &Buffer) {
+        golden::check("ui", name, &describe(buf));
+    }
+
+    #[test]
+    fn the_histogram_at_each_size() {
+        let app = app(5, &[]);
+        for (width, height) in [(80, 24), (120, 40), (40, 12)] {
+            let name = format!("histogram_{}x{}", width, height);
+            assert_golden(&name, &draw_frame(&app, width, height));
+        }
+    }
+
+    #[test]
+    fn each_view() {
+        let views = [
+            ("convergence", '2'),
+            ("qq", '3'),
+            ("walks", '4'),
+            ("stats", '5'),
+            ("errors", '6'),
+        ];
+        for (name, key) in views {
+            let app = app(5, &[KeyCode::Char(key)]);
+            assert_golden(name, &draw_frame(&app, 80, 24));
+        }
+    }
+
+    #[test]
+    fn a_selected_bucket_in_percent() {
+        let app = app(5, &[KeyCode::Right, KeyCode::Right, KeyCode::Char('p')]);
+        assert_golden("selected_percent", &draw_frame(&app, 120, 40));
+    }
+
+    #[test]
+    fn the_settings_panel() {
+        let app = app(5, &[KeyCode::Char('o'), KeyCode::Down]);
+        assert_golden("settings", &draw_frame(&app, 80, 24));
+    }
+
+    #[test]
+    fn labels_that_arent_numbers() {
+        // The charts place buckets by their sums, never by their labels.
+        let mut app = app(5, &[]);
+        // At this width every other bucket is labelled, from the first.
+        let last_labelled = app.data.len() - 2;
+        app.data[0].label = "<−17".to_string();
+        app.data[last_labelled].label = "17+".to_string();
+        let text = describe(&draw_frame(&app, 120, 40));
+        assert!(text.contains("<−17") && text.contains("17+"), "{}", text);
+    }
+
+    #[test]
+    fn minimal_mode() {
+        let app = app(5, &[KeyCode::F(11)]);
+        assert_golden("minimal", &draw_frame(&app, 80, 24));
+    }
+
+    fn text(buf: &Buffer) -> String {
+        buf.content
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn no_data_before_the_first_tick() {
+        for key in ['1', '2', '3', '4', '6'] {
+            let app = app(0, &[KeyCode::Char(key)]);
+            assert!(text(&draw_frame(&app, 80, 24)).contains("No data yet"));
+        }
+        let app = app(0, &[KeyCode::Char('5')]);
+        assert!(!text(&draw_frame(&app, 80, 24)).contains("No data yet"));
+    }
+
+    #[test]
+    fn overlaid_sources_share_a_chart() {
+        let mut app = app(0, &[KeyCode::Char('c')]);
+        let sources = crate::overlay::Source::parse_list("coin,die6,uniform01").unwrap();
+        app.overlay = Some(Overlay::new(&sources, 42, Generator::Std));
+        for _ in 0..5 {
+            app.on_tick();
+        }
+        let frame = draw_frame(&app, 100, 30);
+        assert_golden("overlay", &frame);
+        let text = text(&frame);
+        for name in [
+            "coin, n=25k",
+            "die6, n=25k",
+            "uniform01, n=25k",
+            "standard normal",
+        ] {
+            assert!(text.contains(name), "{}", name);
+        }
+        // The steps change for every source at once.
+        app.set_params(5_000, 20).unwrap();
+        app.on_tick();
+        let overlay = app.overlay.as_ref().unwrap();
+        assert!(overlay
+            .series()
+            .iter()
+            .all(|series| series.samples() == 5_000));
+    }
+
+    #[test]
+    fn means_are_shown_throughout() {
+        let mut app = app(0, &[]);
+        app.sim.set_aggregate(Aggregate::Mean).unwrap();
+        app.on_tick();
+        app.on_key(KeyCode::Right);
+        // Wide enough for the whole header.
+        let histogram = text(&draw_frame(&app, 240, 40));
+        assert!(histogram.contains("Histogram of each sample's mean"));
+        assert!(histogram.contains(" x = 0.1 "));
+        app.on_key(KeyCode::Char('5'));
+        let panel = text(&draw_frame(&app, 120, 40));
+        let stats = app.stats();
+        let mean = format!("Mean: {:.3} ", stats.mean / 19.0);
+        let sd = format!("{:.3} (expected {:.3})", stats.sd / 19.0, app.sd() / 19.0);
+        assert!(panel.contains(&mean) && panel.contains(&sd));
+    }
+
+    #[test]
+    fn maxima_are_drawn_without_a_model() {
+        let mut app = app(0, &[KeyCode::Char('c')]);
+        app.sim.set_aggregate(Aggregate::Max).unwrap();
+        for _ in 0..3 {
+            app.on_tick();
+        }
+        assert_eq!(app.data.len(), 20);
+        let histogram = text(&draw_frame(&app, 240, 40));
+        assert!(histogram.contains("Histogram of each sample's max"));
+        assert!(!histogram.contains("exact binomial") && !histogram.contains("χ²"));
+        let samples = app.samples();
+        for key in ['f', 'x'] {
+            app.on_key(KeyCode::Char(key));
+            app.toasts.update(Instant::now());
+            assert!(app.blind.is_none() && app.samples() == samples);
+            let toast = app.toasts.current().unwrap();
+            assert!(
+                toast.message.contains("no model of the max"),
+                "{}",
+                toast.message
+            );
+        }
+    }
+
+    #[test]
+    fn every_sample_alike_is_called_out() {
+        let mut app = app(0, &[]);
+        app.set_params(50, 19).unwrap();
+        app.sim.set_bias(1e-12).unwrap();
+        app.on_tick();
+        let banner = "all 50 samples equal -19";
+        assert!(text(&draw_frame(&app, 120, 40)).contains(banner));
+        app.on_key(KeyCode::Char('5'));
+        let panel = text(&draw_frame(&app, 120, 40));
+        assert!(panel.contains("all samples equal -19, so there's no shape"));
+
+        app.sim.set_bias(sampling::FAIR).unwrap();
+        app.on_tick();
+        assert!(!text(&draw_frame(&app, 120, 40)).contains("Degenerate"));
+    }
+
+    #[test]
+    fn no_samples_or_steps_or_too_many_are_refused() {
+        let mut app = app(1, &[]);
+        assert!(app.set_params(0, 19).is_err());
+        assert!(app.set_params(5000, 0).is_err());
+        assert!(app.set_params(5000, -3).is_err());
+        assert!(app.set_params(usize::MAX, 19).is_err());
+        assert!(app.set_params(5000, 2_000_000_000).is_err());
+        assert_eq!((app.b_count, app.r_max, app.data.len()), (5000, 19, 20));
+    }
+
+    #[test]
+    fn no_color_draws_no_colors() {
+        use crate::theme::Palette;
+        use tui::style::Color;
+        let mut app = app(5, &[]);
+        app.palette = Palette::Mono;
+        app.no_color = true;
+        // t would switch palettes, and is refused.
+        app.on_key(KeyCode::Char('t'));
+        assert!(matches!(app.palette, Palette::Mono));
+        let screens: [&[KeyCode]; 11] = [
+            &[],
+            &[KeyCode::Right],
+            &[KeyCode::Char('p'), KeyCode::Char('g')],
+            &[KeyCode::Char('2')],
+            &[KeyCode::Char('3')],
+            &[KeyCode::Char('4')],
+            &[KeyCode::Char('5')],
+            &[KeyCode::Char('6')],
+            &[
+                KeyCode::Char('1'),
+                KeyCode::Char('l'),
+                KeyCode::Char('v'),
+                KeyCode::Char('w'),
+                KeyCode::Char('d'),
+                KeyCode::Char('k'),
+            ],
+            &[KeyCode::Char('x')],
+            &[KeyCode::Char('x'), KeyCode::Char('o')],
+        ];
+        for keys in screens {
+            for &key in keys {
+                app.on_key(key);
+            }
+            let buf = draw_frame(&app, 120, 40);
+            for cell in &buf.content {
+                assert_eq!(
+                    (cell.fg, cell.bg),
+                    (Color::Reset, Color::Reset),
+                    "{:?} after {:?}",
+                    cell,
+                    keys
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ascii_draws_only_ascii() {
+        let mut app = app(5, &[]);
+        app.render.ascii = true;
+        // Lines, corners and bars have ASCII of their own.
+        let drawn = text(&draw_frame(&app, 120, 40));
+        assert!(drawn.contains("+-") && drawn.contains('|') && drawn.contains('#'));
+        let screens: [&[KeyCode]; 10] = [
+            &[],
+            &[KeyCode::Right, KeyCode::Char('v'), KeyCode::Char('k')],
+            &[KeyCode::Char('b'), KeyCode::Char('m'), KeyCode::Char('d')],
+            &[KeyCode::Char('2')],
+            &[KeyCode::Char('3')],
+            &[KeyCode::Char('4')],
+            &[KeyCode::Char('5')],
+            &[KeyCode::Char('6')],
+            &[KeyCode::Char('1'), KeyCode::Char('x')],
+            &[KeyCode::Char('x'), KeyCode::Char('o')],
+        ];
+        for keys in screens {
+            for &key in keys {
+                app.on_key(key);
+            }
+            for (width, height) in [(120, 40), (80, 24)] {
+                let drawn = text(&draw_frame(&app, width, height));
+                assert!(drawn.is_ascii(), "{} after {:?}", drawn, keys);
+            }
+        }
+    }
+
+    #[test]
+    fn counts_near_the_limit_draw() {
+        let mut app = app(1, &[]);
+        let middle = app.data.len() / 2;
+        for bucket in &mut app.data {
+            bucket.count = u64::MAX / 2;
+        }
+        app.data[middle].count = u64::MAX;
+        app.tick = u64::MAX;
+        app.drawn = u64::MAX;
+        app.refresh();
+        let drawn = text(&draw_frame(&app, 200, 40));
+        // The count that overflowed is marked as clamped, no other.
+        assert_eq!(drawn.matches("E+").count(), 1, "{}", drawn);
+        assert!(drawn.contains("18E+"), "{}", drawn);
+        for key in ['p', 'g', '2', '3', '5', '6'] {
+            app.on_key(KeyCode::Char(key));
+            draw_frame(&app, 120, 40);
+        }
+    }
+
+    #[test]
+    fn one_step_and_one_sample_draw() {
+        for (b_count, r_max) in [(10, 1), (1, 1), (1, 19)] {
+            let mut app = app(0, &[]);
+            app.set_params(b_count, r_max).unwrap();
+            app.on_tick();
+            for key in ['1', '2', '3', '4', '5', '6'] {
+                app.on_key(KeyCode::Char(key));
+                for (width, height) in [(80, 24), (40, 12), (12, 6)] {
+                    draw_frame(&app, width, height);
+                }
+            }
+            app.on_key(KeyCode::Char('1'));
+            let drawn = text(&draw_frame(&app, 80, 24));
+            assert!(!drawn.contains("No data yet"), "{}", drawn);
+        }
+    }
+}
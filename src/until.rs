@@ -0,0 +1,99 @@
+//! `--until`: counting cumulatively until a statistic meets a condition, such
+//! as `ks<0.005`, for a few ticks in a row, and then stopping.
+
+use crate::alert::Condition;
+use std::collections::VecDeque;
+
+/// Ticks in a row the condition has to hold, unless `--until-ticks` says.
+pub const DEFAULT_TICKS: u64 = 3;
+
+/// Recent ticks the trend toward the threshold is taken over.
+const TREND_TICKS: usize = 20;
+
+#[derive(Debug)]
+pub struct Until {
+    pub condition: Condition,
+    /// Ticks in a row the condition has to hold.
+    pub needed: u64,
+    /// Ticks in a row it has held, up to the latest.
+    streak: u64,
+    /// The tick it was met on.
+    met: Option<u64>,
+    /// The statistic at each recent tick.
+    recent: VecDeque<(u64, f64)>,
+}
+
+impl Until {
+    pub fn new(condition: Condition, needed: u64) -> Until {
+        Until {
+            condition,
+            needed: needed.max(1),
+            streak: 0,
+            met: None,
+            recent: VecDeque::with_capacity(TREND_TICKS),
+        }
+    }
+
+    /// Counts `value` at `tick`, returning true on the tick the condition
+    /// has held for long enough.
+    pub fn record(&mut self, tick: u64, value: f64) -> bool {
+        if !value.is_finite() {
+            return false;
+        }
+        if self.recent.len() == TREND_TICKS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back((tick, value));
+        self.streak = match self.condition.holds(value) {
+            true => self.streak + 1,
+            false => 0,
+        };
+        if self.met.is_none() && self.streak >= self.needed {
+            self.met = Some(tick);
+            return true;
+        }
+        false
+    }
+
+    pub fn met(&self) -> Option<u64> {
+        self.met
+    }
+
+    pub fn streak(&self) -> u64 {
+        self.streak.min(self.needed)
+    }
+
+    /// The latest value of the statistic.
+    pub fn latest(&self) -> Option<f64> {
+        self.recent.back().map(|(_, value)| *value)
+    }
+
+    /// Ticks until the condition is met, going by a straight line through
+    /// the recent values; a crude guess, since the statistics are noisy and
+    /// rarely fall in a straight line. `None` if they aren't heading toward
+    /// the threshold.
+    pub fn ticks_left(&self) -> Option<u64> {
+        let to_hold = self.needed - self.streak();
+        let latest = self.latest()?;
+        if self.condition.holds(latest) {
+            return Some(to_hold);
+        }
+        if self.recent.len() < 2 {
+            return None;
+        }
+        // The least-squares slope, per tick.
+        let n = self.recent.len() as f64;
+        let mean_tick = self.recent.iter().map(|(t, _)| *t as f64).sum::<f64>() / n;
+        let mean_value = self.recent.iter().map(|(_, v)| v).sum::<f64>() / n;
+        let (covariance, variance) =
+            self.recent
+                .iter()
+                .fold((0.0, 0.0), |(covariance, variance), (t, v)| {
+                    let dt = *t as f64 - mean_tick;
+                    (covariance + dt * (v - mean_value), variance + dt * dt)
+                });
+        let slope = covariance / variance;
+        let ticks = (self.condition.threshold - latest) / slope;
+        (ticks.is_finite() && ticks > 0.0).then(|| ticks.ceil() as u64 + to_hold)
+    }
+}
@@ -0,0 +1,67 @@
+//! `--warm-start`: counting on top of the histogram from a previous export.
+
+use crate::{bucket::Bucket, export};
+use std::path::Path;
+
+/// Where a warm-started run's initial counts came from.
+#[derive(Debug, Clone)]
+pub struct WarmStart {
+    /// The export this run started from, preceded by the ones it started from.
+    pub files: Vec<String>,
+    /// Samples carried over from those runs.
+    pub samples: u64,
+}
+
+/// Reads the counts of an export written by `--output-dir`, checking that its
+/// buckets are the ones `data` lays out for `r_max` steps.
+pub fn load(path: &Path, r_max: i32, data: &[Bucket]) -> Result<(WarmStart, Vec<u64>), String> {
+    check(export::read(path)?, path, r_max, data)
+}
+
+/// The counts of `export`, read from `path`, if its buckets are the ones
+/// `data` lays out for `r_max` steps.
+pub fn check(
+    export: export::Histogram,
+    path: &Path,
+    r_max: i32,
+    data: &[Bucket],
+) -> Result<(WarmStart, Vec<u64>), String> {
+    let mut differences = vec![];
+    if export.r_max != r_max {
+        differences.push(format!(
+            "r_max is {} there but {} here",
+            export.r_max, r_max
+        ));
+    }
+    if !export
+        .labels
+        .iter()
+        .eq(data.iter().map(|bucket| &bucket.label))
+    {
+        differences.push(format!(
+            "the buckets are {} there but {} here",
+            describe(&export.labels.iter().map(String::as_str).collect::<Vec<_>>()),
+            describe(&data.iter().map(|b| b.label.as_str()).collect::<Vec<_>>())
+        ));
+    }
+    if !differences.is_empty() {
+        return Err(format!(
+            "it doesn't match this run: {}",
+            differences.join("; ")
+        ));
+    }
+
+    let mut files = export.warm_start;
+    files.push(path.display().to_string());
+
+    let samples = export.counts.iter().copied().fold(0, u64::saturating_add);
+    Ok((WarmStart { files, samples }, export.counts))
+}
+
+/// The range of bucket labels, e.g. `-3..3 (4 buckets)`.
+pub fn describe(labels: &[&str]) -> String {
+    match (labels.first(), labels.last()) {
+        (Some(first), Some(last)) => format!("{}..{} ({} buckets)", first, last, labels.len()),
+        _ => "empty".to_string(),
+    }
+}
@@ -0,0 +1,146 @@
+//! `--watch-config`: re-reading a config file while the TUI runs, so a demo
+//! can be tuned without restarting it and losing the counts.
+
+use crate::{json, theme::Palette};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Ticks between checks of the file's modification time.
+pub const CHECK_EVERY: u64 = 4;
+
+/// How long the header says the config was reloaded.
+pub const RELOADED_FOR: Duration = Duration::from_secs(3);
+
+/// The settings a config file can change. The fields are those of a session's
+/// `config.json`, plus `theme`; any that are left out keep their value, and
+/// `seed` and `rng` are ignored, since they can't change mid-run. The bias
+/// sweep is set by `bias_from` and `bias_to`, which go together, and
+/// `bias_period`.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub samples: Option<usize>,
+    pub r_max: Option<i32>,
+    pub tick_rate: Option<Duration>,
+    pub cumulative: Option<bool>,
+    pub theme: Option<Palette>,
+    pub bias: Option<(f64, f64)>,
+    pub bias_period: Option<u64>,
+}
+
+impl Config {
+    /// The parameters the file sets, by their names in the exports.
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = vec![];
+        if self.samples.is_some() {
+            names.push("samples");
+        }
+        if self.r_max.is_some() {
+            names.push("r_max");
+        }
+        if self.tick_rate.is_some() {
+            names.push("tick_ms");
+        }
+        if self.cumulative.is_some() {
+            names.push("mode");
+        }
+        if self.theme.is_some() {
+            names.push("theme");
+        }
+        if self.bias.is_some() || self.bias_period.is_some() {
+            names.push("bias");
+        }
+        names
+    }
+}
+
+pub fn read(path: &Path) -> Result<Config, String> {
+    parse(&fs::read_to_string(path).map_err(|err| err.to_string())?)
+}
+
+/// The config in `contents`, as `read` reads a file.
+pub fn parse(contents: &str) -> Result<Config, String> {
+    let config = json::parse(contents)?;
+    let count = |name: &str| match config.get(name) {
+        Some(value) => value
+            .as_u64()
+            .map(Some)
+            .ok_or(format!("'{}' is not a count", name)),
+        None => Ok(None),
+    };
+
+    let tick_rate = match count("tick_ms")? {
+        Some(0) => return Err("'tick_ms' must be at least 1".to_string()),
+        ms => ms.map(Duration::from_millis),
+    };
+    let cumulative = match config.get("cumulative") {
+        Some(json::Value::Bool(cumulative)) => Some(*cumulative),
+        Some(_) => return Err("'cumulative' is not true or false".to_string()),
+        None => None,
+    };
+    let theme = match config.get("theme") {
+        Some(value) => {
+            let name = value.as_str().ok_or("'theme' is not a string")?;
+            Some(Palette::parse(name).ok_or(format!("unknown theme '{}'", name))?)
+        }
+        None => None,
+    };
+    let chance = |name: &str| match config.get(name) {
+        Some(value) => value
+            .as_f64()
+            .map(Some)
+            .ok_or(format!("'{}' is not a number", name)),
+        None => Ok(None),
+    };
+    let bias = match (chance("bias_from")?, chance("bias_to")?) {
+        (Some(from), Some(to)) => Some((from, to)),
+        (None, None) => None,
+        _ => return Err("'bias_from' and 'bias_to' go together".to_string()),
+    };
+    Ok(Config {
+        samples: count("samples")?.map(|n| n as usize),
+        r_max: count("r_max")?.map(|n| n.min(i32::MAX as u64) as i32),
+        tick_rate,
+        cumulative,
+        theme,
+        bias,
+        bias_period: count("bias_period")?,
+    })
+}
+
+#[derive(Debug)]
+pub struct Watch {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl Watch {
+    /// Watches `path` for changes from now on.
+    pub fn new(path: PathBuf) -> Watch {
+        let modified = modified(&path);
+        Watch { path, modified }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file has been modified since the last call. A file that
+    /// can't be read counts as unchanged, since editors often replace the
+    /// file rather than write to it.
+    pub fn changed(&mut self) -> bool {
+        match modified(&self.path) {
+            Some(time) if Some(time) != self.modified => {
+                self.modified = Some(time);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}